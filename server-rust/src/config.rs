@@ -0,0 +1,179 @@
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use serde::Serialize;
+use std::time::Duration;
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::utils::jitter;
+
+/// Cleanup task configuration
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupConfig {
+    pub room_cleanup_interval_secs: u64,
+    pub file_cleanup_interval_secs: u64,
+    pub startup_orphaned_files_cleanup: bool,
+    pub dry_run: bool,
+    /// Random ±% applied to each cleanup tick (0 = fixed intervals)
+    pub jitter_percent: u32,
+    /// How often file metadata is snapshotted when FILE_METADATA_PATH is set
+    pub metadata_snapshot_interval_secs: u64,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            room_cleanup_interval_secs: 60,  // 1 minute (aligned with Node.js)
+            file_cleanup_interval_secs: 600, // 10 minutes (aligned with Node.js)
+            startup_orphaned_files_cleanup: true,
+            dry_run: false,
+            jitter_percent: 0,
+            metadata_snapshot_interval_secs: 60,
+        }
+    }
+}
+
+impl CleanupConfig {
+    pub fn from_env() -> Self {
+        Self {
+            room_cleanup_interval_secs: std::env::var("ROOM_CLEANUP_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            file_cleanup_interval_secs: std::env::var("FILE_CLEANUP_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(600),
+            startup_orphaned_files_cleanup: std::env::var("CLEANUP_ORPHANED_FILES_AT_STARTUP")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(true),
+            dry_run: std::env::var("CLEANUP_DRY_RUN")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            jitter_percent: std::env::var("CLEANUP_JITTER_PERCENT")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .map(|p| p.min(jitter::MAX_JITTER_PERCENT))
+                .unwrap_or(0),
+            metadata_snapshot_interval_secs: std::env::var(
+                "FILE_METADATA_SNAPSHOT_INTERVAL_SECONDS",
+            )
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(60),
+        }
+    }
+}
+
+/// Origins allowed by CORS outside production (CLIENT_URL, comma-separated)
+pub fn cors_allowed_origins() -> Vec<String> {
+    std::env::var("CLIENT_URL")
+        .unwrap_or_else(|_| "http://localhost:3000,http://localhost:3002".to_string())
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
+}
+
+/// Default preflight cache lifetime in seconds (CORS_MAX_AGE)
+pub const DEFAULT_CORS_MAX_AGE_SECS: u64 = 600;
+
+/// Response headers browsers may read by default (CORS_EXPOSE_HEADERS overrides)
+pub const DEFAULT_CORS_EXPOSE_HEADERS: &[&str] = &[
+    "x-request-id",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+    "retry-after",
+    "content-disposition",
+];
+
+/// CORS configuration shared by the production and development layers
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsConfig {
+    /// Origins allowed with credentials outside production (empty = any origin)
+    pub allowed_origins: Vec<String>,
+    /// `Access-Control-Max-Age` for preflight responses (0 = header omitted)
+    pub max_age_secs: u64,
+    /// `Access-Control-Expose-Headers` list
+    pub expose_headers: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            max_age_secs: DEFAULT_CORS_MAX_AGE_SECS,
+            expose_headers: DEFAULT_CORS_EXPOSE_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            allowed_origins: cors_allowed_origins(),
+            max_age_secs: std::env::var("CORS_MAX_AGE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_age_secs),
+            expose_headers: std::env::var("CORS_EXPOSE_HEADERS")
+                .map(|v| {
+                    v.split(',')
+                        .map(|h| h.trim().to_lowercase())
+                        .filter(|h| !h.is_empty())
+                        .collect()
+                })
+                .unwrap_or(defaults.expose_headers),
+        }
+    }
+
+    /// Build the CORS layer: any origin without credentials in production (or when no
+    /// origins are configured), otherwise the configured origins with credentials
+    pub fn layer(&self, production: bool) -> CorsLayer {
+        let methods = [Method::GET, Method::POST, Method::PUT, Method::DELETE];
+        let allowed_origins: Vec<HeaderValue> = self
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| origin.parse::<HeaderValue>().ok())
+            .collect();
+
+        let layer = if production || allowed_origins.is_empty() {
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(methods)
+                .allow_headers(Any)
+                .allow_credentials(false)
+        } else {
+            CorsLayer::new()
+                .allow_origin(allowed_origins)
+                .allow_methods(methods)
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    header::ACCEPT,
+                    header::ORIGIN,
+                    header::CACHE_CONTROL,
+                ])
+                .allow_credentials(true)
+        };
+
+        let expose_headers: Vec<HeaderName> = self
+            .expose_headers
+            .iter()
+            .filter_map(|name| name.parse::<HeaderName>().ok())
+            .collect();
+        let layer = layer.expose_headers(expose_headers);
+
+        if self.max_age_secs > 0 {
+            layer.max_age(Duration::from_secs(self.max_age_secs))
+        } else {
+            layer
+        }
+    }
+}
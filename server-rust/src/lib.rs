@@ -1,4 +1,5 @@
 // Library entry point for testing
+pub mod config;
 pub mod middleware;
 pub mod models;
 pub mod routes;
@@ -15,4 +16,52 @@ pub struct AppState {
     pub file_manager: Arc<FileManager>,
     pub share_service: Arc<ShareService>,
     pub start_time: std::time::Instant,
+    /// Set once socket handlers are registered and the listener is bound
+    pub ready: Arc<std::sync::atomic::AtomicBool>,
+    /// Rate-limit rejection counters reported by the HTTP and socket limiters
+    pub rate_limit_metrics: Arc<middleware::rate_limit::RateLimitMetrics>,
+    /// Per-fingerprint / per-user upload budget, on top of the per-IP upload limiter
+    pub upload_quota: Arc<middleware::rate_limit::UploadQuota>,
+}
+
+impl AppState {
+    /// Build the state from its services, starting the uptime clock now
+    pub fn new(
+        room_service: Arc<RoomService>,
+        file_manager: Arc<FileManager>,
+        share_service: Arc<ShareService>,
+    ) -> Self {
+        Self {
+            room_service,
+            file_manager,
+            share_service,
+            start_time: std::time::Instant::now(),
+            ready: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            rate_limit_metrics: Arc::new(middleware::rate_limit::RateLimitMetrics::new()),
+            upload_quota: Arc::new(middleware::rate_limit::UploadQuota::from_config(
+                &middleware::rate_limit::RateLimitConfig::from_env(),
+            )),
+        }
+    }
+
+    /// Override the per-uploader upload budget (USER_UPLOAD_RATE_LIMIT / USER_UPLOAD_MAX_BYTES)
+    pub fn with_upload_quota(mut self, quota: middleware::rate_limit::UploadQuota) -> Self {
+        self.upload_quota = Arc::new(quota);
+        self
+    }
+
+    /// Mark the server as ready to accept traffic (flips the readiness probe to 200)
+    pub fn mark_ready(&self) {
+        self.ready.store(true, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Whether the server has finished starting up
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Time elapsed since the server state was created
+    pub fn uptime(&self) -> std::time::Duration {
+        self.start_time.elapsed()
+    }
 }
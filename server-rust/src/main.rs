@@ -1,17 +1,15 @@
 // Use the library modules instead of redefining them
-use cloud_clipboard_server::{AppState, middleware, routes, services};
+use cloud_clipboard_server::config::{CleanupConfig, CorsConfig};
+use cloud_clipboard_server::{AppState, middleware, routes, services, utils};
 
-use axum::http::{HeaderName, HeaderValue, header};
-use axum::{Json, Router, extract::DefaultBodyLimit, http::Method, http::StatusCode, routing::get};
+use axum::{Router, extract::DefaultBodyLimit, http::StatusCode, routing::get};
 use socketioxide::SocketIo;
 use std::sync::Arc;
 use std::time::Duration;
 use tower_http::{
     compression::CompressionLayer,
-    cors::{Any, CorsLayer},
     limit::RequestBodyLimitLayer,
     services::{ServeDir, ServeFile},
-    set_header::SetResponseHeaderLayer,
     trace::TraceLayer,
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -19,45 +17,10 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use crate::middleware::rate_limit::{
     RateLimitConfig, RateLimitMiddleware, public_download_rate_limiter, strict_rate_limiter,
 };
-use crate::routes::{api_info, files, health, rooms, share};
+use crate::middleware::security_headers::{HstsConfig, SecurityHeadersConfig};
+use crate::routes::{admin, api_info, files, health, rooms, share, stats};
 use crate::services::{FileManager, RoomEvent, RoomService, ShareService};
-
-/// Cleanup task configuration
-#[derive(Clone, Debug)]
-pub struct CleanupConfig {
-    pub room_cleanup_interval_secs: u64,
-    pub file_cleanup_interval_secs: u64,
-    pub startup_orphaned_files_cleanup: bool,
-}
-
-impl Default for CleanupConfig {
-    fn default() -> Self {
-        Self {
-            room_cleanup_interval_secs: 60,  // 1 minute (aligned with Node.js)
-            file_cleanup_interval_secs: 600, // 10 minutes (aligned with Node.js)
-            startup_orphaned_files_cleanup: true,
-        }
-    }
-}
-
-impl CleanupConfig {
-    pub fn from_env() -> Self {
-        Self {
-            room_cleanup_interval_secs: std::env::var("ROOM_CLEANUP_INTERVAL_SECONDS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(60),
-            file_cleanup_interval_secs: std::env::var("FILE_CLEANUP_INTERVAL_SECONDS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(600),
-            startup_orphaned_files_cleanup: std::env::var("CLEANUP_ORPHANED_FILES_AT_STARTUP")
-                .map(|v| v.to_lowercase() == "true")
-                .unwrap_or(true),
-        }
-    }
-}
-
+use crate::utils::jitter;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Load environment variables
@@ -94,6 +57,11 @@ async fn main() -> anyhow::Result<()> {
     let rate_limit_config = RateLimitConfig::from_env();
     tracing::info!(?rate_limit_config, "Rate limit configuration loaded");
 
+    // Fail fast on invalid CSP_HEADER / X_FRAME_OPTIONS / REFERRER_POLICY
+    let security_headers = SecurityHeadersConfig::from_env().map_err(anyhow::Error::msg)?;
+    // Same for HSTS_MAX_AGE / HSTS_INCLUDE_SUBDOMAINS / HSTS_PRELOAD
+    let hsts = HstsConfig::from_env().map_err(anyhow::Error::msg)?;
+
     let cleanup_config = CleanupConfig::from_env();
     tracing::info!(?cleanup_config, "Cleanup configuration loaded");
 
@@ -109,9 +77,44 @@ async fn main() -> anyhow::Result<()> {
     let public_download_limiter = public_download_rate_limiter(&rate_limit_config);
 
     // Initialize services
-    let room_service = Arc::new(RoomService::new());
-    let file_manager = Arc::new(FileManager::new()?);
-    let share_service = Arc::new(ShareService::new());
+    if cleanup_config.dry_run {
+        tracing::warn!(
+            "CLEANUP_DRY_RUN enabled: cleanup tasks will only log what they would delete"
+        );
+    }
+    let room_service = Arc::new(RoomService::new().with_dry_run(cleanup_config.dry_run));
+    let file_manager = Arc::new(FileManager::new()?.with_dry_run(cleanup_config.dry_run));
+    let (min_expiration_days, max_expiration_days) =
+        services::share_service::expiration_bounds_from_env().map_err(anyhow::Error::msg)?;
+    let default_expiration_days = services::share_service::default_expiration_from_env(
+        min_expiration_days,
+        max_expiration_days,
+    )
+    .map_err(anyhow::Error::msg)?;
+    let share_service = Arc::new(
+        ShareService::new()
+            .with_dry_run(cleanup_config.dry_run)
+            .with_expiration_bounds(min_expiration_days, max_expiration_days)
+            .with_default_expiration(default_expiration_days),
+    );
+
+    // Restore file metadata from the last snapshot before orphans are judged
+    if let Some(path) = file_manager.metadata_path() {
+        match file_manager.load_metadata_snapshot().await {
+            Ok(restore) => tracing::info!(
+                "Restored file metadata from {}: {} files, {} missing on disk, {} untracked",
+                path.display(),
+                restore.restored,
+                restore.missing.len(),
+                restore.orphans.len()
+            ),
+            Err(e) => tracing::error!(
+                "Failed to load file metadata snapshot {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
 
     // Startup orphaned files cleanup
     if cleanup_config.startup_orphaned_files_cleanup {
@@ -120,12 +123,11 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("Startup cleanup: removed {} orphaned files", cleaned);
     }
 
-    let app_state = AppState {
-        room_service: room_service.clone(),
-        file_manager: file_manager.clone(),
-        share_service: share_service.clone(),
-        start_time: std::time::Instant::now(),
-    };
+    let app_state = AppState::new(
+        room_service.clone(),
+        file_manager.clone(),
+        share_service.clone(),
+    );
 
     // Setup Socket.IO
     let (socket_layer, io) = SocketIo::builder()
@@ -135,56 +137,88 @@ async fn main() -> anyhow::Result<()> {
         .build_layer();
 
     // Register Socket.IO event handlers
-    services::socket::setup_socket_handlers(&io, room_service.clone());
+    services::socket::setup_socket_handlers(
+        &io,
+        room_service.clone(),
+        file_manager.clone(),
+        app_state.rate_limit_metrics.clone(),
+    );
 
     // Start room event listener for file cleanup and socket broadcasting
     {
         let mut event_rx = room_service.subscribe();
         let file_manager_for_events = file_manager.clone();
+        let room_service_for_events = room_service.clone();
         let io_for_events = io.clone();
         tokio::spawn(async move {
             loop {
                 match event_rx.recv().await {
-                    Ok(RoomEvent::RoomDestroyed { room_key }) => {
+                    Ok(RoomEvent::RoomDestroyed { room_key, reason }) => {
                         tracing::info!(
-                            "Room {} destroyed event received, cleaning up files",
-                            room_key
+                            "Room {} destroyed ({:?}), cleaning up files",
+                            room_key,
+                            reason
                         );
                         let deleted_files = file_manager_for_events.delete_room_files(&room_key);
-
-                        if !deleted_files.is_empty() {
-                            let filenames: Vec<String> = deleted_files
-                                .iter()
-                                .map(|f| f.original_name.clone())
-                                .collect();
-
-                            // Broadcast roomDestroyed event to clients
-                            let event = serde_json::json!({
+                        let filenames: Vec<String> = deleted_files
+                            .iter()
+                            .map(|f| f.original_name.clone())
+                            .collect();
+
+                        // Broadcast roomDestroyed event to clients
+                        let event = serde_json::json!({
+                            "roomKey": room_key,
+                            "reason": reason,
+                            "deletedFiles": filenames,
+                        });
+                        let _ = io_for_events
+                            .to(room_key.clone())
+                            .emit("roomDestroyed", &event);
+
+                        // Also send systemMessage
+                        let sys_msg = serde_json::json!({
+                            "type": "room_destroyed",
+                            "data": {
                                 "roomKey": room_key,
+                                "reason": reason,
                                 "deletedFiles": filenames,
-                            });
-                            let _ = io_for_events
-                                .to(room_key.clone())
-                                .emit("roomDestroyed", &event);
-
-                            // Also send systemMessage
-                            let sys_msg = serde_json::json!({
-                                "type": "room_destroyed",
-                                "data": {
-                                    "roomKey": room_key,
-                                    "deletedFiles": filenames,
-                                }
-                            });
-                            let _ = io_for_events.to(room_key).emit("systemMessage", &sys_msg);
-
-                            tracing::info!(
-                                "Room destroyed - deleted {} files, notified clients",
-                                deleted_files.len()
-                            );
-                        }
+                            }
+                        });
+                        let _ = io_for_events.to(room_key).emit("systemMessage", &sys_msg);
+
+                        tracing::info!(
+                            "Room destroyed - deleted {} files, notified clients",
+                            deleted_files.len()
+                        );
+                    }
+                    Ok(RoomEvent::FileUploaded(file)) => {
+                        let _ = io_for_events
+                            .to(file.room_key.clone())
+                            .emit("fileUploaded", &file);
+                    }
+                    Ok(RoomEvent::MessageDeleted(message)) => {
+                        let _ = io_for_events
+                            .to(message.room_key.clone())
+                            .emit("messageDeleted", &message);
+                    }
+                    Ok(RoomEvent::BannerChanged(banner)) => {
+                        // null tells clients the banner was cleared
+                        let _ = io_for_events.emit("serverBanner", &banner);
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                        tracing::warn!("Room event listener lagged by {} events", n);
+                        // Some RoomDestroyed events were dropped: reconcile so their files don't leak
+                        tracing::warn!(
+                            "Room event listener lagged by {} events, reconciling room files",
+                            n
+                        );
+                        let active_rooms = room_service_for_events.room_keys();
+                        let deleted = file_manager_for_events.reconcile_room_files(&active_rooms);
+                        let orphans = file_manager_for_events.cleanup_orphaned_files().await;
+                        tracing::info!(
+                            "Reconciliation after lag: removed {} room files, {} orphaned files",
+                            deleted.len(),
+                            orphans
+                        );
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => {
                         tracing::info!("Room event channel closed, stopping listener");
@@ -196,47 +230,25 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Build CORS layer
-    let cors = if is_production {
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-            .allow_headers(Any)
-            .allow_credentials(false)
-    } else {
-        let allowed_origins: Vec<HeaderValue> = std::env::var("CLIENT_URL")
-            .unwrap_or_else(|_| "http://localhost:3000,http://localhost:3002".to_string())
-            .split(',')
-            .filter_map(|origin| origin.trim().parse::<HeaderValue>().ok())
-            .collect();
-
-        if allowed_origins.is_empty() {
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-                .allow_headers(Any)
-                .allow_credentials(false)
-        } else {
-            CorsLayer::new()
-                .allow_origin(allowed_origins)
-                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-                .allow_headers([
-                    header::CONTENT_TYPE,
-                    header::AUTHORIZATION,
-                    header::ACCEPT,
-                    header::ORIGIN,
-                    header::CACHE_CONTROL,
-                ])
-                .allow_credentials(true)
-        }
-    };
+    let cors_config = CorsConfig::from_env();
+    tracing::info!(
+        max_age_secs = cors_config.max_age_secs,
+        expose_headers = ?cors_config.expose_headers,
+        "CORS configured"
+    );
+    let cors = cors_config.layer(is_production);
 
     // Build rate limit middleware layers
-    let strict_rate_limit = RateLimitMiddleware::new(strict_limiter);
-    let public_download_rate_limit = RateLimitMiddleware::new(public_download_limiter);
+    let rate_limit_metrics = app_state.rate_limit_metrics.clone();
+    let strict_rate_limit = RateLimitMiddleware::new(strict_limiter)
+        .with_metrics(rate_limit_metrics.clone(), "/api/rooms");
+    let public_download_rate_limit = RateLimitMiddleware::new(public_download_limiter)
+        .with_metrics(rate_limit_metrics.clone(), "/public/file");
 
     // Clone services for background tasks
     let cleanup_room_service = room_service.clone();
     let cleanup_file_manager = file_manager.clone();
+    let snapshot_file_manager = file_manager.clone();
     let cleanup_share_service = share_service.clone();
 
     // Start background cleanup tasks
@@ -250,20 +262,30 @@ async fn main() -> anyhow::Result<()> {
         .await;
     });
 
+    // Flipped after the listener is bound (socket handlers are registered above)
+    let readiness_state = app_state.clone();
+
     // Build the API router (routes relative to base path)
     // Each route group uses its own rate limiting (configured in respective router() functions)
     let api_router = Router::new()
         // Health endpoints (no rate limit)
         .route("/health", get(health::health_check))
         .route("/api/health", get(health::health_check))
+        .route("/health/ready", get(health::readiness_check))
+        .route("/api/health/ready", get(health::readiness_check))
         .route("/api", get(api_info::api_info))
+        .route("/api/version", get(api_info::version))
+        // Aggregate stats (admin token required)
+        .route("/api/stats", get(stats::get_stats))
+        // Operator maintenance (admin token required)
+        .nest("/api/admin", admin::router())
         // Room routes - strict rate limit
         .nest("/api/rooms", rooms::router().layer(strict_rate_limit))
         // File routes - internal per-operation rate limiting
         // Override axum's default 2MB body limit for file uploads (actual limit enforced by RequestBodyLimitLayer)
         .nest(
             "/api/files",
-            files::router().layer(DefaultBodyLimit::disable()),
+            files::router(rate_limit_metrics).layer(DefaultBodyLimit::disable()),
         )
         // Share routes - internal per-operation rate limiting
         .nest("/api/share", share::router())
@@ -272,6 +294,13 @@ async fn main() -> anyhow::Result<()> {
             "/public/file",
             Router::new()
                 .route("/{share_id}", get(share::public_download))
+                .layer(public_download_rate_limit.clone()),
+        )
+        // Share short links redirect to /public/file (same limit as the downloads)
+        .nest(
+            "/s",
+            Router::new()
+                .route("/{code}", get(share::resolve_short_link))
                 .layer(public_download_rate_limit),
         )
         .fallback(api_not_found)
@@ -283,50 +312,19 @@ async fn main() -> anyhow::Result<()> {
     } else {
         Router::new().nest(&base_path, api_router)
     }
-        // Global request body size limit (100MB, matching MAX_FILE_SIZE)
-        .layer(RequestBodyLimitLayer::new(100 * 1024 * 1024))
-        .layer(CompressionLayer::new())
-        .layer(TraceLayer::new_for_http())
-        .layer(cors)
-        // Security headers (similar to helmet)
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("x-content-type-options"),
-            HeaderValue::from_static("nosniff"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("x-frame-options"),
-            HeaderValue::from_static("DENY"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("x-xss-protection"),
-            HeaderValue::from_static("1; mode=block"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("referrer-policy"),
-            HeaderValue::from_static("strict-origin-when-cross-origin"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("cross-origin-opener-policy"),
-            HeaderValue::from_static("same-origin"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("cross-origin-embedder-policy"),
-            HeaderValue::from_static("require-corp"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            HeaderName::from_static("content-security-policy"),
-            HeaderValue::from_static("default-src 'self'; script-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data: blob:; connect-src 'self' ws: wss:; font-src 'self'; object-src 'none'; frame-ancestors 'none'"),
-        ));
+    // Global request body size limit (100MB, matching MAX_FILE_SIZE)
+    .layer(RequestBodyLimitLayer::new(100 * 1024 * 1024))
+    .layer(CompressionLayer::new())
+    .layer(TraceLayer::new_for_http())
+    .layer(cors);
+
+    // Security headers (similar to helmet)
+    let app = security_headers.apply(app);
 
     // Add HSTS header when HTTPS is enforced (ALLOW_HTTP not set)
     let app = if !allow_http {
-        tracing::info!("HSTS enabled (ALLOW_HTTP not set)");
-        axum::Router::new()
-            .merge(app)
-            .layer(SetResponseHeaderLayer::overriding(
-                HeaderName::from_static("strict-transport-security"),
-                HeaderValue::from_static("max-age=31536000; includeSubDomains"),
-            ))
+        tracing::info!(?hsts, "HSTS enabled (ALLOW_HTTP not set)");
+        hsts.apply(axum::Router::new().merge(app))
     } else {
         tracing::info!("HSTS disabled (ALLOW_HTTP=true)");
         app
@@ -354,11 +352,17 @@ async fn main() -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     tracing::info!("Cloud Clipboard server listening on port {}", port);
     tracing::info!("WebSocket server ready for connections");
+    readiness_state.mark_ready();
 
     axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
+    // Final snapshot so metadata written since the last tick survives the restart
+    if let Err(e) = snapshot_file_manager.save_metadata_snapshot().await {
+        tracing::error!("Failed to write file metadata snapshot: {}", e);
+    }
+
     Ok(())
 }
 
@@ -370,11 +374,22 @@ async fn run_cleanup_tasks(
     config: CleanupConfig,
 ) {
     tracing::info!(
-        "Cleanup tasks started: room_interval={}s, file_interval={}s",
+        "Cleanup tasks started: room_interval={}s, file_interval={}s, jitter={}%",
         config.room_cleanup_interval_secs,
-        config.file_cleanup_interval_secs
+        config.file_cleanup_interval_secs,
+        config.jitter_percent
     );
 
+    let room_interval = Duration::from_secs(config.room_cleanup_interval_secs);
+    let file_interval = Duration::from_secs(config.file_cleanup_interval_secs);
+
+    // Spread the first sweep so instances started together don't delete in lockstep
+    let delay = jitter::initial_delay(room_interval, config.jitter_percent);
+    if !delay.is_zero() {
+        tracing::info!("Delaying initial cleanup by {:?}", delay);
+        tokio::time::sleep(delay).await;
+    }
+
     // Initial cleanup
     {
         tracing::info!("Running initial room cleanup...");
@@ -397,27 +412,32 @@ async fn run_cleanup_tasks(
         tracing::info!("Initial cleanup: removed {} expired shares", cleaned.len());
     }
 
-    // Room cleanup interval
-    let room_interval = Duration::from_secs(config.room_cleanup_interval_secs);
-    let mut room_interval = tokio::time::interval(room_interval);
-    room_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-
-    // File cleanup interval (same as share cleanup)
-    let file_interval = Duration::from_secs(config.file_cleanup_interval_secs);
-    let mut file_interval = tokio::time::interval(file_interval);
-    file_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    // Each tick is rescheduled from when the previous one ran (file cleanup also covers shares)
+    let next_tick =
+        |interval| tokio::time::Instant::now() + jitter::jittered(interval, config.jitter_percent);
+    let mut next_room = next_tick(room_interval);
+    let mut next_file = next_tick(file_interval);
+    let snapshot_interval = Duration::from_secs(config.metadata_snapshot_interval_secs);
+    let snapshots_enabled = file_manager.metadata_path().is_some();
+    let mut next_snapshot = tokio::time::Instant::now() + snapshot_interval;
 
     loop {
         tokio::select! {
-            _ = room_interval.tick() => {
+            _ = tokio::time::sleep_until(next_room) => {
+                next_room = next_tick(room_interval);
                 tracing::debug!("Running scheduled room cleanup...");
                 let destroyed = room_service.cleanup_inactive_rooms();
                 if !destroyed.is_empty() {
                     tracing::info!("Scheduled room cleanup: destroyed {} inactive rooms ({:?})",
                         destroyed.len(), destroyed);
                 }
+                let expired = room_service.cleanup_expired_messages();
+                if expired > 0 {
+                    tracing::info!("Scheduled room cleanup: removed {} expired messages", expired);
+                }
             }
-            _ = file_interval.tick() => {
+            _ = tokio::time::sleep_until(next_file) => {
+                next_file = next_tick(file_interval);
                 tracing::debug!("Running scheduled file and share cleanup...");
                 let cleaned_files = file_manager.cleanup_expired_files().await;
                 let cleaned_shares = share_service.cleanup_expired_shares();
@@ -426,18 +446,26 @@ async fn run_cleanup_tasks(
                         cleaned_files.len(), cleaned_shares.len());
                 }
             }
+            _ = tokio::time::sleep_until(next_snapshot), if snapshots_enabled => {
+                next_snapshot = tokio::time::Instant::now() + snapshot_interval;
+                match file_manager.save_metadata_snapshot().await {
+                    Ok(count) => tracing::debug!("File metadata snapshot written ({:?} files)", count),
+                    Err(e) => tracing::error!("Failed to write file metadata snapshot: {}", e),
+                }
+            }
         }
     }
 }
 
 /// Fallback handler for unmatched API routes
-async fn api_not_found() -> (StatusCode, Json<routes::ApiResponse<()>>) {
+async fn api_not_found() -> (StatusCode, routes::ApiJson<routes::ApiResponse<()>>) {
     (
         StatusCode::NOT_FOUND,
-        Json(routes::ApiResponse {
+        routes::ApiJson(routes::ApiResponse {
             success: false,
             message: Some("Not found".to_string()),
             data: None,
+            code: None,
         }),
     )
 }
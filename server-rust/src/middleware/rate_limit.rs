@@ -5,6 +5,9 @@ use axum::{
 use governor::{
     Quota, RateLimiter as GovRateLimiter, clock::DefaultClock, state::keyed::DefaultKeyedStateStore,
 };
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{future::Future, num::NonZeroU32, pin::Pin, sync::Arc};
 
 /// Key type for rate limiting
@@ -13,13 +16,19 @@ pub type RateLimiter = GovRateLimiter<String, DefaultKeyedStateStore<String>, De
 pub type KeyedRateLimiter = Arc<RateLimiter>;
 
 /// Rate limit configuration from environment
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct RateLimitConfig {
     pub window_secs: u64,
     pub general_max: u32,
     pub strict_max: u32,
     pub strict_window_secs: u64,
     pub public_download_max: u32,
+    pub upload_max: u32,
+    /// Uploads per window for one fingerprint / user ID (0 = unlimited)
+    pub user_upload_max: u32,
+    /// Uploaded bytes per window for one fingerprint / user ID (0 = unlimited)
+    pub user_upload_max_bytes: u64,
 }
 
 impl Default for RateLimitConfig {
@@ -30,6 +39,9 @@ impl Default for RateLimitConfig {
             strict_max: 50,
             strict_window_secs: 300, // 5 minutes
             public_download_max: 20,
+            upload_max: 5,
+            user_upload_max: 20,
+            user_upload_max_bytes: 500 * 1024 * 1024,
         }
     }
 }
@@ -69,6 +81,12 @@ impl RateLimitConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(20),
+            upload_max: parse_u32("UPLOAD_RATE_LIMIT", 5),
+            user_upload_max: parse_u32("USER_UPLOAD_RATE_LIMIT", 20),
+            user_upload_max_bytes: std::env::var("USER_UPLOAD_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500 * 1024 * 1024),
         }
     }
 }
@@ -106,6 +124,100 @@ pub fn public_download_rate_limiter(config: &RateLimitConfig) -> KeyedRateLimite
     create_rate_limiter(config, config.public_download_max)
 }
 
+/// Upload rate limiter: configured from UPLOAD_RATE_LIMIT (default 5, matching Node.js
+/// uploadRateLimit) per window, independent of the share and room limits
+pub fn upload_rate_limiter(config: &RateLimitConfig) -> KeyedRateLimiter {
+    create_rate_limiter(config, config.upload_max)
+}
+
+/// Per-uploader upload budget (requests and bytes per fixed window), keyed by device
+/// fingerprint or user ID. Complements the per-IP upload limiter, which users behind
+/// one NAT share and which a single user can spread across addresses.
+#[derive(Debug)]
+pub struct UploadQuota {
+    max_requests: u32, // 0 = unlimited
+    max_bytes: u64,    // 0 = unlimited
+    window: Duration,
+    usage: Mutex<HashMap<String, UploadUsage>>,
+}
+
+#[derive(Debug)]
+struct UploadUsage {
+    window_start: Instant,
+    requests: u32,
+    bytes: u64,
+}
+
+/// Tracked uploaders before windows that have ended are swept
+const UPLOAD_QUOTA_SWEEP_THRESHOLD: usize = 10_000;
+
+impl UploadQuota {
+    pub fn new(max_requests: u32, max_bytes: u64, window: Duration) -> Self {
+        Self {
+            max_requests,
+            max_bytes,
+            window,
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// USER_UPLOAD_RATE_LIMIT / USER_UPLOAD_MAX_BYTES over the general rate limit window
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::new(
+            config.user_upload_max,
+            config.user_upload_max_bytes,
+            Duration::from_secs(config.window_secs.max(1)),
+        )
+    }
+
+    /// Count an upload request; false once `uploader` has used up this window's requests
+    pub fn try_request(&self, uploader: &str) -> bool {
+        self.with_usage(uploader, |usage, quota| {
+            if quota.max_requests > 0 && usage.requests >= quota.max_requests {
+                return false;
+            }
+            usage.requests += 1;
+            true
+        })
+    }
+
+    /// Count uploaded bytes; false (nothing counted) if they would exceed the byte budget
+    pub fn try_bytes(&self, uploader: &str, bytes: u64) -> bool {
+        self.with_usage(uploader, |usage, quota| {
+            if quota.max_bytes > 0 && usage.bytes + bytes > quota.max_bytes {
+                return false;
+            }
+            usage.bytes += bytes;
+            true
+        })
+    }
+
+    fn with_usage(&self, uploader: &str, f: impl FnOnce(&mut UploadUsage, &Self) -> bool) -> bool {
+        let Ok(mut usage) = self.usage.lock() else {
+            return true;
+        };
+        let now = Instant::now();
+        if usage.len() >= UPLOAD_QUOTA_SWEEP_THRESHOLD {
+            usage.retain(|_, u| now.duration_since(u.window_start) < self.window);
+        }
+        let entry = usage
+            .entry(uploader.to_string())
+            .or_insert_with(|| UploadUsage {
+                window_start: now,
+                requests: 0,
+                bytes: 0,
+            });
+        if now.duration_since(entry.window_start) >= self.window {
+            *entry = UploadUsage {
+                window_start: now,
+                requests: 0,
+                bytes: 0,
+            };
+        }
+        f(entry, self)
+    }
+}
+
 /// Extract client IP from request, supporting X-Forwarded-For header
 pub fn extract_client_ip(headers: &HeaderMap) -> String {
     // Check X-Forwarded-For header first
@@ -174,6 +286,7 @@ pub fn rate_limit_exceeded_response(config: &RateLimitConfig, retry_after: u64)
     let body = serde_json::json!({
         "success": false,
         "error": "RATE_LIMIT_EXCEEDED",
+        "code": crate::routes::error_code::RATE_LIMITED,
         "message": "Too many requests. Please try again later.",
         "retryAfter": retry_after
     });
@@ -183,11 +296,48 @@ pub fn rate_limit_exceeded_response(config: &RateLimitConfig, retry_after: u64)
     (StatusCode::TOO_MANY_REQUESTS, headers, body.to_string()).into_response()
 }
 
+/// Rejection counters labeled by endpoint (HTTP route group) or socket event,
+/// shared by every limiter that reports into it and surfaced in /api/stats
+#[derive(Debug, Default)]
+pub struct RateLimitMetrics {
+    rejections: Mutex<HashMap<String, u64>>,
+}
+
+impl RateLimitMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one rejected request for `endpoint`
+    pub fn record_rejection(&self, endpoint: &str) {
+        if let Ok(mut rejections) = self.rejections.lock() {
+            *rejections.entry(endpoint.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Rejections recorded so far for `endpoint`
+    pub fn rejections(&self, endpoint: &str) -> u64 {
+        self.rejections
+            .lock()
+            .map(|r| r.get(endpoint).copied().unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    /// All counters, sorted by label
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.rejections
+            .lock()
+            .map(|r| r.iter().map(|(k, v)| (k.clone(), *v)).collect())
+            .unwrap_or_default()
+    }
+}
+
 /// Rate limiter middleware factory
 #[derive(Clone)]
 pub struct RateLimitMiddleware {
     limiter: KeyedRateLimiter,
     config: RateLimitConfig,
+    metrics: Option<(Arc<RateLimitMetrics>, Arc<str>)>,
 }
 
 impl RateLimitMiddleware {
@@ -196,8 +346,15 @@ impl RateLimitMiddleware {
         Self {
             limiter,
             config: RateLimitConfig::default(),
+            metrics: None,
         }
     }
+
+    /// Report rejections to `metrics` under the `endpoint` label
+    pub fn with_metrics(mut self, metrics: Arc<RateLimitMetrics>, endpoint: &str) -> Self {
+        self.metrics = Some((metrics, Arc::from(endpoint)));
+        self
+    }
 }
 
 impl<S> tower::Layer<S> for RateLimitMiddleware {
@@ -208,6 +365,7 @@ impl<S> tower::Layer<S> for RateLimitMiddleware {
             inner,
             limiter: self.limiter.clone(),
             config: self.config.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -218,6 +376,7 @@ pub struct RateLimitService<S> {
     inner: S,
     limiter: KeyedRateLimiter,
     config: RateLimitConfig,
+    metrics: Option<(Arc<RateLimitMetrics>, Arc<str>)>,
 }
 
 impl<S, B> tower::Service<Request<B>> for RateLimitService<S>
@@ -241,6 +400,7 @@ where
         let mut inner = self.inner.clone();
         let limiter = self.limiter.clone();
         let config = self.config.clone();
+        let metrics = self.metrics.clone();
 
         Box::pin(async move {
             let client_ip = extract_client_ip(req.headers());
@@ -262,6 +422,9 @@ where
                 }
                 Err(_negative) => {
                     let wait_time = config.window_secs;
+                    if let Some((metrics, endpoint)) = &metrics {
+                        metrics.record_rejection(endpoint);
+                    }
 
                     Ok(rate_limit_exceeded_response(&config, wait_time))
                 }
@@ -0,0 +1,304 @@
+use axum::Router;
+use axum::http::{HeaderName, HeaderValue};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+/// Default CSP (strict: same-origin assets, inline styles only)
+pub const DEFAULT_CSP: &str = "default-src 'self'; script-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data: blob:; connect-src 'self' ws: wss:; font-src 'self'; object-src 'none'; frame-ancestors 'none'";
+pub const DEFAULT_FRAME_OPTIONS: &str = "DENY";
+pub const DEFAULT_REFERRER_POLICY: &str = "strict-origin-when-cross-origin";
+/// Default HSTS max-age (one year)
+pub const DEFAULT_HSTS_MAX_AGE: u64 = 31_536_000;
+/// Browsers' preload lists require at least a one-year max-age
+pub const HSTS_PRELOAD_MIN_MAX_AGE: u64 = 31_536_000;
+
+/// Security response headers (similar to helmet).
+///
+/// `CSP_HEADER`, `X_FRAME_OPTIONS` and `REFERRER_POLICY` override the strict defaults;
+/// the remaining headers are fixed.
+#[derive(Clone, Debug)]
+pub struct SecurityHeadersConfig {
+    pub content_security_policy: HeaderValue,
+    pub frame_options: HeaderValue,
+    pub referrer_policy: HeaderValue,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            content_security_policy: HeaderValue::from_static(DEFAULT_CSP),
+            frame_options: HeaderValue::from_static(DEFAULT_FRAME_OPTIONS),
+            referrer_policy: HeaderValue::from_static(DEFAULT_REFERRER_POLICY),
+        }
+    }
+}
+
+impl SecurityHeadersConfig {
+    /// Load from environment variables, failing on values that are not legal header values
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_values(
+            std::env::var("CSP_HEADER").ok().as_deref(),
+            std::env::var("X_FRAME_OPTIONS").ok().as_deref(),
+            std::env::var("REFERRER_POLICY").ok().as_deref(),
+        )
+    }
+
+    /// Build from optional overrides (`None` or blank keeps the default)
+    pub fn from_values(
+        csp: Option<&str>,
+        frame_options: Option<&str>,
+        referrer_policy: Option<&str>,
+    ) -> Result<Self, String> {
+        let defaults = Self::default();
+        Ok(Self {
+            content_security_policy: parse_override(
+                "CSP_HEADER",
+                csp,
+                defaults.content_security_policy,
+            )?,
+            frame_options: parse_override(
+                "X_FRAME_OPTIONS",
+                frame_options,
+                defaults.frame_options,
+            )?,
+            referrer_policy: parse_override(
+                "REFERRER_POLICY",
+                referrer_policy,
+                defaults.referrer_policy,
+            )?,
+        })
+    }
+
+    /// Add the security header layers to a router
+    pub fn apply<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+        let fixed = [
+            ("x-content-type-options", "nosniff"),
+            ("x-xss-protection", "1; mode=block"),
+            ("cross-origin-opener-policy", "same-origin"),
+            ("cross-origin-embedder-policy", "require-corp"),
+        ];
+        let configured = [
+            ("x-frame-options", &self.frame_options),
+            ("referrer-policy", &self.referrer_policy),
+            ("content-security-policy", &self.content_security_policy),
+        ];
+
+        let router = fixed.into_iter().fold(router, |router, (name, value)| {
+            router.layer(SetResponseHeaderLayer::overriding(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            ))
+        });
+        configured
+            .into_iter()
+            .fold(router, |router, (name, value)| {
+                router.layer(SetResponseHeaderLayer::overriding(
+                    HeaderName::from_static(name),
+                    value.clone(),
+                ))
+            })
+    }
+}
+
+/// Strict-Transport-Security directives, sent only when HTTPS is enforced (ALLOW_HTTP unset).
+///
+/// `HSTS_MAX_AGE`, `HSTS_INCLUDE_SUBDOMAINS` and `HSTS_PRELOAD` override the defaults
+/// (`max-age=31536000; includeSubDomains`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HstsConfig {
+    pub max_age: u64,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+impl Default for HstsConfig {
+    fn default() -> Self {
+        Self {
+            max_age: DEFAULT_HSTS_MAX_AGE,
+            include_subdomains: true,
+            preload: false,
+        }
+    }
+}
+
+impl HstsConfig {
+    /// Load from environment variables, failing on malformed values
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_values(
+            std::env::var("HSTS_MAX_AGE").ok().as_deref(),
+            std::env::var("HSTS_INCLUDE_SUBDOMAINS").ok().as_deref(),
+            std::env::var("HSTS_PRELOAD").ok().as_deref(),
+        )
+    }
+
+    /// Build from optional overrides (`None` or blank keeps the default).
+    /// Preload is only accepted with the settings browsers require for the preload list.
+    pub fn from_values(
+        max_age: Option<&str>,
+        include_subdomains: Option<&str>,
+        preload: Option<&str>,
+    ) -> Result<Self, String> {
+        let defaults = Self::default();
+        let max_age = match max_age.map(str::trim).filter(|v| !v.is_empty()) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| format!("Invalid HSTS_MAX_AGE value: {:?}", v))?,
+            None => defaults.max_age,
+        };
+        let config = Self {
+            max_age,
+            include_subdomains: parse_bool(
+                "HSTS_INCLUDE_SUBDOMAINS",
+                include_subdomains,
+                defaults.include_subdomains,
+            )?,
+            preload: parse_bool("HSTS_PRELOAD", preload, defaults.preload)?,
+        };
+
+        if config.preload
+            && (config.max_age < HSTS_PRELOAD_MIN_MAX_AGE || !config.include_subdomains)
+        {
+            return Err(format!(
+                "HSTS_PRELOAD requires HSTS_MAX_AGE >= {} and HSTS_INCLUDE_SUBDOMAINS=true",
+                HSTS_PRELOAD_MIN_MAX_AGE
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Assembled Strict-Transport-Security header value
+    pub fn header_value(&self) -> HeaderValue {
+        let mut value = format!("max-age={}", self.max_age);
+        if self.include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.preload {
+            value.push_str("; preload");
+        }
+        HeaderValue::from_str(&value).expect("HSTS directives are valid header characters")
+    }
+
+    /// Add the HSTS header layer to a router
+    pub fn apply<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+        router.layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static("strict-transport-security"),
+            self.header_value(),
+        ))
+    }
+}
+
+fn parse_bool(var: &str, value: Option<&str>, default: bool) -> Result<bool, String> {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+        None | Some("") => Ok(default),
+        Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        Some(v) => Err(format!(
+            "Invalid {} value: {:?} (expected true or false)",
+            var, v
+        )),
+    }
+}
+
+fn parse_override(
+    var: &str,
+    value: Option<&str>,
+    default: HeaderValue,
+) -> Result<HeaderValue, String> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => HeaderValue::from_str(v).map_err(|_| format!("Invalid {} value: {:?}", var, v)),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_configured_csp_is_applied() {
+        let config = SecurityHeadersConfig::from_values(
+            Some("default-src 'self' https://cdn.example.com"),
+            None,
+            Some("no-referrer"),
+        )
+        .unwrap();
+        let app = config.apply(Router::new().route("/", get(|| async { "ok" })));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers["content-security-policy"],
+            "default-src 'self' https://cdn.example.com"
+        );
+        assert_eq!(headers["referrer-policy"], "no-referrer");
+        assert_eq!(headers["x-frame-options"], DEFAULT_FRAME_OPTIONS);
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+    }
+
+    #[test]
+    fn test_defaults_when_unset() {
+        let config = SecurityHeadersConfig::from_values(None, Some("  "), None).unwrap();
+        assert_eq!(config.content_security_policy, DEFAULT_CSP);
+        assert_eq!(config.frame_options, DEFAULT_FRAME_OPTIONS);
+    }
+
+    #[test]
+    fn test_invalid_header_value_is_rejected() {
+        let err = SecurityHeadersConfig::from_values(Some("default-src\n'self'"), None, None)
+            .unwrap_err();
+        assert!(err.contains("CSP_HEADER"));
+
+        let err = SecurityHeadersConfig::from_values(None, Some("DENY\u{7f}"), None).unwrap_err();
+        assert!(err.contains("X_FRAME_OPTIONS"));
+    }
+
+    #[test]
+    fn test_hsts_header_for_configurations() {
+        let default = HstsConfig::from_values(None, None, Some(" ")).unwrap();
+        assert_eq!(
+            default.header_value(),
+            "max-age=31536000; includeSubDomains"
+        );
+
+        let rollout = HstsConfig::from_values(Some("300"), Some("false"), None).unwrap();
+        assert_eq!(rollout.header_value(), "max-age=300");
+
+        let preload = HstsConfig::from_values(Some("63072000"), None, Some("TRUE")).unwrap();
+        assert_eq!(
+            preload.header_value(),
+            "max-age=63072000; includeSubDomains; preload"
+        );
+    }
+
+    #[test]
+    fn test_hsts_invalid_values_are_rejected() {
+        let err = HstsConfig::from_values(Some("1y"), None, None).unwrap_err();
+        assert!(err.contains("HSTS_MAX_AGE"));
+
+        let err = HstsConfig::from_values(None, Some("yes"), None).unwrap_err();
+        assert!(err.contains("HSTS_INCLUDE_SUBDOMAINS"));
+
+        // Preload-ineligible combinations
+        assert!(HstsConfig::from_values(Some("300"), None, Some("true")).is_err());
+        assert!(HstsConfig::from_values(None, Some("false"), Some("true")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hsts_header_is_applied() {
+        let config = HstsConfig::from_values(None, None, Some("true")).unwrap();
+        let app = config.apply(Router::new().route("/", get(|| async { "ok" })));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["strict-transport-security"],
+            "max-age=31536000; includeSubDomains; preload"
+        );
+    }
+}
@@ -10,6 +10,34 @@ pub enum MessageType {
     System,
 }
 
+/// Rendering hint for text message content
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Plain,
+    Url,
+    Code,
+    Rich,
+}
+
+impl ContentFormat {
+    /// Parse a client-supplied format name (`plain|url|code|rich`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "plain" => Some(Self::Plain),
+            "url" => Some(Self::Url),
+            "code" => Some(Self::Code),
+            "rich" => Some(Self::Rich),
+            _ => None,
+        }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        *self == Self::Plain
+    }
+}
+
 /// Sender info embedded in messages (matches frontend UserSchema)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,7 +47,8 @@ pub struct MessageSender {
     pub is_online: bool,
     pub last_seen: DateTime<Utc>,
     pub device_type: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Sender's device identity; never sent to clients
+    #[serde(skip_serializing)]
     pub fingerprint: Option<String>,
 }
 
@@ -78,9 +107,30 @@ pub struct Message {
     pub download_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_id: Option<String>,
+    /// Per-room sequence number assigned when the message is stored (0 = not yet stored)
+    #[serde(default)]
+    pub seq: u64,
+    /// Content is client-side encrypted ciphertext, relayed without sanitizing or inspection
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// Owner announcement kept pinned in history
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// How clients should render text content (omitted for plain)
+    #[serde(default, skip_serializing_if = "ContentFormat::is_plain")]
+    pub content_format: ContentFormat,
+    /// Self-destruct after this many seconds (removed and broadcast as `messageDeleted`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
 }
 
 impl Message {
+    /// When a self-destructing message is due for removal
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.ttl_secs
+            .map(|secs| self.timestamp + chrono::Duration::seconds(secs as i64))
+    }
+
     pub fn new_text(id: String, room_key: String, sender: MessageSender, content: String) -> Self {
         Self {
             id,
@@ -92,6 +142,11 @@ impl Message {
             file_info: None,
             download_url: None,
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: ContentFormat::Plain,
+            ttl_secs: None,
         }
     }
 
@@ -119,6 +174,11 @@ impl Message {
             }),
             download_url: Some(download_url),
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: ContentFormat::Plain,
+            ttl_secs: None,
         }
     }
 
@@ -133,6 +193,11 @@ impl Message {
             file_info: None,
             download_url: None,
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: ContentFormat::Plain,
+            ttl_secs: None,
         }
     }
 }
@@ -4,6 +4,6 @@ pub mod share;
 pub mod user;
 
 pub use message::Message;
-pub use room::Room;
+pub use room::{AuditAction, AuditEntry, ROOM_RATE_LIMIT_EVENTS, Room, RoomConfig};
 pub use share::{ShareAccessLog, ShareInfo, ShareInfoParams};
-pub use user::User;
+pub use user::{ClientInfo, User};
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+use subtle::ConstantTimeEq;
 
 use super::{Message, User};
 
@@ -8,6 +10,8 @@ use super::{Message, User};
 #[derive(Debug, Clone)]
 pub struct Room {
     pub room_key: String,
+    /// Key as first typed when room key normalization changed it
+    pub display_key: Option<String>,
     pub password_hash: Option<String>,
     pub password: Option<String>,
     pub users: HashMap<String, User>,
@@ -15,10 +19,95 @@ pub struct Room {
     pub created_at: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub is_pinned: bool,
+    /// Provisioned by an admin; like pinned rooms, never destroyed for being empty or idle
+    pub provisioned: bool,
     pub created_by: Option<String>, // fingerprint hash of room creator
-    max_messages: usize,
+    /// Client IP of the creator, counted against the creation cap when no fingerprint was sent
+    pub created_by_ip: Option<String>,
+    /// Secret handed to the creator when the room is created; authorizes owner-only REST calls
+    pub owner_token: Option<String>,
+    pub config: RoomConfig,
+    pub audit_log: VecDeque<AuditEntry>,
     message_count: u64,
     message_dropped_count: u64,
+    /// Room-wide send budget, filled lazily while `config.message_rate_limit` is set
+    message_budget: Option<MessageBudget>,
+}
+
+/// Token bucket shared by everyone sending to the room
+#[derive(Debug, Clone)]
+struct MessageBudget {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Socket events whose per-socket rate limit a room owner may override
+pub const ROOM_RATE_LIMIT_EVENTS: &[&str] = &["sendMessage", "searchMessages"];
+
+/// Per-room configuration (missing fields take their defaults when deserialized)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoomConfig {
+    /// Messages kept in history before the oldest are dropped
+    pub max_messages: usize,
+    /// Owner overrides of per-socket rate limits: event -> max requests per window
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rate_limits: HashMap<String, u32>,
+    /// When false, messages are relayed but never kept in history
+    #[serde(default = "default_persist_messages")]
+    pub persist_messages: bool,
+    /// When true, only existing members (and the owner) may join
+    pub locked: bool,
+    /// Messages per minute shared by the whole room (None: no room-wide limit)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_rate_limit: Option<u32>,
+}
+
+fn default_persist_messages() -> bool {
+    true
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            max_messages: 1000,
+            rate_limits: HashMap::new(),
+            persist_messages: true,
+            locked: false,
+            message_rate_limit: None,
+        }
+    }
+}
+
+/// Pinned announcements kept per room; pinning another unpins the oldest, which then
+/// ages out of history like any other message
+pub const MAX_PINNED_MESSAGES: usize = 10;
+
+/// Maximum audit entries kept per room (oldest are evicted first)
+pub const AUDIT_LOG_CAPACITY: usize = 200;
+
+/// Membership event recorded in a room's audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Join,
+    Reconnect,
+    Leave,
+    Offline,
+}
+
+/// Audit log entry for security review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub user_id: String,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    pub timestamp: DateTime<Utc>,
 }
 
 /// Room info for API responses
@@ -26,6 +115,8 @@ pub struct Room {
 #[serde(rename_all = "camelCase")]
 pub struct RoomInfo {
     pub room_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_key: Option<String>,
     pub user_count: usize,
     pub has_password: bool,
     pub created_at: DateTime<Utc>,
@@ -38,6 +129,7 @@ impl Room {
         let now = Utc::now();
         Self {
             room_key,
+            display_key: None,
             password_hash,
             password,
             users: HashMap::new(),
@@ -45,10 +137,15 @@ impl Room {
             created_at: now,
             last_activity: now,
             is_pinned: false,
+            provisioned: false,
             created_by: None,
-            max_messages: 1000,
+            created_by_ip: None,
+            owner_token: None,
+            config: RoomConfig::default(),
+            audit_log: VecDeque::new(),
             message_count: 0,
             message_dropped_count: 0,
+            message_budget: None,
         }
     }
 
@@ -63,6 +160,10 @@ impl Room {
         user
     }
 
+    pub fn get_user(&self, user_id: &str) -> Option<&User> {
+        self.users.get(user_id)
+    }
+
     pub fn get_user_mut(&mut self, user_id: &str) -> Option<&mut User> {
         self.users.get_mut(user_id)
     }
@@ -79,24 +180,100 @@ impl Room {
         self.users.values().filter(|u| u.is_online).count()
     }
 
-    pub fn add_message(&mut self, message: Message) {
-        self.messages.push_back(message);
+    /// Store a message, assigning the next per-room sequence number. Returns the assigned seq.
+    /// Rooms with `persist_messages` off only assign the seq; the message is relayed, not kept.
+    pub fn add_message(&mut self, mut message: Message) -> u64 {
         self.message_count += 1;
+        // Sequence numbers are monotonic for the room's lifetime and restart when it is recreated
+        message.seq = self.message_count;
+        let seq = message.seq;
+        if !self.config.persist_messages {
+            self.update_activity();
+            return seq;
+        }
+        if message.pinned {
+            let max_pinned = MAX_PINNED_MESSAGES.min(self.config.max_messages).max(1);
+            let mut excess =
+                (self.messages.iter().filter(|m| m.pinned).count() + 1).saturating_sub(max_pinned);
+            for m in self.messages.iter_mut().filter(|m| m.pinned) {
+                if excess == 0 {
+                    break;
+                }
+                m.pinned = false;
+                excess -= 1;
+            }
+        }
+        self.messages.push_back(message);
 
-        // Drop oldest 20% when exceeding max to avoid frequent removals
-        if self.messages.len() > self.max_messages {
-            let remove_count = self.max_messages / 5;
-            self.messages.drain(..remove_count);
-            self.message_dropped_count += remove_count as u64;
+        // Drop oldest 20% when exceeding max to avoid frequent removals.
+        // Pinned announcements are never trimmed.
+        if self.messages.len() > self.config.max_messages {
+            let mut remove_count = (self.config.max_messages / 5).max(1);
+            self.messages.retain(|m| {
+                if remove_count == 0 || m.pinned {
+                    return true;
+                }
+                remove_count -= 1;
+                self.message_dropped_count += 1;
+                false
+            });
         }
 
         self.update_activity();
+        seq
+    }
+
+    /// Take one message from the room-wide budget (`message_rate_limit` per minute, with
+    /// bursts up to the same amount). Returns false when the budget is exhausted.
+    pub fn try_consume_message_budget(&mut self, now: Instant) -> bool {
+        let Some(limit) = self.config.message_rate_limit.filter(|&l| l > 0) else {
+            self.message_budget = None;
+            return true;
+        };
+        let capacity = limit as f64;
+        let budget = self.message_budget.get_or_insert(MessageBudget {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.saturating_duration_since(budget.refilled_at);
+        budget.tokens = (budget.tokens + elapsed.as_secs_f64() * capacity / 60.0).min(capacity);
+        budget.refilled_at = now;
+        if budget.tokens < 1.0 {
+            return false;
+        }
+        budget.tokens -= 1.0;
+        true
+    }
+
+    /// Remove self-destructing messages whose TTL has passed. Returns their IDs.
+    pub fn remove_expired_messages(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.messages.retain(|m| match m.expires_at() {
+            Some(expires_at) if expires_at <= now => {
+                removed.push(m.id.clone());
+                false
+            }
+            _ => true,
+        });
+        removed
+    }
+
+    /// Remove one message by ID. Returns whether it was stored.
+    pub fn remove_message(&mut self, message_id: &str) -> bool {
+        let before = self.messages.len();
+        self.messages.retain(|m| m.id != message_id);
+        self.messages.len() != before
     }
 
     pub fn get_messages(&self) -> &VecDeque<Message> {
         &self.messages
     }
 
+    /// Discard stored history (sequence numbers keep counting up)
+    pub fn clear_messages(&mut self) {
+        self.messages.clear();
+    }
+
     pub fn has_password(&self) -> bool {
         self.password_hash.is_some()
     }
@@ -119,6 +296,7 @@ impl Room {
     pub fn to_info(&self) -> RoomInfo {
         RoomInfo {
             room_key: self.room_key.clone(),
+            display_key: self.display_key.clone(),
             user_count: self.user_count(),
             has_password: self.has_password(),
             created_at: self.created_at,
@@ -132,6 +310,11 @@ impl Room {
         self.update_activity();
     }
 
+    /// Whether the room outlives its members (pinned or admin-provisioned)
+    pub fn is_persistent(&self) -> bool {
+        self.is_pinned || self.provisioned
+    }
+
     pub fn unpin(&mut self) {
         self.is_pinned = false;
         self.update_activity();
@@ -140,9 +323,44 @@ impl Room {
     pub fn set_creator(&mut self, fingerprint: &str) {
         if self.created_by.is_none() {
             self.created_by = Some(fingerprint.to_string());
+            self.owner_token = Some(crate::utils::generate_session_token());
         }
     }
 
+    /// Append a membership event to the bounded audit log
+    pub fn record_audit(&mut self, action: AuditAction, user: &User, ip: Option<&str>) {
+        if self.audit_log.len() >= AUDIT_LOG_CAPACITY {
+            self.audit_log.pop_front();
+        }
+        self.audit_log.push_back(AuditEntry {
+            action,
+            user_id: user.id.clone(),
+            username: user.username.clone(),
+            fingerprint: user.fingerprint.clone(),
+            ip: ip.map(|s| s.to_string()),
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Whether the fingerprint belongs to the room creator
+    pub fn is_owner(&self, fingerprint: &str) -> bool {
+        !fingerprint.trim().is_empty() && self.created_by.as_deref() == Some(fingerprint)
+    }
+
+    /// Whether the token is this room's owner token (compared in constant time)
+    pub fn is_owner_token(&self, token: &str) -> bool {
+        self.owner_token
+            .as_deref()
+            .is_some_and(|owner| bool::from(owner.as_bytes().ct_eq(token.as_bytes())))
+    }
+
+    /// User ID of the room creator (derived from the creator's fingerprint)
+    pub fn owner_id(&self) -> Option<String> {
+        self.created_by
+            .as_deref()
+            .map(crate::utils::generate_user_id_from_fingerprint)
+    }
+
     fn update_activity(&mut self) {
         self.last_activity = Utc::now();
     }
@@ -210,6 +428,56 @@ mod tests {
     use super::*;
     use crate::models::User;
 
+    #[test]
+    fn test_message_budget_refills_over_time() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        room.config.message_rate_limit = Some(6);
+        let start = Instant::now();
+
+        for _ in 0..6 {
+            assert!(room.try_consume_message_budget(start));
+        }
+        assert!(!room.try_consume_message_budget(start));
+
+        // 6/min refills one message every 10 seconds
+        let later = start + std::time::Duration::from_secs(10);
+        assert!(room.try_consume_message_budget(later));
+        assert!(!room.try_consume_message_budget(later));
+
+        // Idle time never banks more than a full budget
+        let much_later = later + std::time::Duration::from_secs(3600);
+        for _ in 0..6 {
+            assert!(room.try_consume_message_budget(much_later));
+        }
+        assert!(!room.try_consume_message_budget(much_later));
+    }
+
+    #[test]
+    fn test_message_budget_unlimited_by_default() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        let now = Instant::now();
+        assert!((0..10_000).all(|_| room.try_consume_message_budget(now)));
+    }
+
+    #[test]
+    fn test_audit_log_evicts_oldest_past_capacity() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        for i in 0..AUDIT_LOG_CAPACITY + 5 {
+            let user = User::new(
+                format!("user{}", i),
+                format!("User{}", i),
+                "room1".to_string(),
+            );
+            room.record_audit(AuditAction::Join, &user, None);
+        }
+        assert_eq!(room.audit_log.len(), AUDIT_LOG_CAPACITY);
+        assert_eq!(room.audit_log.front().unwrap().user_id, "user5");
+        assert_eq!(
+            room.audit_log.back().unwrap().user_id,
+            format!("user{}", AUDIT_LOG_CAPACITY + 4)
+        );
+    }
+
     fn make_room_with_user(username: &str, fingerprint: Option<&str>) -> Room {
         let mut room = Room::new("test_room1".to_string(), None, None);
         let mut user = User::new(
@@ -283,4 +551,66 @@ mod tests {
         assert_eq!(suffix.len(), 5);
         assert!(suffix.chars().all(|c| c.is_ascii_alphanumeric()));
     }
+
+    #[test]
+    fn test_trimming_keeps_pinned_messages() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        room.config.max_messages = 5;
+        let mut pinned =
+            Message::new_system("pin".to_string(), "room1".to_string(), "notice".to_string());
+        pinned.pinned = true;
+        room.add_message(pinned);
+        for i in 0..20 {
+            room.add_message(Message::new_system(
+                format!("m{}", i),
+                "room1".to_string(),
+                "hi".to_string(),
+            ));
+        }
+        assert_eq!(room.messages.front().unwrap().id, "pin");
+        assert!(room.messages.len() <= 6);
+        assert_eq!(room.messages.back().unwrap().id, "m19");
+    }
+
+    #[test]
+    fn test_pinning_beyond_limit_unpins_oldest() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        for i in 0..MAX_PINNED_MESSAGES + 2 {
+            let mut pinned = Message::new_system(
+                format!("pin{}", i),
+                "room1".to_string(),
+                "notice".to_string(),
+            );
+            pinned.pinned = true;
+            room.add_message(pinned);
+        }
+        let pinned: Vec<_> = room.messages.iter().filter(|m| m.pinned).collect();
+        assert_eq!(pinned.len(), MAX_PINNED_MESSAGES);
+        assert_eq!(pinned[0].id, "pin2");
+        assert!(!room.messages[0].pinned);
+    }
+
+    #[test]
+    fn test_owner_token_issued_once_with_creator() {
+        let mut room = Room::new("room1".to_string(), None, None);
+        assert!(room.owner_token.is_none());
+
+        room.set_creator("fp_owner");
+        let token = room.owner_token.clone().unwrap();
+        assert!(room.is_owner_token(&token));
+        assert!(!room.is_owner_token("fp_owner"));
+        assert!(!room.is_owner_token(""));
+        let mut near_miss = token.clone();
+        let last = if near_miss.pop() == Some('0') {
+            '1'
+        } else {
+            '0'
+        };
+        near_miss.push(last);
+        assert!(!room.is_owner_token(&near_miss));
+
+        // A later set_creator keeps the original owner and token
+        room.set_creator("fp_other");
+        assert_eq!(room.owner_token.as_deref(), Some(token.as_str()));
+    }
 }
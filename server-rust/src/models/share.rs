@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+/// Download name of a multi-file share (unless `download_as` overrides it)
+pub const BUNDLE_FILENAME: &str = "bundle.zip";
 
 /// Share access log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +15,9 @@ pub struct ShareAccessLog {
     pub success: bool,
     pub bytes_transferred: Option<u64>,
     pub error_message: Option<String>,
+    /// Set for owner actions logged alongside downloads (e.g. "passwordRotated")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
 }
 
 /// File share information
@@ -30,9 +36,16 @@ pub struct ShareInfo {
     pub is_active: bool,
     pub access_count: u64,
     pub has_password: bool,
-    pub access_logs: Vec<ShareAccessLog>,
+    /// Oldest first; capped by `MAX_ACCESS_LOGS_PER_SHARE` in `ShareService`
+    pub access_logs: VecDeque<ShareAccessLog>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub metadata: Option<HashMap<String, serde_json::Value>>,
+    /// Filename presented on download instead of the stored name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_as: Option<String>,
+    /// Storage filenames streamed as a zip on download (empty for single-file shares)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundle_files: Vec<String>,
 }
 
 /// Share info for API responses (without sensitive data)
@@ -52,6 +65,8 @@ pub struct ShareInfoResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_accessed_at: Option<DateTime<Utc>>,
     pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_as: Option<String>,
 }
 
 /// Parameters for creating a new ShareInfo
@@ -65,6 +80,8 @@ pub struct ShareInfoParams {
     pub expires_in_days: i64,
     pub password_hash: Option<String>,
     pub metadata: Option<HashMap<String, serde_json::Value>>,
+    pub download_as: Option<String>,
+    pub bundle_files: Vec<String>,
 }
 
 impl ShareInfo {
@@ -84,8 +101,10 @@ impl ShareInfo {
             is_active: true,
             access_count: 0,
             has_password,
-            access_logs: Vec::new(),
+            access_logs: VecDeque::new(),
             metadata: params.metadata,
+            download_as: params.download_as,
+            bundle_files: params.bundle_files,
         }
     }
 
@@ -93,6 +112,10 @@ impl ShareInfo {
         Utc::now() > self.expires_at
     }
 
+    pub fn is_bundle(&self) -> bool {
+        !self.bundle_files.is_empty()
+    }
+
     pub fn has_password(&self) -> bool {
         self.password_hash.is_some()
     }
@@ -104,6 +127,23 @@ impl ShareInfo {
         }
     }
 
+    /// Filename for Content-Disposition: `download_as`, else the original
+    /// upload name from metadata, else the stored `file_name`
+    pub fn download_filename(&self) -> &str {
+        if self.is_bundle() {
+            return self.download_as.as_deref().unwrap_or(BUNDLE_FILENAME);
+        }
+        self.download_as
+            .as_deref()
+            .or_else(|| {
+                self.metadata
+                    .as_ref()
+                    .and_then(|m| m.get("originalFilename"))
+                    .and_then(|v| v.as_str())
+            })
+            .unwrap_or(&self.file_name)
+    }
+
     pub fn record_access(
         &mut self,
         ip_address: String,
@@ -112,19 +152,33 @@ impl ShareInfo {
         error: Option<String>,
         user_agent: Option<String>,
     ) {
-        self.access_logs.push(ShareAccessLog {
+        self.access_logs.push_back(ShareAccessLog {
             timestamp: Utc::now(),
             ip_address,
             user_agent,
             success,
             bytes_transferred: bytes,
             error_message: error,
+            event: None,
         });
         if success {
             self.access_count += 1;
         }
     }
 
+    /// Log an owner action; it is not a download, so `access_count` is unchanged
+    pub fn record_event(&mut self, ip_address: String, user_agent: Option<String>, event: &str) {
+        self.access_logs.push_back(ShareAccessLog {
+            timestamp: Utc::now(),
+            ip_address,
+            user_agent,
+            success: true,
+            bytes_transferred: None,
+            error_message: None,
+            event: Some(event.to_string()),
+        });
+    }
+
     pub fn to_response(&self) -> ShareInfoResponse {
         let is_expired = self.is_expired();
         let is_active = self.is_active && !is_expired;
@@ -147,12 +201,13 @@ impl ShareInfo {
             has_password: self.has_password(),
             access_count: self.access_count,
             created_by: self.created_by.clone(),
-            last_accessed_at: self.access_logs.last().map(|log| log.timestamp),
+            last_accessed_at: self.access_logs.back().map(|log| log.timestamp),
             status: if is_active {
                 "active".to_string()
             } else {
                 "expired".to_string()
             },
+            download_as: self.download_as.clone(),
         }
     }
 }
@@ -11,10 +11,60 @@ pub struct User {
     pub room_key: String,
     pub is_online: bool,
     pub last_seen: DateTime<Utc>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Device identity; kept server-side, never sent to other room members
+    #[serde(skip_serializing)]
     pub fingerprint: Option<String>,
     #[serde(default = "default_device_type")]
     pub device_type: String,
+    #[serde(default, flatten)]
+    pub client_info: ClientInfo,
+}
+
+/// Maximum characters kept for the client-reported platform and app version
+pub const MAX_CLIENT_INFO_LEN: usize = 32;
+
+/// Client kinds accepted in `clientType`
+pub const CLIENT_TYPES: [&str; 3] = ["web", "desktop", "mobile"];
+
+/// Optional client-reported platform details shown in room presence
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    /// Operating system, e.g. "macOS"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<String>,
+    /// One of [`CLIENT_TYPES`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_type: Option<String>,
+}
+
+impl ClientInfo {
+    /// Validate raw client values: control characters are stripped, text is bounded to
+    /// [`MAX_CLIENT_INFO_LEN`] characters, and anything empty or unrecognized is dropped
+    pub fn new(
+        platform: Option<&str>,
+        app_version: Option<&str>,
+        client_type: Option<&str>,
+    ) -> Self {
+        let client_type = client_type
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| CLIENT_TYPES.contains(&t.as_str()));
+
+        Self {
+            platform: platform.and_then(bounded_text),
+            app_version: app_version.and_then(bounded_text),
+            client_type,
+        }
+    }
+}
+
+fn bounded_text(value: &str) -> Option<String> {
+    let cleaned: String = value.chars().filter(|c| !c.is_control()).collect();
+    let cleaned: String = cleaned.trim().chars().take(MAX_CLIENT_INFO_LEN).collect();
+    let cleaned = cleaned.trim_end();
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
 }
 
 fn default_device_type() -> String {
@@ -31,6 +81,7 @@ impl User {
             last_seen: Utc::now(),
             fingerprint: None,
             device_type: "desktop".to_string(),
+            client_info: ClientInfo::default(),
         }
     }
 
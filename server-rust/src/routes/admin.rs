@@ -0,0 +1,234 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{ApiJson, ApiResponse, error_code, get_base_path, require_admin, share};
+use crate::AppState;
+use crate::config::{CleanupConfig, CorsConfig};
+use crate::middleware::rate_limit::RateLimitConfig;
+use crate::services::{BannerSeverity, RoomSettings, ServerBanner};
+
+/// Serializes manual maintenance runs. The background cleanup tasks don't take it: each
+/// cleanup claims its entries under the service's write lock, so concurrent sweeps never
+/// delete (or count) the same item twice.
+static MAINTENANCE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Items removed by a maintenance run
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    pub expired_shares: usize,
+    pub expired_files: usize,
+    pub inactive_rooms: usize,
+    pub orphaned_files: usize,
+}
+
+/// Run every cleanup once, in the same order as the background tasks
+pub async fn run_maintenance(state: &AppState) -> MaintenanceReport {
+    let _guard = MAINTENANCE_LOCK.lock().await;
+
+    let expired_shares = state.share_service.cleanup_expired_shares().len();
+    let expired_files = state.file_manager.cleanup_expired_files().await.len();
+    let inactive_rooms = state.room_service.cleanup_inactive_rooms().len();
+    let orphaned_files = state.file_manager.cleanup_orphaned_files().await;
+
+    tracing::info!(
+        "Maintenance run: {} expired shares, {} expired files, {} inactive rooms, {} orphaned files",
+        expired_shares,
+        expired_files,
+        inactive_rooms,
+        orphaned_files
+    );
+
+    MaintenanceReport {
+        expired_shares,
+        expired_files,
+        inactive_rooms,
+        orphaned_files,
+    }
+}
+
+/// Effective configuration with secrets left out: ADMIN_TOKEN, SHARE_URL_SECRET and
+/// the like only ever show up as the feature flags they switch on
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReport {
+    pub production: bool,
+    pub base_path: String,
+    /// `["*"]` when any origin is allowed
+    pub cors_origins: Vec<String>,
+    pub cors_max_age_secs: u64,
+    pub cors_expose_headers: Vec<String>,
+    pub rate_limits: RateLimitConfig,
+    pub cleanup: CleanupConfig,
+    pub files: FileSettings,
+    pub shares: ShareSettings,
+    pub rooms: RoomSettings,
+    pub features: FeatureFlags,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSettings {
+    pub max_file_size: u64,
+    pub retention_hours: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareSettings {
+    pub min_expiration_days: i64,
+    pub max_expiration_days: i64,
+    pub default_expiration_days: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlags {
+    pub signed_share_urls: bool,
+    pub virus_scan: bool,
+    pub log_redact: bool,
+}
+
+/// Resolve the configuration the same way startup does
+pub fn collect_config(state: &AppState) -> ConfigReport {
+    let production = std::env::var("NODE_ENV").is_ok_and(|v| v == "production");
+    let cors = CorsConfig::from_env();
+    let (min_expiration_days, max_expiration_days) = state.share_service.expiration_bounds();
+
+    ConfigReport {
+        production,
+        base_path: get_base_path().to_string(),
+        cors_origins: if production || cors.allowed_origins.is_empty() {
+            vec!["*".to_string()]
+        } else {
+            cors.allowed_origins
+        },
+        cors_max_age_secs: cors.max_age_secs,
+        cors_expose_headers: cors.expose_headers,
+        rate_limits: RateLimitConfig::from_env(),
+        cleanup: CleanupConfig::from_env(),
+        files: FileSettings {
+            max_file_size: state.file_manager.max_file_size(),
+            retention_hours: state.file_manager.get_retention_hours(),
+        },
+        shares: ShareSettings {
+            min_expiration_days,
+            max_expiration_days,
+            default_expiration_days: state.share_service.default_expiration(),
+        },
+        rooms: state.room_service.settings(),
+        features: FeatureFlags {
+            signed_share_urls: share::signed_share_urls_enabled(),
+            virus_scan: state.file_manager.virus_scan_enabled(),
+            log_redact: crate::utils::log_redaction_enabled(),
+        },
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/config", get(get_config))
+        .route("/maintenance", post(maintenance))
+        .route("/banner", post(set_banner))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBannerRequest {
+    /// Banner text; null or empty clears the banner
+    pub text: Option<String>,
+    /// info (default), warning or critical
+    pub severity: Option<String>,
+}
+
+/// POST /api/admin/maintenance - Purge expired shares/files, inactive rooms and orphans now
+async fn maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ApiJson<ApiResponse<MaintenanceReport>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(run_maintenance(&state).await),
+        code: None,
+    }))
+}
+
+/// GET /api/admin/config - Effective non-secret configuration, for debugging deployments
+async fn get_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ApiJson<ApiResponse<ConfigReport>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(collect_config(&state)),
+        code: None,
+    }))
+}
+
+/// POST /api/admin/banner - Set or clear the server banner, re-broadcast to every client
+async fn set_banner(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SetBannerRequest>,
+) -> Result<ApiJson<ApiResponse<Option<ServerBanner>>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    let invalid = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(message),
+                data: None,
+                code: Some(error_code::INVALID_REQUEST.to_string()),
+            }),
+        )
+    };
+
+    let severity = match payload.severity.as_deref() {
+        None => BannerSeverity::default(),
+        Some(value) => BannerSeverity::parse(value)
+            .ok_or_else(|| invalid(format!("Invalid banner severity: {}", value)))?,
+    };
+    let banner = match payload.text.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(text) => Some(ServerBanner::new(text, severity).map_err(invalid)?),
+    };
+
+    state.room_service.set_banner(banner.clone()).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(e),
+                data: None,
+                code: Some(error_code::INTERNAL_ERROR.to_string()),
+            }),
+        )
+    })?;
+    tracing::info!(
+        "Server banner {}",
+        if banner.is_some() {
+            "updated"
+        } else {
+            "cleared"
+        }
+    );
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(banner),
+        code: None,
+    }))
+}
@@ -1,4 +1,5 @@
 use axum::Json;
+use chrono::{DateTime, SecondsFormat};
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -23,6 +24,7 @@ pub struct ApiEndpoints {
     pub files: String,
     pub share: String,
     pub health: String,
+    pub version: String,
 }
 
 pub async fn api_info() -> Json<ApiInfoResponse> {
@@ -37,7 +39,41 @@ pub async fn api_info() -> Json<ApiInfoResponse> {
                 files: "/api/files".to_string(),
                 share: "/api/share".to_string(),
                 health: "/api/health".to_string(),
+                version: "/api/version".to_string(),
             },
         },
     })
 }
+
+/// Build information embedded by `build.rs`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub version: String,
+    pub git_sha: String,
+    /// RFC 3339, UTC
+    pub build_time: String,
+    pub rust_version: String,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        let build_time = env!("BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            build_time,
+            rust_version: env!("RUSTC_VERSION").to_string(),
+        }
+    }
+}
+
+/// GET /api/version
+pub async fn version() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
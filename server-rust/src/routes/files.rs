@@ -1,18 +1,31 @@
 use axum::{
-    Json, Router,
+    Router,
     body::Body,
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::{HeaderMap, StatusCode, header},
     response::IntoResponse,
     routing::{delete, get, post},
 };
 use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
 use tokio_util::io::ReaderStream;
 
-use super::ApiResponse;
+use super::{ApiJson, ApiResponse, error_code, has_admin_token};
 use crate::AppState;
+use crate::middleware::rate_limit::{RateLimitMetrics, extract_client_ip};
+use crate::services::file_manager::{
+    FileInfo, PendingUpload, StorageTimeout, is_blank_filename, stored_file_reader,
+};
+use crate::services::{RoomService, ScanVerdict, UploadedFile};
+use crate::utils::{StreamPool, throttle_download};
+
+/// Concurrent in-room file download streams (FILE_MAX_CONCURRENT_*), separate from
+/// the public share pool so neither class can starve the other
+pub static FILE_STREAMS: std::sync::LazyLock<StreamPool> =
+    std::sync::LazyLock::new(|| StreamPool::from_env("FILE", 100, 5));
 
 // ============= Response Types =============
 
@@ -28,9 +41,107 @@ pub struct UploadResponse {
     pub file_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_modified: Option<u64>,
+    /// SHA-256 of the content, for client-side dedup/caching
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
     pub is_duplicate: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original_file_id: Option<String>,
+    /// When the file will be deleted by retention cleanup
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileListItem {
+    pub file_id: String,
+    pub name: String,
+    pub size: u64,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub uploaded_at: chrono::DateTime<chrono::Utc>,
+    pub download_count: u64,
+    /// When the file will be deleted by retention cleanup
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl FileListItem {
+    pub fn new(info: FileInfo, expires_at: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            file_id: info.filename,
+            name: info.original_name,
+            size: info.size,
+            file_type: info.mime_type,
+            uploaded_at: info.uploaded_at,
+            download_count: info.download_count,
+            expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileListResponse {
+    pub files: Vec<FileListItem>,
+    /// Room file count before pagination
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Result of re-hashing a stored file against its recorded hash
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVerification {
+    pub ok: bool,
+    pub expected_hash: Option<String>,
+    pub actual_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    /// Leading bytes decoded as UTF-8 (invalid sequences replaced)
+    pub content: String,
+    /// Number of bytes read for the preview
+    pub bytes: usize,
+    /// Whether the file is longer than the preview
+    pub truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewQuery {
+    pub bytes: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListFilesQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// `<field>[:asc|:desc]` with field `uploadedAt` (default) or `size`
+    pub sort: Option<String>,
+}
+
+/// Sort field for the room file listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileSortField {
+    UploadedAt,
+    Size,
+}
+
+/// Parse a `sort` query value into its field and whether it's descending
+pub fn parse_file_sort(value: &str) -> Option<(FileSortField, bool)> {
+    let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+    let field = match field {
+        "uploadedAt" => FileSortField::UploadedAt,
+        "size" => FileSortField::Size,
+        _ => return None,
+    };
+    match direction {
+        "asc" => Some((field, false)),
+        "desc" => Some((field, true)),
+        _ => None,
+    }
 }
 
 // ============= Constants =============
@@ -47,6 +158,28 @@ static DANGEROUS_EXTENSIONS: std::sync::LazyLock<HashSet<&'static str>> =
         .collect()
     });
 
+/// File parts accepted per upload request
+pub const MAX_FILES_PER_REQUEST: usize = 1;
+
+/// Upper bound for a per-upload `retentionHours` override (30 days). Fixed: it matches the
+/// default share expiry cap but does not follow `SHARE_MAX_EXPIRATION_DAYS`.
+pub const MAX_RETENTION_HOURS: i64 = 30 * 24;
+
+/// Preview length when `?bytes=` is omitted
+pub const DEFAULT_PREVIEW_BYTES: usize = 4096;
+
+/// Largest preview a client may request; larger values are clamped
+pub const MAX_PREVIEW_BYTES: usize = 64 * 1024;
+
+/// Parse a per-upload retention override; `None` if not an integer in 1..=MAX_RETENTION_HOURS
+pub fn parse_retention_hours(value: &str) -> Option<i64> {
+    value
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|h| (1..=MAX_RETENTION_HOURS).contains(h))
+}
+
 pub fn is_valid_filename(filename: &str) -> bool {
     !filename.contains("..")
         && !filename.contains('/')
@@ -81,28 +214,76 @@ fn extract_room_key(headers: &HeaderMap) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-fn require_room_key(headers: &HeaderMap) -> Result<String, (StatusCode, Json<ApiResponse<()>>)> {
-    extract_room_key(headers).ok_or_else(|| {
+/// Caller's room from x-room-key, resolved to the key the room is stored under
+fn require_room_key(
+    room_service: &RoomService,
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let room_key = extract_room_key(headers).ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Missing x-room-key header".to_string()),
                 data: None,
+                code: None,
             }),
         )
-    })
+    })?;
+    Ok(room_service.canonical_room_key(&room_key).into_owned())
+}
+
+/// Who is uploading, for the per-uploader quota: the device fingerprint, else the user
+/// ID, but only when it belongs to a current member of `room_key` (the headers are
+/// unverified). Anyone else is keyed by client IP.
+fn uploader_key(room_service: &RoomService, room_key: &str, headers: &HeaderMap) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    if let Some(fp) = header("x-user-fingerprint")
+        && room_service
+            .find_user_by_fingerprint(room_key, fp)
+            .is_some()
+    {
+        return format!("fp:{}", fp);
+    }
+    if let Some(id) = header("x-user-id")
+        && room_service
+            .get_room_users(room_key)
+            .iter()
+            .any(|u| u.id == id)
+    {
+        return format!("user:{}", id);
+    }
+    format!("ip:{}", extract_client_ip(headers))
 }
 
-fn validate_file_id(file_id: &str) -> Result<(), (StatusCode, Json<ApiResponse<()>>)> {
+fn upload_quota_error(message: &str) -> (StatusCode, ApiJson<ApiResponse<()>>) {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        ApiJson(ApiResponse {
+            success: false,
+            message: Some(message.to_string()),
+            data: None,
+            code: Some(error_code::RATE_LIMITED.to_string()),
+        }),
+    )
+}
+
+fn validate_file_id(file_id: &str) -> Result<(), (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Check for path traversal attempts
     if file_id.contains("..") || file_id.contains('/') || file_id.contains('\\') {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Invalid file ID".to_string()),
                 data: None,
+                code: None,
             }),
         ));
     }
@@ -111,10 +292,11 @@ fn validate_file_id(file_id: &str) -> Result<(), (StatusCode, Json<ApiResponse<(
     if file_id.len() > 255 || file_id.is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Invalid file ID format".to_string()),
                 data: None,
+                code: None,
             }),
         ));
     }
@@ -122,25 +304,59 @@ fn validate_file_id(file_id: &str) -> Result<(), (StatusCode, Json<ApiResponse<(
     Ok(())
 }
 
+fn upload_error(status: StatusCode, message: &str) -> (StatusCode, ApiJson<ApiResponse<()>>) {
+    (
+        status,
+        ApiJson(ApiResponse {
+            success: false,
+            message: Some(message.to_string()),
+            data: None,
+            code: None,
+        }),
+    )
+}
+
+/// 503 when a storage operation timed out (e.g. a hung network mount), else 500
+fn storage_error(e: anyhow::Error) -> (StatusCode, ApiJson<ApiResponse<()>>) {
+    if e.is::<StorageTimeout>() {
+        tracing::error!(error = %e, "Upload storage timed out");
+        return upload_error(StatusCode::SERVICE_UNAVAILABLE, "Storage unavailable");
+    }
+    upload_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
+
 // ============= Router =============
 
-pub fn router() -> Router<AppState> {
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadQuery {
+    /// Session token of the downloading room member, recorded for `requestFileDownloaders`
+    pub session_token: Option<String>,
+}
+
+pub fn router(rate_limit_metrics: Arc<RateLimitMetrics>) -> Router<AppState> {
     use crate::middleware::rate_limit::{
-        RateLimitConfig, RateLimitMiddleware, create_rate_limiter,
+        RateLimitConfig, RateLimitMiddleware, upload_rate_limiter,
     };
 
     let config = RateLimitConfig::from_env();
 
-    // Upload rate limit: 5/min (matching Node.js uploadRateLimit)
-    let upload_limiter = RateLimitMiddleware::new(create_rate_limiter(&config, 5));
+    let upload_limiter = RateLimitMiddleware::new(upload_rate_limiter(&config))
+        .with_metrics(rate_limit_metrics, "/api/files/upload");
 
     let upload_routes = Router::new()
         .route("/upload", post(upload_file))
         .layer(upload_limiter);
 
     let other_routes = Router::new()
-        .route("/download/{file_id}", get(download_file))
-        .route("/{file_id}", delete(delete_file));
+        .route("/", get(list_files))
+        .route(
+            "/download/{file_id}",
+            get(download_file).head(head_download_file),
+        )
+        .route("/{file_id}", delete(delete_file))
+        .route("/{file_id}/verify", get(verify_file))
+        .route("/{file_id}/preview", get(preview_file));
 
     Router::new().merge(upload_routes).merge(other_routes)
 }
@@ -148,70 +364,93 @@ pub fn router() -> Router<AppState> {
 // ============= Handlers =============
 
 /// POST /api/files/upload
+///
+/// The `file` part is streamed to disk as it arrives. `roomKey` (header or form field)
+/// must precede it, and at most [`MAX_FILES_PER_REQUEST`] file parts are accepted.
 async fn upload_file(
     State(state): State<AppState>,
     headers: HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<ApiResponse<UploadResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<UploadResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // First try to get room_key from header
     let room_key_header = extract_room_key(&headers);
     let mut room_key = room_key_header;
-    let mut file_data: Option<(String, String, Vec<u8>)> = None;
+    let mut upload: Option<(String, String, PendingUpload)> = None;
+    let mut file_count = 0;
+    let mut retention_hours: Option<i64> = None;
 
     // Debug: Log room key from header
     tracing::debug!(?room_key, "Room key from header");
 
     let mut field_count = 0;
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
         tracing::warn!(?e, "Multipart parse error");
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
-                success: false,
-                message: Some("Failed to parse multipart".to_string()),
-                data: None,
-            }),
-        )
+        upload_error(StatusCode::BAD_REQUEST, "Failed to parse multipart")
     })? {
         let name = field.name().unwrap_or("").to_string();
         field_count += 1;
         tracing::debug!(field_name = %name, field_count, "Processing multipart field");
 
         if name == "roomKey" && room_key.is_none() {
-            room_key = Some(field.text().await.map_err(|_| {
-                (
+            room_key =
+                Some(field.text().await.map_err(|_| {
+                    upload_error(StatusCode::BAD_REQUEST, "Failed to read roomKey")
+                })?);
+        } else if name == "retentionHours" {
+            let value = field.text().await.map_err(|_| {
+                upload_error(StatusCode::BAD_REQUEST, "Failed to read retentionHours")
+            })?;
+            retention_hours = Some(parse_retention_hours(&value).ok_or_else(|| {
+                upload_error(
                     StatusCode::BAD_REQUEST,
-                    Json(ApiResponse {
-                        success: false,
-                        message: Some("Failed to read roomKey".to_string()),
-                        data: None,
-                    }),
+                    &format!(
+                        "retentionHours must be between 1 and {}",
+                        MAX_RETENTION_HOURS
+                    ),
                 )
             })?);
         } else if name == "file" {
-            let filename = field.file_name().unwrap_or("unknown").to_string();
+            file_count += 1;
+            if file_count > MAX_FILES_PER_REQUEST {
+                return Err(upload_error(
+                    StatusCode::BAD_REQUEST,
+                    &format!("At most {} file per request", MAX_FILES_PER_REQUEST),
+                ));
+            }
+            // The room must be known before any file bytes are written
+            let Some(target_room) = room_key.as_deref() else {
+                return Err(upload_error(
+                    StatusCode::BAD_REQUEST,
+                    "roomKey must be sent before the file",
+                ));
+            };
+
+            // Membership is checked against the target room, so the quota is charged here
+            let uploader = uploader_key(&state.room_service, target_room, &headers);
+            if !state.upload_quota.try_request(&uploader) {
+                state
+                    .rate_limit_metrics
+                    .record_rejection("/api/files/upload");
+                return Err(upload_quota_error("Upload rate limit exceeded"));
+            }
+
+            // Checked in normalized form: stripping control characters can reveal an extension
+            let raw_filename = field.file_name().unwrap_or_default();
+            if is_blank_filename(raw_filename) {
+                return Err(upload_error(StatusCode::BAD_REQUEST, "Filename required"));
+            }
+            let filename = state.file_manager.normalize_name(raw_filename);
 
             // Validate filename
             if !is_valid_filename(&filename) {
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(ApiResponse {
-                        success: false,
-                        message: Some("Invalid filename".to_string()),
-                        data: None,
-                    }),
-                ));
+                return Err(upload_error(StatusCode::BAD_REQUEST, "Invalid filename"));
             }
 
             // Check for dangerous extensions
             if is_dangerous_extension(&filename) {
-                return Err((
+                return Err(upload_error(
                     StatusCode::BAD_REQUEST,
-                    Json(ApiResponse {
-                        success: false,
-                        message: Some("File type not allowed".to_string()),
-                        data: None,
-                    }),
+                    "File type not allowed",
                 ));
             }
 
@@ -219,17 +458,33 @@ async fn upload_file(
                 .content_type()
                 .unwrap_or("application/octet-stream")
                 .to_string();
-            let data = field.bytes().await.map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ApiResponse {
-                        success: false,
-                        message: Some("Failed to read file".to_string()),
-                        data: None,
-                    }),
-                )
-            })?;
-            file_data = Some((filename, content_type, data.to_vec()));
+
+            // Stream chunks to a temp file (removed automatically if we bail out)
+            let mut pending = state
+                .file_manager
+                .begin_upload()
+                .await
+                .map_err(storage_error)?;
+            while let Some(chunk) = field
+                .chunk()
+                .await
+                .map_err(|_| upload_error(StatusCode::BAD_REQUEST, "Failed to read file"))?
+            {
+                if pending.size() + chunk.len() as u64 > state.file_manager.max_file_size() {
+                    return Err(upload_error(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "File too large",
+                    ));
+                }
+                if !state.upload_quota.try_bytes(&uploader, chunk.len() as u64) {
+                    state
+                        .rate_limit_metrics
+                        .record_rejection("/api/files/upload");
+                    return Err(upload_quota_error("Upload volume limit exceeded"));
+                }
+                pending.write_chunk(&chunk).await.map_err(storage_error)?;
+            }
+            upload = Some((filename, content_type, pending));
         }
     }
 
@@ -237,42 +492,27 @@ async fn upload_file(
 
     let room_key = room_key.ok_or_else(|| {
         tracing::warn!("Room key missing from both header and multipart");
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
-                success: false,
-                message: Some("roomKey is required".to_string()),
-                data: None,
-            }),
-        )
+        upload_error(StatusCode::BAD_REQUEST, "roomKey is required")
     })?;
+    let room_key = state
+        .room_service
+        .canonical_room_key(&room_key)
+        .into_owned();
 
-    let (filename, content_type, data) = file_data.ok_or_else(|| {
+    let (filename, content_type, mut pending) = upload.ok_or_else(|| {
         tracing::warn!("File data missing from multipart");
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
-                success: false,
-                message: Some("file is required".to_string()),
-                data: None,
-            }),
-        )
+        upload_error(StatusCode::BAD_REQUEST, "file is required")
     })?;
 
-    // Check file size
-    if data.len() as u64 > state.file_manager.max_file_size() {
-        return Err((
-            StatusCode::PAYLOAD_TOO_LARGE,
-            Json(ApiResponse {
-                success: false,
-                message: Some("File too large".to_string()),
-                data: None,
-            }),
-        ));
+    if let Err(e) = state
+        .file_manager
+        .validate_upload(&filename, pending.size())
+    {
+        return Err(upload_error(StatusCode::BAD_REQUEST, e));
     }
 
     // P2.2: Validate file type via magic bytes
-    if let Some(inferred) = infer::get(&data) {
+    if let Some(inferred) = infer::get(pending.head()) {
         let inferred_mime = inferred.mime_type();
         // Block executable types detected by magic bytes
         let blocked_mimes = [
@@ -283,37 +523,60 @@ async fn upload_file(
             "application/vnd.microsoft.portable-executable",
         ];
         if blocked_mimes.contains(&inferred_mime) {
-            return Err((
+            return Err(upload_error(
                 StatusCode::BAD_REQUEST,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some("File type not allowed (executable detected)".to_string()),
-                    data: None,
-                }),
+                "File type not allowed (executable detected)",
+            ));
+        }
+    }
+
+    // Rejected uploads are removed when `pending` is dropped
+    match state.file_manager.scan_upload(&mut pending).await {
+        Ok(ScanVerdict::Clean) => {}
+        Ok(ScanVerdict::Infected(signature)) => {
+            tracing::warn!(%room_key, %filename, %signature, "Upload rejected by virus scan");
+            return Err(upload_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "File rejected by virus scan",
+            ));
+        }
+        Err(e) => {
+            tracing::error!(?e, "Virus scan failed");
+            return Err(upload_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Virus scan unavailable",
             ));
         }
     }
 
     let file_info = state
         .file_manager
-        .save_file(&room_key, &filename, &content_type, &data)
+        .commit_upload(
+            pending,
+            &room_key,
+            &filename,
+            &content_type,
+            retention_hours,
+        )
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some(e.to_string()),
-                    data: None,
-                }),
-            )
-        })?;
+        .map_err(storage_error)?;
 
-    let base_url = super::build_base_url(&headers);
+    let base_url = super::build_base_url(&headers)?;
     let download_url = format!("{}/api/files/download/{}", base_url, file_info.filename);
     let last_modified = file_info.uploaded_at.timestamp_millis() as u64;
+    let expires_at = state.file_manager.expires_at(&file_info);
+
+    // Let the room know without requiring the uploader to also send a file message
+    state.room_service.notify_file_uploaded(UploadedFile {
+        room_key: room_key.clone(),
+        file_id: file_info.filename.clone(),
+        name: file_info.original_name.clone(),
+        size: file_info.size,
+        file_type: file_info.mime_type.clone(),
+        download_url: download_url.clone(),
+    });
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: Some("File uploaded successfully".to_string()),
         data: Some(UploadResponse {
@@ -323,9 +586,12 @@ async fn upload_file(
             size: file_info.size,
             file_type: file_info.mime_type,
             last_modified: Some(last_modified),
+            hash: file_info.hash,
             is_duplicate: file_info.is_duplicate.unwrap_or(false),
             original_file_id: file_info.original_file_id,
+            expires_at,
         }),
+        code: None,
     }))
 }
 
@@ -333,17 +599,89 @@ async fn upload_file(
 async fn download_file(
     State(state): State<AppState>,
     Path(file_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ApiResponse<()>>)> {
+    Query(query): Query<DownloadQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let client_ip = extract_client_ip(&headers);
+    let stream_guard = FILE_STREAMS.acquire(&client_ip).ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Too many concurrent downloads. Please try again later.".to_string()),
+                data: None,
+                code: Some(error_code::RATE_LIMITED.to_string()),
+            }),
+        )
+    })?;
+
+    let file_info = resolve_download(&state, &file_id)?;
+
+    let file = tokio::fs::File::open(&file_info.path).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Failed to open file".to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    })?;
+
+    state.file_manager.record_download(&file_id);
+    // Attribute the download only to a member proven by their session token; user IDs
+    // are visible to the whole room, so a bare ID could be spoofed
+    let session_token = query.session_token.or_else(|| {
+        headers
+            .get("x-session-token")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    });
+    if let Some(user_id) =
+        session_token.and_then(|token| state.room_service.session_user(&token, &file_info.room_key))
+        && state
+            .room_service
+            .get_room_users(&file_info.room_key)
+            .iter()
+            .any(|u| u.id == user_id)
+    {
+        state.file_manager.record_downloader(&file_id, &user_id);
+    }
+
+    let reader = stream_guard.hold_while(stored_file_reader(file, file_info.compressed));
+    let stream = ReaderStream::new(throttle_download(Box::new(reader)));
+    let body = Body::from_stream(stream);
+
+    Ok((download_headers(file_info), body))
+}
+
+/// HEAD /api/files/download/:fileId - Same checks and headers as GET, without a body
+/// (does not count as a download)
+async fn head_download_file(
+    State(state): State<AppState>,
+    Path(file_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let file_info = resolve_download(&state, &file_id)?;
+    Ok(download_headers(file_info))
+}
+
+/// Look up a file for download and verify it is a regular file inside the upload directory
+fn resolve_download(
+    state: &AppState,
+    file_id: &str,
+) -> Result<FileInfo, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Validate file ID
-    validate_file_id(&file_id)?;
+    validate_file_id(file_id)?;
 
-    let file_info = state.file_manager.get_file(&file_id).ok_or_else(|| {
+    let file_info = state.file_manager.get_room_file(file_id).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("File not found".to_string()),
                 data: None,
+                code: Some(error_code::FILE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -356,10 +694,11 @@ async fn download_file(
         .map_err(|_| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse {
+                ApiJson(ApiResponse {
                     success: false,
                     message: Some("Server error".to_string()),
                     data: None,
+                    code: None,
                 }),
             )
         })?;
@@ -368,10 +707,11 @@ async fn download_file(
     let metadata = std::fs::symlink_metadata(&file_info.path).map_err(|_| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("File not found".to_string()),
                 data: None,
+                code: Some(error_code::FILE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -379,10 +719,11 @@ async fn download_file(
     if metadata.file_type().is_symlink() {
         return Err((
             StatusCode::FORBIDDEN,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Access denied".to_string()),
                 data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
             }),
         ));
     }
@@ -390,10 +731,11 @@ async fn download_file(
     let file_path = file_info.path.canonicalize().map_err(|_| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("File not found".to_string()),
                 data: None,
+                code: Some(error_code::FILE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -401,28 +743,20 @@ async fn download_file(
     if !file_path.starts_with(&upload_dir) {
         return Err((
             StatusCode::FORBIDDEN,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Access denied".to_string()),
                 data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
             }),
         ));
     }
 
-    let file = tokio::fs::File::open(&file_info.path).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
-                success: false,
-                message: Some("Failed to open file".to_string()),
-                data: None,
-            }),
-        )
-    })?;
-
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+    Ok(file_info)
+}
 
+/// Response headers shared by GET and HEAD downloads
+fn download_headers(file_info: FileInfo) -> [(header::HeaderName, String); 3] {
     // RFC 5987 encoding for non-ASCII filenames
     let filename_encoded =
         utf8_percent_encode(&file_info.original_name, NON_ALPHANUMERIC).to_string();
@@ -432,14 +766,215 @@ async fn download_file(
         filename_encoded
     );
 
-    Ok((
-        [
-            (header::CONTENT_TYPE, file_info.mime_type),
-            (header::CONTENT_DISPOSITION, content_disposition),
-            (header::CONTENT_LENGTH, file_info.size.to_string()),
-        ],
-        body,
-    ))
+    [
+        (header::CONTENT_TYPE, file_info.mime_type),
+        (header::CONTENT_DISPOSITION, content_disposition),
+        (header::CONTENT_LENGTH, file_info.size.to_string()),
+    ]
+}
+
+/// GET /api/files/:fileId/verify - Re-hash the stored file and compare with its recorded hash
+/// (admin token or membership of the file's room required)
+async fn verify_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(file_id): Path<String>,
+) -> Result<ApiJson<ApiResponse<FileVerification>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let file_info = resolve_download(&state, &file_id)?;
+
+    if !has_admin_token(&headers) {
+        let room_key = require_room_key(&state.room_service, &headers)?;
+        if file_info.room_key != room_key {
+            return Err((
+                StatusCode::FORBIDDEN,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some("Access denied".to_string()),
+                    data: None,
+                    code: Some(error_code::ACCESS_DENIED.to_string()),
+                }),
+            ));
+        }
+    }
+
+    let actual_hash = state
+        .file_manager
+        .hash_stored_file(&file_info)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to hash file {}: {}", file_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some("Failed to read file".to_string()),
+                    data: None,
+                    code: None,
+                }),
+            )
+        })?;
+
+    let ok = file_info.hash.as_deref() == Some(actual_hash.as_str());
+    if !ok {
+        tracing::warn!("Integrity check failed for file {}", file_id);
+    }
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(FileVerification {
+            ok,
+            expected_hash: file_info.hash,
+            actual_hash,
+        }),
+        code: None,
+    }))
+}
+
+/// Whether a MIME type is plain enough to preview as text
+fn is_text_mime(mime_type: &str) -> bool {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence.starts_with("text/")
+        || matches!(
+            essence.as_str(),
+            "application/json" | "application/xml" | "application/javascript"
+        )
+}
+
+/// GET /api/files/{file_id}/preview?bytes=N - First N bytes of a text file (requires x-room-key)
+async fn preview_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(file_id): Path<String>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<ApiJson<ApiResponse<FilePreview>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let file_info = resolve_download(&state, &file_id)?;
+
+    let room_key = require_room_key(&state.room_service, &headers)?;
+    if file_info.room_key != room_key {
+        return Err((
+            StatusCode::FORBIDDEN,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Access denied".to_string()),
+                data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
+            }),
+        ));
+    }
+
+    if !is_text_mime(&file_info.mime_type) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Preview is only available for text files".to_string()),
+                data: None,
+                code: Some(error_code::INVALID_REQUEST.to_string()),
+            }),
+        ));
+    }
+
+    let limit = query
+        .bytes
+        .unwrap_or(DEFAULT_PREVIEW_BYTES)
+        .clamp(1, MAX_PREVIEW_BYTES);
+
+    let read_failed = |e: std::io::Error| {
+        tracing::error!("Failed to read preview of file {}: {}", file_id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Failed to read file".to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    };
+    let file = tokio::fs::File::open(&file_info.path)
+        .await
+        .map_err(read_failed)?;
+    let mut buf = Vec::with_capacity(limit);
+    stored_file_reader(file, file_info.compressed)
+        .take(limit as u64)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(read_failed)?;
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(FilePreview {
+            content: String::from_utf8_lossy(&buf).into_owned(),
+            bytes: buf.len(),
+            truncated: file_info.size > buf.len() as u64,
+        }),
+        code: None,
+    }))
+}
+
+/// GET /api/files - List files in the caller's room (requires x-room-key header)
+async fn list_files(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListFilesQuery>,
+) -> Result<ApiJson<ApiResponse<FileListResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let room_key = require_room_key(&state.room_service, &headers)?;
+
+    let (field, descending) = match query.sort.as_deref() {
+        Some(sort) => parse_file_sort(sort).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some(format!("Invalid sort: {}", sort)),
+                    data: None,
+                    code: Some(error_code::INVALID_REQUEST.to_string()),
+                }),
+            )
+        })?,
+        None => (FileSortField::UploadedAt, false),
+    };
+    let limit = query.limit.unwrap_or(50);
+    let offset = query.offset.unwrap_or(0);
+
+    // Already ordered by upload time, so equal sizes stay in upload order (stable sort)
+    let mut files = state.file_manager.list_room_files(&room_key);
+    match (field, descending) {
+        (FileSortField::UploadedAt, false) => {}
+        (FileSortField::UploadedAt, true) => files.reverse(),
+        (FileSortField::Size, false) => files.sort_by_key(|f| f.size),
+        (FileSortField::Size, true) => files.sort_by_key(|f| std::cmp::Reverse(f.size)),
+    }
+    let total = files.len();
+
+    let files = files
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|info| {
+            let expires_at = state.file_manager.expires_at(&info);
+            FileListItem::new(info, expires_at)
+        })
+        .collect();
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(FileListResponse {
+            files,
+            total,
+            limit,
+            offset,
+        }),
+        code: None,
+    }))
 }
 
 /// DELETE /api/files/:fileId
@@ -447,21 +982,22 @@ async fn delete_file(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(file_id): Path<String>,
-) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<()>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Require authentication
-    let room_key = require_room_key(&headers)?;
+    let room_key = require_room_key(&state.room_service, &headers)?;
 
     // Validate file ID
     validate_file_id(&file_id)?;
 
     // Get file info
-    let file_info = state.file_manager.get_file(&file_id).ok_or_else(|| {
+    let file_info = state.file_manager.get_room_file(&file_id).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("File not found".to_string()),
                 data: None,
+                code: Some(error_code::FILE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -470,10 +1006,11 @@ async fn delete_file(
     if file_info.room_key != room_key {
         return Err((
             StatusCode::FORBIDDEN,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Access denied".to_string()),
                 data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
             }),
         ));
     }
@@ -486,17 +1023,19 @@ async fn delete_file(
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse {
+                ApiJson(ApiResponse {
                     success: false,
                     message: Some(e.to_string()),
                     data: None,
+                    code: None,
                 }),
             )
         })?;
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: Some("File deleted successfully".to_string()),
         data: None,
+        code: None,
     }))
 }
@@ -1,4 +1,4 @@
-use axum::{Json, extract::State};
+use axum::{Json, extract::State, http::StatusCode};
 use serde::Serialize;
 
 use crate::AppState;
@@ -52,7 +52,7 @@ pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse>
         success: true,
         message: "Server is healthy".to_string(),
         data: HealthData {
-            uptime: state.start_time.elapsed().as_secs_f64(),
+            uptime: state.uptime().as_secs_f64(),
             timestamp: chrono::Utc::now().to_rfc3339(),
             total_rooms: room_stats.total_rooms,
             total_users: room_stats.total_users,
@@ -65,3 +65,23 @@ pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse>
         },
     })
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessResponse {
+    pub ready: bool,
+}
+
+/// Readiness probe: 200 once the socket layer is set up and the server is listening,
+/// 503 while still starting
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let ready = state.is_ready();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadinessResponse { ready }))
+}
@@ -1,10 +1,15 @@
+pub mod admin;
 pub mod api_info;
 pub mod files;
 pub mod health;
 pub mod rooms;
 pub mod share;
+pub mod stats;
 
-use axum::http::HeaderMap;
+use axum::{
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
 use serde::Serialize;
 
 static BASE_PATH: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
@@ -14,6 +19,71 @@ static BASE_PATH: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
         .to_string()
 });
 
+/// Allowed Host header values for building absolute URLs (ALLOWED_HOSTS, comma-separated).
+/// Empty means any host is accepted.
+static ALLOWED_HOSTS: std::sync::LazyLock<Vec<String>> = std::sync::LazyLock::new(|| {
+    std::env::var("ALLOWED_HOSTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| !h.is_empty())
+        .collect()
+});
+
+/// Scheme policy for generated URLs (TRUST_PROXY_PROTO / ALLOW_HTTP)
+static SCHEME_POLICY: std::sync::LazyLock<SchemePolicy> =
+    std::sync::LazyLock::new(SchemePolicy::from_env);
+
+/// How the scheme of generated absolute URLs is chosen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemePolicy {
+    /// Honor `X-Forwarded-Proto` (`http`/`https` only), defaulting to http
+    TrustProxy,
+    /// Ignore proxy headers and always use this scheme
+    Fixed(&'static str),
+}
+
+impl SchemePolicy {
+    /// `TRUST_PROXY_PROTO` (default true) keeps honoring the proxy header. When false,
+    /// the scheme follows the server's own setting: https unless `ALLOW_HTTP=true`.
+    pub fn from_env() -> Self {
+        let trust_proxy = std::env::var("TRUST_PROXY_PROTO")
+            .map(|v| v.to_lowercase() != "false")
+            .unwrap_or(true);
+        let allow_http = std::env::var("ALLOW_HTTP")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+        Self::new(trust_proxy, allow_http)
+    }
+
+    pub fn new(trust_proxy: bool, allow_http: bool) -> Self {
+        match (trust_proxy, allow_http) {
+            (true, _) => Self::TrustProxy,
+            (false, true) => Self::Fixed("http"),
+            (false, false) => Self::Fixed("https"),
+        }
+    }
+
+    fn scheme<'a>(&self, headers: &'a HeaderMap) -> &'a str {
+        match self {
+            Self::TrustProxy => headers
+                .get("x-forwarded-proto")
+                .and_then(|v| v.to_str().ok())
+                .filter(|proto| matches!(*proto, "http" | "https"))
+                .unwrap_or("http"),
+            Self::Fixed(scheme) => scheme,
+        }
+    }
+}
+
+/// Admin token for operator endpoints (ADMIN_TOKEN env var; unset disables them)
+static ADMIN_TOKEN: std::sync::LazyLock<Option<String>> = std::sync::LazyLock::new(|| {
+    std::env::var("ADMIN_TOKEN")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+});
+
 /// Unified API response type used across all route modules
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,25 +93,189 @@ pub struct ApiResponse<T> {
     pub message: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
+    /// Stable machine-readable error code (see `error_code`); `message` stays human-readable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Render API responses indented instead of compact (PRETTY_JSON, default false)
+static PRETTY_JSON: std::sync::LazyLock<bool> = std::sync::LazyLock::new(|| {
+    std::env::var("PRETTY_JSON")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+});
+
+/// JSON response body for `ApiResponse`, honoring `PRETTY_JSON`.
+///
+/// Used in place of `axum::Json` by the REST handlers only; socket payloads and file
+/// downloads are serialized elsewhere and stay compact.
+#[derive(Debug)]
+pub struct ApiJson<T>(pub T);
+
+impl<T: Serialize> ApiJson<T> {
+    /// Serialize the body, indented when `pretty` is set
+    pub fn render(&self, pretty: bool) -> serde_json::Result<Vec<u8>> {
+        if pretty {
+            serde_json::to_vec_pretty(&self.0)
+        } else {
+            serde_json::to_vec(&self.0)
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiJson<T> {
+    fn into_response(self) -> Response {
+        match self.render(*PRETTY_JSON) {
+            Ok(body) => ([(header::CONTENT_TYPE, "application/json")], body).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+/// Error codes carried in `ApiResponse.code`. Clients may match on these; never rename them.
+pub mod error_code {
+    pub const SHARE_NOT_FOUND: &str = "SHARE_NOT_FOUND";
+    pub const ROOM_NOT_FOUND: &str = "ROOM_NOT_FOUND";
+    pub const FILE_NOT_FOUND: &str = "FILE_NOT_FOUND";
+    pub const ACCESS_DENIED: &str = "ACCESS_DENIED";
+    pub const PASSWORD_REQUIRED: &str = "PASSWORD_REQUIRED";
+    pub const INVALID_PASSWORD: &str = "INVALID_PASSWORD";
+    pub const TOO_MANY_ATTEMPTS: &str = "TOO_MANY_ATTEMPTS";
+    pub const RATE_LIMITED: &str = "RATE_LIMITED";
+    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
+    pub const ADMIN_DISABLED: &str = "ADMIN_DISABLED";
+    pub const INVALID_REQUEST: &str = "INVALID_REQUEST";
+    /// A server-wide limit (e.g. `MAX_TOTAL_ROOMS`) is reached; retry later
+    pub const SERVER_AT_CAPACITY: &str = "SERVER_AT_CAPACITY";
+    pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
 }
 
 /// Build base URL from PUBLIC_URL env var or request headers for constructing absolute URLs
 /// Priority: PUBLIC_URL > request headers (X-Forwarded-Proto + Host)
-pub fn build_base_url(headers: &HeaderMap) -> String {
-    if let Ok(public_url) = std::env::var("PUBLIC_URL") {
-        return public_url.trim_end_matches('/').to_string();
+/// When ALLOWED_HOSTS is set, a Host header outside the allowlist is rejected with 400.
+pub fn build_base_url(
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    base_url_from_headers(headers).map_err(|msg| {
+        (
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(msg.to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    })
+}
+
+/// Same as `build_base_url` with a plain error, for callers outside HTTP handlers
+/// (e.g. the socket layer building URLs from its handshake headers)
+pub fn base_url_from_headers(headers: &HeaderMap) -> Result<String, &'static str> {
+    let public_url = std::env::var("PUBLIC_URL").ok();
+    resolve_base_url(
+        headers,
+        public_url.as_deref(),
+        &ALLOWED_HOSTS,
+        *SCHEME_POLICY,
+    )
+}
+
+/// Resolve the base URL from an explicit public URL, or from the request headers
+/// validated against `allowed_hosts` (empty = any host), with the scheme chosen by `scheme`
+pub fn resolve_base_url(
+    headers: &HeaderMap,
+    public_url: Option<&str>,
+    allowed_hosts: &[String],
+    scheme: SchemePolicy,
+) -> Result<String, &'static str> {
+    if let Some(public_url) = public_url {
+        return Ok(public_url.trim_end_matches('/').to_string());
     }
-    let proto = headers
-        .get("x-forwarded-proto")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("http");
+    let proto = scheme.scheme(headers);
     let host = headers
         .get("host")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("localhost:3001");
-    format!("{}://{}", proto, host)
+    if !allowed_hosts.is_empty() && !is_host_allowed(host, allowed_hosts) {
+        tracing::warn!("Rejected request with disallowed Host header: {}", host);
+        return Err("Host not allowed");
+    }
+    Ok(format!("{}://{}", proto, host))
+}
+
+/// Match a Host header against the allowlist. Entries without a port match any port.
+fn is_host_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.to_lowercase();
+    let hostname = host
+        .rsplit_once(':')
+        .map_or(host.as_str(), |(name, _)| name);
+    allowed_hosts
+        .iter()
+        .any(|allowed| *allowed == host || *allowed == hostname)
 }
 
 pub fn get_base_path() -> &'static str {
     &BASE_PATH
 }
+
+/// Check the admin token from `X-Admin-Token` or `Authorization: Bearer <token>`
+pub fn is_admin_authorized(headers: &HeaderMap, expected: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return false;
+    };
+    let provided = headers
+        .get("x-admin-token")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        });
+    match provided {
+        // Constant-time comparison to avoid leaking the token through timing
+        Some(token) => {
+            token.len() == expected.len()
+                && token
+                    .bytes()
+                    .zip(expected.bytes())
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        }
+        None => false,
+    }
+}
+
+/// Whether the request carries the configured admin token (always false when ADMIN_TOKEN is unset)
+pub fn has_admin_token(headers: &HeaderMap) -> bool {
+    is_admin_authorized(headers, ADMIN_TOKEN.as_deref())
+}
+
+/// Guard for admin-only endpoints. Returns 403 when ADMIN_TOKEN is not configured
+/// and 401 when the provided token is missing or wrong.
+pub fn require_admin(headers: &HeaderMap) -> Result<(), (StatusCode, ApiJson<ApiResponse<()>>)> {
+    if ADMIN_TOKEN.is_none() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Admin API is disabled".to_string()),
+                data: None,
+                code: Some(error_code::ADMIN_DISABLED.to_string()),
+            }),
+        ));
+    }
+    if !is_admin_authorized(headers, ADMIN_TOKEN.as_deref()) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Unauthorized".to_string()),
+                data: None,
+                code: Some(error_code::UNAUTHORIZED.to_string()),
+            }),
+        ));
+    }
+    Ok(())
+}
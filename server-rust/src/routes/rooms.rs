@@ -1,15 +1,17 @@
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    routing::{get, post},
+    http::{HeaderMap, StatusCode, header},
+    routing::{get, post, put},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::ApiResponse;
+use super::{ApiJson, ApiResponse, error_code, require_admin};
 use crate::AppState;
-use crate::models::Message;
-use crate::utils::validate_room_key;
+use crate::middleware::rate_limit::extract_client_ip;
+use crate::models::{AuditEntry, Message, RoomConfig};
+use crate::services::{RoomError, RoomService};
 
 // ============= Request/Response Types =============
 
@@ -26,6 +28,44 @@ pub struct VerifyPasswordRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminCreateRoomRequest {
+    pub room_key: String,
+    pub password: Option<String>,
+    /// Initial configuration; omitted fields keep their defaults
+    pub config: Option<RoomConfig>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminRoomResponse {
+    #[serde(flatten)]
+    pub room: RoomInfoResponse,
+    pub config: RoomConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRateLimitsRequest {
+    /// Socket event -> max requests per window (bounded by ROOM_RATE_LIMIT_MAX)
+    pub rate_limits: HashMap<String, u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetMessageRateLimitRequest {
+    /// Messages per minute shared by the whole room; null removes the limit
+    pub messages_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPersistenceRequest {
+    /// false: relay messages without keeping history (existing history is discarded)
+    pub persist_messages: bool,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidateUserRequest {
@@ -34,8 +74,11 @@ pub struct ValidateUserRequest {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MessagesQuery {
     pub limit: Option<usize>,
+    /// Only return messages with seq greater than this (gap backfill)
+    pub after_seq: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -58,8 +101,6 @@ pub struct UserResponse {
     pub is_online: bool,
     pub last_seen: chrono::DateTime<chrono::Utc>,
     pub device_type: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub fingerprint: Option<String>,
 }
 
 impl From<&crate::models::User> for UserResponse {
@@ -70,7 +111,6 @@ impl From<&crate::models::User> for UserResponse {
             is_online: user.is_online,
             last_seen: user.last_seen,
             device_type: user.device_type.clone(),
-            fingerprint: user.fingerprint.clone(),
         }
     }
 }
@@ -115,25 +155,30 @@ fn extract_room_key(headers: &HeaderMap) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-fn require_room_key(headers: &HeaderMap) -> Result<String, (StatusCode, Json<ApiResponse<()>>)> {
+fn require_room_key(
+    room_service: &RoomService,
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, ApiJson<ApiResponse<()>>)> {
     let room_key = extract_room_key(headers).ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Missing x-room-key header".to_string()),
                 data: None,
+                code: None,
             }),
         )
     })?;
 
-    if let Err(msg) = validate_room_key(&room_key) {
+    if let Err(msg) = room_service.validated_room_key(&room_key) {
         return Err((
             StatusCode::UNAUTHORIZED,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(format!("Invalid room key format: {}", msg)),
                 data: None,
+                code: None,
             }),
         ));
     }
@@ -141,12 +186,72 @@ fn require_room_key(headers: &HeaderMap) -> Result<String, (StatusCode, Json<Api
     Ok(room_key)
 }
 
+/// Resolve the `x-owner-token` header to the owner's fingerprint. The token is only
+/// sent to the creator's socket, unlike fingerprints, which any client can claim.
+fn require_owner(
+    state: &AppState,
+    room_key: &str,
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let token = headers
+        .get("x-owner-token")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some("Missing x-owner-token header".to_string()),
+                    data: None,
+                    code: Some(error_code::UNAUTHORIZED.to_string()),
+                }),
+            )
+        })?;
+
+    state
+        .room_service
+        .verify_owner_token(room_key, token)
+        .map_err(room_error)
+}
+
+/// HTTP status and error body for a failed owner-only room operation
+fn room_error(err: RoomError) -> (StatusCode, ApiJson<ApiResponse<()>>) {
+    let (status, code) = match err {
+        RoomError::NotFound => (StatusCode::NOT_FOUND, error_code::ROOM_NOT_FOUND),
+        RoomError::NotOwner => (StatusCode::FORBIDDEN, error_code::ACCESS_DENIED),
+        RoomError::Invalid(_) => (StatusCode::BAD_REQUEST, error_code::INVALID_REQUEST),
+        RoomError::CreationDisabled => (StatusCode::FORBIDDEN, error_code::ACCESS_DENIED),
+        RoomError::AtCapacity => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            error_code::SERVER_AT_CAPACITY,
+        ),
+        RoomError::CreationLimit => (StatusCode::TOO_MANY_REQUESTS, error_code::RATE_LIMITED),
+        RoomError::Internal(_) | RoomError::Lock => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            error_code::INTERNAL_ERROR,
+        ),
+    };
+    (
+        status,
+        ApiJson(ApiResponse {
+            success: false,
+            message: Some(err.to_string()),
+            data: None,
+            code: Some(code.to_string()),
+        }),
+    )
+}
+
 // ============= Router =============
 
 pub fn router() -> Router<AppState> {
     Router::new()
         // 创建房间
         .route("/create", post(create_room))
+        // 管理员创建房间 (ALLOW_ROOM_CREATION=false 时唯一的建房方式)
+        .route("/", post(admin_create_room))
         // 需要 x-room-key header 的端点
         .route("/info", get(get_room_info))
         .route("/users", get(get_room_users))
@@ -155,9 +260,17 @@ pub fn router() -> Router<AppState> {
         .route("/stats", get(get_stats))
         .route("/validate-user", post(validate_user))
         // 路径参数端点
-        .route("/{room_key}", get(get_room_by_path))
+        .route("/{room_key}", get(get_room_by_path).delete(destroy_room))
         .route("/{room_key}/exists", get(room_exists))
         .route("/{room_key}/verify-password", post(verify_password))
+        // 房主专用端点 (x-owner-token header)
+        .route("/{room_key}/audit", get(get_audit_log))
+        .route("/{room_key}/rate-limits", put(set_rate_limits))
+        .route("/{room_key}/persistence", put(set_persistence))
+        .route(
+            "/{room_key}/message-rate-limit",
+            put(set_message_rate_limit),
+        )
 }
 
 // ============= Handlers =============
@@ -165,24 +278,29 @@ pub fn router() -> Router<AppState> {
 /// POST /api/rooms/create
 async fn create_room(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<CreateRoomRequest>,
-) -> Result<Json<ApiResponse<RoomInfoResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<RoomInfoResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Validate room key format
-    if let Err(msg) = validate_room_key(&payload.room_key) {
+    if let Err(msg) = state.room_service.validated_room_key(&payload.room_key) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(msg.to_string()),
                 data: None,
+                code: None,
             }),
         ));
     }
 
-    match state
-        .room_service
-        .create_room(&payload.room_key, payload.password.as_deref(), None)
-    {
+    let client_ip = extract_client_ip(&headers);
+    match state.room_service.create_room_from(
+        &payload.room_key,
+        payload.password.as_deref(),
+        None,
+        Some(&client_ip),
+    ) {
         Ok(info) => {
             let response = RoomInfoResponse {
                 key: info.room_key,
@@ -193,20 +311,63 @@ async fn create_room(
                 has_password: info.has_password,
                 is_pinned: info.is_pinned,
             };
-            Ok(Json(ApiResponse {
+            Ok(ApiJson(ApiResponse {
                 success: true,
                 message: Some("Room created successfully".to_string()),
                 data: Some(response),
+                code: None,
             }))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
+        Err(e) => Err(room_error(e)),
+    }
+}
+
+/// POST /api/rooms (admin only) - Provision a room even when self-service creation is disabled
+async fn admin_create_room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminCreateRoomRequest>,
+) -> Result<ApiJson<ApiResponse<AdminRoomResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    if let Err(msg) = state.room_service.validated_room_key(&payload.room_key) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
                 success: false,
-                message: Some(e),
+                message: Some(msg.to_string()),
                 data: None,
+                code: Some(error_code::INVALID_REQUEST.to_string()),
             }),
-        )),
+        ));
+    }
+
+    match state.room_service.admin_create_room(
+        &payload.room_key,
+        payload.password.as_deref(),
+        payload.config,
+    ) {
+        Ok(info) => Ok(ApiJson(ApiResponse {
+            success: true,
+            message: Some("Room created successfully".to_string()),
+            data: Some(AdminRoomResponse {
+                room: RoomInfoResponse {
+                    key: info.room_key.clone(),
+                    users: vec![],
+                    message_count: 0,
+                    created_at: info.created_at,
+                    last_activity: info.last_activity,
+                    has_password: info.has_password,
+                    is_pinned: info.is_pinned,
+                },
+                config: state
+                    .room_service
+                    .get_room_config(&info.room_key)
+                    .unwrap_or_default(),
+            }),
+            code: None,
+        })),
+        Err(e) => Err(room_error(e)),
     }
 }
 
@@ -214,16 +375,17 @@ async fn create_room(
 async fn get_room_info(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<ApiResponse<RoomInfoResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
-    let room_key = require_room_key(&headers)?;
+) -> Result<ApiJson<ApiResponse<RoomInfoResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let room_key = require_room_key(&state.room_service, &headers)?;
 
     let info = state.room_service.get_room_info(&room_key).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Room not found".to_string()),
                 data: None,
+                code: Some(error_code::ROOM_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -241,10 +403,11 @@ async fn get_room_info(
         is_pinned: info.is_pinned,
     };
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(response),
+        code: None,
     }))
 }
 
@@ -252,29 +415,35 @@ async fn get_room_info(
 async fn get_room_users(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<ApiResponse<Vec<UserResponse>>>, (StatusCode, Json<ApiResponse<()>>)> {
-    let room_key = require_room_key(&headers)?;
+) -> Result<ApiJson<ApiResponse<Vec<UserResponse>>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let room_key = require_room_key(&state.room_service, &headers)?;
 
     let users = state.room_service.get_room_users(&room_key);
     let response: Vec<UserResponse> = users.iter().map(UserResponse::from).collect();
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(response),
+        code: None,
     }))
 }
 
-/// GET /api/rooms/messages?limit=N (requires x-room-key header)
+/// GET /api/rooms/messages?limit=N&afterSeq=S (requires x-room-key header)
 async fn get_room_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<MessagesQuery>,
-) -> Result<Json<ApiResponse<Vec<Message>>>, (StatusCode, Json<ApiResponse<()>>)> {
-    let room_key = require_room_key(&headers)?;
+) -> Result<ApiJson<ApiResponse<Vec<Message>>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let room_key = require_room_key(&state.room_service, &headers)?;
 
     let mut messages = state.room_service.get_messages(&room_key);
 
+    // Backfill: only messages after the last seq the client has seen
+    if let Some(after_seq) = query.after_seq {
+        messages.retain(|m| m.seq > after_seq);
+    }
+
     // Apply limit if specified
     if let Some(limit) = query.limit
         && messages.len() > limit
@@ -282,18 +451,19 @@ async fn get_room_messages(
         messages = messages.into_iter().rev().take(limit).rev().collect();
     }
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(messages),
+        code: None,
     }))
 }
 
 /// GET /api/rooms/stats
-async fn get_stats(State(state): State<AppState>) -> Json<ApiResponse<RoomStats>> {
+async fn get_stats(State(state): State<AppState>) -> ApiJson<ApiResponse<RoomStats>> {
     let stats = state.room_service.get_room_stats();
 
-    Json(ApiResponse {
+    ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(RoomStats {
@@ -302,6 +472,7 @@ async fn get_stats(State(state): State<AppState>) -> Json<ApiResponse<RoomStats>
             online_users: stats.online_users,
             total_messages: 0, // TODO: track total messages
         }),
+        code: None,
     })
 }
 
@@ -309,11 +480,11 @@ async fn get_stats(State(state): State<AppState>) -> Json<ApiResponse<RoomStats>
 async fn validate_user(
     State(state): State<AppState>,
     Json(payload): Json<ValidateUserRequest>,
-) -> Json<ApiResponse<ValidateUserData>> {
+) -> ApiJson<ApiResponse<ValidateUserData>> {
     let room_exists = state.room_service.room_exists(&payload.room_key);
 
     if !room_exists {
-        return Json(ApiResponse {
+        return ApiJson(ApiResponse {
             success: false,
             message: Some("Room not found".to_string()),
             data: Some(ValidateUserData {
@@ -321,6 +492,7 @@ async fn validate_user(
                 user_exists: false,
                 user: None,
             }),
+            code: Some(error_code::ROOM_NOT_FOUND.to_string()),
         });
     }
 
@@ -332,7 +504,7 @@ async fn validate_user(
     match found_user {
         Some(user) => {
             let user_response = UserResponse::from(&user);
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: true,
                 message: None,
                 data: Some(ValidateUserData {
@@ -340,9 +512,10 @@ async fn validate_user(
                     user_exists: true,
                     user: Some(user_response),
                 }),
+                code: None,
             })
         }
-        None => Json(ApiResponse {
+        None => ApiJson(ApiResponse {
             success: true,
             message: None,
             data: Some(ValidateUserData {
@@ -350,6 +523,7 @@ async fn validate_user(
                 user_exists: false,
                 user: None,
             }),
+            code: None,
         }),
     }
 }
@@ -358,14 +532,15 @@ async fn validate_user(
 async fn get_room_by_path(
     State(state): State<AppState>,
     Path(room_key): Path<String>,
-) -> Result<Json<ApiResponse<RoomInfoResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<RoomInfoResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     let info = state.room_service.get_room_info(&room_key).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Room not found".to_string()),
                 data: None,
+                code: Some(error_code::ROOM_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -383,10 +558,11 @@ async fn get_room_by_path(
         is_pinned: info.is_pinned,
     };
 
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(response),
+        code: None,
     }))
 }
 
@@ -394,42 +570,195 @@ async fn get_room_by_path(
 async fn room_exists(
     State(state): State<AppState>,
     Path(room_key): Path<String>,
-) -> Json<ApiResponse<RoomExistsData>> {
+) -> ApiJson<ApiResponse<RoomExistsData>> {
     let exists = state.room_service.room_exists(&room_key);
     let has_password = state.room_service.room_has_password(&room_key);
 
-    Json(ApiResponse {
+    ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(RoomExistsData {
             exists,
             has_password,
         }),
+        code: None,
     })
 }
 
 /// POST /api/rooms/:roomKey/verify-password
+///
+/// Failures count against the same per-IP lockout as password joins over the socket.
 async fn verify_password(
     State(state): State<AppState>,
     Path(room_key): Path<String>,
+    headers: HeaderMap,
     Json(payload): Json<VerifyPasswordRequest>,
-) -> Result<Json<ApiResponse<PasswordVerifyData>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<
+    ApiJson<ApiResponse<PasswordVerifyData>>,
+    (StatusCode, HeaderMap, ApiJson<ApiResponse<()>>),
+> {
+    let client_ip = extract_client_ip(&headers);
+    if let Some(remaining) = state
+        .room_service
+        .password_lockout_remaining(&room_key, &client_ip)
+    {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, remaining.as_secs().max(1).into());
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            headers,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(
+                    "Too many failed password attempts. Please try again later.".to_string(),
+                ),
+                data: None,
+                code: Some(error_code::TOO_MANY_ATTEMPTS.to_string()),
+            }),
+        ));
+    }
+
     match state
         .room_service
         .verify_room_password(&room_key, &payload.password)
     {
-        Ok(valid) => Ok(Json(ApiResponse {
-            success: true,
-            message: None,
-            data: Some(PasswordVerifyData { valid }),
-        })),
+        Ok(valid) => {
+            if valid {
+                state
+                    .room_service
+                    .reset_password_failures(&room_key, &client_ip);
+            } else {
+                state
+                    .room_service
+                    .record_password_failure(&room_key, &client_ip);
+            }
+            Ok(ApiJson(ApiResponse {
+                success: true,
+                message: None,
+                data: Some(PasswordVerifyData { valid }),
+                code: None,
+            }))
+        }
         Err(e) => Err((
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            HeaderMap::new(),
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(e),
                 data: None,
+                code: None,
             }),
         )),
     }
 }
+
+/// GET /api/rooms/{room_key}/audit (owner only, requires x-owner-token header)
+async fn get_audit_log(
+    State(state): State<AppState>,
+    Path(room_key): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiJson<ApiResponse<Vec<AuditEntry>>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let fingerprint = require_owner(&state, &room_key, &headers)?;
+
+    let entries = state
+        .room_service
+        .get_audit_log(&room_key, &fingerprint)
+        .map_err(room_error)?;
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(entries),
+        code: None,
+    }))
+}
+
+/// PUT /api/rooms/{room_key}/rate-limits (owner only, requires x-owner-token header)
+async fn set_rate_limits(
+    State(state): State<AppState>,
+    Path(room_key): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SetRateLimitsRequest>,
+) -> Result<ApiJson<ApiResponse<RoomConfig>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let fingerprint = require_owner(&state, &room_key, &headers)?;
+
+    let config = state
+        .room_service
+        .set_room_rate_limits(&room_key, &fingerprint, payload.rate_limits)
+        .map_err(room_error)?;
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: Some("Rate limits updated".to_string()),
+        data: Some(config),
+        code: None,
+    }))
+}
+
+/// PUT /api/rooms/{room_key}/message-rate-limit (owner only, requires x-owner-token header)
+async fn set_message_rate_limit(
+    State(state): State<AppState>,
+    Path(room_key): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SetMessageRateLimitRequest>,
+) -> Result<ApiJson<ApiResponse<RoomConfig>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let fingerprint = require_owner(&state, &room_key, &headers)?;
+
+    let config = state
+        .room_service
+        .set_room_message_rate_limit(&room_key, &fingerprint, payload.messages_per_minute)
+        .map_err(room_error)?;
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: Some("Message rate limit updated".to_string()),
+        data: Some(config),
+        code: None,
+    }))
+}
+
+/// PUT /api/rooms/{room_key}/persistence (owner only, requires x-owner-token header)
+async fn set_persistence(
+    State(state): State<AppState>,
+    Path(room_key): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SetPersistenceRequest>,
+) -> Result<ApiJson<ApiResponse<RoomConfig>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let fingerprint = require_owner(&state, &room_key, &headers)?;
+
+    let config = state
+        .room_service
+        .set_persist_messages(&room_key, &fingerprint, payload.persist_messages)
+        .map_err(room_error)?;
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: Some("Message persistence updated".to_string()),
+        data: Some(config),
+        code: None,
+    }))
+}
+
+/// DELETE /api/rooms/{room_key} (admin only, requires admin token)
+async fn destroy_room(
+    State(state): State<AppState>,
+    Path(room_key): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiJson<ApiResponse<()>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    if !state.room_service.destroy_room(&room_key) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Room not found".to_string()),
+                data: None,
+                code: Some(error_code::ROOM_NOT_FOUND.to_string()),
+            }),
+        ));
+    }
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: Some("Room destroyed".to_string()),
+        data: None,
+        code: None,
+    }))
+}
@@ -1,17 +1,20 @@
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
 use axum::{
     Json, Router,
     body::Body,
-    extract::{Path, Query, State},
+    extract::{Path, Query, RawQuery, State},
     http::{HeaderMap, HeaderName, StatusCode, header},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
 };
 use base64::{Engine, engine::general_purpose};
 use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
 use tokio_util::io::ReaderStream;
 
 // Download timeout configuration (matching Node.js DOWNLOAD_TIMEOUT env var, default 30s)
@@ -24,75 +27,72 @@ static DOWNLOAD_TIMEOUT: std::sync::LazyLock<std::time::Duration> =
         std::time::Duration::from_millis(timeout_ms)
     });
 
-use super::ApiResponse;
+use super::{ApiJson, ApiResponse, error_code};
 use crate::AppState;
 use crate::middleware::rate_limit::extract_client_ip;
-
-// ============= Stream & Bandwidth Tracking =============
-
-/// Global concurrent stream counter
-static ACTIVE_STREAMS: AtomicUsize = AtomicUsize::new(0);
-
-/// Per-IP concurrent stream tracker
-static PER_IP_STREAMS: std::sync::LazyLock<std::sync::Mutex<HashMap<String, usize>>> =
-    std::sync::LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
-
-const MAX_CONCURRENT_GLOBAL: usize = 100;
-const MAX_CONCURRENT_PER_IP: usize = 5;
-
-/// RAII guard for stream tracking - decrements counter on drop
-struct StreamGuard {
-    ip: String,
+use crate::models::ShareInfo;
+use crate::models::share::BUNDLE_FILENAME;
+use crate::services::RotatePasswordError;
+use crate::services::file_manager::{FileInfo, stored_file_reader};
+use crate::utils::{ShareUrlSigner, StreamGuard, StreamPool, redact_ip, throttle_download};
+
+/// Signs public share URLs when SIGNED_SHARE_URLS=true (keyed by SHARE_URL_SECRET)
+static SHARE_URL_SIGNER: std::sync::LazyLock<Option<ShareUrlSigner>> =
+    std::sync::LazyLock::new(ShareUrlSigner::from_env);
+
+/// Whether public share URLs are signed (SIGNED_SHARE_URLS)
+pub fn signed_share_urls_enabled() -> bool {
+    SHARE_URL_SIGNER.is_some()
 }
 
-impl StreamGuard {
-    fn acquire(ip: String) -> Result<Self, ()> {
-        // Atomic check-and-increment for global counter
-        let result = ACTIVE_STREAMS.fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
-            if current >= MAX_CONCURRENT_GLOBAL {
-                None // Abort update, limit exceeded
-            } else {
-                Some(current + 1) // Increment
-            }
-        });
+/// Public download URL for a share: signed when SIGNED_SHARE_URLS is on, with the
+/// password appended when one should be embedded
+fn public_share_url(base_url: &str, share: &ShareInfo, password: Option<&str>) -> String {
+    let mut url = format!(
+        "{}{}/public/file/{}",
+        base_url,
+        super::get_base_path(),
+        share.share_id
+    );
+    let mut params = Vec::new();
+    if let Some(signer) = SHARE_URL_SIGNER.as_ref() {
+        params.push(signer.query(&share.share_id, share.expires_at.timestamp()));
+    }
+    if let Some(pwd) = password {
+        params.push(format!(
+            "password={}",
+            utf8_percent_encode(pwd, NON_ALPHANUMERIC)
+        ));
+    }
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&params.join("&"));
+    }
+    url
+}
 
-        if result.is_err() {
-            return Err(()); // Global limit exceeded
-        }
+/// What a short link's signature covers: its code, kept apart from share IDs so a
+/// signature for one can't be replayed as the other
+fn short_link_subject(code: &str) -> String {
+    format!("s/{}", code)
+}
 
-        // Check per-IP limit
-        let mut map = PER_IP_STREAMS.lock().map_err(|_| ())?;
-        let count = map.entry(ip.clone()).or_insert(0);
-        if *count >= MAX_CONCURRENT_PER_IP {
-            // Rollback global counter
-            ACTIVE_STREAMS.fetch_sub(1, Ordering::AcqRel);
-            return Err(());
-        }
-        *count += 1;
-        drop(map);
+// ============= Stream & Bandwidth Tracking =============
 
-        Ok(StreamGuard { ip })
-    }
-}
+/// Concurrent public share download streams (SHARE_MAX_CONCURRENT_*)
+pub static SHARE_STREAMS: std::sync::LazyLock<StreamPool> =
+    std::sync::LazyLock::new(|| StreamPool::from_env("SHARE", 100, 5));
 
-impl Drop for StreamGuard {
-    fn drop(&mut self) {
-        ACTIVE_STREAMS.fetch_sub(1, Ordering::AcqRel);
-        if let Ok(mut map) = PER_IP_STREAMS.lock()
-            && let Some(count) = map.get_mut(&self.ip)
-        {
-            *count = count.saturating_sub(1);
-            if *count == 0 {
-                map.remove(&self.ip);
-            }
-        }
-    }
+/// Number of public download streams currently in flight
+pub fn active_stream_count() -> usize {
+    SHARE_STREAMS.active()
 }
 
 /// Per-IP bandwidth tracker
 pub struct BandwidthTracker {
     entries: std::sync::RwLock<HashMap<String, BandwidthEntry>>,
     max_bytes_per_minute: u64,
+    window: Duration,
 }
 
 struct BandwidthEntry {
@@ -120,17 +120,31 @@ impl BandwidthTracker {
             .and_then(|v| v.parse().ok())
             .unwrap_or(max_file_size * 10); // MAX_FILE_SIZE * 10 default
 
+        Self::with_limit(max_bytes)
+    }
+
+    /// Tracker allowing `max_bytes_per_minute` per IP in each window
+    pub fn with_limit(max_bytes_per_minute: u64) -> Self {
         Self {
             entries: std::sync::RwLock::new(HashMap::new()),
-            max_bytes_per_minute: max_bytes,
+            max_bytes_per_minute,
+            window: Duration::from_secs(60),
         }
     }
 
-    pub fn check_and_record(&self, ip: &str, bytes: u64) -> bool {
+    /// Override the accounting window (1 minute by default)
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Record `bytes` for `ip` if it fits in the current window. On rejection returns
+    /// the whole seconds (at least 1) until the window resets.
+    pub fn check_and_record(&self, ip: &str, bytes: u64) -> Result<(), u64> {
         let now = Instant::now();
         let mut entries = match self.entries.write() {
             Ok(e) => e,
-            Err(_) => return true, // Allow on lock error
+            Err(_) => return Ok(()), // Allow on lock error
         };
 
         let entry = entries.entry(ip.to_string()).or_insert(BandwidthEntry {
@@ -138,18 +152,21 @@ impl BandwidthTracker {
             window_start: now,
         });
 
-        // Reset window if expired (1 minute)
-        if now.duration_since(entry.window_start).as_secs() >= 60 {
+        // Reset window if expired
+        if now.duration_since(entry.window_start) >= self.window {
             entry.bytes = 0;
             entry.window_start = now;
         }
 
         if entry.bytes + bytes > self.max_bytes_per_minute {
-            return false;
+            let remaining = self
+                .window
+                .saturating_sub(now.duration_since(entry.window_start));
+            return Err(remaining.as_secs_f64().ceil().max(1.0) as u64);
         }
 
         entry.bytes += bytes;
-        true
+        Ok(())
     }
 }
 
@@ -162,9 +179,16 @@ static BANDWIDTH_TRACKER: std::sync::LazyLock<BandwidthTracker> =
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateShareRequest {
+    #[serde(default)]
     pub file_id: String,
+    /// Several files shared as one zip bundle (takes precedence over `fileId`)
+    pub file_ids: Option<Vec<String>>,
     pub expires_in_days: Option<i64>,
     pub password: Option<String>,
+    pub download_as: Option<String>,
+    /// Remove the shared file(s) from the room listing; they stay downloadable via the share
+    #[serde(default)]
+    pub detach_from_room: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -181,6 +205,18 @@ pub struct CreateShareResponse {
     pub access_count: u64,
 }
 
+/// Maximum number of share IDs accepted by POST /api/share/batch
+pub const MAX_BATCH_SHARE_IDS: usize = 100;
+
+/// Maximum number of files in a multi-file (zip) share
+pub const MAX_BUNDLE_FILES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSharesRequest {
+    pub share_ids: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListSharesQuery {
@@ -213,6 +249,13 @@ pub struct ShareListResponse {
     pub offset: usize,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortLinkResponse {
+    pub code: String,
+    pub short_url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AccessLogsResponse {
     pub logs: Vec<crate::models::ShareAccessLog>,
@@ -222,6 +265,12 @@ pub struct AccessLogsResponse {
 #[derive(Debug, Deserialize)]
 pub struct DownloadQuery {
     pub password: Option<String>,
+    /// Signature expiry (unix seconds), required with SIGNED_SHARE_URLS
+    pub exp: Option<i64>,
+    /// HMAC over the share ID and `exp`, required with SIGNED_SHARE_URLS
+    pub sig: Option<String>,
+    /// `inline` asks for in-browser display; honored only for safe previewable types
+    pub disposition: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -239,22 +288,53 @@ fn extract_user_id(headers: &HeaderMap) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// `data` of a download rejection the client may retry later (429)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryAfter {
+    pub retry_after_secs: u64,
+}
+
 /// Build a standard error response for public_download (3-tuple with empty headers)
 fn download_error(
     status: StatusCode,
+    code: &str,
     message: &str,
-) -> (StatusCode, HeaderMap, Json<ApiResponse<()>>) {
+) -> (StatusCode, HeaderMap, ApiJson<ApiResponse<RetryAfter>>) {
     (
         status,
         HeaderMap::new(),
-        Json(ApiResponse {
+        ApiJson(ApiResponse {
             success: false,
             message: Some(message.to_string()),
             data: None,
+            code: Some(code.to_string()),
         }),
     )
 }
 
+/// 429 for an exhausted bandwidth window, telling the client when it resets
+fn bandwidth_limited(
+    retry_after_secs: u64,
+) -> (StatusCode, HeaderMap, ApiJson<ApiResponse<RetryAfter>>) {
+    let (status, mut headers, mut body) = download_error(
+        StatusCode::TOO_MANY_REQUESTS,
+        error_code::RATE_LIMITED,
+        &format!(
+            "Download bandwidth limit exceeded. Please try again in {} seconds.",
+            retry_after_secs
+        ),
+    );
+    headers.insert(header::RETRY_AFTER, retry_after_secs.into());
+    let reset_at = chrono::Utc::now().timestamp() + retry_after_secs as i64;
+    headers.insert(
+        HeaderName::from_static("x-ratelimit-reset"),
+        reset_at.into(),
+    );
+    body.0.data = Some(RetryAfter { retry_after_secs });
+    (status, headers, body)
+}
+
 /// Extract password from Authorization: Basic <base64> header
 /// Basic Auth format: base64("username:password"), username can be empty
 fn extract_basic_auth_password(headers: &HeaderMap) -> Option<String> {
@@ -292,6 +372,8 @@ pub fn router() -> Router<AppState> {
     // Create route: POST /
     let create_routes = Router::new()
         .route("/", post(create_share))
+        .route("/{share_id}/shorten", post(shorten_share))
+        .route("/{share_id}/rotate-password", post(rotate_share_password))
         .layer(create_limiter);
 
     // List route: GET /
@@ -301,6 +383,7 @@ pub fn router() -> Router<AppState> {
 
     // Detail/delete routes
     let detail_routes = Router::new()
+        .route("/batch", post(batch_get_shares))
         .route("/{share_id}", get(get_share))
         .layer(list_limiter);
 
@@ -330,40 +413,65 @@ async fn create_share(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<CreateShareRequest>,
-) -> Result<Json<ApiResponse<CreateShareResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
-    let expires_in_days = payload.expires_in_days.unwrap_or(7);
+) -> Result<ApiJson<ApiResponse<CreateShareResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let (min_days, max_days) = state.share_service.expiration_bounds();
+    let expires_in_days = payload
+        .expires_in_days
+        .unwrap_or_else(|| state.share_service.default_expiration());
 
-    if !(1..=30).contains(&expires_in_days) {
+    if !state.share_service.is_valid_expiration(expires_in_days) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
-                message: Some("Expiration must be 1-30 days".to_string()),
+                message: Some(format!("Expiration must be {}-{} days", min_days, max_days)),
                 data: None,
+                code: None,
+            }),
+        ));
+    }
+
+    if let Some(ref name) = payload.download_as
+        && let Err(e) = crate::utils::validate_download_name(name)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(e.to_string()),
+                data: None,
+                code: None,
             }),
         ));
     }
 
     // Look up file info from FileManager using fileId (matching Node.js behavior)
-    let file_info = state
-        .file_manager
-        .get_file(&payload.file_id)
-        .ok_or_else(|| {
+    let file_ids = match &payload.file_ids {
+        Some(ids) if !ids.is_empty() => ids.clone(),
+        _ => vec![payload.file_id.clone()],
+    };
+    let files = resolve_share_files(&state, &file_ids)?;
+
+    let (file_path, file_name, file_size, room_key, original_filename, bundle_files) =
+        if let [file_info] = files.as_slice() {
             (
-                StatusCode::NOT_FOUND,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some("File not found".to_string()),
-                    data: None,
-                }),
+                file_info.path.to_string_lossy().to_string(),
+                file_info.filename.clone(),
+                file_info.size,
+                file_info.room_key.clone(),
+                file_info.original_name.clone(),
+                Vec::new(),
             )
-        })?;
-
-    let file_path = file_info.path.to_string_lossy().to_string();
-    let file_name = file_info.filename.clone();
-    let file_size = file_info.size;
-    let room_key = file_info.room_key.clone();
-    let original_filename = file_info.original_name.clone();
+        } else {
+            (
+                String::new(),
+                BUNDLE_FILENAME.to_string(),
+                files.iter().map(|f| f.size).sum(),
+                files[0].room_key.clone(),
+                BUNDLE_FILENAME.to_string(),
+                files.iter().map(|f| f.filename.clone()).collect(),
+            )
+        };
 
     // Use createdBy from request body or fallback
     let user_id = extract_user_id(&headers).unwrap_or_else(|| "temp-user-id".to_string());
@@ -393,28 +501,33 @@ async fn create_share(
             enable_password,
             password: None, // Never pass password directly; auto-generate if enabled
             metadata,
+            download_as: payload.download_as.clone(),
+            bundle_files,
         }) {
         Ok((share, generated_password)) => {
+            if payload.detach_from_room {
+                for file in &files {
+                    if let Err(e) = state
+                        .file_manager
+                        .detach_from_room(&file.filename, share.expires_at)
+                    {
+                        tracing::warn!("Failed to detach shared file {}: {}", file.filename, e);
+                    }
+                }
+            }
+
             // Generate full share URL using base URL and BASE_PATH
-            let base_url = super::build_base_url(&headers);
-            let base_path = super::get_base_path();
-            let mut share_url = format!("{}{}/public/file/{}", base_url, base_path, share.share_id);
-            // Append password to URL if available
+            let base_url = super::build_base_url(&headers)?;
             let generated_password_string = generated_password.map(|s| s.to_string());
-            if let Some(ref pwd) = generated_password_string {
-                share_url = format!(
-                    "{}?password={}",
-                    share_url,
-                    utf8_percent_encode(pwd, NON_ALPHANUMERIC)
-                );
-            }
+            let share_url =
+                public_share_url(&base_url, &share, generated_password_string.as_deref());
             let has_password = share.has_password();
-            Ok(Json(ApiResponse {
+            Ok(ApiJson(ApiResponse {
                 success: true,
                 message: Some("Share link created successfully".to_string()),
                 data: Some(CreateShareResponse {
                     share_id: share.share_id,
-                    file_id: payload.file_id,
+                    file_id: file_ids[0].clone(),
                     has_password,
                     created_by: share.created_by,
                     url: share_url,
@@ -423,25 +536,92 @@ async fn create_share(
                     expires_at: share.expires_at.to_rfc3339(),
                     access_count: 0,
                 }),
+                code: None,
             }))
         }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(e),
                 data: None,
+                code: None,
             }),
         )),
     }
 }
 
+/// Look up the files for a new share. Several ids form a zip bundle: they must be
+/// distinct, come from one room and stay within the upload size limit in total.
+fn resolve_share_files(
+    state: &AppState,
+    file_ids: &[String],
+) -> Result<Vec<FileInfo>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(message),
+                data: None,
+                code: Some(error_code::INVALID_REQUEST.to_string()),
+            }),
+        )
+    };
+
+    if file_ids.len() > MAX_BUNDLE_FILES {
+        return Err(bad_request(format!(
+            "Too many files (max {})",
+            MAX_BUNDLE_FILES
+        )));
+    }
+
+    let mut files: Vec<FileInfo> = Vec::with_capacity(file_ids.len());
+    for file_id in file_ids {
+        if files.iter().any(|f| &f.filename == file_id) {
+            return Err(bad_request("Duplicate file ID".to_string()));
+        }
+        // Tracked metadata alone isn't enough: a file removed from disk would only
+        // fail later, at download time
+        let file_info = state
+            .file_manager
+            .get_room_file(file_id)
+            .filter(|f| f.path.is_file())
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    ApiJson(ApiResponse {
+                        success: false,
+                        message: Some("File not found".to_string()),
+                        data: None,
+                        code: Some(error_code::FILE_NOT_FOUND.to_string()),
+                    }),
+                )
+            })?;
+        if files
+            .first()
+            .is_some_and(|f| f.room_key != file_info.room_key)
+        {
+            return Err(bad_request(
+                "All files must belong to the same room".to_string(),
+            ));
+        }
+        files.push(file_info);
+    }
+
+    let total: u64 = files.iter().map(|f| f.size).sum();
+    if files.len() > 1 && total > state.file_manager.max_file_size() {
+        return Err(bad_request("Files exceed maximum share size".to_string()));
+    }
+    Ok(files)
+}
+
 /// GET /api/share
 async fn list_shares(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<ListSharesQuery>,
-) -> Json<ApiResponse<ShareListResponse>> {
+) -> Result<ApiJson<ApiResponse<ShareListResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Get user_id from header or query
     let user_id = extract_user_id(&headers)
         .or(query.user_id)
@@ -452,8 +632,7 @@ async fn list_shares(
     let offset = query.offset.unwrap_or(0);
 
     // Build base URL for share links
-    let base_url = super::build_base_url(&headers);
-    let base_path = super::get_base_path();
+    let base_url = super::build_base_url(&headers)?;
 
     // Get all user's shares
     let all_shares = state.share_service.get_user_shares(&user_id);
@@ -483,20 +662,13 @@ async fn list_shares(
             } else {
                 "expired"
             };
-            let mut url = format!("{}{}/public/file/{}", base_url, base_path, share.share_id);
             // Append password to URL if available in metadata
-            if let Some(pwd) = share
+            let password = share
                 .metadata
                 .as_ref()
                 .and_then(|m| m.get("plainPassword"))
-                .and_then(|v| v.as_str())
-            {
-                url = format!(
-                    "{}?password={}",
-                    url,
-                    utf8_percent_encode(pwd, NON_ALPHANUMERIC)
-                );
-            }
+                .and_then(|v| v.as_str());
+            let url = public_share_url(&base_url, &share, password);
             // Use originalFilename from metadata if available, fallback to file_name
             let original_filename = share
                 .metadata
@@ -519,7 +691,7 @@ async fn list_shares(
         })
         .collect();
 
-    Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(ShareListResponse {
@@ -528,7 +700,8 @@ async fn list_shares(
             limit,
             offset,
         }),
-    })
+        code: None,
+    }))
 }
 
 /// GET /api/share/:shareId
@@ -536,40 +709,87 @@ async fn get_share(
     State(state): State<AppState>,
     Path(share_id): Path<String>,
 ) -> Result<
-    Json<ApiResponse<crate::models::share::ShareInfoResponse>>,
-    (StatusCode, Json<ApiResponse<()>>),
+    ApiJson<ApiResponse<crate::models::share::ShareInfoResponse>>,
+    (StatusCode, ApiJson<ApiResponse<()>>),
 > {
     match state.share_service.get_share_info(&share_id) {
-        Some(info) => Ok(Json(ApiResponse {
+        Some(info) => Ok(ApiJson(ApiResponse {
             success: true,
             message: None,
             data: Some(info),
+            code: None,
         })),
         None => Err((
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         )),
     }
 }
 
+/// POST /api/share/batch
+async fn batch_get_shares(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BatchSharesRequest>,
+) -> Result<
+    ApiJson<ApiResponse<HashMap<String, crate::models::share::ShareInfoResponse>>>,
+    (StatusCode, ApiJson<ApiResponse<()>>),
+> {
+    let user_id = extract_user_id(&headers).ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("User ID required (x-user-id header)".to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    })?;
+
+    if payload.share_ids.len() > MAX_BATCH_SHARE_IDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(format!("Too many share IDs (max {})", MAX_BATCH_SHARE_IDS)),
+                data: None,
+                code: None,
+            }),
+        ));
+    }
+
+    let shares = state
+        .share_service
+        .get_owned_share_infos(&payload.share_ids, &user_id);
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(shares),
+        code: None,
+    }))
+}
+
 /// DELETE /api/share/:shareId
 async fn delete_share(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(share_id): Path<String>,
-) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<()>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Require user_id for ownership verification
     let user_id = extract_user_id(&headers).ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("User ID required (x-user-id header)".to_string()),
                 data: None,
+                code: None,
             }),
         )
     })?;
@@ -578,10 +798,11 @@ async fn delete_share(
     let share = state.share_service.get_share(&share_id).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -589,34 +810,38 @@ async fn delete_share(
     if share.created_by != user_id {
         return Err((
             StatusCode::FORBIDDEN,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("You do not have permission to revoke this share".to_string()),
                 data: None,
+                code: None,
             }),
         ));
     }
 
     match state.share_service.revoke_share(&share_id) {
-        Ok(true) => Ok(Json(ApiResponse {
+        Ok(true) => Ok(ApiJson(ApiResponse {
             success: true,
             message: Some("Share revoked".to_string()),
             data: None,
+            code: None,
         })),
         Ok(false) => Err((
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(e),
                 data: None,
+                code: None,
             }),
         )),
     }
@@ -628,7 +853,7 @@ async fn permanent_delete(
     headers: HeaderMap,
     Path(share_id): Path<String>,
     payload: Option<Json<PermanentDeleteRequest>>,
-) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<()>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     // Get user_id from header or body
     let user_id = extract_user_id(&headers)
         .or_else(|| payload.and_then(|p| p.0.user_id))
@@ -638,10 +863,11 @@ async fn permanent_delete(
     let share = state.share_service.get_share(&share_id).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         )
     })?;
@@ -650,35 +876,39 @@ async fn permanent_delete(
     if share.created_by != user_id {
         return Err((
             StatusCode::FORBIDDEN,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("You do not have permission to delete this share".to_string()),
                 data: None,
+                code: None,
             }),
         ));
     }
 
     // Permanently delete
     match state.share_service.delete_share(&share_id) {
-        Ok(Some(_)) => Ok(Json(ApiResponse {
+        Ok(Some(_)) => Ok(ApiJson(ApiResponse {
             success: true,
             message: Some("Share permanently deleted".to_string()),
             data: None,
+            code: None,
         })),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some(e),
                 data: None,
+                code: None,
             }),
         )),
     }
@@ -688,38 +918,282 @@ async fn permanent_delete(
 async fn get_access_logs(
     State(state): State<AppState>,
     Path(share_id): Path<String>,
-) -> Result<Json<ApiResponse<AccessLogsResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<ApiJson<ApiResponse<AccessLogsResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
     if state.share_service.get_share(&share_id).is_none() {
         return Err((
             StatusCode::NOT_FOUND,
-            Json(ApiResponse {
+            ApiJson(ApiResponse {
                 success: false,
                 message: Some("Share not found".to_string()),
                 data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
             }),
         ));
     }
 
     let logs = state.share_service.get_access_logs(&share_id);
     let total = logs.len();
-    Ok(Json(ApiResponse {
+    Ok(ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(AccessLogsResponse { logs, total }),
+        code: None,
+    }))
+}
+
+/// POST /api/share/:shareId/shorten - Short link for one of the caller's shares
+async fn shorten_share(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(share_id): Path<String>,
+) -> Result<ApiJson<ApiResponse<ShortLinkResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let user_id = extract_user_id(&headers).ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("User ID required (x-user-id header)".to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    })?;
+
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Share not found".to_string()),
+                data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
+            }),
+        )
+    };
+    let share = state
+        .share_service
+        .get_share(&share_id)
+        .ok_or_else(not_found)?;
+    if share.created_by != user_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("You do not have permission to shorten this share".to_string()),
+                data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
+            }),
+        ));
+    }
+
+    let code = state
+        .share_service
+        .shorten_share(&share_id)
+        .map_err(|_| not_found())?;
+    let base_url = super::build_base_url(&headers)?;
+    let mut short_url = format!("{}{}/s/{}", base_url, super::get_base_path(), code);
+    // Short codes are easy to enumerate, so with SIGNED_SHARE_URLS they need a signature too
+    if let Some(signer) = SHARE_URL_SIGNER.as_ref() {
+        short_url.push('?');
+        short_url.push_str(&signer.query(&short_link_subject(&code), share.expires_at.timestamp()));
+    }
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(ShortLinkResponse { code, short_url }),
+        code: None,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatePasswordRequest {
+    /// New password (6-64 chars); omitted to generate one
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatePasswordResponse {
+    pub share_id: String,
+    /// Returned only here; it is not stored in plaintext
+    pub password: String,
+}
+
+/// POST /api/share/:shareId/rotate-password - Replace a leaked share password
+async fn rotate_share_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(share_id): Path<String>,
+    payload: Option<Json<RotatePasswordRequest>>,
+) -> Result<ApiJson<ApiResponse<RotatePasswordResponse>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    let user_id = extract_user_id(&headers).ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("User ID required (x-user-id header)".to_string()),
+                data: None,
+                code: None,
+            }),
+        )
+    })?;
+
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Share not found".to_string()),
+                data: None,
+                code: Some(error_code::SHARE_NOT_FOUND.to_string()),
+            }),
+        )
+    };
+    let share = state
+        .share_service
+        .get_share(&share_id)
+        .ok_or_else(not_found)?;
+    if share.created_by != user_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some(
+                    "You do not have permission to change this share's password".to_string(),
+                ),
+                data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
+            }),
+        ));
+    }
+
+    let new_password = payload.and_then(|Json(p)| p.password);
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let password = state
+        .share_service
+        .rotate_password(
+            &share_id,
+            new_password.as_deref(),
+            extract_client_ip(&headers),
+            user_agent,
+        )
+        .map_err(|e| {
+            let (status, code) = match e {
+                RotatePasswordError::NotFound => return not_found(),
+                RotatePasswordError::Invalid(_) => {
+                    (StatusCode::BAD_REQUEST, error_code::INVALID_REQUEST)
+                }
+                RotatePasswordError::Internal(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    error_code::INTERNAL_ERROR,
+                ),
+            };
+            (
+                status,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some(e.to_string()),
+                    data: None,
+                    code: Some(code.to_string()),
+                }),
+            )
+        })?;
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: Some("Share password rotated".to_string()),
+        data: Some(RotatePasswordResponse { share_id, password }),
+        code: None,
+    }))
+}
+
+/// GET /s/:code - Redirect a short link to its share's public download.
+///
+/// The target is always the local `/public/file/{share_id}` path (never a
+/// client-supplied URL); the query string, e.g. `?password=`, is passed through.
+/// With SIGNED_SHARE_URLS the short link's own `exp`/`sig` must be valid.
+pub async fn resolve_short_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(signature): Query<DownloadQuery>,
+    RawQuery(query): RawQuery,
+) -> Result<Response, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    // Checked before any lookup so unsigned requests can't probe for valid codes
+    if let Some(signer) = SHARE_URL_SIGNER.as_ref()
+        && !signer.verify(
+            &short_link_subject(&code),
+            signature.exp,
+            signature.sig.as_deref(),
+        )
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            ApiJson(ApiResponse {
+                success: false,
+                message: Some("Invalid or missing short link signature".to_string()),
+                data: None,
+                code: Some(error_code::ACCESS_DENIED.to_string()),
+            }),
+        ));
+    }
+
+    let share_id = state
+        .share_service
+        .resolve_short_code(&code)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some("Short link not found".to_string()),
+                    data: None,
+                    code: Some(error_code::SHARE_NOT_FOUND.to_string()),
+                }),
+            )
+        })?;
+
+    let mut location = format!("{}/public/file/{}", super::get_base_path(), share_id);
+    let mut params = Vec::new();
+    // The signed short link proved knowledge of the share, so the redirect is signed
+    // here; the short link's own signature isn't valid for the share and is dropped
+    if let Some(signer) = SHARE_URL_SIGNER.as_ref() {
+        if let Some(share) = state.share_service.get_share(&share_id) {
+            params.push(signer.query(&share_id, share.expires_at.timestamp()));
+        }
+        params.extend(
+            query
+                .iter()
+                .flat_map(|q| q.split('&'))
+                .filter(|p| !p.is_empty() && !p.starts_with("exp=") && !p.starts_with("sig="))
+                .map(str::to_string),
+        );
+    } else {
+        params.extend(query.filter(|q| !q.is_empty()));
+    }
+    if !params.is_empty() {
+        location.push('?');
+        location.push_str(&params.join("&"));
+    }
+    Ok((StatusCode::FOUND, [(header::LOCATION, location)]).into_response())
+}
+
 /// GET /api/share/user/:userId
 async fn get_user_shares(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
-) -> Json<ApiResponse<Vec<crate::models::share::ShareInfoResponse>>> {
+) -> ApiJson<ApiResponse<Vec<crate::models::share::ShareInfoResponse>>> {
     let response = state.share_service.get_user_shares_response(&user_id);
 
-    Json(ApiResponse {
+    ApiJson(ApiResponse {
         success: true,
         message: None,
         data: Some(response),
+        code: None,
     })
 }
 
@@ -729,7 +1203,7 @@ pub async fn public_download(
     headers: HeaderMap,
     Path(share_id): Path<String>,
     Query(query): Query<DownloadQuery>,
-) -> Result<impl IntoResponse, (StatusCode, HeaderMap, Json<ApiResponse<()>>)> {
+) -> Result<Response, (StatusCode, HeaderMap, ApiJson<ApiResponse<RetryAfter>>)> {
     // Validate shareId format (8-10 character base62: [a-zA-Z0-9])
     if share_id.len() < 8
         || share_id.len() > 10
@@ -737,10 +1211,22 @@ pub async fn public_download(
     {
         return Err(download_error(
             StatusCode::BAD_REQUEST,
+            error_code::INVALID_REQUEST,
             "Invalid share ID format",
         ));
     }
 
+    // Checked before any lookup so unsigned requests can't probe for valid IDs
+    if let Some(signer) = SHARE_URL_SIGNER.as_ref()
+        && !signer.verify(&share_id, query.exp, query.sig.as_deref())
+    {
+        return Err(download_error(
+            StatusCode::FORBIDDEN,
+            error_code::ACCESS_DENIED,
+            "Invalid or missing share URL signature",
+        ));
+    }
+
     // Extract client IP early for per-IP stream limiting
     let client_ip = extract_client_ip(&headers);
 
@@ -751,36 +1237,85 @@ pub async fn public_download(
         .map(|s| s.to_string());
 
     // P2.1: Check concurrent stream limit (per-IP + global)
-    let _stream_guard = StreamGuard::acquire(client_ip.clone()).map_err(|_| {
+    let stream_guard = SHARE_STREAMS.acquire(&client_ip).ok_or_else(|| {
         download_error(
             StatusCode::SERVICE_UNAVAILABLE,
+            error_code::RATE_LIMITED,
             "Too many concurrent downloads. Please try again later.",
         )
     })?;
 
-    let share = state
-        .share_service
-        .get_share(&share_id)
-        .ok_or_else(|| download_error(StatusCode::NOT_FOUND, "Share not found"))?;
+    let share = state.share_service.get_share(&share_id).ok_or_else(|| {
+        download_error(
+            StatusCode::NOT_FOUND,
+            error_code::SHARE_NOT_FOUND,
+            "Share not found",
+        )
+    })?;
 
     // Check expiration
     if share.is_expired() {
-        return Err(download_error(StatusCode::NOT_FOUND, "Share not found"));
+        return Err(download_error(
+            StatusCode::NOT_FOUND,
+            error_code::SHARE_NOT_FOUND,
+            "Share not found",
+        ));
     }
 
     // Check if share is active
     if !share.is_active {
-        return Err(download_error(StatusCode::NOT_FOUND, "Share not found"));
+        return Err(download_error(
+            StatusCode::NOT_FOUND,
+            error_code::SHARE_NOT_FOUND,
+            "Share not found",
+        ));
     }
 
     // Verify password if required
     if share.has_password() {
+        // Reject locked-out clients before even checking the password
+        if let Some(remaining) = state
+            .share_service
+            .password_lockout_remaining(&share_id, &client_ip)
+        {
+            let _ = state.share_service.record_access(
+                &share_id,
+                client_ip,
+                false,
+                None,
+                Some("Too many failed password attempts".to_string()),
+                user_agent,
+            );
+            let retry_after_secs = remaining.as_secs().max(1);
+            let mut headers = HeaderMap::new();
+            headers.insert(header::RETRY_AFTER, retry_after_secs.into());
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                headers,
+                ApiJson(ApiResponse {
+                    success: false,
+                    message: Some(
+                        "Too many failed password attempts. Please try again later.".to_string(),
+                    ),
+                    data: Some(RetryAfter { retry_after_secs }),
+                    code: Some(error_code::TOO_MANY_ATTEMPTS.to_string()),
+                }),
+            ));
+        }
+
         // Prefer Basic Auth, fallback to query parameter
         let password = extract_basic_auth_password(&headers).or_else(|| query.password.clone());
 
         match password {
-            Some(pwd) if share.verify_password(&pwd) => {}
+            Some(pwd) if share.verify_password(&pwd) => {
+                state
+                    .share_service
+                    .reset_password_failures(&share_id, &client_ip);
+            }
             Some(_) => {
+                state
+                    .share_service
+                    .record_password_failure(&share_id, &client_ip);
                 let _ = state.share_service.record_access(
                     &share_id,
                     client_ip,
@@ -799,10 +1334,11 @@ pub async fn public_download(
                 return Err((
                     StatusCode::UNAUTHORIZED,
                     headers,
-                    Json(ApiResponse {
+                    ApiJson(ApiResponse {
                         success: false,
                         message: Some("Invalid password".to_string()),
                         data: None,
+                        code: Some(error_code::INVALID_PASSWORD.to_string()),
                     }),
                 ));
             }
@@ -817,37 +1353,203 @@ pub async fn public_download(
                 return Err((
                     StatusCode::UNAUTHORIZED,
                     headers,
-                    Json(ApiResponse {
+                    ApiJson(ApiResponse {
                         success: false,
                         message: Some("Password required".to_string()),
                         data: None,
+                        code: Some(error_code::PASSWORD_REQUIRED.to_string()),
                     }),
                 ));
             }
         }
     }
 
+    if share.is_bundle() {
+        return bundle_download(&state, &share, client_ip, user_agent, stream_guard).await;
+    }
+
     // Get file (use file_name which is the storage filename, not the full disk path)
     let file_info = state
         .file_manager
         .get_file(&share.file_name)
-        .ok_or_else(|| download_error(StatusCode::NOT_FOUND, "File not found"))?;
+        .ok_or_else(|| {
+            download_error(
+                StatusCode::NOT_FOUND,
+                error_code::FILE_NOT_FOUND,
+                "File not found",
+            )
+        })?;
 
     // P2.1: Check per-IP bandwidth limit
-    if !BANDWIDTH_TRACKER.check_and_record(&client_ip, file_info.size) {
-        return Err(download_error(
-            StatusCode::TOO_MANY_REQUESTS,
-            "Download bandwidth limit exceeded. Please try again later.",
-        ));
+    BANDWIDTH_TRACKER
+        .check_and_record(&client_ip, file_info.size)
+        .map_err(bandwidth_limited)?;
+
+    let file = open_shared_file(&state, &file_info, &share_id, &client_ip).await?;
+
+    // Record successful access
+    let _ = state.share_service.record_access(
+        &share_id,
+        client_ip,
+        true,
+        Some(file_info.size),
+        None,
+        user_agent,
+    );
+
+    let reader = stream_guard.hold_while(stored_file_reader(file, file_info.compressed));
+    let stream = ReaderStream::new(throttle_download(Box::new(reader)));
+    let body = Body::from_stream(stream);
+
+    let inline =
+        query.disposition.as_deref() == Some("inline") && is_inline_safe(&file_info.mime_type);
+    let disposition = content_disposition(
+        if inline { "inline" } else { "attachment" },
+        share.download_filename(),
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, file_info.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CONTENT_LENGTH, file_info.size.to_string()),
+            (
+                header::CACHE_CONTROL,
+                "no-store, no-cache, must-revalidate".to_string(),
+            ),
+            (
+                HeaderName::from_static("x-content-type-options"),
+                "nosniff".to_string(),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Stream a multi-file share as a zip assembled on the fly. Bandwidth is charged
+/// for the total size up front; entries are stored uncompressed.
+async fn bundle_download(
+    state: &AppState,
+    share: &ShareInfo,
+    client_ip: String,
+    user_agent: Option<String>,
+    stream_guard: StreamGuard<'static>,
+) -> Result<Response, (StatusCode, HeaderMap, ApiJson<ApiResponse<RetryAfter>>)> {
+    let files = share
+        .bundle_files
+        .iter()
+        .map(|name| state.file_manager.get_file(name))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            download_error(
+                StatusCode::NOT_FOUND,
+                error_code::FILE_NOT_FOUND,
+                "File not found",
+            )
+        })?;
+
+    let total: u64 = files.iter().map(|f| f.size).sum();
+    BANDWIDTH_TRACKER
+        .check_and_record(&client_ip, total)
+        .map_err(bandwidth_limited)?;
+
+    // Open everything before responding so a bad file fails the request, not the stream
+    let mut entries = Vec::with_capacity(files.len());
+    let mut used_names = std::collections::HashSet::new();
+    for (index, file_info) in files.iter().enumerate() {
+        let file = open_shared_file(state, file_info, &share.share_id, &client_ip).await?;
+        let mut name = file_info.original_name.clone();
+        if !used_names.insert(name.clone()) {
+            name = format!("{}_{}", index + 1, name);
+            used_names.insert(name.clone());
+        }
+        entries.push((name, stored_file_reader(file, file_info.compressed)));
     }
 
+    let _ = state.share_service.record_access(
+        &share.share_id,
+        client_ip,
+        true,
+        Some(total),
+        None,
+        user_agent,
+    );
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let share_id = share.share_id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = write_zip_bundle(writer, entries).await {
+            tracing::warn!("Zip bundle for share {} aborted: {}", share_id, e);
+        }
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment_disposition(share.download_filename()),
+            ),
+            (
+                header::CACHE_CONTROL,
+                "no-store, no-cache, must-revalidate".to_string(),
+            ),
+            (
+                HeaderName::from_static("x-content-type-options"),
+                "nosniff".to_string(),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(throttle_download(Box::new(
+            stream_guard.hold_while(reader),
+        )))),
+    )
+        .into_response())
+}
+
+/// Write `entries` (name, plaintext reader) as a zip archive into `writer`
+async fn write_zip_bundle<W>(
+    writer: W,
+    entries: Vec<(String, Box<dyn AsyncRead + Send + Unpin>)>,
+) -> anyhow::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    for (name, mut reader) in entries {
+        let entry = ZipEntryBuilder::new(name.into(), Compression::Stored);
+        let mut entry_writer = zip.write_entry_stream(entry).await?.compat_write();
+        tokio::io::copy(&mut reader, &mut entry_writer).await?;
+        entry_writer.into_inner().close().await?;
+    }
+    zip.close().await?;
+    Ok(())
+}
+
+/// Open a shared file for download, rejecting symlinks, hard links and paths
+/// outside the upload directory (P2.3 TOCTOU prevention)
+async fn open_shared_file(
+    state: &AppState,
+    file_info: &FileInfo,
+    share_id: &str,
+    client_ip: &str,
+) -> Result<tokio::fs::File, (StatusCode, HeaderMap, ApiJson<ApiResponse<RetryAfter>>)> {
     // P2.3: TOCTOU prevention - use symlink_metadata to detect symlinks
-    let metadata = std::fs::symlink_metadata(&file_info.path)
-        .map_err(|_| download_error(StatusCode::NOT_FOUND, "File not found"))?;
+    let metadata = std::fs::symlink_metadata(&file_info.path).map_err(|_| {
+        download_error(
+            StatusCode::NOT_FOUND,
+            error_code::FILE_NOT_FOUND,
+            "File not found",
+        )
+    })?;
 
     if metadata.file_type().is_symlink() {
         tracing::warn!("Symlink detected for file: {:?}", file_info.path);
-        return Err(download_error(StatusCode::FORBIDDEN, "Access denied"));
+        return Err(download_error(
+            StatusCode::FORBIDDEN,
+            error_code::ACCESS_DENIED,
+            "Access denied",
+        ));
     }
 
     // Detect hard link attacks
@@ -856,7 +1558,11 @@ pub async fn public_download(
         use std::os::unix::fs::MetadataExt;
         if metadata.nlink() > 1 {
             tracing::warn!("Hard link detected for file: {:?}", file_info.path);
-            return Err(download_error(StatusCode::FORBIDDEN, "Access denied"));
+            return Err(download_error(
+                StatusCode::FORBIDDEN,
+                error_code::ACCESS_DENIED,
+                "Access denied",
+            ));
         }
     }
 
@@ -865,85 +1571,89 @@ pub async fn public_download(
         .file_manager
         .upload_dir()
         .canonicalize()
-        .map_err(|_| download_error(StatusCode::INTERNAL_SERVER_ERROR, "Server error"))?;
+        .map_err(|_| {
+            download_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                error_code::INTERNAL_ERROR,
+                "Server error",
+            )
+        })?;
 
-    let canonical_path = file_info
-        .path
-        .canonicalize()
-        .map_err(|_| download_error(StatusCode::NOT_FOUND, "File not found"))?;
+    let canonical_path = file_info.path.canonicalize().map_err(|_| {
+        download_error(
+            StatusCode::NOT_FOUND,
+            error_code::FILE_NOT_FOUND,
+            "File not found",
+        )
+    })?;
 
     if !canonical_path.starts_with(&upload_dir) {
         tracing::warn!("Path traversal attempt: {:?}", file_info.path);
-        return Err(download_error(StatusCode::FORBIDDEN, "Access denied"));
+        return Err(download_error(
+            StatusCode::FORBIDDEN,
+            error_code::ACCESS_DENIED,
+            "Access denied",
+        ));
     }
 
     // Open file with timeout protection (matching Node.js DOWNLOAD_TIMEOUT)
-    let file = match tokio::time::timeout(*DOWNLOAD_TIMEOUT, tokio::fs::File::open(&canonical_path))
-        .await
-    {
-        Ok(Ok(f)) => f,
-        Ok(Err(_)) => {
-            return Err(download_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to open file",
-            ));
-        }
+    match tokio::time::timeout(*DOWNLOAD_TIMEOUT, tokio::fs::File::open(&canonical_path)).await {
+        Ok(Ok(f)) => Ok(f),
+        Ok(Err(_)) => Err(download_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            error_code::INTERNAL_ERROR,
+            "Failed to open file",
+        )),
         Err(_) => {
             tracing::warn!(
                 "Download timeout for shareId: {} from IP: {}",
                 share_id,
-                client_ip
+                redact_ip(client_ip)
             );
-            return Err(download_error(
+            Err(download_error(
                 StatusCode::REQUEST_TIMEOUT,
+                error_code::INTERNAL_ERROR,
                 "Download timeout",
-            ));
+            ))
         }
-    };
-
-    // Record successful access
-    let _ = state.share_service.record_access(
-        &share_id,
-        client_ip,
-        true,
-        Some(file_info.size),
-        None,
-        user_agent,
-    );
+    }
+}
 
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+/// `attachment` Content-Disposition with an RFC 5987 encoded name for non-ASCII filenames
+fn attachment_disposition(filename: &str) -> String {
+    content_disposition("attachment", filename)
+}
 
-    // Use originalFilename from metadata if available, fallback to file_name
-    let download_filename = share
-        .metadata
-        .as_ref()
-        .and_then(|m| m.get("originalFilename"))
-        .and_then(|v| v.as_str())
-        .unwrap_or(&share.file_name);
-
-    // RFC 5987 encoding for non-ASCII filenames
-    let filename_encoded = utf8_percent_encode(download_filename, NON_ALPHANUMERIC).to_string();
-    let content_disposition = format!(
-        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
-        download_filename.replace('"', "\\\""),
+fn content_disposition(kind: &str, filename: &str) -> String {
+    let filename_encoded = utf8_percent_encode(filename, NON_ALPHANUMERIC).to_string();
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        kind,
+        filename.replace('"', "\\\""),
         filename_encoded
-    );
+    )
+}
 
-    Ok((
-        [
-            (header::CONTENT_TYPE, file_info.mime_type),
-            (header::CONTENT_DISPOSITION, content_disposition),
-            (header::CONTENT_LENGTH, file_info.size.to_string()),
-            (
-                header::CACHE_CONTROL,
-                "no-store, no-cache, must-revalidate".to_string(),
-            ),
-            (
-                HeaderName::from_static("x-content-type-options"),
-                "nosniff".to_string(),
-            ),
-        ],
-        body,
-    ))
+/// MIME types safe to render in the browser: raster images, PDF and plain text.
+/// HTML, SVG and anything scriptable are always downloaded as attachments.
+fn is_inline_safe(mime_type: &str) -> bool {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    matches!(
+        essence.as_str(),
+        "image/png"
+            | "image/jpeg"
+            | "image/gif"
+            | "image/webp"
+            | "image/bmp"
+            | "image/avif"
+            | "application/pdf"
+            | "text/plain"
+            | "text/csv"
+            | "text/markdown"
+    )
 }
@@ -0,0 +1,49 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{ApiJson, ApiResponse, files, require_admin, share};
+use crate::AppState;
+use crate::services::{FileStats, RoomStats};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStats {
+    pub uptime: f64,
+    pub timestamp: String,
+    pub rooms: RoomStats,
+    pub files: FileStats,
+    pub active_downloads: usize,
+    /// Rate-limited requests per endpoint / socket event since startup
+    pub rate_limit_rejections: BTreeMap<String, u64>,
+}
+
+/// Collect aggregate stats from all services
+pub fn collect_stats(state: &AppState) -> ServerStats {
+    ServerStats {
+        uptime: state.uptime().as_secs_f64(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        rooms: state.room_service.get_room_stats(),
+        files: state.file_manager.get_stats(),
+        active_downloads: share::active_stream_count() + files::FILE_STREAMS.active(),
+        rate_limit_rejections: state.rate_limit_metrics.snapshot(),
+    }
+}
+
+/// GET /api/stats - Aggregate server stats (admin only)
+pub async fn get_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ApiJson<ApiResponse<ServerStats>>, (StatusCode, ApiJson<ApiResponse<()>>)> {
+    require_admin(&headers)?;
+
+    Ok(ApiJson(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(collect_stats(&state)),
+        code: None,
+    }))
+}
@@ -1,16 +1,250 @@
 use chrono::{DateTime, Duration, Utc};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::{
-    RwLock,
+    Arc, RwLock,
     atomic::{AtomicU64, Ordering},
 };
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::virus_scan::{ClamAvScanner, ScanVerdict, VirusScanner};
+
+/// Orphaned files younger than this are never removed (may be mid-upload)
+const ORPHAN_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// zstd level used for FILE_COMPRESSION=zstd (favours speed over ratio)
+const ZSTD_LEVEL: i32 = 3;
+
+/// MIME types that are already compressed and not worth recompressing
+const COMPRESSED_MIME_TYPES: &[&str] = &[
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/vnd.rar",
+    "application/zstd",
+    "application/pdf",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+];
+
+/// Whether a MIME type is already compressed (images except SVG, audio, video, archives)
+fn is_precompressed_mime(mime_type: &str) -> bool {
+    let mime = mime_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    if mime.starts_with("image/") {
+        return mime != "image/svg+xml" && mime != "image/bmp";
+    }
+    mime.starts_with("audio/")
+        || mime.starts_with("video/")
+        || COMPRESSED_MIME_TYPES.contains(&mime.as_str())
+}
+
+/// Reader yielding the original bytes of a stored file, decompressing if it was stored compressed
+pub fn stored_file_reader(file: fs::File, compressed: bool) -> Box<dyn AsyncRead + Send + Unpin> {
+    if compressed {
+        Box::new(async_compression::tokio::bufread::ZstdDecoder::new(
+            BufReader::new(file),
+        ))
+    } else {
+        Box::new(file)
+    }
+}
+
+/// Run blocking filesystem work on its own thread, giving up after `limit` (the thread
+/// is left behind if the operation never returns). Async callers wrap it in `spawn_blocking`.
+fn blocking_storage_io<T: Send + 'static>(
+    limit: std::time::Duration,
+    op: &'static str,
+    f: impl FnOnce() -> std::io::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    match rx.recv_timeout(limit) {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(StorageTimeout(op).into()),
+    }
+}
+
+/// Zstd-compress `plain` into `compressed`, returning the compressed size
+fn compress_file(plain: &Path, compressed: &Path) -> std::io::Result<u64> {
+    let mut input = std::fs::File::open(plain)?;
+    let mut encoder = zstd::Encoder::new(std::fs::File::create(compressed)?, ZSTD_LEVEL)?;
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    Ok(std::fs::metadata(compressed)?.len())
+}
+
+/// Bytes kept from the start of a streamed upload for magic-byte type detection
+const UPLOAD_HEAD_LEN: usize = 8192;
+
+/// Default limit for a single upload filesystem operation (STORAGE_IO_TIMEOUT_SECS)
+const DEFAULT_STORAGE_IO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// A storage operation did not finish within the configured timeout (e.g. a hung
+/// network mount); routes report it as 503
+#[derive(Debug, thiserror::Error)]
+#[error("Storage {0} timed out")]
+pub struct StorageTimeout(pub &'static str);
+
+/// Writable handle an upload is streamed into
+pub type UploadWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Creates the temp files uploads are streamed into
+pub trait UploadFs: Send + Sync {
+    fn create<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Pin<Box<dyn Future<Output = std::io::Result<UploadWriter>> + Send + 'a>>;
+}
+
+/// Upload temp files on the local filesystem
+pub struct LocalUploadFs;
+
+impl UploadFs for LocalUploadFs {
+    fn create<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Pin<Box<dyn Future<Output = std::io::Result<UploadWriter>> + Send + 'a>> {
+        Box::pin(async move { Ok(Box::new(fs::File::create(path).await?) as UploadWriter) })
+    }
+}
+
+/// Run a filesystem operation, failing with [`StorageTimeout`] after `limit`
+async fn storage_io<T>(
+    limit: Option<std::time::Duration>,
+    op: &'static str,
+    fut: impl Future<Output = std::io::Result<T>>,
+) -> anyhow::Result<T> {
+    match limit {
+        Some(limit) => Ok(tokio::time::timeout(limit, fut)
+            .await
+            .map_err(|_| StorageTimeout(op))??),
+        None => Ok(fut.await?),
+    }
+}
+
+/// Upload being streamed into a temp file in the upload directory.
+/// Hash and size are computed as chunks arrive; the temp file is removed on drop
+/// unless committed with [`FileManager::commit_upload`].
+pub struct PendingUpload {
+    temp_path: PathBuf,
+    file: Option<UploadWriter>,
+    io_timeout: Option<std::time::Duration>,
+    hasher: Sha256,
+    size: u64,
+    max_size: u64,
+    head: Vec<u8>,
+}
+
+impl PendingUpload {
+    /// Append a chunk, failing once the upload exceeds the max file size
+    pub async fn write_chunk(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        if self.size + chunk.len() as u64 > self.max_size {
+            anyhow::bail!("File too large");
+        }
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Upload already finished"))?;
+        if let Err(e) = storage_io(self.io_timeout, "write", file.write_all(chunk)).await {
+            // Abandon the handle; the partial temp file goes when the upload is dropped
+            self.file = None;
+            return Err(e);
+        }
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+        if self.head.len() < UPLOAD_HEAD_LEN {
+            let take = (UPLOAD_HEAD_LEN - self.head.len()).min(chunk.len());
+            self.head.extend_from_slice(&chunk[..take]);
+        }
+        Ok(())
+    }
+
+    /// Remove the temp file now, within the storage timeout, rather than in the
+    /// background on drop
+    async fn discard(mut self) {
+        let path = std::mem::take(&mut self.temp_path);
+        self.file = None;
+        if let Err(e) = storage_io(self.io_timeout, "remove", fs::remove_file(&path)).await {
+            tracing::warn!("Failed to remove upload temp file: {}", e);
+        }
+    }
+
+    /// Bytes received so far
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// First bytes of the upload (for magic-byte detection)
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+}
+
+impl Drop for PendingUpload {
+    fn drop(&mut self) {
+        // Best effort: an uncommitted (rejected or aborted) upload leaves no file behind.
+        // The removal runs on the blocking pool so a hung mount can't stall this worker.
+        let path = std::mem::take(&mut self.temp_path);
+        if path.as_os_str().is_empty() {
+            // Already moved into storage or discarded
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            let _ = std::fs::remove_file(&path);
+            return;
+        };
+        let limit = self.io_timeout;
+        runtime.spawn(async move {
+            let removal = tokio::task::spawn_blocking(move || std::fs::remove_file(path));
+            if let Some(limit) = limit {
+                if tokio::time::timeout(limit, removal).await.is_err() {
+                    tracing::warn!("Timed out removing an abandoned upload temp file");
+                }
+            } else {
+                let _ = removal.await;
+            }
+        });
+    }
+}
+
+/// Sibling the snapshot is written to before being renamed into place
+fn snapshot_temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// `path` with its directory resolved, so one file compares equal however it was
+/// spelled (`uploads/x`, `./uploads/x`, absolute). Works before the file itself exists.
+async fn canonical_file_path(path: &Path) -> PathBuf {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    match (fs::canonicalize(dir).await, path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
 
 /// File metadata
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileInfo {
     pub filename: String,
@@ -26,6 +260,35 @@ pub struct FileInfo {
     pub is_duplicate: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original_file_id: Option<String>,
+    /// Number of successful downloads via /api/files/download
+    pub download_count: u64,
+    /// Per-file expiry overriding the global retention window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Stored on disk zstd-compressed (`size` is still the original size)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+    /// Removed from its room's listing; only reachable through the share that detached it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub detached: bool,
+    /// Distinct room members who downloaded the file, in order of first download
+    #[serde(skip)]
+    pub downloaders: Vec<String>,
+}
+
+/// Which uploads may share a physical file (DEDUP_SCOPE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupScope {
+    /// Identical content is stored once across all rooms
+    #[default]
+    Global,
+    /// Dedup only within a room: rooms never share physical files (more disk, no cross-room leaks)
+    Room,
+}
+
+/// Whether an uploaded name has nothing left once whitespace and control characters go
+pub fn is_blank_filename(name: &str) -> bool {
+    name.chars().all(|c| c.is_whitespace() || c.is_control())
 }
 
 /// File manager service
@@ -33,11 +296,42 @@ pub struct FileManager {
     upload_dir: PathBuf,
     files: RwLock<HashMap<String, FileInfo>>,
     room_files: RwLock<HashMap<String, Vec<String>>>, // room_key -> [filename]
-    hash_to_file_id: RwLock<HashMap<String, String>>, // sha256_hash -> filename
+    hash_to_file_id: RwLock<HashMap<String, String>>, // dedup_key (see `dedup_key`) -> filename
     max_file_size: u64,
     retention_hours: i64,
     deleted_file_count: AtomicU64,
     total_deleted_size: AtomicU64,
+    sharding: bool,    // store files under ab/cd/<filename> instead of flat
+    dry_run: bool,     // cleanup only reports, never deletes
+    compression: bool, // zstd-compress new files when it shrinks them
+    dedup_scope: DedupScope,
+    max_filename_len: usize, // display names are normalized to at most this many chars
+    scanner: Option<Arc<dyn VirusScanner>>, // malware scan before commit (CLAMAV_ADDR)
+    allow_empty_uploads: bool, // accept zero-byte files (they all share one hash)
+    metadata_path: Option<PathBuf>, // metadata snapshot file (FILE_METADATA_PATH)
+    upload_fs: Arc<dyn UploadFs>,
+    io_timeout: Option<std::time::Duration>, // per create/write/flush/rename (None = unbounded)
+}
+
+/// On-disk form of the in-memory file maps (`FILE_METADATA_PATH`)
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataSnapshot {
+    saved_at: DateTime<Utc>,
+    files: Vec<FileInfo>,
+    room_files: HashMap<String, Vec<String>>,
+    hash_to_file_id: HashMap<String, String>,
+}
+
+/// Outcome of restoring a metadata snapshot against the upload directory
+#[derive(Debug, Default)]
+pub struct MetadataRestore {
+    /// Entries whose stored file still exists
+    pub restored: usize,
+    /// Entries dropped because their stored file is gone
+    pub missing: Vec<String>,
+    /// Files on disk that no restored entry references
+    pub orphans: Vec<PathBuf>,
 }
 
 impl FileManager {
@@ -56,7 +350,64 @@ impl FileManager {
             .and_then(|s| s.parse().ok())
             .unwrap_or(12);
 
-        Self::new_with_config(upload_dir, max_file_size, retention_hours)
+        let sharding = std::env::var("UPLOAD_SHARDING")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+
+        let compression = match std::env::var("FILE_COMPRESSION") {
+            Ok(v) if v.eq_ignore_ascii_case("zstd") => true,
+            Ok(v) if v.is_empty() || v.eq_ignore_ascii_case("none") => false,
+            Ok(v) => {
+                tracing::warn!(
+                    "Unsupported FILE_COMPRESSION {:?}, storing files uncompressed",
+                    v
+                );
+                false
+            }
+            Err(_) => false,
+        };
+
+        let dedup_scope = match std::env::var("DEDUP_SCOPE") {
+            Ok(v) if v.eq_ignore_ascii_case("room") => DedupScope::Room,
+            Ok(v) if v.is_empty() || v.eq_ignore_ascii_case("global") => DedupScope::Global,
+            Ok(v) => {
+                tracing::warn!("Unsupported DEDUP_SCOPE {:?}, deduplicating globally", v);
+                DedupScope::Global
+            }
+            Err(_) => DedupScope::Global,
+        };
+
+        let max_filename_len = std::env::var("MAX_FILENAME_LEN")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&len: &usize| len > 0)
+            .unwrap_or(crate::utils::DEFAULT_MAX_FILENAME_LEN);
+
+        let mut manager = Self::new_with_config(upload_dir, max_file_size, retention_hours)?
+            .with_sharding(sharding)
+            .with_compression(compression)
+            .with_dedup_scope(dedup_scope)
+            .with_max_filename_len(max_filename_len);
+        if let Some(scanner) = ClamAvScanner::from_env() {
+            manager = manager.with_scanner(Arc::new(scanner));
+        }
+        if let Some(path) = std::env::var("FILE_METADATA_PATH")
+            .ok()
+            .filter(|v| !v.is_empty())
+        {
+            manager = manager.with_metadata_path(PathBuf::from(path));
+        }
+        let allow_empty_uploads = std::env::var("ALLOW_EMPTY_UPLOADS")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+        let io_timeout = std::env::var("STORAGE_IO_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(DEFAULT_STORAGE_IO_TIMEOUT);
+        Ok(manager
+            .with_allow_empty_uploads(allow_empty_uploads)
+            .with_io_timeout(io_timeout))
     }
 
     pub fn new_with_config(
@@ -65,7 +416,10 @@ impl FileManager {
         retention_hours: i64,
     ) -> anyhow::Result<Self> {
         // Create upload directory if it doesn't exist
-        std::fs::create_dir_all(&upload_dir)?;
+        let dir = upload_dir.clone();
+        blocking_storage_io(DEFAULT_STORAGE_IO_TIMEOUT, "create", move || {
+            std::fs::create_dir_all(dir)
+        })?;
 
         Ok(Self {
             upload_dir,
@@ -76,7 +430,176 @@ impl FileManager {
             retention_hours,
             deleted_file_count: AtomicU64::new(0),
             total_deleted_size: AtomicU64::new(0),
+            sharding: false,
+            dry_run: false,
+            compression: false,
+            dedup_scope: DedupScope::Global,
+            max_filename_len: crate::utils::DEFAULT_MAX_FILENAME_LEN,
+            scanner: None,
+            allow_empty_uploads: false,
+            metadata_path: None,
+            upload_fs: Arc::new(LocalUploadFs),
+            io_timeout: Some(DEFAULT_STORAGE_IO_TIMEOUT),
+        })
+    }
+
+    /// Bound each upload create/write/flush/rename by `timeout` (zero = no limit)
+    pub fn with_io_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.io_timeout = (!timeout.is_zero()).then_some(timeout);
+        self
+    }
+
+    /// Create upload temp files through `upload_fs` instead of the local filesystem
+    pub fn with_upload_fs(mut self, upload_fs: Arc<dyn UploadFs>) -> Self {
+        self.upload_fs = upload_fs;
+        self
+    }
+
+    /// Persist file metadata snapshots to `path` (see `save_metadata_snapshot`)
+    pub fn with_metadata_path(mut self, path: PathBuf) -> Self {
+        self.metadata_path = Some(path);
+        self
+    }
+
+    /// Where metadata snapshots are written, if persistence is enabled
+    pub fn metadata_path(&self) -> Option<&Path> {
+        self.metadata_path.as_deref()
+    }
+
+    /// Accept zero-byte uploads instead of rejecting them as "Empty file"
+    pub fn with_allow_empty_uploads(mut self, allow: bool) -> Self {
+        self.allow_empty_uploads = allow;
+        self
+    }
+
+    /// Reject uploads without a usable original name, and empty files unless allowed
+    pub fn validate_upload(&self, original_name: &str, size: u64) -> Result<(), &'static str> {
+        if is_blank_filename(original_name) {
+            return Err("Filename required");
+        }
+        if size == 0 && !self.allow_empty_uploads {
+            return Err("Empty file");
+        }
+        Ok(())
+    }
+
+    /// Scan every upload with `scanner` before it is committed
+    pub fn with_scanner(mut self, scanner: Arc<dyn VirusScanner>) -> Self {
+        self.scanner = Some(scanner);
+        self
+    }
+
+    /// Whether uploads are scanned before commit (CLAMAV_ADDR)
+    pub fn virus_scan_enabled(&self) -> bool {
+        self.scanner.is_some()
+    }
+
+    /// Cap display filenames at `len` characters (extension preserved)
+    pub fn with_max_filename_len(mut self, len: usize) -> Self {
+        self.max_filename_len = len.max(1);
+        self
+    }
+
+    /// Display name an upload named `original_name` will be stored under
+    pub fn normalize_name(&self, original_name: &str) -> String {
+        crate::utils::normalize_filename(original_name, self.max_filename_len)
+    }
+
+    /// Choose whether identical uploads are shared across rooms or only within one
+    pub fn with_dedup_scope(mut self, scope: DedupScope) -> Self {
+        self.dedup_scope = scope;
+        self
+    }
+
+    /// Key into `hash_to_file_id` for content `hash` uploaded to `room_key`
+    fn dedup_key(&self, room_key: &str, hash: &str) -> String {
+        match self.dedup_scope {
+            DedupScope::Global => hash.to_string(),
+            DedupScope::Room => format!("{}:{}", room_key, hash),
+        }
+    }
+
+    /// In dry-run mode cleanup logs and returns candidates without deleting anything
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Enable 2-level directory sharding (`ab/cd/<filename>`) for newly saved files
+    pub fn with_sharding(mut self, enabled: bool) -> Self {
+        self.sharding = enabled;
+        self
+    }
+
+    /// Enable transparent zstd compression of newly saved files
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Stream-compress a plaintext temp file next to itself. Returns the compressed
+    /// file's path if compression is enabled, applicable and actually shrinks the file.
+    async fn compress_for_storage(
+        &self,
+        mime_type: &str,
+        plain: &Path,
+        size: u64,
+    ) -> Option<PathBuf> {
+        if !self.compression || is_precompressed_mime(mime_type) {
+            return None;
+        }
+        let compressed = plain.with_extension("zst");
+        // Compression reads and writes the whole file, so it is bounded like other storage I/O
+        let (source, target) = (plain.to_path_buf(), compressed.clone());
+        let limit = self.io_timeout;
+        let result = tokio::task::spawn_blocking(move || match limit {
+            Some(limit) => {
+                blocking_storage_io(limit, "compress", move || compress_file(&source, &target))
+            }
+            None => Ok(compress_file(&source, &target)?),
         })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+        match result {
+            Ok(len) if len < size => Some(compressed),
+            Ok(_) => {
+                let _ = fs::remove_file(&compressed).await;
+                None
+            }
+            Err(e) => {
+                tracing::warn!("File compression failed, storing uncompressed: {}", e);
+                let _ = fs::remove_file(&compressed).await;
+                None
+            }
+        }
+    }
+
+    /// Physical path for a stored filename, applying sharding if enabled
+    fn storage_path(&self, filename: &str) -> PathBuf {
+        if self.sharding && filename.len() >= 4 && filename.is_char_boundary(4) {
+            self.upload_dir
+                .join(&filename[0..2])
+                .join(&filename[2..4])
+                .join(filename)
+        } else {
+            self.upload_dir.join(filename)
+        }
+    }
+
+    /// Remove now-empty shard directories above a deleted file (best effort)
+    fn prune_shard_dirs(&self, path: &Path) {
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if d == self.upload_dir || !d.starts_with(&self.upload_dir) {
+                break;
+            }
+            // remove_dir only succeeds on empty directories
+            if std::fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
     }
 
     /// Get upload directory
@@ -96,15 +619,90 @@ impl FileManager {
         original_name: &str,
         mime_type: &str,
         data: &[u8],
+    ) -> anyhow::Result<FileInfo> {
+        self.save_file_with_retention(room_key, original_name, mime_type, data, None)
+            .await
+    }
+
+    /// Save uploaded file, optionally overriding the global retention window
+    /// (`retention_hours` is stored on the file as `expires_at`)
+    pub async fn save_file_with_retention(
+        &self,
+        room_key: &str,
+        original_name: &str,
+        mime_type: &str,
+        data: &[u8],
+        retention_hours: Option<i64>,
     ) -> anyhow::Result<FileInfo> {
         if data.len() as u64 > self.max_file_size {
             anyhow::bail!("File too large");
         }
+        let mut upload = self.begin_upload().await?;
+        upload.write_chunk(data).await?;
+        self.commit_upload(upload, room_key, original_name, mime_type, retention_hours)
+            .await
+    }
 
-        // Compute SHA-256 hash
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let hash_hex = format!("{:x}", hasher.finalize());
+    /// Start a streamed upload; write chunks to it, then [`commit_upload`](Self::commit_upload)
+    pub async fn begin_upload(&self) -> anyhow::Result<PendingUpload> {
+        let temp_path = self
+            .upload_dir
+            .join(format!(".upload-{}.tmp", uuid::Uuid::new_v4()));
+        let file =
+            match storage_io(self.io_timeout, "create", self.upload_fs.create(&temp_path)).await {
+                Ok(file) => file,
+                Err(e) => {
+                    // The create may still land after we gave up; clean it up off this task
+                    if e.is::<StorageTimeout>() {
+                        tokio::spawn(async move {
+                            let _ = fs::remove_file(&temp_path).await;
+                        });
+                    }
+                    return Err(e);
+                }
+            };
+        Ok(PendingUpload {
+            temp_path,
+            file: Some(file),
+            io_timeout: self.io_timeout,
+            hasher: Sha256::new(),
+            size: 0,
+            max_size: self.max_file_size,
+            head: Vec::new(),
+        })
+    }
+
+    /// Scan a finished upload's bytes; always clean when no scanner is configured
+    pub async fn scan_upload(&self, upload: &mut PendingUpload) -> anyhow::Result<ScanVerdict> {
+        let Some(scanner) = &self.scanner else {
+            return Ok(ScanVerdict::Clean);
+        };
+        if let Some(file) = upload.file.as_mut() {
+            storage_io(self.io_timeout, "flush", file.flush()).await?;
+        }
+        scanner.scan(&upload.temp_path).await
+    }
+
+    /// Finish a streamed upload with SHA-256 deduplication: a duplicate discards the
+    /// temp file and references the existing one, otherwise it is moved into storage
+    pub async fn commit_upload(
+        &self,
+        mut upload: PendingUpload,
+        room_key: &str,
+        original_name: &str,
+        mime_type: &str,
+        retention_hours: Option<i64>,
+    ) -> anyhow::Result<FileInfo> {
+        if let Some(mut file) = upload.file.take() {
+            storage_io(self.io_timeout, "flush", file.flush()).await?;
+        }
+        self.validate_upload(original_name, upload.size)
+            .map_err(anyhow::Error::msg)?;
+        let original_name = &self.normalize_name(original_name);
+        let size = upload.size;
+        let expires_at = retention_hours.map(|hours| Utc::now() + Duration::hours(hours));
+        let hash_hex = format!("{:x}", std::mem::take(&mut upload.hasher).finalize());
+        let dedup_key = self.dedup_key(room_key, &hash_hex);
 
         // Unified lock order: files → hash_to_file_id
         // Check for duplicate (acquire files read lock first)
@@ -118,7 +716,7 @@ impl FileManager {
                 .read()
                 .map_err(|_| anyhow::anyhow!("Lock error"))?;
             hash_map
-                .get(&hash_hex)
+                .get(&dedup_key)
                 .and_then(|existing_filename| files.get(existing_filename).cloned())
         };
 
@@ -138,7 +736,7 @@ impl FileManager {
             let file_info = FileInfo {
                 filename: filename.clone(),
                 original_name: original_name.to_string(),
-                size: data.len() as u64,
+                size,
                 mime_type: mime_type.to_string(),
                 room_key: room_key.to_string(),
                 uploaded_at: Utc::now(),
@@ -146,6 +744,11 @@ impl FileManager {
                 hash: Some(hash_hex),
                 is_duplicate: Some(true),
                 original_file_id: Some(existing.filename.clone()),
+                download_count: 0,
+                expires_at,
+                compressed: existing.compressed,
+                detached: false,
+                downloaders: Vec::new(),
             };
 
             {
@@ -172,6 +775,7 @@ impl FileManager {
                 original_name,
                 existing.filename
             );
+            upload.discard().await;
             return Ok(file_info);
         }
 
@@ -187,17 +791,28 @@ impl FileManager {
             ext
         );
 
-        let file_path = self.upload_dir.join(&filename);
+        let file_path = self.storage_path(&filename);
+        if let Some(parent) = file_path.parent() {
+            storage_io(self.io_timeout, "create", fs::create_dir_all(parent)).await?;
+        }
 
-        // Write file
-        let mut file = fs::File::create(&file_path).await?;
-        file.write_all(data).await?;
-        file.flush().await?;
+        // Move into storage (dedup hash above is always over the original bytes)
+        let compressed = self
+            .compress_for_storage(mime_type, &upload.temp_path, size)
+            .await;
+        let source = compressed.as_deref().unwrap_or(&upload.temp_path);
+        storage_io(self.io_timeout, "rename", fs::rename(source, &file_path)).await?;
+        if compressed.is_some() {
+            upload.discard().await;
+        } else {
+            // The temp file is now the stored file
+            upload.temp_path = PathBuf::new();
+        }
 
         let file_info = FileInfo {
             filename: filename.clone(),
             original_name: original_name.to_string(),
-            size: data.len() as u64,
+            size,
             mime_type: mime_type.to_string(),
             room_key: room_key.to_string(),
             uploaded_at: Utc::now(),
@@ -205,6 +820,11 @@ impl FileManager {
             hash: Some(hash_hex.clone()),
             is_duplicate: Some(false),
             original_file_id: None,
+            download_count: 0,
+            expires_at,
+            compressed: compressed.is_some(),
+            detached: false,
+            downloaders: Vec::new(),
         };
 
         // Track file
@@ -233,18 +853,177 @@ impl FileManager {
                 .hash_to_file_id
                 .write()
                 .map_err(|_| anyhow::anyhow!("Lock error"))?;
-            hash_map.insert(hash_hex, filename);
+            hash_map.insert(dedup_key, filename);
         }
 
         tracing::info!("File uploaded: {} for room {}", original_name, room_key);
         Ok(file_info)
     }
 
+    /// Re-hash a stored file's original contents (streamed, decompressing if needed)
+    pub async fn hash_stored_file(&self, info: &FileInfo) -> std::io::Result<String> {
+        use tokio::io::AsyncReadExt;
+
+        let file = fs::File::open(&info.path).await?;
+        let mut reader = stored_file_reader(file, info.compressed);
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
     /// Get file info by filename
     pub fn get_file(&self, filename: &str) -> Option<FileInfo> {
         self.files.read().ok()?.get(filename).cloned()
     }
 
+    /// Reference an existing file from another room without copying its contents.
+    /// The new entry points at the same physical file, like a deduplicated upload.
+    pub fn copy_to_room(&self, filename: &str, target_room: &str) -> anyhow::Result<FileInfo> {
+        let source = self
+            .get_file(filename)
+            .ok_or_else(|| anyhow::anyhow!("File not found"))?;
+
+        let ext = Path::new(&source.original_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        let new_filename = format!(
+            "{}_{}.{}",
+            uuid::Uuid::new_v4(),
+            Utc::now().timestamp_millis(),
+            ext
+        );
+        let file_info = FileInfo {
+            filename: new_filename.clone(),
+            room_key: target_room.to_string(),
+            uploaded_at: Utc::now(),
+            is_duplicate: Some(true),
+            original_file_id: Some(
+                source
+                    .original_file_id
+                    .clone()
+                    .unwrap_or_else(|| source.filename.clone()),
+            ),
+            download_count: 0,
+            detached: false,
+            downloaders: Vec::new(),
+            ..source
+        };
+
+        // Unified lock order: files → room_files
+        {
+            let mut files = self
+                .files
+                .write()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            files.insert(new_filename.clone(), file_info.clone());
+        }
+        {
+            let mut room_files = self
+                .room_files
+                .write()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            room_files
+                .entry(target_room.to_string())
+                .or_default()
+                .push(new_filename);
+        }
+
+        tracing::info!(
+            "File {} copied to room {} as {}",
+            filename,
+            target_room,
+            file_info.filename
+        );
+        Ok(file_info)
+    }
+
+    /// Get file info for in-room access (detached files are excluded)
+    pub fn get_room_file(&self, filename: &str) -> Option<FileInfo> {
+        self.get_file(filename).filter(|f| !f.detached)
+    }
+
+    /// Remove a file from its room's listing while keeping it (and its bytes) tracked
+    /// until at least `keep_until`, so a share can still serve it after the room is gone
+    pub fn detach_from_room(
+        &self,
+        filename: &str,
+        keep_until: DateTime<Utc>,
+    ) -> anyhow::Result<FileInfo> {
+        // Unified lock order: files → room_files
+        let mut files = self
+            .files
+            .write()
+            .map_err(|_| anyhow::anyhow!("Lock error"))?;
+        let info = files
+            .get_mut(filename)
+            .ok_or_else(|| anyhow::anyhow!("File not found"))?;
+        info.detached = true;
+        info.expires_at = Some(info.expires_at.map_or(keep_until, |e| e.max(keep_until)));
+        let detached = info.clone();
+
+        let mut room_files = self
+            .room_files
+            .write()
+            .map_err(|_| anyhow::anyhow!("Lock error"))?;
+        if let Some(names) = room_files.get_mut(&detached.room_key) {
+            names.retain(|f| f != filename);
+        }
+
+        tracing::info!(
+            "File {} detached from room {} (kept until {})",
+            filename,
+            detached.room_key,
+            keep_until
+        );
+        Ok(detached)
+    }
+
+    /// List files of a room, oldest first
+    pub fn list_room_files(&self, room_key: &str) -> Vec<FileInfo> {
+        let files = match self.files.read() {
+            Ok(f) => f,
+            Err(_) => return Vec::new(),
+        };
+        let mut list: Vec<FileInfo> = files
+            .values()
+            .filter(|f| f.room_key == room_key && !f.detached)
+            .cloned()
+            .collect();
+        list.sort_by_key(|f| f.uploaded_at);
+        list
+    }
+
+    /// Increment a file's download counter. Returns the new count.
+    pub fn record_download(&self, filename: &str) -> Option<u64> {
+        let mut files = self.files.write().ok()?;
+        let info = files.get_mut(filename)?;
+        info.download_count += 1;
+        Some(info.download_count)
+    }
+
+    /// Remember that room member `user_id` downloaded the file (each user listed once)
+    pub fn record_downloader(&self, filename: &str, user_id: &str) {
+        if let Ok(mut files) = self.files.write()
+            && let Some(info) = files.get_mut(filename)
+            && !info.downloaders.iter().any(|id| id == user_id)
+        {
+            info.downloaders.push(user_id.to_string());
+        }
+    }
+
+    /// User ids that downloaded the file, in order of first download
+    pub fn downloaders(&self, filename: &str) -> Option<Vec<String>> {
+        Some(self.files.read().ok()?.get(filename)?.downloaders.clone())
+    }
+
     /// Get file path
     pub fn get_file_path(&self, filename: &str) -> Option<PathBuf> {
         self.files
@@ -283,14 +1062,17 @@ impl FileManager {
 
             if !other_references {
                 // No other references, safe to delete physical file
-                if info.path.exists() {
-                    fs::remove_file(&info.path).await?;
+                // An orphan sweep may have removed the file first; that's not a failure
+                match fs::remove_file(&info.path).await {
+                    Ok(()) => self.prune_shard_dirs(&info.path),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
                 }
                 // Clean hash mapping
                 if let Some(ref hash) = info.hash
                     && let Ok(mut hash_map) = self.hash_to_file_id.write()
                 {
-                    hash_map.remove(hash);
+                    hash_map.remove(&self.dedup_key(&info.room_key, hash));
                 }
             }
 
@@ -328,12 +1110,14 @@ impl FileManager {
 
                 if !other_references {
                     // No other references, safe to delete physical file
-                    let _ = std::fs::remove_file(&info.path);
+                    if std::fs::remove_file(&info.path).is_ok() {
+                        self.prune_shard_dirs(&info.path);
+                    }
                     // Clean hash mapping
                     if let Some(ref hash) = info.hash
                         && let Ok(mut hash_map) = self.hash_to_file_id.write()
                     {
-                        hash_map.remove(hash);
+                        hash_map.remove(&self.dedup_key(&info.room_key, hash));
                     }
                 }
 
@@ -348,9 +1132,57 @@ impl FileManager {
         deleted
     }
 
-    /// Cleanup expired files
+    /// Delete tracked files whose room no longer exists. Used to recover when
+    /// `RoomDestroyed` events were missed (e.g. the event listener lagged).
+    pub fn reconcile_room_files(&self, active_rooms: &[String]) -> Vec<FileInfo> {
+        let stale_rooms: Vec<String> = {
+            let room_files = match self.room_files.read() {
+                Ok(rf) => rf,
+                Err(_) => return Vec::new(),
+            };
+            room_files
+                .keys()
+                .filter(|key| !active_rooms.contains(key))
+                .cloned()
+                .collect()
+        };
+
+        if self.dry_run {
+            let files = match self.files.read() {
+                Ok(f) => f,
+                Err(_) => return Vec::new(),
+            };
+            return files
+                .values()
+                .filter(|info| !info.detached && stale_rooms.contains(&info.room_key))
+                .inspect(|info| {
+                    tracing::info!(
+                        "[dry-run] File {} of destroyed room {} would be deleted",
+                        info.filename,
+                        info.room_key
+                    )
+                })
+                .cloned()
+                .collect();
+        }
+
+        let mut deleted = Vec::new();
+        for room_key in stale_rooms {
+            deleted.extend(self.delete_room_files(&room_key));
+        }
+        if !deleted.is_empty() {
+            tracing::info!(
+                "Reconciliation removed {} files of destroyed rooms",
+                deleted.len()
+            );
+        }
+        deleted
+    }
+
+    /// Cleanup expired files (per-file `expires_at` wins over the global retention)
     pub async fn cleanup_expired_files(&self) -> Vec<FileInfo> {
-        let cutoff = Utc::now() - Duration::hours(self.retention_hours);
+        let now = Utc::now();
+        let cutoff = now - Duration::hours(self.retention_hours);
 
         // Collect expired filenames first (avoid nested locking)
         let filenames: Vec<String> = {
@@ -360,11 +1192,32 @@ impl FileManager {
             };
             files
                 .iter()
-                .filter(|(_, info)| info.uploaded_at < cutoff)
+                .filter(|(_, info)| match info.expires_at {
+                    Some(expires_at) => expires_at <= now,
+                    None => info.uploaded_at < cutoff,
+                })
                 .map(|(name, _)| name.clone())
                 .collect()
         };
 
+        if self.dry_run {
+            let files = match self.files.read() {
+                Ok(f) => f,
+                Err(_) => return Vec::new(),
+            };
+            return filenames
+                .iter()
+                .filter_map(|name| files.get(name).cloned())
+                .inspect(|info| {
+                    tracing::info!(
+                        "[dry-run] File {} ({}) would be deleted",
+                        info.filename,
+                        info.original_name
+                    )
+                })
+                .collect();
+        }
+
         // Delete files one by one (delete_file handles its own locking)
         let mut expired = Vec::new();
         for filename in filenames {
@@ -393,6 +1246,7 @@ impl FileManager {
         FileStats {
             total_files: files.len(),
             total_size,
+            total_downloads: files.values().map(|f| f.download_count).sum(),
             room_count,
             deleted_files: self.deleted_file_count.load(Ordering::Relaxed),
             deleted_size: self.total_deleted_size.load(Ordering::Relaxed),
@@ -400,43 +1254,227 @@ impl FileManager {
     }
 
     /// Cleanup orphaned files (files in upload directory not tracked in memory)
-    /// Called at startup to clean up any files from previous sessions
+    /// Runs at startup and is safe to call periodically: directory entries are read
+    /// asynchronously and files modified within the grace period are left alone, since
+    /// they may belong to an upload that has been written but not yet tracked.
     pub async fn cleanup_orphaned_files(&self) -> usize {
+        self.cleanup_orphaned_files_with_grace(ORPHAN_GRACE_PERIOD)
+            .await
+    }
+
+    /// Cleanup orphaned files older than `grace`
+    pub async fn cleanup_orphaned_files_with_grace(&self, grace: std::time::Duration) -> usize {
         let mut cleaned = 0;
 
-        if let Ok(entries) = std::fs::read_dir(&self.upload_dir) {
-            let tracked_files: std::collections::HashSet<String> = {
-                let files = match self.files.read() {
-                    Ok(f) => f,
-                    Err(_) => return 0,
-                };
-                files.keys().cloned().collect()
+        for (path, metadata) in self.untracked_files().await {
+            let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
             };
-
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file()
-                    && let Some(filename) = path.file_name().and_then(|n| n.to_str())
-                {
-                    // Check if file is tracked
-                    if !tracked_files.contains(filename) {
-                        // File is orphaned, delete it
-                        if let Ok(()) = std::fs::remove_file(&path) {
-                            tracing::warn!("Cleaned up orphaned file: {}", filename);
-                            cleaned += 1;
-                        }
-                    }
-                }
+            // Skip files that may still be in the middle of being saved
+            let recently_modified = metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.elapsed().ok())
+                .is_some_and(|age| age < grace);
+            if recently_modified {
+                continue;
+            }
+            if self.dry_run {
+                tracing::info!("[dry-run] Orphaned file {} would be deleted", filename);
+                cleaned += 1;
+                continue;
+            }
+            // File is orphaned, delete it
+            if fs::remove_file(&path).await.is_ok() {
+                tracing::warn!("Cleaned up orphaned file: {}", filename);
+                cleaned += 1;
+                self.prune_shard_dirs(&path);
             }
         }
 
         if cleaned > 0 {
-            tracing::info!("Startup cleanup: removed {} orphaned files", cleaned);
+            tracing::info!("Orphan cleanup: removed {} orphaned files", cleaned);
         }
 
         cleaned
     }
 
+    /// Files in the upload directory (including shard subdirectories) that no tracked
+    /// entry references. The metadata snapshot itself is never reported.
+    async fn untracked_files(&self) -> Vec<(PathBuf, std::fs::Metadata)> {
+        // Physical file names still referenced (duplicates point at the original's path)
+        let tracked_files: std::collections::HashSet<String> = {
+            let files = match self.files.read() {
+                Ok(f) => f,
+                Err(_) => return Vec::new(),
+            };
+            files
+                .iter()
+                .flat_map(|(name, info)| {
+                    let physical = info
+                        .path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .map(|n| n.to_string());
+                    std::iter::once(name.clone()).chain(physical)
+                })
+                .collect()
+        };
+        let mut snapshot_files = Vec::new();
+        if let Some(path) = &self.metadata_path {
+            for path in [path.clone(), snapshot_temp_path(path)] {
+                snapshot_files.push(canonical_file_path(&path).await);
+            }
+        }
+
+        let mut untracked = Vec::new();
+        let mut dirs = vec![self.upload_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(mut entries) = fs::read_dir(&dir).await else {
+                continue;
+            };
+            // Compared against the snapshot paths in resolved form
+            let canonical_dir = fs::canonicalize(&dir).await.unwrap_or_else(|_| dir.clone());
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                let Ok(metadata) = entry.metadata().await else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if !metadata.is_file() {
+                    continue;
+                }
+                let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if tracked_files.contains(filename) {
+                    continue;
+                }
+                if snapshot_files.contains(&canonical_dir.join(filename)) {
+                    continue;
+                }
+                untracked.push((path, metadata));
+            }
+        }
+        untracked
+    }
+
+    /// Write the file maps to the metadata path (temp file + rename, so a crash mid-write
+    /// leaves the previous snapshot intact). Returns the number of entries written, or
+    /// `None` when persistence is disabled. Downloader lists are not persisted.
+    pub async fn save_metadata_snapshot(&self) -> anyhow::Result<Option<usize>> {
+        let Some(path) = &self.metadata_path else {
+            return Ok(None);
+        };
+
+        let snapshot = {
+            let files = self
+                .files
+                .read()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            let room_files = self
+                .room_files
+                .read()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            let hashes = self
+                .hash_to_file_id
+                .read()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            MetadataSnapshot {
+                saved_at: Utc::now(),
+                files: files.values().cloned().collect(),
+                room_files: room_files.clone(),
+                hash_to_file_id: hashes.clone(),
+            }
+        };
+        let count = snapshot.files.len();
+        let json = serde_json::to_vec(&snapshot)?;
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+        let temp_path = snapshot_temp_path(path);
+        fs::write(&temp_path, json).await?;
+        fs::rename(&temp_path, path).await?;
+        Ok(Some(count))
+    }
+
+    /// Load the snapshot at the metadata path and reconcile it with the upload directory:
+    /// entries whose stored file is gone are dropped (along with room and dedup references
+    /// to them), and files on disk that nothing references are reported as orphans.
+    /// A missing snapshot restores nothing. Intended for startup, before orphan cleanup.
+    pub async fn load_metadata_snapshot(&self) -> anyhow::Result<MetadataRestore> {
+        let mut report = MetadataRestore::default();
+        let Some(path) = &self.metadata_path else {
+            return Ok(report);
+        };
+        let bytes = match fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e.into()),
+        };
+        let snapshot: MetadataSnapshot = serde_json::from_slice(&bytes)?;
+
+        let mut kept = HashMap::new();
+        for info in snapshot.files {
+            if fs::try_exists(&info.path).await.unwrap_or(false) {
+                kept.insert(info.filename.clone(), info);
+            } else {
+                report.missing.push(info.filename);
+            }
+        }
+        report.restored = kept.len();
+
+        {
+            let mut room_files = self
+                .room_files
+                .write()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            for (room_key, names) in snapshot.room_files {
+                let names: Vec<String> = names
+                    .into_iter()
+                    .filter(|name| kept.contains_key(name))
+                    .collect();
+                if !names.is_empty() {
+                    room_files.entry(room_key).or_default().extend(names);
+                }
+            }
+        }
+        {
+            let mut hashes = self
+                .hash_to_file_id
+                .write()
+                .map_err(|_| anyhow::anyhow!("Lock error"))?;
+            hashes.extend(
+                snapshot
+                    .hash_to_file_id
+                    .into_iter()
+                    .filter(|(_, name)| kept.contains_key(name)),
+            );
+        }
+        self.files
+            .write()
+            .map_err(|_| anyhow::anyhow!("Lock error"))?
+            .extend(kept);
+
+        report.orphans = self
+            .untracked_files()
+            .await
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        for orphan in &report.orphans {
+            tracing::warn!(
+                "Untracked file after metadata restore: {}",
+                orphan.display()
+            );
+        }
+        Ok(report)
+    }
+
     /// Get upload directory path for external use
     pub fn get_upload_dir_path(&self) -> &Path {
         &self.upload_dir
@@ -446,6 +1484,13 @@ impl FileManager {
     pub fn get_retention_hours(&self) -> i64 {
         self.retention_hours
     }
+
+    /// When cleanup will delete `info`: its own `expires_at`, else upload time plus the
+    /// global retention (same rule as `cleanup_expired_files`)
+    pub fn expires_at(&self, info: &FileInfo) -> DateTime<Utc> {
+        info.expires_at
+            .unwrap_or(info.uploaded_at + Duration::hours(self.retention_hours))
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -453,6 +1498,7 @@ impl FileManager {
 pub struct FileStats {
     pub total_files: usize,
     pub total_size: u64,
+    pub total_downloads: u64,
     pub room_count: usize,
     pub deleted_files: u64,
     pub deleted_size: u64,
@@ -516,6 +1562,59 @@ mod tests {
         assert!(file_info.path.exists());
     }
 
+    #[tokio::test]
+    async fn test_save_file_rejects_empty_file_and_blank_name() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+
+        let err = manager
+            .save_file("room123", "empty.txt", "text/plain", b"")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Empty file");
+        let err = manager
+            .save_file("room123", " \u{7} ", "text/plain", b"data")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Filename required");
+
+        let manager = manager.with_allow_empty_uploads(true);
+        let info = manager
+            .save_file("room123", "empty.txt", "text/plain", b"")
+            .await
+            .unwrap();
+        assert_eq!(info.size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_save_file_normalizes_original_name() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let manager = manager.with_max_filename_len(12);
+
+        let cleaned = manager
+            .save_file("room123", "a\u{202E}b\r\n.txt", "text/plain", b"one")
+            .await
+            .unwrap();
+        assert_eq!(cleaned.original_name, "ab.txt");
+
+        let long = manager
+            .save_file(
+                "room123",
+                "quarterly-report.tar",
+                "application/x-tar",
+                b"two",
+            )
+            .await
+            .unwrap();
+        assert_eq!(long.original_name, "quarterl.tar");
+        assert!(long.filename.ends_with(".tar"));
+
+        let normal = manager
+            .save_file("room123", "notes.md", "text/markdown", b"three")
+            .await
+            .unwrap();
+        assert_eq!(normal.original_name, "notes.md");
+    }
+
     #[tokio::test]
     async fn test_save_file_tracks_by_room() {
         let (manager, _tmp_dir) = setup_test_manager().await;
@@ -688,6 +1787,81 @@ mod tests {
         assert!(manager.get_file(&file_info.filename).is_none());
     }
 
+    #[tokio::test]
+    async fn test_cleanup_dry_run_keeps_files() {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        let manager = manager.with_dry_run(true);
+
+        let file_info = manager
+            .save_file("room123", "old.txt", "text/plain", b"old content")
+            .await
+            .unwrap();
+        {
+            let mut files = manager.files.write().unwrap();
+            if let Some(info) = files.get_mut(&file_info.filename) {
+                info.uploaded_at = Utc::now() - Duration::hours(13);
+            }
+        }
+        let orphan = tmp_dir.path().join("orphan.bin");
+        std::fs::write(&orphan, b"x").unwrap();
+
+        let expired = manager.cleanup_expired_files().await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].filename, file_info.filename);
+        assert!(manager.get_file(&file_info.filename).is_some());
+        assert!(file_info.path.exists());
+
+        let orphans = manager
+            .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+            .await;
+        assert_eq!(orphans, 1);
+        assert!(orphan.exists());
+    }
+
+    /// Simulate time passing by shifting a file's timestamps into the past
+    fn age_file(manager: &FileManager, filename: &str, hours: i64) {
+        let mut files = manager.files.write().unwrap();
+        let info = files.get_mut(filename).unwrap();
+        info.uploaded_at -= Duration::hours(hours);
+        info.expires_at = info.expires_at.map(|e| e - Duration::hours(hours));
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_honors_per_file_retention() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+
+        let short = manager
+            .save_file_with_retention("room123", "paste.txt", "text/plain", b"short", Some(1))
+            .await
+            .unwrap();
+        let long = manager
+            .save_file_with_retention("room123", "keep.txt", "text/plain", b"long", Some(48))
+            .await
+            .unwrap();
+        let default = manager
+            .save_file("room123", "default.txt", "text/plain", b"default")
+            .await
+            .unwrap();
+        assert!(short.expires_at.is_some());
+        assert!(default.expires_at.is_none());
+
+        // 2h: only the 1h file is past its expiry (global window is 12h)
+        age_file(&manager, &short.filename, 2);
+        age_file(&manager, &long.filename, 2);
+        age_file(&manager, &default.filename, 2);
+        let expired = manager.cleanup_expired_files().await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].filename, short.filename);
+
+        // 13h: the default file falls out of the global window, the 48h file survives
+        age_file(&manager, &long.filename, 11);
+        age_file(&manager, &default.filename, 11);
+        let expired = manager.cleanup_expired_files().await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].filename, default.filename);
+        assert!(manager.get_file(&long.filename).is_some());
+    }
+
     #[tokio::test]
     async fn test_cleanup_keeps_recent_files() {
         let (manager, _tmp_dir) = setup_test_manager().await;
@@ -774,6 +1948,44 @@ mod tests {
         assert_eq!(file1.path, file2.path);
     }
 
+    #[tokio::test]
+    async fn test_room_dedup_scope_never_shares_across_rooms() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let manager = manager.with_dedup_scope(DedupScope::Room);
+        let data = b"identical content";
+
+        let file1 = manager
+            .save_file("room1", "file1.txt", "text/plain", data)
+            .await
+            .unwrap();
+        let other_room = manager
+            .save_file("room2", "file2.txt", "text/plain", data)
+            .await
+            .unwrap();
+        let same_room = manager
+            .save_file("room1", "file3.txt", "text/plain", data)
+            .await
+            .unwrap();
+
+        // Same hash, but room2 gets its own physical copy
+        assert_eq!(file1.hash, other_room.hash);
+        assert_eq!(other_room.is_duplicate, Some(false));
+        assert_ne!(file1.path, other_room.path);
+
+        // Within a room dedup still applies
+        assert_eq!(same_room.is_duplicate, Some(true));
+        assert_eq!(same_room.path, file1.path);
+
+        // Deleting room1's files leaves room2's copy and dedup entry intact
+        manager.delete_room_files("room1");
+        assert!(other_room.path.exists());
+        let again = manager
+            .save_file("room2", "file4.txt", "text/plain", data)
+            .await
+            .unwrap();
+        assert_eq!(again.original_file_id, Some(other_room.filename));
+    }
+
     #[tokio::test]
     async fn test_file_deduplication_different_content() {
         let (manager, _tmp_dir) = setup_test_manager().await;
@@ -829,4 +2041,655 @@ mod tests {
                 .unwrap();
         assert_eq!(manager.max_file_size(), 100 * 1024 * 1024); // 100MB
     }
+
+    // Sharding tests
+    async fn setup_sharded_manager() -> (FileManager, TempDir) {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        (manager.with_sharding(true), tmp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_sharded_file_placed_in_subdirs() {
+        let (manager, tmp_dir) = setup_sharded_manager().await;
+        let info = manager
+            .save_file("room1", "a.txt", "text/plain", b"sharded")
+            .await
+            .unwrap();
+
+        let expected = tmp_dir
+            .path()
+            .join(&info.filename[0..2])
+            .join(&info.filename[2..4])
+            .join(&info.filename);
+        assert_eq!(info.path, expected);
+        assert!(expected.exists());
+        assert_eq!(manager.get_file_path(&info.filename), Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_sharded_file_delete_prunes_dirs() {
+        let (manager, tmp_dir) = setup_sharded_manager().await;
+        let info = manager
+            .save_file("room1", "a.txt", "text/plain", b"to delete")
+            .await
+            .unwrap();
+
+        manager.delete_file(&info.filename).await.unwrap();
+        assert!(!info.path.exists());
+        assert!(!tmp_dir.path().join(&info.filename[0..2]).exists());
+        assert!(tmp_dir.path().exists());
+    }
+
+    #[tokio::test]
+    async fn test_orphan_cleanup_walks_shard_dirs() {
+        let (manager, tmp_dir) = setup_sharded_manager().await;
+        let tracked = manager
+            .save_file("room1", "keep.txt", "text/plain", b"keep")
+            .await
+            .unwrap();
+
+        let orphan_dir = tmp_dir.path().join("ff").join("ee");
+        std::fs::create_dir_all(&orphan_dir).unwrap();
+        std::fs::write(orphan_dir.join("ffee_orphan.txt"), b"orphan").unwrap();
+        std::fs::write(tmp_dir.path().join("flat_orphan.txt"), b"orphan").unwrap();
+
+        let cleaned = manager
+            .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+            .await;
+        assert_eq!(cleaned, 2);
+        assert!(tracked.path.exists());
+        assert!(!orphan_dir.exists());
+    }
+
+    // Storage timeout tests
+    /// Local files whose writes stall forever once `stall_after` bytes are written,
+    /// like a network mount that stops responding mid-upload
+    struct StallingFs {
+        stall_after: usize,
+        hang_on_create: bool,
+    }
+
+    struct StallingWriter {
+        inner: fs::File,
+        remaining: usize,
+    }
+
+    impl AsyncWrite for StallingWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            if self.remaining == 0 {
+                return std::task::Poll::Pending;
+            }
+            let len = buf.len().min(self.remaining);
+            let result = Pin::new(&mut self.inner).poll_write(cx, &buf[..len]);
+            if let std::task::Poll::Ready(Ok(n)) = result {
+                self.remaining -= n;
+            }
+            result
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    impl UploadFs for StallingFs {
+        fn create<'a>(
+            &'a self,
+            path: &'a Path,
+        ) -> Pin<Box<dyn Future<Output = std::io::Result<UploadWriter>> + Send + 'a>> {
+            Box::pin(async move {
+                if self.hang_on_create {
+                    std::future::pending::<()>().await;
+                }
+                Ok(Box::new(StallingWriter {
+                    inner: fs::File::create(path).await?,
+                    remaining: self.stall_after,
+                }) as UploadWriter)
+            })
+        }
+    }
+
+    fn stalling_manager(dir: &TempDir, stall_after: usize, hang_on_create: bool) -> FileManager {
+        FileManager::new_with_config(dir.path().to_path_buf(), 100 * 1024 * 1024, 12)
+            .unwrap()
+            .with_upload_fs(Arc::new(StallingFs {
+                stall_after,
+                hang_on_create,
+            }))
+            .with_io_timeout(std::time::Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn test_stalled_write_times_out_and_removes_partial_file() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = stalling_manager(&tmp_dir, 5, false);
+
+        let mut upload = manager.begin_upload().await.unwrap();
+        upload.write_chunk(b"hello").await.unwrap();
+        let err = upload.write_chunk(b" world").await.unwrap_err();
+        assert!(err.is::<StorageTimeout>(), "{}", err);
+        assert_eq!(upload.size(), 5);
+
+        // The handle is abandoned: later chunks fail fast instead of hanging again
+        assert!(
+            !upload
+                .write_chunk(b"!")
+                .await
+                .unwrap_err()
+                .is::<StorageTimeout>()
+        );
+
+        let temp_path = upload.temp_path.clone();
+        assert!(temp_path.exists());
+        drop(upload);
+        wait_until_removed(&temp_path).await;
+        assert_eq!(manager.get_stats().total_files, 0);
+    }
+
+    #[test]
+    fn test_blocking_storage_io_times_out() {
+        let err = blocking_storage_io(std::time::Duration::from_millis(10), "create", || {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(err.is::<StorageTimeout>(), "{}", err);
+        assert_eq!(
+            blocking_storage_io(std::time::Duration::from_secs(1), "create", || Ok(7)).unwrap(),
+            7
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stalled_create_times_out() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = stalling_manager(&tmp_dir, 0, true);
+
+        let Err(err) = manager.begin_upload().await else {
+            panic!("create should time out");
+        };
+        assert!(err.is::<StorageTimeout>(), "{}", err);
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_io_timeout_disables_limit() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = FileManager::new_with_config(tmp_dir.path().to_path_buf(), 1024, 12)
+            .unwrap()
+            .with_io_timeout(std::time::Duration::ZERO);
+        assert!(manager.io_timeout.is_none());
+        let info = manager
+            .save_file("room1", "a.txt", "text/plain", b"data")
+            .await
+            .unwrap();
+        assert!(info.path.exists());
+    }
+
+    // Metadata snapshot tests
+    async fn setup_snapshot_manager(dir: &TempDir) -> FileManager {
+        FileManager::new_with_config(dir.path().to_path_buf(), 100 * 1024 * 1024, 12)
+            .unwrap()
+            .with_metadata_path(dir.path().join("metadata.json"))
+    }
+
+    #[tokio::test]
+    async fn test_metadata_snapshot_round_trip() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = setup_snapshot_manager(&tmp_dir).await;
+        let first = manager
+            .save_file("room1", "a.txt", "text/plain", b"alpha")
+            .await
+            .unwrap();
+        let dup = manager
+            .save_file("room2", "copy.txt", "text/plain", b"alpha")
+            .await
+            .unwrap();
+        manager.record_download(&first.filename);
+        assert_eq!(manager.save_metadata_snapshot().await.unwrap(), Some(2));
+
+        // A fresh manager (as after a restart) picks everything back up
+        let restored = setup_snapshot_manager(&tmp_dir).await;
+        let report = restored.load_metadata_snapshot().await.unwrap();
+        assert_eq!(report.restored, 2);
+        assert!(report.missing.is_empty());
+        assert!(report.orphans.is_empty());
+
+        let info = restored.get_file(&first.filename).unwrap();
+        assert_eq!(info.original_name, "a.txt");
+        assert_eq!(info.download_count, 1);
+        assert_eq!(restored.list_room_files("room1").len(), 1);
+        assert_eq!(restored.list_room_files("room2")[0].filename, dup.filename);
+
+        // Dedup index survives: the same content links to the restored file
+        let again = restored
+            .save_file("room3", "b.txt", "text/plain", b"alpha")
+            .await
+            .unwrap();
+        assert_eq!(again.is_duplicate, Some(true));
+
+        // The snapshot file is never treated as an orphan
+        assert_eq!(
+            restored
+                .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+                .await,
+            0
+        );
+        assert!(tmp_dir.path().join("metadata.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_not_an_orphan_under_another_path_form() {
+        let tmp_dir = TempDir::new().unwrap();
+        let uploads = tmp_dir.path().join("uploads");
+        std::fs::create_dir_all(&uploads).unwrap();
+        std::os::unix::fs::symlink(&uploads, tmp_dir.path().join("link")).unwrap();
+
+        // Upload dir reached through a symlink, snapshot path spelled with `.` and `..`
+        let manager =
+            FileManager::new_with_config(tmp_dir.path().join("link"), 100 * 1024 * 1024, 12)
+                .unwrap()
+                .with_metadata_path(
+                    tmp_dir
+                        .path()
+                        .join(".")
+                        .join("uploads")
+                        .join("..")
+                        .join("uploads")
+                        .join("metadata.json"),
+                );
+        manager
+            .save_file("room1", "a.txt", "text/plain", b"alpha")
+            .await
+            .unwrap();
+        manager.save_metadata_snapshot().await.unwrap();
+        std::fs::write(uploads.join("metadata.json.tmp"), b"{}").unwrap();
+
+        assert_eq!(
+            manager
+                .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+                .await,
+            0
+        );
+        assert!(uploads.join("metadata.json").exists());
+        assert!(uploads.join("metadata.json.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_restore_reconciles_with_disk() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = setup_snapshot_manager(&tmp_dir).await;
+        let kept = manager
+            .save_file("room1", "kept.txt", "text/plain", b"kept")
+            .await
+            .unwrap();
+        let gone = manager
+            .save_file("room1", "gone.txt", "text/plain", b"gone")
+            .await
+            .unwrap();
+        manager.save_metadata_snapshot().await.unwrap();
+
+        // Deleted behind the server's back, plus a file nothing knows about
+        std::fs::remove_file(&gone.path).unwrap();
+        let stray = tmp_dir.path().join("stray.bin");
+        std::fs::write(&stray, b"stray").unwrap();
+
+        let restored = setup_snapshot_manager(&tmp_dir).await;
+        let report = restored.load_metadata_snapshot().await.unwrap();
+        assert_eq!(report.restored, 1);
+        assert_eq!(report.missing, vec![gone.filename.clone()]);
+        assert_eq!(report.orphans, vec![stray]);
+
+        assert!(restored.get_file(&kept.filename).is_some());
+        assert!(restored.get_file(&gone.filename).is_none());
+        let names: Vec<String> = restored
+            .list_room_files("room1")
+            .into_iter()
+            .map(|f| f.filename)
+            .collect();
+        assert_eq!(names, vec![kept.filename]);
+
+        // Re-uploading the vanished content stores a fresh copy instead of a dangling dup
+        let reupload = restored
+            .save_file("room1", "gone.txt", "text/plain", b"gone")
+            .await
+            .unwrap();
+        assert_ne!(reupload.is_duplicate, Some(true));
+        assert!(reupload.path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_snapshot_disabled_or_absent() {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        assert_eq!(manager.save_metadata_snapshot().await.unwrap(), None);
+
+        let manager = setup_snapshot_manager(&tmp_dir).await;
+        let report = manager.load_metadata_snapshot().await.unwrap();
+        assert_eq!(report.restored, 0);
+    }
+
+    // Orphan cleanup tests
+    #[tokio::test]
+    async fn test_orphan_cleanup_removes_only_orphans() {
+        let (manager, tmp_dir) = setup_test_manager().await;
+
+        let mut tracked = Vec::new();
+        for i in 0..20 {
+            let info = manager
+                .save_file(
+                    "room1",
+                    &format!("keep{}.txt", i),
+                    "text/plain",
+                    format!("keep {}", i).as_bytes(),
+                )
+                .await
+                .unwrap();
+            tracked.push(info);
+        }
+        // Duplicate whose original metadata entry is gone still references the physical file
+        let dup = manager
+            .save_file("room2", "dup.txt", "text/plain", b"keep 0")
+            .await
+            .unwrap();
+        manager.delete_file(&tracked[0].filename).await.unwrap();
+
+        for i in 0..50 {
+            std::fs::write(tmp_dir.path().join(format!("orphan{}.bin", i)), b"x").unwrap();
+        }
+
+        let cleaned = manager
+            .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+            .await;
+        assert_eq!(cleaned, 50);
+        for info in &tracked[1..] {
+            assert!(info.path.exists());
+        }
+        assert!(dup.path.exists());
+
+        // Calling again (periodically) is a no-op
+        let cleaned = manager
+            .cleanup_orphaned_files_with_grace(std::time::Duration::ZERO)
+            .await;
+        assert_eq!(cleaned, 0);
+    }
+
+    #[tokio::test]
+    async fn test_orphan_cleanup_skips_recent_files() {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        let fresh = tmp_dir.path().join("in_flight.bin");
+        std::fs::write(&fresh, b"uploading").unwrap();
+
+        assert_eq!(manager.cleanup_orphaned_files().await, 0);
+        assert!(fresh.exists());
+    }
+
+    // Reconciliation tests
+    #[tokio::test]
+    async fn test_reconcile_after_lagged_room_events() {
+        use crate::services::{JoinRoomRequest, RoomService};
+        use tokio::sync::broadcast::error::TryRecvError;
+
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let rooms = RoomService::new().with_event_capacity(2);
+        let mut events = rooms.subscribe();
+
+        // Five rooms each with a file, then four are destroyed while the listener is idle
+        let mut files = Vec::new();
+        for i in 0..5 {
+            let key = format!("lagroom{}", i);
+            rooms
+                .join_room(JoinRoomRequest::new(
+                    &key,
+                    "user",
+                    "User",
+                    &format!("sock{}", i),
+                ))
+                .unwrap();
+            files.push(
+                manager
+                    .save_file(
+                        &key,
+                        "f.txt",
+                        "text/plain",
+                        format!("data {}", i).as_bytes(),
+                    )
+                    .await
+                    .unwrap(),
+            );
+        }
+        for i in 0..4 {
+            rooms.leave_room(&format!("sock{}", i));
+        }
+
+        // The listener missed events
+        assert!(matches!(events.try_recv(), Err(TryRecvError::Lagged(_))));
+
+        let deleted = manager.reconcile_room_files(&rooms.room_keys());
+        assert_eq!(deleted.len(), 4);
+        for info in &files[..4] {
+            assert!(manager.get_file(&info.filename).is_none());
+            assert!(!info.path.exists());
+        }
+        assert!(manager.get_file(&files[4].filename).is_some());
+        assert!(files[4].path.exists());
+    }
+
+    // Download count tests
+    #[tokio::test]
+    async fn test_record_download_increments_count() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let info = manager
+            .save_file("room1", "a.txt", "text/plain", b"popular")
+            .await
+            .unwrap();
+        assert_eq!(info.download_count, 0);
+
+        assert_eq!(manager.record_download(&info.filename), Some(1));
+        assert_eq!(manager.record_download(&info.filename), Some(2));
+        assert_eq!(manager.record_download("missing.txt"), None);
+
+        // Downloaders are tracked separately and listed once each
+        manager.record_downloader(&info.filename, "u1");
+        manager.record_downloader(&info.filename, "u2");
+        manager.record_downloader(&info.filename, "u1");
+        assert_eq!(
+            manager.downloaders(&info.filename),
+            Some(vec!["u1".to_string(), "u2".to_string()])
+        );
+
+        assert_eq!(manager.get_file(&info.filename).unwrap().download_count, 2);
+        assert_eq!(manager.get_stats().total_downloads, 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_room_files() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let a = manager
+            .save_file("room1", "a.txt", "text/plain", b"a")
+            .await
+            .unwrap();
+        manager
+            .save_file("room2", "b.txt", "text/plain", b"b")
+            .await
+            .unwrap();
+        manager.record_download(&a.filename);
+
+        let list = manager.list_room_files("room1");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].filename, a.filename);
+        assert_eq!(list[0].download_count, 1);
+    }
+
+    async fn setup_compressing_manager() -> (FileManager, TempDir) {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        (manager.with_compression(true), tmp_dir)
+    }
+
+    async fn read_stored(info: &FileInfo) -> Vec<u8> {
+        use tokio::io::AsyncReadExt;
+        let file = fs::File::open(&info.path).await.unwrap();
+        let mut out = Vec::new();
+        stored_file_reader(file, info.compressed)
+            .read_to_end(&mut out)
+            .await
+            .unwrap();
+        out
+    }
+
+    #[tokio::test]
+    async fn test_compressed_file_round_trip() {
+        let (manager, _tmp_dir) = setup_compressing_manager().await;
+        let data = "timestamp,level,message\n".repeat(2000).into_bytes();
+
+        let info = manager
+            .save_file("room1", "log.csv", "text/csv", &data)
+            .await
+            .unwrap();
+        assert!(info.compressed);
+        assert_eq!(info.size, data.len() as u64);
+        assert!(std::fs::metadata(&info.path).unwrap().len() < data.len() as u64);
+        assert_eq!(read_stored(&info).await, data);
+
+        // Dedup still matches on the plaintext hash and shares the compressed blob
+        let dup = manager
+            .save_file("room2", "copy.csv", "text/csv", &data)
+            .await
+            .unwrap();
+        assert_eq!(dup.is_duplicate, Some(true));
+        assert_eq!(dup.path, info.path);
+        assert!(dup.compressed);
+    }
+
+    #[tokio::test]
+    async fn test_incompressible_file_stored_uncompressed() {
+        let (manager, _tmp_dir) = setup_compressing_manager().await;
+        let data: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+
+        let info = manager
+            .save_file("room1", "random.bin", "application/octet-stream", &data)
+            .await
+            .unwrap();
+        assert!(!info.compressed);
+        assert_eq!(std::fs::read(&info.path).unwrap(), data);
+        assert_eq!(read_stored(&info).await, data);
+    }
+
+    #[tokio::test]
+    async fn test_precompressed_mime_skips_compression() {
+        let (manager, _tmp_dir) = setup_compressing_manager().await;
+        let data = vec![0u8; 4096];
+
+        let info = manager
+            .save_file("room1", "archive.zip", "application/zip", &data)
+            .await
+            .unwrap();
+        assert!(!info.compressed);
+        assert_eq!(std::fs::read(&info.path).unwrap(), data);
+
+        let svg = manager
+            .save_file("room1", "icon.svg", "image/svg+xml", &[b' '; 4096])
+            .await
+            .unwrap();
+        assert!(svg.compressed);
+    }
+
+    #[tokio::test]
+    async fn test_compression_disabled_by_default() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let info = manager
+            .save_file("room1", "zeros.txt", "text/plain", &[b'a'; 4096])
+            .await
+            .unwrap();
+        assert!(!info.compressed);
+        assert_eq!(std::fs::metadata(&info.path).unwrap().len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_upload_in_chunks() {
+        let (manager, tmp_dir) = setup_test_manager().await;
+        let mut upload = manager.begin_upload().await.unwrap();
+        upload.write_chunk(b"hello ").await.unwrap();
+        upload.write_chunk(b"world").await.unwrap();
+        assert_eq!(upload.size(), 11);
+        assert_eq!(upload.head(), b"hello world");
+
+        let info = manager
+            .commit_upload(upload, "room1", "hello.txt", "text/plain", None)
+            .await
+            .unwrap();
+        assert_eq!(info.size, 11);
+        assert_eq!(std::fs::read(&info.path).unwrap(), b"hello world");
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+
+        // Same bytes in one shot dedupe against the streamed file
+        let dup = manager
+            .save_file("room1", "again.txt", "text/plain", b"hello world")
+            .await
+            .unwrap();
+        assert_eq!(dup.is_duplicate, Some(true));
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_upload_removes_temp_file() {
+        let tmp_dir = TempDir::new().unwrap();
+        let manager = FileManager::new_with_config(tmp_dir.path().to_path_buf(), 8, 12).unwrap();
+        let mut upload = manager.begin_upload().await.unwrap();
+        upload.write_chunk(b"12345").await.unwrap();
+        assert!(upload.write_chunk(b"6789").await.is_err());
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+
+        let temp_path = upload.temp_path.clone();
+        drop(upload);
+        wait_until_removed(&temp_path).await;
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 0);
+    }
+
+    /// Dropped uploads remove their temp file in the background
+    async fn wait_until_removed(path: &Path) {
+        for _ in 0..100 {
+            if !path.exists() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("{} was not removed", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_detached_file_leaves_room_but_survives_room_deletion() {
+        let (manager, _tmp_dir) = setup_test_manager().await;
+        let info = manager
+            .save_file("room1", "a.txt", "text/plain", b"detach me")
+            .await
+            .unwrap();
+        let keep_until = Utc::now() + Duration::days(3);
+
+        let detached = manager
+            .detach_from_room(&info.filename, keep_until)
+            .unwrap();
+        assert!(detached.detached);
+        assert_eq!(detached.expires_at, Some(keep_until));
+        assert!(manager.list_room_files("room1").is_empty());
+        assert!(manager.get_room_file(&info.filename).is_none());
+
+        // Destroying the room doesn't touch it; the share path can still read it
+        assert!(manager.delete_room_files("room1").is_empty());
+        assert!(manager.get_file(&info.filename).is_some());
+        assert!(info.path.exists());
+    }
 }
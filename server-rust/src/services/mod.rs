@@ -2,7 +2,15 @@ pub mod file_manager;
 pub mod room_service;
 pub mod share_service;
 pub mod socket;
+pub mod virus_scan;
 
-pub use file_manager::FileManager;
-pub use room_service::{JoinRoomRequest, RoomEvent, RoomService};
-pub use share_service::{CreateShareRequest, ShareService};
+pub use file_manager::{
+    DedupScope, FileManager, FileStats, LocalUploadFs, MetadataRestore, StorageTimeout, UploadFs,
+    UploadWriter,
+};
+pub use room_service::{
+    BannerSeverity, DeletedMessage, JoinRoomRequest, RoomDestroyReason, RoomError, RoomEvent,
+    RoomService, RoomSettings, RoomState, RoomStats, ServerBanner, UploadedFile,
+};
+pub use share_service::{CreateShareRequest, RotatePasswordError, ShareService};
+pub use virus_scan::{ClamAvScanner, ScanVerdict, VirusScanner};
@@ -1,10 +1,18 @@
 use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::{Arc, OnceLock, RwLock};
 use tokio::sync::broadcast;
 
 use crate::models::room::RoomInfo;
-use crate::models::{Message, Room, User};
+use crate::models::{
+    AuditAction, AuditEntry, ClientInfo, Message, ROOM_RATE_LIMIT_EVENTS, Room, RoomConfig, User,
+};
+use crate::utils::{
+    PasswordLockout, generate_session_token, normalize_room_key, redact_fingerprint,
+    validate_room_key,
+};
 
 /// Grace period before destroying a room when all users disconnect (in seconds).
 /// This allows users to reconnect after browser refresh without losing their session.
@@ -23,10 +31,140 @@ fn max_pinned_rooms() -> usize {
     })
 }
 
+/// Failure of a room creation or owner-only operation; `Display` is the client-facing message
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RoomError {
+    #[error("Room not found")]
+    NotFound,
+    #[error("Only the room owner can perform this action")]
+    NotOwner,
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Room creation is disabled")]
+    CreationDisabled,
+    #[error("Server at capacity")]
+    AtCapacity,
+    #[error("Room creation limit reached")]
+    CreationLimit,
+    #[error("{0}")]
+    Internal(String),
+    #[error("Lock error")]
+    Lock,
+}
+
+/// Why a room was destroyed (sent to clients as `reason`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomDestroyReason {
+    /// The last member left
+    Empty,
+    /// Members remain but are all offline (leave or periodic cleanup)
+    AllOffline,
+    /// No activity within the inactivity window
+    Idle,
+    /// Nobody reconnected within the grace period after the last disconnect
+    GracePeriodExpired,
+    /// Destroyed by an operator via the admin API
+    Admin,
+}
+
+/// File metadata broadcast to a room as `fileUploaded` after a REST upload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedFile {
+    pub room_key: String,
+    pub file_id: String,
+    pub name: String,
+    pub size: u64,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub download_url: String,
+}
+
+/// Self-destructing message removed from a room, broadcast as `messageDeleted`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedMessage {
+    pub room_key: String,
+    pub message_id: String,
+}
+
+/// Maximum length of the server banner text, in characters
+pub const MAX_BANNER_LEN: usize = 500;
+
+/// How prominently clients should display the server banner
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BannerSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl BannerSeverity {
+    /// Parse a severity name (`info|warning|critical`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "info" => Some(Self::Info),
+            "warning" => Some(Self::Warning),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Operator announcement shown to every client, broadcast as `serverBanner`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerBanner {
+    pub text: String,
+    pub severity: BannerSeverity,
+}
+
+impl ServerBanner {
+    /// Validate banner text (trimmed, non-empty, at most MAX_BANNER_LEN characters)
+    pub fn new(text: &str, severity: BannerSeverity) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Banner text is required".to_string());
+        }
+        if text.chars().count() > MAX_BANNER_LEN {
+            return Err(format!(
+                "Banner text must be at most {} characters",
+                MAX_BANNER_LEN
+            ));
+        }
+        Ok(Self {
+            text: text.to_string(),
+            severity,
+        })
+    }
+
+    /// Banner from SERVER_BANNER / SERVER_BANNER_SEVERITY (default info), None when unset
+    pub fn from_env() -> Option<Self> {
+        let text = std::env::var("SERVER_BANNER").ok()?;
+        let severity = std::env::var("SERVER_BANNER_SEVERITY")
+            .ok()
+            .and_then(|v| BannerSeverity::parse(&v))
+            .unwrap_or_default();
+        Self::new(&text, severity)
+            .inspect_err(|e| tracing::warn!("Ignoring SERVER_BANNER: {}", e))
+            .ok()
+    }
+}
+
 /// Events emitted by RoomService
 #[derive(Debug, Clone)]
 pub enum RoomEvent {
-    RoomDestroyed { room_key: String },
+    RoomDestroyed {
+        room_key: String,
+        reason: RoomDestroyReason,
+    },
+    FileUploaded(UploadedFile),
+    MessageDeleted(DeletedMessage),
+    /// Server banner set or cleared (None), broadcast to every client
+    BannerChanged(Option<ServerBanner>),
 }
 
 /// Request parameters for joining a room
@@ -38,7 +176,12 @@ pub struct JoinRoomRequest<'a> {
     pub socket_id: &'a str,
     pub password: Option<&'a str>,
     pub device_type: &'a str,
+    /// Optional platform details reported by the client
+    pub client_info: Option<&'a ClientInfo>,
     pub fingerprint: Option<&'a str>,
+    pub client_ip: Option<&'a str>,
+    /// Reconnection token from a previous join; takes precedence over the fingerprint
+    pub session_token: Option<&'a str>,
 }
 
 impl<'a> JoinRoomRequest<'a> {
@@ -51,7 +194,10 @@ impl<'a> JoinRoomRequest<'a> {
             socket_id,
             password: None,
             device_type: "desktop",
+            client_info: None,
             fingerprint: None,
+            client_ip: None,
+            session_token: None,
         }
     }
 
@@ -69,6 +215,60 @@ impl<'a> JoinRoomRequest<'a> {
         self.device_type = device_type;
         self
     }
+
+    pub fn with_client_info(mut self, client_info: &'a ClientInfo) -> Self {
+        self.client_info = Some(client_info);
+        self
+    }
+
+    pub fn with_client_ip(mut self, client_ip: &'a str) -> Self {
+        self.client_ip = Some(client_ip);
+        self
+    }
+
+    pub fn with_session_token(mut self, session_token: &'a str) -> Self {
+        self.session_token = Some(session_token);
+        self
+    }
+
+    /// Key identifying the joining client for password lockout tracking. The IP comes
+    /// first: the fingerprint is client-chosen, so rotating it must not reset the count.
+    /// Fingerprint, then socket, only when no IP is known.
+    fn lockout_key(&self) -> String {
+        let client = self
+            .client_ip
+            .or(self.fingerprint.filter(|fp| !fp.trim().is_empty()))
+            .unwrap_or(self.socket_id);
+        format!("{}:{}", self.room_key, client)
+    }
+}
+
+/// Resolved room policy settings, reported by the admin config endpoint
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomSettings {
+    pub allow_room_creation: bool,
+    /// 0 = unlimited
+    pub max_total_rooms: usize,
+    /// 0 = unlimited
+    pub max_rooms_per_fingerprint: usize,
+    pub auto_room_password: bool,
+    pub room_rate_limit_ceiling: u32,
+    pub session_token_ttl_secs: i64,
+    pub normalize_room_keys: bool,
+    pub inactive_cutoff_secs: i64,
+}
+
+/// Capacity of the room event broadcast channel. A listener that falls further
+/// behind than this receives `Lagged` and must reconcile instead.
+const ROOM_EVENT_CAPACITY: usize = 1024;
+
+/// Identity a reconnection session token resolves to
+#[derive(Debug, Clone)]
+struct SessionToken {
+    room_key: String,
+    user_id: String,
+    expires_at: chrono::DateTime<Utc>,
 }
 
 /// Service for managing rooms
@@ -77,42 +277,621 @@ pub struct RoomService {
     socket_users: RwLock<HashMap<String, User>>, // socket_id -> User
     user_sockets: RwLock<HashMap<String, String>>, // user_id -> socket_id
     event_sender: broadcast::Sender<RoomEvent>,
+    password_lockout: PasswordLockout, // "room_key:client" -> failed attempts
+    dry_run: bool,                     // cleanup only reports, never destroys
+    max_rooms_per_fingerprint: usize,  // 0 = unlimited
+    session_tokens: RwLock<HashMap<String, SessionToken>>, // token -> identity
+    session_token_ttl: Duration,
+    room_rate_limit_ceiling: u32, // upper bound for owner rate limit overrides
+    destroy_grace_period: std::time::Duration,
+    allow_room_creation: bool, // false: only admin-created rooms can be joined
+    max_total_rooms: usize,    // 0 = unlimited
+    fingerprint_reconnect_max_age: Option<Duration>, // None = reconnect regardless of age
+    auto_room_password: bool,  // rooms created by joining get a generated password
+    banner: RwLock<Option<ServerBanner>>, // sent to sockets on connect
+    normalize_room_keys: bool, // equivalent keys (case, separators) share one room
+    /// Idle rooms older than this are destroyed by cleanup
+    inactive_cutoff: Duration,
 }
 
 impl RoomService {
     pub fn new() -> Self {
-        let (event_sender, _) = broadcast::channel(64);
+        let (event_sender, _) = broadcast::channel(ROOM_EVENT_CAPACITY);
         Self {
             rooms: RwLock::new(HashMap::new()),
             socket_users: RwLock::new(HashMap::new()),
             user_sockets: RwLock::new(HashMap::new()),
             event_sender,
+            // ROOM_PASSWORD_MAX_ATTEMPTS (default 5) / ROOM_PASSWORD_LOCKOUT_SECS (default 300)
+            password_lockout: PasswordLockout::from_env("ROOM_PASSWORD", 5, 300),
+            dry_run: false,
+            max_rooms_per_fingerprint: std::env::var("MAX_ROOMS_PER_FINGERPRINT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20),
+            session_tokens: RwLock::new(HashMap::new()),
+            // SESSION_TOKEN_TTL_SECS (default 24h, matching inactive room cleanup)
+            session_token_ttl: Duration::seconds(
+                std::env::var("SESSION_TOKEN_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(24 * 3600),
+            ),
+            room_rate_limit_ceiling: std::env::var("ROOM_RATE_LIMIT_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&max: &u32| max > 0)
+                .unwrap_or(300),
+            destroy_grace_period: std::time::Duration::from_secs(ROOM_DESTROY_GRACE_PERIOD_SECS),
+            allow_room_creation: std::env::var("ALLOW_ROOM_CREATION")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(true),
+            max_total_rooms: std::env::var("MAX_TOTAL_ROOMS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            // FINGERPRINT_RECONNECT_MAX_AGE_SECS (default 0 = no limit)
+            fingerprint_reconnect_max_age: std::env::var("FINGERPRINT_RECONNECT_MAX_AGE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs: &i64| secs > 0)
+                .map(Duration::seconds),
+            auto_room_password: std::env::var("AUTO_ROOM_PASSWORD")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            banner: RwLock::new(ServerBanner::from_env()),
+            normalize_room_keys: std::env::var("ROOM_KEY_NORMALIZE")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            // ROOM_INACTIVE_CUTOFF_HOURS (default 24; values beyond what a Duration
+            // holds are clamped to the maximum)
+            inactive_cutoff: std::env::var("ROOM_INACTIVE_CUTOFF_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&hours: &i64| hours > 0)
+                .map(|hours| Duration::try_hours(hours).unwrap_or(Duration::MAX))
+                .unwrap_or_else(|| Duration::hours(24)),
+        }
+    }
+
+    /// Destroy unpinned rooms during cleanup once idle for longer than `cutoff`
+    pub fn with_inactive_cutoff(mut self, cutoff: Duration) -> Self {
+        self.inactive_cutoff = cutoff;
+        self
+    }
+
+    /// Protect every room created through `join_room` with a generated password.
+    /// The creator joins without it; everyone after needs it.
+    pub fn with_auto_room_password(mut self, enabled: bool) -> Self {
+        self.auto_room_password = enabled;
+        self
+    }
+
+    /// Only resume an offline user by fingerprint if they were seen within `max_age`;
+    /// older identities are replaced by a fresh user. `None` disables the limit.
+    pub fn with_fingerprint_reconnect_max_age(
+        mut self,
+        max_age: Option<std::time::Duration>,
+    ) -> Self {
+        self.fingerprint_reconnect_max_age =
+            max_age.map(|age| Duration::from_std(age).unwrap_or(Duration::MAX));
+        self
+    }
+
+    /// Whether an existing member may be resumed by fingerprint (online, or offline
+    /// for less than the configured max age)
+    fn can_reconnect_by_fingerprint(&self, user: &User) -> bool {
+        match self.fingerprint_reconnect_max_age {
+            Some(max_age) if !user.is_online => Utc::now() - user.last_seen <= max_age,
+            _ => true,
+        }
+    }
+
+    /// Cap the number of live rooms server-wide (0 = unlimited)
+    pub fn with_max_total_rooms(mut self, max: usize) -> Self {
+        self.max_total_rooms = max;
+        self
+    }
+
+    /// Reject creating a room when the server already holds `max_total_rooms`
+    fn check_capacity(&self, rooms: &HashMap<String, Room>) -> Result<(), RoomError> {
+        if self.max_total_rooms > 0 && rooms.len() >= self.max_total_rooms {
+            tracing::warn!(
+                "Room capacity reached ({} rooms), rejecting new room",
+                rooms.len()
+            );
+            return Err(RoomError::AtCapacity);
+        }
+        Ok(())
+    }
+
+    /// Allow or forbid self-service room creation (joins and `create_room`).
+    /// Admin-created rooms are unaffected.
+    pub fn with_room_creation(mut self, allow: bool) -> Self {
+        self.allow_room_creation = allow;
+        self
+    }
+
+    /// Override the reconnection grace period used by `schedule_room_destroy_check`
+    pub fn with_destroy_grace_period(mut self, grace: std::time::Duration) -> Self {
+        self.destroy_grace_period = grace;
+        self
+    }
+
+    /// Destroy a room immediately (admin action), dropping its members' socket mappings.
+    /// Returns false if the room does not exist.
+    pub fn destroy_room(&self, room_key: &str) -> bool {
+        let room_key = &*self.canonical_room_key(room_key);
+        {
+            // Unified lock order: rooms → socket_users → user_sockets
+            let Ok(mut rooms) = self.rooms.write() else {
+                return false;
+            };
+            let Some(room) = rooms.remove(room_key) else {
+                return false;
+            };
+            if let (Ok(mut socket_users), Ok(mut user_sockets)) =
+                (self.socket_users.write(), self.user_sockets.write())
+            {
+                for user_id in room.users.keys() {
+                    if let Some(socket_id) = user_sockets.remove(user_id) {
+                        socket_users.remove(&socket_id);
+                    }
+                }
+            }
+        }
+        tracing::info!("Room {} destroyed by admin", room_key);
+        let _ = self.event_sender.send(RoomEvent::RoomDestroyed {
+            room_key: room_key.to_string(),
+            reason: RoomDestroyReason::Admin,
+        });
+        true
+    }
+
+    /// Override the upper bound for per-room rate limit overrides
+    pub fn with_room_rate_limit_ceiling(mut self, max: u32) -> Self {
+        self.room_rate_limit_ceiling = max.max(1);
+        self
+    }
+
+    /// Set a room's rate limit overrides (owner only). Values are clamped to
+    /// `1..=ROOM_RATE_LIMIT_MAX`; unknown events are rejected. Replaces previous overrides.
+    pub fn set_room_rate_limits(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+        limits: HashMap<String, u32>,
+    ) -> Result<RoomConfig, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        if let Some(event) = limits
+            .keys()
+            .find(|e| !ROOM_RATE_LIMIT_EVENTS.contains(&e.as_str()))
+        {
+            return Err(RoomError::Invalid(format!(
+                "Unsupported rate limit event: {}",
+                event
+            )));
+        }
+
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get_mut(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        room.config.rate_limits = limits
+            .into_iter()
+            .map(|(event, max)| (event, max.clamp(1, self.room_rate_limit_ceiling)))
+            .collect();
+        Ok(room.config.clone())
+    }
+
+    /// Set the room-wide message budget in messages per minute (owner only); `None`
+    /// removes it. Values are clamped to `1..=ROOM_RATE_LIMIT_MAX`.
+    pub fn set_room_message_rate_limit(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+        per_minute: Option<u32>,
+    ) -> Result<RoomConfig, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get_mut(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        room.config.message_rate_limit =
+            per_minute.map(|max| max.clamp(1, self.room_rate_limit_ceiling));
+        Ok(room.config.clone())
+    }
+
+    /// Toggle whether a room keeps message history (owner only). Disabling it also
+    /// discards the history already stored.
+    pub fn set_persist_messages(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+        persist: bool,
+    ) -> Result<RoomConfig, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get_mut(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        room.config.persist_messages = persist;
+        if !persist {
+            room.clear_messages();
+        }
+        Ok(room.config.clone())
+    }
+
+    /// Lock or unlock a room (owner only). A locked room rejects new users while
+    /// existing members can still reconnect by session token or fingerprint.
+    pub fn set_room_locked(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+        locked: bool,
+    ) -> Result<RoomConfig, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get_mut(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        room.config.locked = locked;
+        Ok(room.config.clone())
+    }
+
+    /// Create an owner announcement (a system message). When `pinned`, it is also
+    /// stored in the room history and gets a seq; otherwise it is only broadcast.
+    /// `content` must already be sanitized.
+    pub fn create_announcement(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+        content: String,
+        pinned: bool,
+    ) -> Result<Message, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get_mut(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        let mut message = Message::new_system(
+            crate::utils::generate_message_id(),
+            room_key.to_string(),
+            content,
+        );
+        message.pinned = pinned;
+        if pinned {
+            message.seq = room.add_message(message.clone());
+        }
+        Ok(message)
+    }
+
+    /// Per-socket limit for `event` in a room, if the owner overrode it (bounded by the ceiling)
+    pub fn room_rate_limit(&self, room_key: &str, event: &str) -> Option<u32> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let rooms = self.rooms.read().ok()?;
+        let max = *rooms.get(room_key)?.config.rate_limits.get(event)?;
+        Some(max.min(self.room_rate_limit_ceiling))
+    }
+
+    /// Override how long reconnection session tokens stay valid
+    pub fn with_session_token_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.session_token_ttl = Duration::from_std(ttl).unwrap_or(Duration::zero());
+        self
+    }
+
+    /// Issue a reconnection token for a user that has joined a room
+    pub fn issue_session_token(&self, room_key: &str, user_id: &str) -> Result<String, String> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let token = generate_session_token();
+        let mut tokens = self.session_tokens.write().map_err(|_| "Lock error")?;
+        tokens.insert(
+            token.clone(),
+            SessionToken {
+                room_key: room_key.to_string(),
+                user_id: user_id.to_string(),
+                expires_at: Utc::now() + self.session_token_ttl,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Consume a session token, returning the user id it resumes in `room_key`
+    /// (tokens are single-use; expired or foreign-room tokens resolve to nothing)
+    fn take_session_token(&self, token: &str, room_key: &str) -> Option<String> {
+        let mut tokens = self.session_tokens.write().ok()?;
+        match tokens.get(token) {
+            Some(entry) if entry.room_key == room_key => {
+                let entry = tokens.remove(token)?;
+                (entry.expires_at > Utc::now()).then_some(entry.user_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// User a live session token was issued to in `room_key`, without consuming it
+    /// (lets REST calls prove which member is calling)
+    pub fn session_user(&self, token: &str, room_key: &str) -> Option<String> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let tokens = self.session_tokens.read().ok()?;
+        tokens
+            .get(token)
+            .filter(|entry| entry.room_key == room_key && entry.expires_at > Utc::now())
+            .map(|entry| entry.user_id.clone())
+    }
+
+    /// Whether a locked room turns away a new user; the owner is always let back in
+    fn is_locked_for(room: &Room, fingerprint: Option<&str>) -> bool {
+        room.config.locked && !fingerprint.is_some_and(|fp| room.is_owner(fp))
+    }
+
+    /// Move an existing room member onto a new socket (reconnection)
+    fn resume_user(
+        &self,
+        room: &mut Room,
+        existing_user: &User,
+        req: &JoinRoomRequest,
+        via: &str,
+    ) -> Result<(User, Vec<User>), String> {
+        let mut user = existing_user.clone();
+        user.update_activity();
+        // The same identity may come back on a different device
+        user.device_type = req.device_type.to_string();
+        user.client_info = req.client_info.cloned().unwrap_or_default();
+
+        // Update socket mappings
+        {
+            let mut socket_users = self.socket_users.write().map_err(|_| "Lock error")?;
+            let mut user_sockets = self.user_sockets.write().map_err(|_| "Lock error")?;
+            // Remove old socket mapping
+            if let Some(old_socket) = user_sockets.get(&user.id) {
+                socket_users.remove(old_socket);
+            }
+            socket_users.insert(req.socket_id.to_string(), user.clone());
+            user_sockets.insert(user.id.clone(), req.socket_id.to_string());
+        }
+
+        // Update user in room
+        if let Some(u) = room.get_user_mut(&user.id) {
+            u.update_activity();
+            u.device_type = user.device_type.clone();
+            u.client_info = user.client_info.clone();
+        }
+        room.record_audit(AuditAction::Reconnect, &user, req.client_ip);
+
+        let users: Vec<User> = room.get_users().into_iter().cloned().collect();
+        tracing::info!(
+            "User {} reconnected to room {} via {}",
+            user.username,
+            req.room_key,
+            via
+        );
+        Ok((user, users))
+    }
+
+    /// Limit how many live rooms a single fingerprint may create (0 = unlimited)
+    pub fn with_max_rooms_per_fingerprint(mut self, max: usize) -> Self {
+        self.max_rooms_per_fingerprint = max;
+        self
+    }
+
+    /// Effective room policy settings
+    pub fn settings(&self) -> RoomSettings {
+        RoomSettings {
+            allow_room_creation: self.allow_room_creation,
+            max_total_rooms: self.max_total_rooms,
+            max_rooms_per_fingerprint: self.max_rooms_per_fingerprint,
+            auto_room_password: self.auto_room_password,
+            room_rate_limit_ceiling: self.room_rate_limit_ceiling,
+            session_token_ttl_secs: self.session_token_ttl.num_seconds(),
+            normalize_room_keys: self.normalize_room_keys,
+            inactive_cutoff_secs: self.inactive_cutoff.num_seconds(),
+        }
+    }
+
+    /// Number of live rooms created by a fingerprint
+    pub fn rooms_created_by(&self, fingerprint: &str) -> usize {
+        self.rooms
+            .read()
+            .map(|rooms| count_rooms_created_by(&rooms, fingerprint))
+            .unwrap_or(0)
+    }
+
+    /// Reject creating another room when the creator already owns too many live rooms.
+    /// Creators without a fingerprint are counted by client IP instead.
+    fn check_room_creation_limit(
+        &self,
+        rooms: &HashMap<String, Room>,
+        creator_fingerprint: Option<&str>,
+        creator_ip: Option<&str>,
+    ) -> Result<(), RoomError> {
+        if self.max_rooms_per_fingerprint == 0 {
+            return Ok(());
+        }
+        let created = match creator_fingerprint.filter(|fp| !fp.trim().is_empty()) {
+            Some(fp) => count_rooms_created_by(rooms, fp),
+            None => match creator_ip.filter(|ip| !ip.trim().is_empty()) {
+                Some(ip) => count_rooms_created_from(rooms, ip),
+                None => return Ok(()),
+            },
+        };
+        if created >= self.max_rooms_per_fingerprint {
+            tracing::warn!("Room creation limit reached for client");
+            return Err(RoomError::CreationLimit);
+        }
+        Ok(())
+    }
+
+    /// Override the room event channel capacity (existing subscribers are detached)
+    pub fn with_event_capacity(mut self, capacity: usize) -> Self {
+        let (event_sender, _) = broadcast::channel(capacity.max(1));
+        self.event_sender = event_sender;
+        self
+    }
+
+    /// In dry-run mode cleanup logs and returns candidates without destroying anything
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Override the password lockout policy (0 attempts disables lockout)
+    pub fn with_password_lockout(
+        mut self,
+        max_attempts: u32,
+        lockout: std::time::Duration,
+    ) -> Self {
+        self.password_lockout = PasswordLockout::new(max_attempts, lockout);
+        self
+    }
+
+    /// Resolve equivalent room keys to one room (see `normalize_room_key`)
+    pub fn with_room_key_normalization(mut self, normalize: bool) -> Self {
+        self.normalize_room_keys = normalize;
+        self
+    }
+
+    /// Key a room is stored and broadcast under; unchanged unless normalization is on
+    pub fn canonical_room_key<'a>(&self, room_key: &'a str) -> Cow<'a, str> {
+        if self.normalize_room_keys {
+            Cow::Owned(normalize_room_key(room_key))
+        } else {
+            Cow::Borrowed(room_key)
         }
     }
 
+    /// Canonical key for a client-supplied room key. Both the key as typed and its
+    /// canonical form must be valid, since normalization can shorten a key (`a-----1`
+    /// collapses to `a1`).
+    pub fn validated_room_key<'a>(&self, room_key: &'a str) -> Result<Cow<'a, str>, &'static str> {
+        validate_room_key(room_key)?;
+        let canonical = self.canonical_room_key(room_key);
+        validate_room_key(&canonical)?;
+        Ok(canonical)
+    }
+
     /// Subscribe to room events
     pub fn subscribe(&self) -> broadcast::Receiver<RoomEvent> {
         self.event_sender.subscribe()
     }
 
+    /// Announce a file uploaded over HTTP so the socket layer can tell the room
+    pub fn notify_file_uploaded(&self, file: UploadedFile) {
+        let _ = self.event_sender.send(RoomEvent::FileUploaded(file));
+    }
+
+    /// Current server banner, sent to each socket as it connects
+    pub fn banner(&self) -> Option<ServerBanner> {
+        self.banner.read().ok().and_then(|banner| banner.clone())
+    }
+
+    /// Replace or clear (None) the server banner and broadcast it to every client
+    pub fn set_banner(&self, banner: Option<ServerBanner>) -> Result<(), String> {
+        *self.banner.write().map_err(|_| "Lock error")? = banner.clone();
+        let _ = self.event_sender.send(RoomEvent::BannerChanged(banner));
+        Ok(())
+    }
+
     /// Create a new room (idempotent - returns existing room if already exists)
     pub fn create_room(
         &self,
         room_key: &str,
         password: Option<&str>,
         creator_fingerprint: Option<&str>,
-    ) -> Result<RoomInfo, String> {
-        let mut rooms = self.rooms.write().map_err(|_| "Lock error")?;
+    ) -> Result<RoomInfo, RoomError> {
+        self.create_room_from(room_key, password, creator_fingerprint, None)
+    }
+
+    /// `create_room` recording the creator's client IP, which the creation cap falls
+    /// back to when no fingerprint is given
+    pub fn create_room_from(
+        &self,
+        room_key: &str,
+        password: Option<&str>,
+        creator_fingerprint: Option<&str>,
+        creator_ip: Option<&str>,
+    ) -> Result<RoomInfo, RoomError> {
+        self.insert_room(
+            room_key,
+            password,
+            creator_fingerprint,
+            creator_ip,
+            None,
+            true,
+        )
+    }
+
+    /// Create a room on behalf of an admin, bypassing `ALLOW_ROOM_CREATION` and the
+    /// per-fingerprint cap (idempotent like `create_room`). `config` only applies when
+    /// the room is newly created; rate limit overrides are validated and clamped like
+    /// `set_room_rate_limits`.
+    pub fn admin_create_room(
+        &self,
+        room_key: &str,
+        password: Option<&str>,
+        config: Option<RoomConfig>,
+    ) -> Result<RoomInfo, RoomError> {
+        let config = config
+            .map(|mut config| {
+                if let Some(event) = config
+                    .rate_limits
+                    .keys()
+                    .find(|e| !ROOM_RATE_LIMIT_EVENTS.contains(&e.as_str()))
+                {
+                    return Err(RoomError::Invalid(format!(
+                        "Unsupported rate limit event: {}",
+                        event
+                    )));
+                }
+                for max in config.rate_limits.values_mut() {
+                    *max = (*max).clamp(1, self.room_rate_limit_ceiling);
+                }
+                config.max_messages = config.max_messages.max(1);
+                Ok(config)
+            })
+            .transpose()?;
+        self.insert_room(room_key, password, None, None, config, false)
+    }
 
-        if let Some(room) = rooms.get(room_key) {
+    fn insert_room(
+        &self,
+        room_key: &str,
+        password: Option<&str>,
+        creator_fingerprint: Option<&str>,
+        creator_ip: Option<&str>,
+        config: Option<RoomConfig>,
+        enforce_policy: bool,
+    ) -> Result<RoomInfo, RoomError> {
+        let canonical_key = self.canonical_room_key(room_key);
+        let display_key = (canonical_key != room_key).then_some(room_key);
+        let room_key = &*canonical_key;
+        let mut rooms = self.rooms.write().map_err(|_| RoomError::Lock)?;
+
+        if let Some(room) = rooms.get_mut(room_key) {
             // Room already exists, return existing room info (idempotent, matching Node.js behavior)
+            if !enforce_policy {
+                room.provisioned = true;
+            }
             return Ok(room.to_info());
         }
 
+        if enforce_policy {
+            if !self.allow_room_creation {
+                return Err(RoomError::CreationDisabled);
+            }
+            self.check_room_creation_limit(&rooms, creator_fingerprint, creator_ip)?;
+        }
+        self.check_capacity(&rooms)?;
+
         let password_hash = match password {
             Some(p) => Some(
                 bcrypt::hash(p, bcrypt::DEFAULT_COST)
-                    .map_err(|e| format!("Password hash error: {}", e))?,
+                    .map_err(|e| RoomError::Internal(format!("Password hash error: {}", e)))?,
             ),
             None => None,
         };
@@ -122,6 +901,11 @@ impl RoomService {
             password.map(|p| p.to_string()),
             password_hash,
         );
+        room.display_key = display_key.map(|key| key.trim().to_string());
+        room.created_by_ip = creator_ip.map(|ip| ip.to_string());
+        // Admin rooms must survive their members leaving: they can't be recreated
+        // by joining when ALLOW_ROOM_CREATION=false
+        room.provisioned = !enforce_policy;
 
         // Set creator when room is first created (验证 fingerprint 有效性)
         if let Some(fp) = creator_fingerprint {
@@ -129,6 +913,9 @@ impl RoomService {
                 room.set_creator(fp);
             }
         }
+        if let Some(config) = config {
+            room.config = config;
+        }
 
         let info = room.to_info();
         rooms.insert(room_key.to_string(), room);
@@ -137,14 +924,37 @@ impl RoomService {
         Ok(info)
     }
 
+    /// Keys of rooms where a user with this fingerprint is a member (online or
+    /// offline), sorted
+    pub fn rooms_for_fingerprint(&self, fingerprint: &str) -> Vec<String> {
+        if fingerprint.trim().is_empty() {
+            return Vec::new();
+        }
+        let mut keys: Vec<String> = self
+            .rooms
+            .read()
+            .map(|rooms| {
+                rooms
+                    .values()
+                    .filter(|room| room.find_user_by_fingerprint(fingerprint).is_some())
+                    .map(|room| room.room_key.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+
     /// Get room info
     pub fn get_room_info(&self, room_key: &str) -> Option<RoomInfo> {
+        let room_key = &*self.canonical_room_key(room_key);
         let rooms = self.rooms.read().ok()?;
         rooms.get(room_key).map(|r| r.to_info())
     }
 
     /// Check if room exists
     pub fn room_exists(&self, room_key: &str) -> bool {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .map(|rooms| rooms.contains_key(room_key))
@@ -153,6 +963,7 @@ impl RoomService {
 
     /// Check if room has password
     pub fn room_has_password(&self, room_key: &str) -> bool {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .ok()
@@ -162,6 +973,7 @@ impl RoomService {
 
     /// Get room password (plaintext) for share link generation
     pub fn get_room_password(&self, room_key: &str) -> Option<String> {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .ok()
@@ -170,6 +982,7 @@ impl RoomService {
 
     /// Verify room password
     pub fn verify_room_password(&self, room_key: &str, password: &str) -> Result<bool, String> {
+        let room_key = &*self.canonical_room_key(room_key);
         let rooms = self.rooms.read().map_err(|_| "Lock error")?;
         match rooms.get(room_key) {
             Some(room) => Ok(room.verify_password(password)),
@@ -177,93 +990,204 @@ impl RoomService {
         }
     }
 
-    /// Join a room
-    pub fn join_room(&self, req: JoinRoomRequest) -> Result<(User, Vec<User>), String> {
-        let mut rooms = self.rooms.write().map_err(|_| "Lock error")?;
+    /// Remaining password lockout for a client on a room, if it is locked out. Shares
+    /// the counter socket joins use when the client IP is known.
+    pub fn password_lockout_remaining(
+        &self,
+        room_key: &str,
+        client: &str,
+    ) -> Option<std::time::Duration> {
+        let room_key = self.canonical_room_key(room_key);
+        self.password_lockout
+            .remaining_lockout(&format!("{}:{}", room_key, client))
+    }
+
+    /// Record a failed password attempt. Returns true if the client is now locked out.
+    pub fn record_password_failure(&self, room_key: &str, client: &str) -> bool {
+        let room_key = self.canonical_room_key(room_key);
+        let locked = self
+            .password_lockout
+            .record_failure(&format!("{}:{}", room_key, client));
+        if locked {
+            tracing::warn!(
+                "Room {} locked for client after {} failed password attempts",
+                room_key,
+                self.password_lockout.max_attempts()
+            );
+        }
+        locked
+    }
+
+    /// Clear failed password attempts after a successful verification
+    pub fn reset_password_failures(&self, room_key: &str, client: &str) {
+        let room_key = self.canonical_room_key(room_key);
+        self.password_lockout
+            .reset(&format!("{}:{}", room_key, client));
+    }
+
+    /// Join a room; the third element is the originally requested username when it had
+    /// to be adjusted to stay unique within the room
+    pub fn join_room(
+        &self,
+        req: JoinRoomRequest,
+    ) -> Result<(User, Vec<User>, Option<String>), String> {
+        let canonical_key = self.canonical_room_key(req.room_key);
+        let display_key = (canonical_key != req.room_key).then_some(req.room_key);
+        let req = JoinRoomRequest {
+            room_key: &canonical_key,
+            ..req
+        };
+        // Hash an auto-generated password before taking the lock: bcrypt is deliberately
+        // slow. If the room appears meanwhile the password is simply unused.
+        let mut generated_password = None;
+        let mut rooms = loop {
+            let rooms = self.rooms.write().map_err(|_| "Lock error")?;
+            if generated_password.is_some()
+                || !self.auto_room_password
+                || !self.allow_room_creation
+                || rooms.contains_key(req.room_key)
+            {
+                break rooms;
+            }
+            drop(rooms);
+            // Same generation as setting an empty password on an existing room
+            let password = uuid::Uuid::new_v4().to_string();
+            let hash = bcrypt::hash(&password, bcrypt::DEFAULT_COST).map_err(|e| e.to_string())?;
+            generated_password = Some((password, hash));
+        };
+
+        // Joining existing rooms is always allowed; only new rooms count toward the cap
+        let created = !rooms.contains_key(req.room_key);
+        if created {
+            if !self.allow_room_creation {
+                return Err("Room does not exist".to_string());
+            }
+            self.check_room_creation_limit(&rooms, req.fingerprint, req.client_ip)
+                .map_err(|e| e.to_string())?;
+            self.check_capacity(&rooms).map_err(|e| e.to_string())?;
+        }
 
         // Create room if it doesn't exist, setting creator on creation
         let room = rooms.entry(req.room_key.to_string()).or_insert_with(|| {
-            let mut new_room = Room::new(req.room_key.to_string(), None, None);
+            let (password, password_hash) = generated_password.unzip();
+            let mut new_room = Room::new(req.room_key.to_string(), password, password_hash);
+            new_room.display_key = display_key.map(|key| key.trim().to_string());
             // Set creator when room is first created (验证 fingerprint 有效性)
             if let Some(fp) = req.fingerprint {
                 if !fp.trim().is_empty() {
                     new_room.set_creator(fp);
                 }
             }
+            new_room.created_by_ip = req.client_ip.map(|ip| ip.to_string());
             new_room
         });
 
-        // Verify password if room has one
-        if room.has_password() {
+        // Verify password if room has one (locked-out clients are rejected before verification).
+        // The creator of an auto-protected room is let in to receive the generated password.
+        if room.has_password() && !created {
+            let lockout_key = req.lockout_key();
+            if self.password_lockout.is_locked(&lockout_key) {
+                tracing::warn!("Password attempts locked for room {}", req.room_key);
+                return Err("Too many attempts, locked".to_string());
+            }
             match req.password {
-                Some(pwd) if room.verify_password(pwd) => {}
-                Some(_) => return Err("Invalid password".to_string()),
+                Some(pwd) if room.verify_password(pwd) => {
+                    self.password_lockout.reset(&lockout_key);
+                }
+                Some(_) => {
+                    if self.password_lockout.record_failure(&lockout_key) {
+                        tracing::warn!(
+                            "Room {} locked for client after {} failed password attempts",
+                            req.room_key,
+                            self.password_lockout.max_attempts()
+                        );
+                    }
+                    return Err("Invalid password".to_string());
+                }
                 None => return Err("Password required".to_string()),
             }
         }
 
+        // Resume the identity bound to a session token (works without a stable fingerprint)
+        if let Some(user_id) = req
+            .session_token
+            .and_then(|token| self.take_session_token(token, req.room_key))
+            && let Some(existing_user) = room.get_user(&user_id).cloned()
+        {
+            return self
+                .resume_user(room, &existing_user, &req, "session token")
+                .map(|(user, users)| (user, users, None));
+        }
+
         // Check if user with this fingerprint already exists (reconnection)
+        let mut fresh_user_id = None;
         if let Some(fp) = req.fingerprint
-            && let Some(existing_user) = room.find_user_by_fingerprint(fp)
+            && let Some(existing_user) = room.find_user_by_fingerprint(fp).cloned()
         {
-            let mut user = existing_user.clone();
-            user.update_activity();
-
-            // Update socket mappings
-            {
-                let mut socket_users = self.socket_users.write().map_err(|_| "Lock error")?;
-                let mut user_sockets = self.user_sockets.write().map_err(|_| "Lock error")?;
-                // Remove old socket mapping
-                if let Some(old_socket) = user_sockets.get(&user.id) {
-                    socket_users.remove(old_socket);
-                }
-                socket_users.insert(req.socket_id.to_string(), user.clone());
-                user_sockets.insert(user.id.clone(), req.socket_id.to_string());
+            if self.can_reconnect_by_fingerprint(&existing_user) {
+                return self
+                    .resume_user(room, &existing_user, &req, "fingerprint")
+                    .map(|(user, users)| (user, users, None));
             }
-
-            // Update user in room
-            if let Some(u) = room.get_user_mut(&user.id) {
-                u.update_activity();
+            if Self::is_locked_for(room, req.fingerprint) {
+                return Err("Room is locked".to_string());
             }
-
-            let users: Vec<User> = room.get_users().into_iter().cloned().collect();
+            // Offline for too long: retire the old identity and join as a new user
             tracing::info!(
-                "User {} reconnected to room {} via fingerprint",
-                user.username,
+                "Identity {} in room {} too old to resume, creating a fresh user",
+                existing_user.id,
                 req.room_key
             );
-            return Ok((user, users));
+            room.remove_user(&existing_user.id);
+            let mut socket_users = self.socket_users.write().map_err(|_| "Lock error")?;
+            let mut user_sockets = self.user_sockets.write().map_err(|_| "Lock error")?;
+            if let Some(old_socket) = user_sockets.remove(&existing_user.id) {
+                socket_users.remove(&old_socket);
+            }
+            // The requested id is derived from the fingerprint, so it would bring the
+            // retired identity straight back
+            fresh_user_id = Some(crate::utils::generate_user_id());
+        }
+        let user_id = fresh_user_id.as_deref().unwrap_or(req.user_id);
+
+        if Self::is_locked_for(room, req.fingerprint) {
+            return Err("Room is locked".to_string());
         }
 
         // Generate unique username
         let unique_username = room.generate_unique_username(req.username, req.fingerprint);
+        let requested_username =
+            (unique_username != req.username).then(|| req.username.to_string());
 
         // Create user
         let mut user = User::new(
-            req.user_id.to_string(),
+            user_id.to_string(),
             unique_username,
             req.room_key.to_string(),
         );
         user.device_type = req.device_type.to_string();
+        user.client_info = req.client_info.cloned().unwrap_or_default();
         user.fingerprint = req.fingerprint.map(|f| f.to_string());
         room.add_user(user.clone());
+        room.record_audit(AuditAction::Join, &user, req.client_ip);
 
         // Track socket mapping
         {
             let mut socket_users = self.socket_users.write().map_err(|_| "Lock error")?;
             let mut user_sockets = self.user_sockets.write().map_err(|_| "Lock error")?;
             socket_users.insert(req.socket_id.to_string(), user.clone());
-            user_sockets.insert(req.user_id.to_string(), req.socket_id.to_string());
+            user_sockets.insert(user_id.to_string(), req.socket_id.to_string());
         }
 
         let users: Vec<User> = room.get_users().into_iter().cloned().collect();
 
         tracing::info!("User {} joined room {}", user.username, req.room_key);
-        Ok((user, users))
+        Ok((user, users, requested_username))
     }
 
     /// Update user online status
     pub fn update_user_status(&self, room_key: &str, user_id: &str, is_online: bool) {
+        let room_key = &*self.canonical_room_key(room_key);
         let mut rooms = match self.rooms.write() {
             Ok(r) => r,
             Err(_) => return,
@@ -275,6 +1199,8 @@ impl RoomService {
                 u.update_activity();
             } else {
                 u.set_offline();
+                let user = u.clone();
+                room.record_audit(AuditAction::Offline, &user, None);
             }
         }
         // NOTE: Don't immediately destroy the room when all users go offline.
@@ -285,22 +1211,20 @@ impl RoomService {
     /// Schedule a delayed check to destroy a room if all users are still offline.
     /// Called after a user disconnects to give a grace period for reconnection.
     pub fn schedule_room_destroy_check(self: &Arc<Self>, room_key: &str) {
+        let room_key = &*self.canonical_room_key(room_key);
         let room_key = room_key.to_string();
         let service = Arc::clone(self);
         tokio::spawn(async move {
-            tokio::time::sleep(std::time::Duration::from_secs(
-                ROOM_DESTROY_GRACE_PERIOD_SECS,
-            ))
-            .await;
+            tokio::time::sleep(service.destroy_grace_period).await;
             let should_destroy = {
                 let mut rooms = match service.rooms.write() {
                     Ok(r) => r,
                     Err(_) => return,
                 };
                 if let Some(room) = rooms.get(&room_key) {
-                    // Skip pinned rooms - they persist even when all users are offline
-                    if room.is_pinned {
-                        tracing::info!("Room {} is pinned, skipping destruction", room_key);
+                    // Skip pinned and provisioned rooms - they persist even when all users are offline
+                    if room.is_persistent() {
+                        tracing::info!("Room {} is persistent, skipping destruction", room_key);
                         false
                     } else if room.all_users_offline() {
                         rooms.remove(&room_key);
@@ -317,9 +1241,10 @@ impl RoomService {
                 }
             };
             if should_destroy {
-                let _ = service
-                    .event_sender
-                    .send(RoomEvent::RoomDestroyed { room_key });
+                let _ = service.event_sender.send(RoomEvent::RoomDestroyed {
+                    room_key,
+                    reason: RoomDestroyReason::GracePeriodExpired,
+                });
             }
         });
     }
@@ -336,18 +1261,23 @@ impl RoomService {
         user_sockets.remove(&user.id);
 
         let room_key = user.room_key.clone();
-        let mut room_destroyed = false;
+        let mut destroy_reason = None;
 
         // Remove from room
         if let Some(room) = rooms.get_mut(&room_key) {
             room.remove_user(&user.id);
+            room.record_audit(AuditAction::Leave, &user, None);
 
-            // Check if room should be destroyed (skip pinned rooms)
-            if !room.is_pinned && (room.is_empty() || room.all_users_offline()) {
+            // Check if room should be destroyed (skip pinned and provisioned rooms)
+            if !room.is_persistent() && (room.is_empty() || room.all_users_offline()) {
+                destroy_reason = Some(if room.is_empty() {
+                    RoomDestroyReason::Empty
+                } else {
+                    RoomDestroyReason::AllOffline
+                });
                 let key = room_key.clone();
                 rooms.remove(&key);
                 tracing::info!("Room {} destroyed (empty/all offline after leave)", key);
-                room_destroyed = true;
             }
         }
 
@@ -356,9 +1286,10 @@ impl RoomService {
         drop(socket_users);
         drop(user_sockets);
 
-        if room_destroyed {
+        if let Some(reason) = destroy_reason {
             let _ = self.event_sender.send(RoomEvent::RoomDestroyed {
                 room_key: room_key.clone(),
+                reason,
             });
         }
 
@@ -387,6 +1318,7 @@ impl RoomService {
 
     /// Get users in a room
     pub fn get_room_users(&self, room_key: &str) -> Vec<User> {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .map(|rooms| {
@@ -400,6 +1332,7 @@ impl RoomService {
 
     /// Find user by fingerprint in a room
     pub fn find_user_by_fingerprint(&self, room_key: &str, fingerprint_hash: &str) -> Option<User> {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms.read().ok().and_then(|rooms| {
             rooms
                 .get(room_key)
@@ -407,20 +1340,84 @@ impl RoomService {
         })
     }
 
-    /// Add message to room
-    pub fn add_message(&self, room_key: &str, message: Message) -> Result<(), String> {
+    /// Add message to room, returning its assigned sequence number
+    pub fn add_message(&self, room_key: &str, message: Message) -> Result<u64, String> {
+        let room_key = &*self.canonical_room_key(room_key);
         let mut rooms = self.rooms.write().map_err(|_| "Lock error")?;
         match rooms.get_mut(room_key) {
             Some(room) => {
-                room.add_message(message);
-                Ok(())
+                if !room.try_consume_message_budget(std::time::Instant::now()) {
+                    return Err("Room message rate limit exceeded".to_string());
+                }
+                Ok(room.add_message(message))
             }
             None => Err("Room not found".to_string()),
         }
     }
 
+    /// Remove a self-destructing message once its TTL has passed. Rooms that don't persist
+    /// history still announce the deletion so clients drop their relayed copy.
+    pub fn expire_message(&self, room_key: &str, message_id: &str) {
+        let room_key = &*self.canonical_room_key(room_key);
+        {
+            let Ok(mut rooms) = self.rooms.write() else {
+                return;
+            };
+            let Some(room) = rooms.get_mut(room_key) else {
+                return;
+            };
+            room.remove_message(message_id);
+        }
+        let _ = self
+            .event_sender
+            .send(RoomEvent::MessageDeleted(DeletedMessage {
+                room_key: room_key.to_string(),
+                message_id: message_id.to_string(),
+            }));
+    }
+
+    /// Expire a message after `ttl` without waiting for the next cleanup pass
+    pub fn schedule_message_expiry(self: &Arc<Self>, room_key: &str, message_id: &str, ttl: u64) {
+        let room_key = &*self.canonical_room_key(room_key);
+        let room_key = room_key.to_string();
+        let message_id = message_id.to_string();
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(ttl)).await;
+            service.expire_message(&room_key, &message_id);
+        });
+    }
+
+    /// Remove every stored message past its TTL (catches any whose timer didn't run).
+    /// Returns how many were removed.
+    pub fn cleanup_expired_messages(&self) -> usize {
+        let now = Utc::now();
+        let removed: Vec<DeletedMessage> = {
+            let Ok(mut rooms) = self.rooms.write() else {
+                return 0;
+            };
+            rooms
+                .iter_mut()
+                .flat_map(|(room_key, room)| {
+                    room.remove_expired_messages(now)
+                        .into_iter()
+                        .map(|message_id| DeletedMessage {
+                            room_key: room_key.clone(),
+                            message_id,
+                        })
+                })
+                .collect()
+        };
+        let count = removed.len();
+        for message in removed {
+            let _ = self.event_sender.send(RoomEvent::MessageDeleted(message));
+        }
+        count
+    }
+
     /// Get room messages
     pub fn get_messages(&self, room_key: &str) -> Vec<Message> {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .map(|rooms| {
@@ -432,6 +1429,43 @@ impl RoomService {
             .unwrap_or_default()
     }
 
+    /// Search text content and file names in a room (case-insensitive), newest first.
+    /// Encrypted messages are opaque to the server and never match.
+    pub fn search_messages(&self, room_key: &str, query: &str, limit: usize) -> Vec<Message> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let query = query.trim().to_lowercase();
+        if query.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let rooms = match self.rooms.read() {
+            Ok(r) => r,
+            Err(_) => return Vec::new(),
+        };
+        let Some(room) = rooms.get(room_key) else {
+            return Vec::new();
+        };
+
+        room.get_messages()
+            .iter()
+            .rev()
+            .filter(|m| !m.encrypted)
+            .filter(|m| {
+                let content_match = m
+                    .content
+                    .as_ref()
+                    .is_some_and(|c| c.to_lowercase().contains(&query));
+                let file_match = m
+                    .file_info
+                    .as_ref()
+                    .is_some_and(|f| f.name.to_lowercase().contains(&query));
+                content_match || file_match
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
     /// Get user by socket ID
     pub fn get_user_by_socket(&self, socket_id: &str) -> Option<User> {
         self.socket_users.read().ok()?.get(socket_id).cloned()
@@ -448,6 +1482,7 @@ impl RoomService {
         room_key: &str,
         password: Option<&str>,
     ) -> Result<bool, String> {
+        let room_key = &*self.canonical_room_key(room_key);
         let mut rooms = self.rooms.write().map_err(|_| "Lock error")?;
         match rooms.get_mut(room_key) {
             Some(room) => {
@@ -469,6 +1504,7 @@ impl RoomService {
 
     /// Pin a room (any user can pin)
     pub fn pin_room(&self, room_key: &str, fingerprint: &str) -> Result<bool, String> {
+        let room_key = &*self.canonical_room_key(room_key);
         // 验证 fingerprint 有效性
         if fingerprint.trim().is_empty() {
             return Err("Invalid fingerprint".to_string());
@@ -496,12 +1532,17 @@ impl RoomService {
         let room = rooms.get_mut(room_key).ok_or("Room not found")?;
         room.pin();
 
-        tracing::info!("Room {} pinned by {}", room_key, fingerprint);
+        tracing::info!(
+            "Room {} pinned by {}",
+            room_key,
+            redact_fingerprint(fingerprint)
+        );
         Ok(true)
     }
 
     /// Unpin a room (any user can unpin)
     pub fn unpin_room(&self, room_key: &str, fingerprint: &str) -> Result<bool, String> {
+        let room_key = &*self.canonical_room_key(room_key);
         // 验证 fingerprint 有效性
         if fingerprint.trim().is_empty() {
             return Err("Invalid fingerprint".to_string());
@@ -515,12 +1556,111 @@ impl RoomService {
         }
 
         room.unpin();
-        tracing::info!("Room {} unpinned by {}", room_key, fingerprint);
+        tracing::info!(
+            "Room {} unpinned by {}",
+            room_key,
+            redact_fingerprint(fingerprint)
+        );
         Ok(false)
     }
 
+    /// Keys of all current rooms
+    pub fn room_keys(&self) -> Vec<String> {
+        self.rooms
+            .read()
+            .map(|rooms| rooms.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether the fingerprint is the room's owner (creator)
+    pub fn is_room_owner(&self, room_key: &str, fingerprint: &str) -> bool {
+        let room_key = &*self.canonical_room_key(room_key);
+        self.rooms
+            .read()
+            .ok()
+            .and_then(|rooms| rooms.get(room_key).map(|r| r.is_owner(fingerprint)))
+            .unwrap_or(false)
+    }
+
+    /// Owner token of a room, if `fingerprint` is its owner. Sent only to the socket
+    /// whose join created the room.
+    pub fn owner_token(&self, room_key: &str, fingerprint: &str) -> Option<String> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let rooms = self.rooms.read().ok()?;
+        let room = rooms.get(room_key)?;
+        room.is_owner(fingerprint)
+            .then(|| room.owner_token.clone())
+            .flatten()
+    }
+
+    /// Resolve an owner token to the room owner's fingerprint, for owner-only calls
+    /// made outside a socket session
+    pub fn verify_owner_token(&self, room_key: &str, token: &str) -> Result<String, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let rooms = self.rooms.read().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get(room_key).ok_or(RoomError::NotFound)?;
+        match &room.created_by {
+            Some(fingerprint) if room.is_owner_token(token) => Ok(fingerprint.clone()),
+            _ => Err(RoomError::NotOwner),
+        }
+    }
+
+    /// Room membership audit log, oldest first. Only the room owner may read it.
+    pub fn get_audit_log(
+        &self,
+        room_key: &str,
+        fingerprint: &str,
+    ) -> Result<Vec<AuditEntry>, RoomError> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let rooms = self.rooms.read().map_err(|_| RoomError::Lock)?;
+        let room = rooms.get(room_key).ok_or(RoomError::NotFound)?;
+        if !room.is_owner(fingerprint) {
+            return Err(RoomError::NotOwner);
+        }
+        Ok(room.audit_log.iter().cloned().collect())
+    }
+
+    /// Get a room's configuration
+    pub fn get_room_config(&self, room_key: &str) -> Option<RoomConfig> {
+        let room_key = &*self.canonical_room_key(room_key);
+        self.rooms
+            .read()
+            .ok()?
+            .get(room_key)
+            .map(|r| r.config.clone())
+    }
+
+    /// Get the user ID of the room's owner (creator), if known
+    pub fn get_room_owner_id(&self, room_key: &str) -> Option<String> {
+        let room_key = &*self.canonical_room_key(room_key);
+        self.rooms.read().ok()?.get(room_key)?.owner_id()
+    }
+
+    /// Current room state for a member socket. Fails if the socket is not in the room.
+    pub fn get_room_state(&self, socket_id: &str, room_key: &str) -> Result<RoomState, String> {
+        let room_key = &*self.canonical_room_key(room_key);
+        let user = self
+            .get_user_by_socket(socket_id)
+            .ok_or_else(|| "User not authenticated".to_string())?;
+        if user.room_key != room_key {
+            return Err("User not in room".to_string());
+        }
+
+        let info = self
+            .get_room_info(room_key)
+            .ok_or_else(|| "Room not found".to_string())?;
+        Ok(RoomState {
+            room_key: info.room_key,
+            has_password: info.has_password,
+            user_count: info.user_count,
+            config: self.get_room_config(room_key).unwrap_or_default(),
+            owner_id: self.get_room_owner_id(room_key),
+        })
+    }
+
     /// Check if a room is pinned
     pub fn is_room_pinned(&self, room_key: &str) -> bool {
+        let room_key = &*self.canonical_room_key(room_key);
         self.rooms
             .read()
             .ok()
@@ -543,22 +1683,60 @@ impl RoomService {
 
     /// Cleanup inactive rooms (older than 24 hours with no activity)
     pub fn cleanup_inactive_rooms(&self) -> Vec<String> {
-        let cutoff = Utc::now() - Duration::hours(24);
+        // A cutoff reaching past the earliest representable time never expires a room
+        let cutoff = Utc::now()
+            .checked_sub_signed(self.inactive_cutoff)
+            .unwrap_or(chrono::DateTime::<Utc>::MIN_UTC);
         let mut destroyed = Vec::new();
 
+        // Drop expired password lockout entries and session tokens
+        self.password_lockout.cleanup();
+        if let Ok(mut tokens) = self.session_tokens.write() {
+            let now = Utc::now();
+            tokens.retain(|_, t| t.expires_at > now);
+        }
+
+        if self.dry_run {
+            if let Ok(rooms) = self.rooms.read() {
+                for (key, room) in rooms.iter() {
+                    let inactive = room.last_activity < cutoff;
+                    let all_offline = !room.is_empty() && room.all_users_offline();
+                    if !room.is_persistent() && (inactive || all_offline) {
+                        tracing::info!(
+                            "[dry-run] Room {} would be destroyed (inactive={}, all_offline={})",
+                            key,
+                            inactive,
+                            all_offline
+                        );
+                        destroyed.push(key.clone());
+                    }
+                }
+            }
+            return destroyed;
+        }
+
+        let mut events = Vec::new();
         if let Ok(mut rooms) = self.rooms.write() {
             rooms.retain(|key, room| {
-                // Pinned rooms are never cleaned up by inactivity
-                if room.is_pinned {
+                // Pinned and provisioned rooms are never cleaned up by inactivity
+                if room.is_persistent() {
                     return true;
                 }
-                // Destroy if inactive for 24h OR all users are offline
+                // Destroy if idle past the cutoff OR all users are offline
                 let inactive = room.last_activity < cutoff;
                 let all_offline = !room.is_empty() && room.all_users_offline();
                 let should_keep = !inactive && !all_offline;
 
                 if !should_keep {
                     destroyed.push(key.clone());
+                    events.push(RoomEvent::RoomDestroyed {
+                        room_key: key.clone(),
+                        reason: if inactive {
+                            RoomDestroyReason::Idle
+                        } else {
+                            RoomDestroyReason::AllOffline
+                        },
+                    });
                     tracing::info!(
                         "Room {} destroyed (cleanup: inactive={}, all_offline={})",
                         key,
@@ -571,10 +1749,8 @@ impl RoomService {
         }
 
         // Send events for destroyed rooms
-        for room_key in &destroyed {
-            let _ = self.event_sender.send(RoomEvent::RoomDestroyed {
-                room_key: room_key.clone(),
-            });
+        for event in events {
+            let _ = self.event_sender.send(event);
         }
 
         destroyed
@@ -587,6 +1763,33 @@ impl Default for RoomService {
     }
 }
 
+/// Count live rooms whose creator is the given fingerprint
+fn count_rooms_created_by(rooms: &HashMap<String, Room>, fingerprint: &str) -> usize {
+    rooms
+        .values()
+        .filter(|r| r.created_by.as_deref() == Some(fingerprint))
+        .count()
+}
+
+/// Count live rooms created from the given client IP without a fingerprint
+fn count_rooms_created_from(rooms: &HashMap<String, Room>, ip: &str) -> usize {
+    rooms
+        .values()
+        .filter(|r| r.created_by.is_none() && r.created_by_ip.as_deref() == Some(ip))
+        .count()
+}
+
+/// Room state snapshot sent to members (`roomState` socket event)
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomState {
+    pub room_key: String,
+    pub has_password: bool,
+    pub user_count: usize,
+    pub config: RoomConfig,
+    pub owner_id: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RoomStats {
@@ -600,6 +1803,62 @@ mod tests {
     use super::*;
     use crate::models::message::{MessageSender, MessageType};
 
+    #[test]
+    fn test_normalized_keys_share_one_room() {
+        let service = RoomService::new().with_room_key_normalization(true);
+        service
+            .join_room(JoinRoomRequest::new("Team-Chat1", "u1", "Alice", "s1"))
+            .unwrap();
+        let (_, users, _) = service
+            .join_room(JoinRoomRequest::new("TEAMCHAT1", "u2", "Bob", "s2"))
+            .unwrap();
+
+        assert_eq!(users.len(), 2);
+        assert_eq!(service.room_keys(), vec!["teamchat1".to_string()]);
+        assert_eq!(service.get_room_users("team_chat1").len(), 2);
+        // The key as first typed is kept for display
+        let info = service.get_room_info("teamchat1").unwrap();
+        assert_eq!(info.display_key.as_deref(), Some("Team-Chat1"));
+    }
+
+    #[test]
+    fn test_validated_room_key_checks_canonical_form() {
+        let service = RoomService::new().with_room_key_normalization(true);
+        assert_eq!(
+            service.validated_room_key("Team-Chat1").unwrap(),
+            "teamchat1"
+        );
+        assert_eq!(
+            service.validated_room_key("a-----1").unwrap_err(),
+            "Room key must be 6-50 characters"
+        );
+        assert!(service.validated_room_key("ab@123").is_err());
+
+        let service = RoomService::new().with_room_key_normalization(false);
+        assert_eq!(service.validated_room_key("a-----1").unwrap(), "a-----1");
+    }
+
+    #[test]
+    fn test_keys_are_distinct_without_normalization() {
+        let service = RoomService::new().with_room_key_normalization(false);
+        service
+            .join_room(JoinRoomRequest::new("Team-Chat1", "u1", "Alice", "s1"))
+            .unwrap();
+        let (_, users, _) = service
+            .join_room(JoinRoomRequest::new("TEAMCHAT1", "u2", "Bob", "s2"))
+            .unwrap();
+
+        assert_eq!(users.len(), 1);
+        assert_eq!(service.room_keys().len(), 2);
+        assert!(
+            service
+                .get_room_info("Team-Chat1")
+                .unwrap()
+                .display_key
+                .is_none()
+        );
+    }
+
     fn create_service_with_user() -> (Arc<RoomService>, String, String) {
         let service = Arc::new(RoomService::new());
         let room_key = "test1room";
@@ -676,145 +1935,569 @@ mod tests {
                 .with_fingerprint("fp1"),
         );
         assert!(result.is_ok());
-        let (user, users) = result.unwrap();
+        let (user, users, _) = result.unwrap();
         assert_eq!(user.username, "TestUser");
         assert_eq!(users.len(), 1);
     }
 
-    // joinRoomWithPassword tests
     #[test]
-    fn test_join_room_with_correct_password() {
+    fn test_join_room_unique_username_not_flagged() {
         let service = RoomService::new();
-        service
-            .create_room("testroom", Some("password123"), None)
+        let (user, _, requested) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Alice", "socket1")
+                    .with_fingerprint("fp1"),
+            )
             .unwrap();
-        let result = service.join_room(
-            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
-                .with_password("password123")
-                .with_fingerprint("fp1"),
-        );
-        assert!(result.is_ok());
+        assert_eq!(user.username, "Alice");
+        assert!(requested.is_none());
     }
 
     #[test]
-    fn test_join_room_with_wrong_password() {
+    fn test_join_room_duplicate_username_flagged() {
         let service = RoomService::new();
         service
-            .create_room("testroom", Some("password123"), None)
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Alice", "socket1")
+                    .with_fingerprint("fp1"),
+            )
             .unwrap();
-        let result = service.join_room(
-            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
-                .with_password("wrongpass")
-                .with_fingerprint("fp1"),
-        );
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Invalid password");
+        let (user, _, requested) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user2", "Alice", "socket2")
+                    .with_fingerprint("fp2"),
+            )
+            .unwrap();
+        assert_ne!(user.username, "Alice");
+        assert!(user.username.starts_with("Alice_"));
+        assert_eq!(requested.as_deref(), Some("Alice"));
     }
 
     #[test]
-    fn test_join_room_password_required() {
+    fn test_disable_persistence_owner_only_and_clears_history() {
         let service = RoomService::new();
         service
-            .create_room("testroom", Some("password123"), None)
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Owner", "socket1")
+                    .with_fingerprint("fp_owner"),
+            )
             .unwrap();
-        let result = service.join_room(
-            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
-                .with_fingerprint("fp1"),
+        service
+            .add_message(
+                "testroom",
+                Message::new_system("m1".to_string(), "testroom".to_string(), "hi".to_string()),
+            )
+            .unwrap();
+
+        assert!(
+            service
+                .set_persist_messages("testroom", "fp_other", false)
+                .is_err()
         );
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Password required");
+        let config = service
+            .set_persist_messages("testroom", "fp_owner", false)
+            .unwrap();
+        assert!(!config.persist_messages);
+        assert!(service.get_messages("testroom").is_empty());
     }
 
-    // setRoomPassword tests
     #[test]
-    fn test_set_room_password() {
+    fn test_room_message_budget_shared_across_users() {
         let service = RoomService::new();
-        service.create_room("testroom", None, None).unwrap();
-        let result = service.set_room_password("testroom", Some("newpassword"));
-        assert!(result.is_ok());
-        assert!(result.unwrap());
-        assert!(service.room_has_password("testroom"));
-    }
+        let (owner, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Owner", "socket1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        let (member, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user2", "Member", "socket2")
+                    .with_fingerprint("fp_member"),
+            )
+            .unwrap();
+        let send = |user: &User, id: &str| {
+            service.add_message(
+                "testroom",
+                Message::new_text(
+                    id.to_string(),
+                    "testroom".to_string(),
+                    crate::models::message::MessageSender::from_user(user),
+                    "hi".to_string(),
+                ),
+            )
+        };
 
-    #[test]
-    fn test_set_room_password_nonexistent() {
-        let service = RoomService::new();
-        let result = service.set_room_password("nonexistent", Some("password"));
-        assert!(result.is_err());
-    }
+        assert!(
+            service
+                .set_room_message_rate_limit("testroom", "fp_member", Some(3))
+                .is_err()
+        );
+        let config = service
+            .set_room_message_rate_limit("testroom", "fp_owner", Some(3))
+            .unwrap();
+        assert_eq!(config.message_rate_limit, Some(3));
+
+        send(&owner, "m1").unwrap();
+        send(&member, "m2").unwrap();
+        send(&owner, "m3").unwrap();
+        // Both senders drew from the same budget
+        assert_eq!(
+            send(&member, "m4").unwrap_err(),
+            "Room message rate limit exceeded"
+        );
+        assert_eq!(service.get_messages("testroom").len(), 3);
 
-    // isRoomPasswordProtected tests
-    #[test]
-    fn test_room_has_password_true() {
-        let service = RoomService::new();
         service
-            .create_room("testroom", Some("password123"), None)
+            .set_room_message_rate_limit("testroom", "fp_owner", None)
             .unwrap();
-        assert!(service.room_has_password("testroom"));
+        send(&member, "m4").unwrap();
     }
 
     #[test]
-    fn test_room_has_password_false() {
-        let service = RoomService::new();
-        service.create_room("testroom", None, None).unwrap();
-        assert!(!service.room_has_password("testroom"));
-    }
+    fn test_join_without_create_when_creation_disabled() {
+        let service = RoomService::new().with_room_creation(false);
+        let result = service.join_room(JoinRoomRequest::new("newroom1", "user1", "A", "s1"));
+        assert_eq!(result.unwrap_err(), "Room does not exist");
+        assert!(!service.room_exists("newroom1"));
+        assert_eq!(
+            service.create_room("newroom1", None, None).unwrap_err(),
+            RoomError::CreationDisabled
+        );
 
-    #[test]
-    fn test_room_has_password_nonexistent() {
-        let service = RoomService::new();
-        assert!(!service.room_has_password("nonexistent"));
+        // Admin-provisioned rooms can still be joined
+        service.admin_create_room("newroom1", None, None).unwrap();
+        assert!(
+            service
+                .join_room(JoinRoomRequest::new("newroom1", "user1", "A", "s1"))
+                .is_ok()
+        );
     }
 
-    // leaveRoom tests
     #[test]
-    fn test_leave_room_success() {
-        let (service, room_key, socket_id) = create_service_with_user();
-        let result = service.leave_room(&socket_id);
-        assert!(result.is_some());
-        let (left_room_key, _user) = result.unwrap();
-        assert_eq!(left_room_key, room_key);
+    fn test_provisioned_room_survives_leave_and_cleanup() {
+        let service = RoomService::new()
+            .with_room_creation(false)
+            .with_inactive_cutoff(Duration::zero());
+        service.admin_create_room("adminroom1", None, None).unwrap();
+
+        service
+            .join_room(JoinRoomRequest::new("adminroom1", "user1", "A", "s1"))
+            .unwrap();
+        service.leave_room("s1");
+        assert!(service.room_exists("adminroom1"));
+
+        // Still joinable after the last member left, and not destroyed by cleanup
+        service
+            .join_room(JoinRoomRequest::new("adminroom1", "user2", "B", "s2"))
+            .unwrap();
+        service.set_user_offline("s2");
+        assert!(service.cleanup_inactive_rooms().is_empty());
+        assert!(service.room_exists("adminroom1"));
     }
 
     #[test]
-    fn test_leave_room_nonexistent_socket() {
-        let service = RoomService::new();
-        let result = service.leave_room("nonexistent");
-        assert!(result.is_none());
+    fn test_join_creates_room_when_creation_enabled() {
+        let service = RoomService::new().with_room_creation(true);
+        assert!(
+            service
+                .join_room(JoinRoomRequest::new("newroom1", "user1", "A", "s1"))
+                .is_ok()
+        );
+        assert!(service.room_exists("newroom1"));
     }
 
     #[test]
-    fn test_leave_room_destroys_empty_room() {
-        let (service, room_key, socket_id) = create_service_with_user();
-        service.leave_room(&socket_id);
-        assert!(!service.room_exists(&room_key));
+    fn test_auto_room_password_protects_new_rooms() {
+        let service = RoomService::new().with_auto_room_password(true);
+
+        // The creator gets in and the room now carries a generated password
+        service
+            .join_room(JoinRoomRequest::new("newroom1", "user1", "A", "s1"))
+            .unwrap();
+        assert!(service.room_has_password("newroom1"));
+        let password = service.get_room_password("newroom1").unwrap();
+        assert!(!password.is_empty());
+
+        let result = service.join_room(JoinRoomRequest::new("newroom1", "user2", "B", "s2"));
+        assert_eq!(result.unwrap_err(), "Password required");
+        assert!(
+            service
+                .join_room(
+                    JoinRoomRequest::new("newroom1", "user2", "B", "s2").with_password(&password)
+                )
+                .is_ok()
+        );
+
+        // Off by default
+        let service = RoomService::new().with_auto_room_password(false);
+        service
+            .join_room(JoinRoomRequest::new("newroom1", "user1", "A", "s1"))
+            .unwrap();
+        assert!(!service.room_has_password("newroom1"));
     }
 
-    // addMessage tests
     #[test]
-    fn test_add_message_to_room() {
+    fn test_rooms_for_fingerprint_includes_offline_memberships() {
         let service = RoomService::new();
-        service.create_room("testroom", None, None).unwrap();
+        for (room, socket) in [("roomb1", "s1"), ("rooma1", "s2")] {
+            service
+                .join_room(
+                    JoinRoomRequest::new(room, "u1", "Alice", socket).with_fingerprint("fp1"),
+                )
+                .unwrap();
+        }
+        service
+            .join_room(JoinRoomRequest::new("roomc1", "u2", "Bob", "s3").with_fingerprint("fp2"))
+            .unwrap();
+        service.update_user_status("roomb1", "u1", false);
 
-        let user = User::new(
-            "user1".to_string(),
-            "User1".to_string(),
-            "testroom".to_string(),
+        assert_eq!(
+            service.rooms_for_fingerprint("fp1"),
+            vec!["rooma1", "roomb1"]
         );
-        let message = Message {
-            id: "msg1".to_string(),
-            message_type: MessageType::Text,
-            content: Some("Test message".to_string()),
-            sender: MessageSender::from_user(&user),
-            timestamp: Utc::now(),
-            room_key: "testroom".to_string(),
-            file_id: None,
-            file_info: None,
-            download_url: None,
-        };
-
-        let result = service.add_message("testroom", message);
-        assert!(result.is_ok());
+        assert!(service.rooms_for_fingerprint("unknown").is_empty());
+    }
+
+    #[test]
+    fn test_max_total_rooms_blocks_new_rooms_only() {
+        let service = RoomService::new().with_max_total_rooms(2);
+        service.create_room("room1abc", None, None).unwrap();
+        service
+            .join_room(JoinRoomRequest::new("room2abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        assert_eq!(
+            service.create_room("room3abc", None, None).unwrap_err(),
+            RoomError::AtCapacity
+        );
+        assert_eq!(
+            service
+                .join_room(JoinRoomRequest::new("room3abc", "u2", "Bob", "s2"))
+                .unwrap_err(),
+            "Server at capacity"
+        );
+
+        // Existing rooms stay joinable at the cap
+        assert!(
+            service
+                .join_room(JoinRoomRequest::new("room1abc", "u3", "Carol", "s3"))
+                .is_ok()
+        );
+
+        // Destroying a room frees capacity
+        assert!(service.destroy_room("room1abc"));
+        assert!(service.create_room("room3abc", None, None).is_ok());
+    }
+
+    fn age_user(service: &RoomService, room_key: &str, user_id: &str, secs: i64) {
+        let mut rooms = service.rooms.write().unwrap();
+        let user = rooms
+            .get_mut(room_key)
+            .unwrap()
+            .get_user_mut(user_id)
+            .unwrap();
+        user.last_seen = Utc::now() - Duration::seconds(secs);
+    }
+
+    #[test]
+    fn test_fingerprint_reconnect_within_max_age_keeps_identity() {
+        let service = RoomService::new()
+            .with_fingerprint_reconnect_max_age(Some(std::time::Duration::from_secs(600)));
+        let (first, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Alice", "s1").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        service.update_user_status("testroom", "user1", false);
+        age_user(&service, "testroom", "user1", 60);
+
+        let (again, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user2", "Other", "s2").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.username, "Alice");
+        assert_eq!(users.len(), 1);
+    }
+
+    #[test]
+    fn test_fingerprint_reconnect_after_max_age_creates_new_identity() {
+        let service = RoomService::new()
+            .with_fingerprint_reconnect_max_age(Some(std::time::Duration::from_secs(600)));
+        service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Alice", "s1").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        service.update_user_status("testroom", "user1", false);
+        age_user(&service, "testroom", "user1", 3600);
+
+        let (fresh, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", "user1", "Other", "s2").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        assert_ne!(fresh.id, "user1");
+        assert_eq!(fresh.username, "Other");
+        assert_eq!(
+            service.get_user_by_socket("s2").map(|u| u.id),
+            Some(fresh.id.clone())
+        );
+        assert_eq!(users.len(), 1);
+        assert!(service.get_user_by_socket("s1").is_none());
+    }
+
+    // joinRoomWithPassword tests
+    #[test]
+    fn test_join_room_with_correct_password() {
+        let service = RoomService::new();
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("password123")
+                .with_fingerprint("fp1"),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_join_room_with_wrong_password() {
+        let service = RoomService::new();
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("wrongpass")
+                .with_fingerprint("fp1"),
+        );
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Invalid password");
+    }
+
+    #[test]
+    fn test_join_room_password_required() {
+        let service = RoomService::new();
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_fingerprint("fp1"),
+        );
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Password required");
+    }
+
+    // Password lockout tests
+    #[test]
+    fn test_join_room_locks_after_failed_attempts() {
+        let service =
+            RoomService::new().with_password_lockout(3, std::time::Duration::from_secs(60));
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        for _ in 0..3 {
+            let result = service.join_room(
+                JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                    .with_password("wrongpass")
+                    .with_fingerprint("fp1")
+                    .with_client_ip("10.0.0.1"),
+            );
+            assert_eq!(result.unwrap_err(), "Invalid password");
+        }
+        // Even the correct password is rejected while locked
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("password123")
+                .with_fingerprint("fp1")
+                .with_client_ip("10.0.0.1"),
+        );
+        assert_eq!(result.unwrap_err(), "Too many attempts, locked");
+
+        // A different client is not affected
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user2", "Other", "socket2")
+                .with_password("password123")
+                .with_fingerprint("fp2")
+                .with_client_ip("10.0.0.2"),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_rotating_fingerprint_does_not_bypass_lockout() {
+        let service =
+            RoomService::new().with_password_lockout(2, std::time::Duration::from_secs(60));
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        for fp in ["fp1", "fp2"] {
+            let result = service.join_room(
+                JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                    .with_password("wrongpass")
+                    .with_fingerprint(fp)
+                    .with_client_ip("10.0.0.1"),
+            );
+            assert_eq!(result.unwrap_err(), "Invalid password");
+        }
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("password123")
+                .with_fingerprint("fp3")
+                .with_client_ip("10.0.0.1"),
+        );
+        assert_eq!(result.unwrap_err(), "Too many attempts, locked");
+    }
+
+    #[test]
+    fn test_join_room_unlocks_after_window() {
+        let service =
+            RoomService::new().with_password_lockout(1, std::time::Duration::from_millis(50));
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        let _ = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("wrongpass")
+                .with_client_ip("10.0.0.1"),
+        );
+        let locked = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("password123")
+                .with_client_ip("10.0.0.1"),
+        );
+        assert_eq!(locked.unwrap_err(), "Too many attempts, locked");
+
+        std::thread::sleep(std::time::Duration::from_millis(80));
+        let result = service.join_room(
+            JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                .with_password("password123")
+                .with_client_ip("10.0.0.1"),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_join_room_success_resets_failed_attempts() {
+        let service =
+            RoomService::new().with_password_lockout(2, std::time::Duration::from_secs(60));
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        let attempt = |pwd: &str| {
+            service.join_room(
+                JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
+                    .with_password(pwd)
+                    .with_fingerprint("fp1"),
+            )
+        };
+        assert!(attempt("wrongpass").is_err());
+        assert!(attempt("password123").is_ok());
+        // Counter was reset, so one more failure doesn't lock
+        assert_eq!(attempt("wrongpass").unwrap_err(), "Invalid password");
+        assert!(attempt("password123").is_ok());
+    }
+
+    // setRoomPassword tests
+    #[test]
+    fn test_set_room_password() {
+        let service = RoomService::new();
+        service.create_room("testroom", None, None).unwrap();
+        let result = service.set_room_password("testroom", Some("newpassword"));
+        assert!(result.is_ok());
+        assert!(result.unwrap());
+        assert!(service.room_has_password("testroom"));
+    }
+
+    #[test]
+    fn test_set_room_password_nonexistent() {
+        let service = RoomService::new();
+        let result = service.set_room_password("nonexistent", Some("password"));
+        assert!(result.is_err());
+    }
+
+    // isRoomPasswordProtected tests
+    #[test]
+    fn test_room_has_password_true() {
+        let service = RoomService::new();
+        service
+            .create_room("testroom", Some("password123"), None)
+            .unwrap();
+        assert!(service.room_has_password("testroom"));
+    }
+
+    #[test]
+    fn test_room_has_password_false() {
+        let service = RoomService::new();
+        service.create_room("testroom", None, None).unwrap();
+        assert!(!service.room_has_password("testroom"));
+    }
+
+    #[test]
+    fn test_room_has_password_nonexistent() {
+        let service = RoomService::new();
+        assert!(!service.room_has_password("nonexistent"));
+    }
+
+    // leaveRoom tests
+    #[test]
+    fn test_leave_room_success() {
+        let (service, room_key, socket_id) = create_service_with_user();
+        let result = service.leave_room(&socket_id);
+        assert!(result.is_some());
+        let (left_room_key, _user) = result.unwrap();
+        assert_eq!(left_room_key, room_key);
+    }
+
+    #[test]
+    fn test_leave_room_nonexistent_socket() {
+        let service = RoomService::new();
+        let result = service.leave_room("nonexistent");
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_leave_room_destroys_empty_room() {
+        let (service, room_key, socket_id) = create_service_with_user();
+        service.leave_room(&socket_id);
+        assert!(!service.room_exists(&room_key));
+    }
+
+    // addMessage tests
+    #[test]
+    fn test_add_message_to_room() {
+        let service = RoomService::new();
+        service.create_room("testroom", None, None).unwrap();
+
+        let user = User::new(
+            "user1".to_string(),
+            "User1".to_string(),
+            "testroom".to_string(),
+        );
+        let message = Message {
+            id: "msg1".to_string(),
+            message_type: MessageType::Text,
+            content: Some("Test message".to_string()),
+            sender: MessageSender::from_user(&user),
+            timestamp: Utc::now(),
+            room_key: "testroom".to_string(),
+            file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
+            file_info: None,
+            download_url: None,
+        };
+
+        let result = service.add_message("testroom", message);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -833,6 +2516,11 @@ mod tests {
             timestamp: Utc::now(),
             room_key: "nonexistent".to_string(),
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: None,
             download_url: None,
         };
@@ -875,6 +2563,11 @@ mod tests {
             timestamp: Utc::now(),
             room_key: "testroom".to_string(),
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: None,
             download_url: None,
         };
@@ -923,6 +2616,81 @@ mod tests {
         assert_eq!(stats.online_users, 2);
     }
 
+    fn ttl_message(room_key: &str, id: &str, ttl_secs: Option<u64>, age_secs: i64) -> Message {
+        let mut message = Message::new_text(
+            id.to_string(),
+            room_key.to_string(),
+            crate::models::message::MessageSender::system(),
+            "secret".to_string(),
+        );
+        message.timestamp = Utc::now() - Duration::seconds(age_secs);
+        message.ttl_secs = ttl_secs;
+        message
+    }
+
+    #[test]
+    fn test_cleanup_removes_only_expired_ttl_messages() {
+        let service = RoomService::new();
+        let mut events = service.subscribe();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+        for message in [
+            ttl_message("room123abc", "otp", Some(5), 10),
+            ttl_message("room123abc", "fresh", Some(60), 10),
+            ttl_message("room123abc", "plain", None, 3600),
+        ] {
+            service.add_message("room123abc", message).unwrap();
+        }
+
+        assert_eq!(service.cleanup_expired_messages(), 1);
+        let remaining: Vec<String> = service
+            .get_messages("room123abc")
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(remaining, ["fresh", "plain"]);
+
+        match events.try_recv() {
+            Ok(RoomEvent::MessageDeleted(deleted)) => {
+                assert_eq!(deleted.room_key, "room123abc");
+                assert_eq!(deleted.message_id, "otp");
+            }
+            other => panic!("expected MessageDeleted, got {:?}", other),
+        }
+        assert_eq!(service.cleanup_expired_messages(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_expiry_removes_message_and_broadcasts() {
+        let service = Arc::new(RoomService::new());
+        let mut events = service.subscribe();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+        service
+            .add_message("room123abc", ttl_message("room123abc", "otp", Some(1), 0))
+            .unwrap();
+        service
+            .add_message("room123abc", ttl_message("room123abc", "plain", None, 0))
+            .unwrap();
+
+        service.schedule_message_expiry("room123abc", "otp", 1);
+        assert_eq!(service.get_messages("room123abc").len(), 2);
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(3), events.recv())
+            .await
+            .expect("deletion broadcast")
+            .unwrap();
+        assert!(matches!(
+            event,
+            RoomEvent::MessageDeleted(DeletedMessage { ref message_id, .. }) if message_id == "otp"
+        ));
+        let remaining = service.get_messages("room123abc");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "plain");
+    }
+
     // cleanupInactiveRooms tests
     #[test]
     fn test_cleanup_inactive_rooms_keeps_active() {
@@ -932,6 +2700,392 @@ mod tests {
         assert!(service.room_exists(&room_key));
     }
 
+    #[test]
+    fn test_cleanup_dry_run_keeps_rooms() {
+        let service = RoomService::new().with_dry_run(true);
+        service
+            .join_room(JoinRoomRequest::new(
+                "dryroom1", "user1", "TestUser", "socket1",
+            ))
+            .unwrap();
+        let mut events = service.subscribe();
+        service.set_user_offline("socket1");
+
+        let destroyed = service.cleanup_inactive_rooms();
+        assert_eq!(destroyed, vec!["dryroom1".to_string()]);
+        assert!(service.room_exists("dryroom1"));
+        assert!(events.try_recv().is_err());
+    }
+
+    // get_room_state tests
+    #[test]
+    fn test_room_state_reflects_password_set_by_other_member() {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("stateroom1", "user1", "Alice", "socket1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("stateroom1", "user2", "Bob", "socket2")
+                    .with_fingerprint("fp_other"),
+            )
+            .unwrap();
+
+        let state = service.get_room_state("socket2", "stateroom1").unwrap();
+        assert!(!state.has_password);
+
+        // Alice sets a password; Bob's requested state reflects it
+        service
+            .set_room_password("stateroom1", Some("secret1"))
+            .unwrap();
+        let state = service.get_room_state("socket2", "stateroom1").unwrap();
+        assert!(state.has_password);
+        assert_eq!(state.user_count, 2);
+        assert_eq!(state.config, RoomConfig::default());
+        assert_eq!(
+            state.owner_id,
+            Some(crate::utils::generate_user_id_from_fingerprint("fp_owner"))
+        );
+    }
+
+    #[test]
+    fn test_room_state_denied_for_non_member() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new(
+                "stateroom1",
+                "user1",
+                "Alice",
+                "socket1",
+            ))
+            .unwrap();
+        service
+            .join_room(JoinRoomRequest::new(
+                "stateroom2",
+                "user2",
+                "Bob",
+                "socket2",
+            ))
+            .unwrap();
+
+        assert_eq!(
+            service.get_room_state("socket2", "stateroom1").unwrap_err(),
+            "User not in room"
+        );
+        assert_eq!(
+            service.get_room_state("unknown", "stateroom1").unwrap_err(),
+            "User not authenticated"
+        );
+    }
+
+    // room creation cap tests
+    #[test]
+    fn test_room_creation_cap_per_fingerprint() {
+        let service = RoomService::new().with_max_rooms_per_fingerprint(2);
+        for i in 0..2 {
+            service
+                .join_room(
+                    JoinRoomRequest::new(&format!("caproom{}", i), "u", "User", &format!("s{}", i))
+                        .with_fingerprint("fp_spammer"),
+                )
+                .unwrap();
+        }
+        assert_eq!(service.rooms_created_by("fp_spammer"), 2);
+
+        let err = service
+            .join_room(
+                JoinRoomRequest::new("caproom2", "u", "User", "s2").with_fingerprint("fp_spammer"),
+            )
+            .unwrap_err();
+        assert_eq!(err, "Room creation limit reached");
+        assert!(!service.room_exists("caproom2"));
+        assert!(
+            service
+                .create_room("caproom3", None, Some("fp_spammer"))
+                .is_err()
+        );
+        // Other fingerprints are unaffected
+        assert!(
+            service
+                .join_room(
+                    JoinRoomRequest::new("caproom2", "u2", "Other", "s9")
+                        .with_fingerprint("fp_other"),
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_room_creation_cap_falls_back_to_client_ip() {
+        let service = RoomService::new().with_max_rooms_per_fingerprint(1);
+        service
+            .join_room(JoinRoomRequest::new("iproom1", "u1", "A", "s1").with_client_ip("10.0.0.1"))
+            .unwrap();
+
+        // Omitting the fingerprint does not lift the cap
+        let err = service
+            .join_room(JoinRoomRequest::new("iproom2", "u1", "A", "s2").with_client_ip("10.0.0.1"))
+            .unwrap_err();
+        assert_eq!(err, "Room creation limit reached");
+        assert!(
+            service
+                .create_room_from("iproom3", None, None, Some("10.0.0.1"))
+                .is_err()
+        );
+        // Other IPs are unaffected
+        assert!(
+            service
+                .create_room_from("iproom3", None, None, Some("10.0.0.2"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_room_creation_cap_allows_joining_existing() {
+        let service = RoomService::new().with_max_rooms_per_fingerprint(1);
+        service
+            .join_room(
+                JoinRoomRequest::new("ownroom1", "u1", "Alice", "s1").with_fingerprint("fp_a"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("otherroom1", "u2", "Bob", "s2").with_fingerprint("fp_b"),
+            )
+            .unwrap();
+
+        // fp_a is at the cap but can still join a room it didn't create
+        assert!(
+            service
+                .join_room(
+                    JoinRoomRequest::new("otherroom1", "u3", "Alice", "s3")
+                        .with_fingerprint("fp_a"),
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_room_creation_cap_frees_when_room_destroyed() {
+        let service = RoomService::new().with_max_rooms_per_fingerprint(1);
+        service
+            .join_room(JoinRoomRequest::new("gone1room", "u1", "A", "s1").with_fingerprint("fp_a"))
+            .unwrap();
+        assert_eq!(service.rooms_created_by("fp_a"), 1);
+
+        service.leave_room("s1");
+        assert_eq!(service.rooms_created_by("fp_a"), 0);
+        assert!(
+            service
+                .join_room(
+                    JoinRoomRequest::new("next1room", "u1", "A", "s2").with_fingerprint("fp_a"),
+                )
+                .is_ok()
+        );
+    }
+
+    // audit log tests
+    #[test]
+    fn test_audit_log_records_events_in_order() {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("auditroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner")
+                    .with_client_ip("10.0.0.1"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("auditroom1", "u2", "Bob", "s2")
+                    .with_fingerprint("fp_bob")
+                    .with_client_ip("10.0.0.2"),
+            )
+            .unwrap();
+        service.set_user_offline("s2");
+        service.leave_room("s2");
+
+        let log = service.get_audit_log("auditroom1", "fp_owner").unwrap();
+        let actions: Vec<AuditAction> = log.iter().map(|e| e.action.clone()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::Join,
+                AuditAction::Join,
+                AuditAction::Offline,
+                AuditAction::Leave
+            ]
+        );
+        assert_eq!(log[1].username, "Bob");
+        assert_eq!(log[1].ip.as_deref(), Some("10.0.0.2"));
+        assert_eq!(log[1].fingerprint.as_deref(), Some("fp_bob"));
+    }
+
+    #[test]
+    fn test_audit_log_owner_only() {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("auditroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+
+        assert!(service.get_audit_log("auditroom1", "fp_owner").is_ok());
+        assert!(service.get_audit_log("auditroom1", "fp_intruder").is_err());
+        assert!(service.get_audit_log("auditroom1", "").is_err());
+        assert_eq!(
+            service.get_audit_log("missing1", "fp_owner").unwrap_err(),
+            RoomError::NotFound
+        );
+    }
+
+    // seq tests
+    #[test]
+    fn test_add_message_assigns_increasing_seq() {
+        let (service, room_key, _) = create_service_with_user();
+        add_text(&service, &room_key, "first");
+        add_text(&service, &room_key, "second");
+        add_text(&service, &room_key, "third");
+
+        let messages = service.get_messages(&room_key);
+        let seqs: Vec<u64> = messages.iter().map(|m| m.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert_eq!(messages[2].content.as_deref(), Some("third"));
+    }
+
+    #[test]
+    fn test_seq_returned_and_resets_on_room_recreation() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new(
+                "seqroom1", "user1", "TestUser", "socket1",
+            ))
+            .unwrap();
+        let seq = service
+            .add_message(
+                "seqroom1",
+                Message::new_system(
+                    crate::utils::generate_message_id(),
+                    "seqroom1".to_string(),
+                    "hi".to_string(),
+                ),
+            )
+            .unwrap();
+        assert_eq!(seq, 1);
+
+        // Room destroyed when the last user leaves; a new room starts over
+        service.leave_room("socket1");
+        assert!(!service.room_exists("seqroom1"));
+        service
+            .join_room(JoinRoomRequest::new(
+                "seqroom1", "user2", "Other", "socket2",
+            ))
+            .unwrap();
+        add_text(&service, "seqroom1", "again");
+        assert_eq!(service.get_messages("seqroom1")[0].seq, 1);
+    }
+
+    // search_messages tests
+    fn add_text(service: &RoomService, room_key: &str, content: &str) {
+        let sender = crate::models::message::MessageSender::system();
+        service
+            .add_message(
+                room_key,
+                Message::new_text(
+                    crate::utils::generate_message_id(),
+                    room_key.to_string(),
+                    sender,
+                    content.to_string(),
+                ),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_search_messages_matches_text() {
+        let (service, room_key, _) = create_service_with_user();
+        add_text(&service, &room_key, "hello world");
+        add_text(&service, &room_key, "something else");
+
+        let results = service.search_messages(&room_key, "world", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content.as_deref(), Some("hello world"));
+    }
+
+    #[test]
+    fn test_search_messages_matches_file_name() {
+        let (service, room_key, _) = create_service_with_user();
+        add_text(&service, &room_key, "unrelated");
+        service
+            .add_message(
+                &room_key,
+                Message::new_file(
+                    crate::utils::generate_message_id(),
+                    room_key.clone(),
+                    crate::models::message::MessageSender::system(),
+                    "Quarterly-Report.pdf".to_string(),
+                    1024,
+                    "application/pdf".to_string(),
+                    "/api/files/download/x.pdf".to_string(),
+                ),
+            )
+            .unwrap();
+
+        let results = service.search_messages(&room_key, "report", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].file_info.as_ref().unwrap().name,
+            "Quarterly-Report.pdf"
+        );
+    }
+
+    #[test]
+    fn test_search_messages_case_insensitive() {
+        let (service, room_key, _) = create_service_with_user();
+        add_text(&service, &room_key, "Meeting Notes");
+
+        assert_eq!(service.search_messages(&room_key, "MEETING", 10).len(), 1);
+        assert_eq!(service.search_messages(&room_key, "notes", 10).len(), 1);
+        assert!(service.search_messages(&room_key, "", 10).is_empty());
+        assert!(
+            service
+                .search_messages("missing_room", "notes", 10)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_search_messages_skips_encrypted() {
+        let (service, room_key, _) = create_service_with_user();
+        let mut msg = Message::new_text(
+            crate::utils::generate_message_id(),
+            room_key.clone(),
+            crate::models::message::MessageSender::system(),
+            "ciphertext-secret".to_string(),
+        );
+        msg.encrypted = true;
+        service.add_message(&room_key, msg).unwrap();
+
+        assert!(service.search_messages(&room_key, "secret", 10).is_empty());
+    }
+
+    #[test]
+    fn test_search_messages_respects_limit_newest_first() {
+        let (service, room_key, _) = create_service_with_user();
+        for i in 0..5 {
+            add_text(&service, &room_key, &format!("item {}", i));
+        }
+
+        let results = service.search_messages(&room_key, "item", 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].content.as_deref(), Some("item 4"));
+        assert_eq!(results[1].content.as_deref(), Some("item 3"));
+    }
+
     // Other existing tests
     #[test]
     fn test_set_user_offline_preserves_room() {
@@ -960,7 +3114,7 @@ mod tests {
         );
 
         assert!(result.is_ok());
-        let (user, users) = result.unwrap();
+        let (user, users, _) = result.unwrap();
         assert!(user.is_online);
         assert_eq!(users.len(), 1);
     }
@@ -1008,4 +3162,398 @@ mod tests {
 
         assert!(service.room_exists(&room_key));
     }
+
+    #[test]
+    fn test_session_token_resumes_identity_after_disconnect() {
+        let service = RoomService::new();
+        let (user, _, _) = service
+            .join_room(JoinRoomRequest::new(
+                "tokenroom1",
+                "user1",
+                "Alice",
+                "socket1",
+            ))
+            .unwrap();
+        let token = service.issue_session_token("tokenroom1", &user.id).unwrap();
+        service.set_user_offline("socket1");
+
+        // Randomized browser: no fingerprint, fresh user id on reconnect
+        let (resumed, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom1", "user-random", "Someone", "socket2")
+                    .with_session_token(&token),
+            )
+            .unwrap();
+        assert_eq!(resumed.id, user.id);
+        assert_eq!(resumed.username, "Alice");
+        assert!(resumed.is_online);
+        assert_eq!(users.len(), 1);
+        assert_eq!(
+            service.get_socket_by_user(&user.id).as_deref(),
+            Some("socket2")
+        );
+
+        // Tokens are single-use
+        let (other, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom1", "user3", "Bob", "socket3")
+                    .with_session_token(&token),
+            )
+            .unwrap();
+        assert_eq!(other.id, "user3");
+        assert_eq!(users.len(), 2);
+    }
+
+    #[test]
+    fn test_expired_session_token_creates_new_identity() {
+        let service = RoomService::new().with_session_token_ttl(std::time::Duration::ZERO);
+        let (user, _, _) = service
+            .join_room(JoinRoomRequest::new(
+                "tokenroom2",
+                "user1",
+                "Alice",
+                "socket1",
+            ))
+            .unwrap();
+        let token = service.issue_session_token("tokenroom2", &user.id).unwrap();
+        service.set_user_offline("socket1");
+
+        let (joined, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom2", "user2", "Alice", "socket2")
+                    .with_session_token(&token),
+            )
+            .unwrap();
+        assert_eq!(joined.id, "user2");
+        assert_eq!(users.len(), 2);
+    }
+
+    #[test]
+    fn test_session_token_bound_to_room() {
+        let service = RoomService::new();
+        let (user, _, _) = service
+            .join_room(JoinRoomRequest::new(
+                "tokenroom3",
+                "user1",
+                "Alice",
+                "socket1",
+            ))
+            .unwrap();
+        let token = service.issue_session_token("tokenroom3", &user.id).unwrap();
+
+        let (joined, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom4", "user2", "Alice", "socket2")
+                    .with_session_token(&token),
+            )
+            .unwrap();
+        assert_eq!(joined.id, "user2");
+    }
+
+    #[test]
+    fn test_session_token_takes_precedence_over_fingerprint() {
+        let service = RoomService::new();
+        let (alice, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom5", "user1", "Alice", "socket1")
+                    .with_fingerprint("fp_alice"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("tokenroom5", "user2", "Bob", "socket2")
+                    .with_fingerprint("fp_bob"),
+            )
+            .unwrap();
+        let token = service
+            .issue_session_token("tokenroom5", &alice.id)
+            .unwrap();
+        service.set_user_offline("socket1");
+
+        // Token wins even though the fingerprint matches another member
+        let (resumed, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom5", "user3", "Alice", "socket3")
+                    .with_fingerprint("fp_bob")
+                    .with_session_token(&token),
+            )
+            .unwrap();
+        assert_eq!(resumed.id, alice.id);
+
+        // An unknown token falls back to the fingerprint path
+        let (by_fingerprint, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("tokenroom5", "user4", "Bob", "socket4")
+                    .with_fingerprint("fp_bob")
+                    .with_session_token("not-a-real-token"),
+            )
+            .unwrap();
+        assert_eq!(by_fingerprint.id, "user2");
+    }
+
+    #[test]
+    fn test_set_room_locked_errors_are_typed() {
+        let (service, room_key, _) = create_service_with_user();
+
+        let err = service
+            .set_room_locked(&room_key, "someone_else", true)
+            .unwrap_err();
+        assert_eq!(err, RoomError::NotOwner);
+        let err = service
+            .set_room_locked("missing1", "fp_hash_1", true)
+            .unwrap_err();
+        assert_eq!(err, RoomError::NotFound);
+        assert!(!service.get_room_config(&room_key).unwrap().locked);
+    }
+
+    #[test]
+    fn test_create_announcement_errors_are_typed() {
+        let (service, room_key, _) = create_service_with_user();
+
+        let err = service
+            .create_announcement(&room_key, "someone_else", "hi".to_string(), true)
+            .unwrap_err();
+        assert_eq!(err, RoomError::NotOwner);
+        let err = service
+            .create_announcement("missing1", "fp_hash_1", "hi".to_string(), true)
+            .unwrap_err();
+        assert_eq!(err, RoomError::NotFound);
+        assert!(service.get_messages(&room_key).is_empty());
+    }
+
+    #[test]
+    fn test_set_room_rate_limits_owner_only() {
+        let (service, room_key, _) = create_service_with_user();
+        let limits = HashMap::from([("sendMessage".to_string(), 120)]);
+
+        let err = service
+            .set_room_rate_limits(&room_key, "someone_else", limits.clone())
+            .unwrap_err();
+        assert_eq!(err, RoomError::NotOwner);
+        assert_eq!(service.room_rate_limit(&room_key, "sendMessage"), None);
+
+        service
+            .set_room_rate_limits(&room_key, "fp_hash_1", limits)
+            .unwrap();
+        assert_eq!(service.room_rate_limit(&room_key, "sendMessage"), Some(120));
+        assert_eq!(service.room_rate_limit(&room_key, "searchMessages"), None);
+    }
+
+    #[test]
+    fn test_set_room_rate_limits_rejects_unknown_event_and_zero() {
+        let (service, room_key, _) = create_service_with_user();
+
+        let err = service
+            .set_room_rate_limits(
+                &room_key,
+                "fp_hash_1",
+                HashMap::from([("joinRoom".to_string(), 100)]),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("joinRoom"));
+
+        // Owners cannot disable limiting with 0
+        let config = service
+            .set_room_rate_limits(
+                &room_key,
+                "fp_hash_1",
+                HashMap::from([("sendMessage".to_string(), 0)]),
+            )
+            .unwrap();
+        assert_eq!(config.rate_limits["sendMessage"], 1);
+    }
+
+    #[test]
+    fn test_server_banner_validation() {
+        let banner =
+            ServerBanner::new("  Maintenance at 22:00  ", BannerSeverity::Warning).unwrap();
+        assert_eq!(banner.text, "Maintenance at 22:00");
+        assert!(ServerBanner::new("   ", BannerSeverity::Info).is_err());
+        assert!(ServerBanner::new(&"x".repeat(MAX_BANNER_LEN + 1), BannerSeverity::Info).is_err());
+
+        assert_eq!(
+            BannerSeverity::parse("CRITICAL"),
+            Some(BannerSeverity::Critical)
+        );
+        assert_eq!(BannerSeverity::parse("loud"), None);
+    }
+
+    #[test]
+    fn test_set_banner_broadcasts_change() {
+        let service = RoomService::new();
+        let mut rx = service.subscribe();
+        let banner = ServerBanner::new("Back soon", BannerSeverity::Info).unwrap();
+
+        service.set_banner(Some(banner.clone())).unwrap();
+        assert_eq!(service.banner(), Some(banner.clone()));
+        assert!(matches!(rx.try_recv(), Ok(RoomEvent::BannerChanged(Some(b))) if b == banner));
+
+        service.set_banner(None).unwrap();
+        assert_eq!(service.banner(), None);
+        assert!(matches!(rx.try_recv(), Ok(RoomEvent::BannerChanged(None))));
+    }
+
+    fn next_destroy_reason(
+        rx: &mut tokio::sync::broadcast::Receiver<RoomEvent>,
+    ) -> Option<RoomDestroyReason> {
+        match rx.try_recv() {
+            Ok(RoomEvent::RoomDestroyed { reason, .. }) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_destroy_reason_on_leave_and_cleanup() {
+        let (service, _, socket_id) = create_service_with_user();
+        let mut rx = service.subscribe();
+        service.leave_room(&socket_id);
+        assert_eq!(next_destroy_reason(&mut rx), Some(RoomDestroyReason::Empty));
+
+        let (service, _, socket_id) = create_service_with_user();
+        let mut rx = service.subscribe();
+        service.set_user_offline(&socket_id);
+        service.cleanup_inactive_rooms();
+        assert_eq!(
+            next_destroy_reason(&mut rx),
+            Some(RoomDestroyReason::AllOffline)
+        );
+
+        // Online but idle for more than 24h
+        let (service, room_key, _) = create_service_with_user();
+        let mut rx = service.subscribe();
+        service
+            .rooms
+            .write()
+            .unwrap()
+            .get_mut(&room_key)
+            .unwrap()
+            .last_activity = Utc::now() - Duration::hours(25);
+        service.cleanup_inactive_rooms();
+        assert_eq!(next_destroy_reason(&mut rx), Some(RoomDestroyReason::Idle));
+    }
+
+    #[test]
+    fn test_huge_inactive_cutoff_never_expires_rooms() {
+        let service = RoomService::new().with_inactive_cutoff(Duration::MAX);
+        service
+            .join_room(JoinRoomRequest::new("idle1room", "u1", "User", "s1"))
+            .unwrap();
+        service
+            .rooms
+            .write()
+            .unwrap()
+            .get_mut("idle1room")
+            .unwrap()
+            .last_activity = Utc::now() - Duration::days(365);
+
+        assert!(service.cleanup_inactive_rooms().is_empty());
+        assert!(service.room_exists("idle1room"));
+    }
+
+    #[test]
+    fn test_cleanup_uses_configured_inactive_cutoff() {
+        let service = RoomService::new().with_inactive_cutoff(Duration::minutes(5));
+        for (room_key, socket_id) in [("idle1room", "s1"), ("busy1room", "s2")] {
+            service
+                .join_room(JoinRoomRequest::new(room_key, socket_id, "User", socket_id))
+                .unwrap();
+        }
+        // Idle past the 5 minute cutoff (well inside the old hardcoded 24h)
+        service
+            .rooms
+            .write()
+            .unwrap()
+            .get_mut("idle1room")
+            .unwrap()
+            .last_activity = Utc::now() - Duration::minutes(10);
+        service
+            .rooms
+            .write()
+            .unwrap()
+            .get_mut("busy1room")
+            .unwrap()
+            .last_activity = Utc::now() - Duration::minutes(1);
+
+        let mut rx = service.subscribe();
+        assert_eq!(service.cleanup_inactive_rooms(), vec!["idle1room"]);
+        assert_eq!(next_destroy_reason(&mut rx), Some(RoomDestroyReason::Idle));
+        assert!(service.room_exists("busy1room"));
+        assert_eq!(service.settings().inactive_cutoff_secs, 300);
+
+        // All-offline destruction still applies to a recently active room
+        service.set_user_offline("s2");
+        assert_eq!(service.cleanup_inactive_rooms(), vec!["busy1room"]);
+        assert_eq!(
+            next_destroy_reason(&mut rx),
+            Some(RoomDestroyReason::AllOffline)
+        );
+    }
+
+    #[test]
+    fn test_long_inactive_cutoff_keeps_idle_room() {
+        let service = RoomService::new().with_inactive_cutoff(Duration::hours(72));
+        service
+            .join_room(JoinRoomRequest::new("idle2room", "u1", "User", "s1"))
+            .unwrap();
+        service
+            .rooms
+            .write()
+            .unwrap()
+            .get_mut("idle2room")
+            .unwrap()
+            .last_activity = Utc::now() - Duration::hours(25);
+
+        assert!(service.cleanup_inactive_rooms().is_empty());
+        assert!(service.room_exists("idle2room"));
+    }
+
+    #[tokio::test]
+    async fn test_destroy_reason_after_grace_period() {
+        let service = Arc::new(
+            RoomService::new().with_destroy_grace_period(std::time::Duration::from_millis(10)),
+        );
+        service
+            .join_room(JoinRoomRequest::new("grace1room", "u1", "User", "s1"))
+            .unwrap();
+        let mut rx = service.subscribe();
+        service.set_user_offline("s1");
+        service.schedule_room_destroy_check("grace1room");
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let RoomEvent::RoomDestroyed { room_key, reason } = event else {
+            panic!("expected RoomDestroyed, got {:?}", event);
+        };
+        assert_eq!(room_key, "grace1room");
+        assert_eq!(reason, RoomDestroyReason::GracePeriodExpired);
+        assert!(!service.room_exists("grace1room"));
+    }
+
+    #[test]
+    fn test_admin_destroy_room_clears_sockets() {
+        let (service, room_key, socket_id) = create_service_with_user();
+        let mut rx = service.subscribe();
+
+        assert!(service.destroy_room(&room_key));
+        assert!(!service.room_exists(&room_key));
+        assert!(service.get_user_by_socket(&socket_id).is_none());
+        assert_eq!(next_destroy_reason(&mut rx), Some(RoomDestroyReason::Admin));
+
+        assert!(!service.destroy_room(&room_key));
+        assert_eq!(next_destroy_reason(&mut rx), None);
+    }
+
+    #[test]
+    fn test_destroy_reason_serializes_snake_case() {
+        assert_eq!(
+            serde_json::to_value(RoomDestroyReason::GracePeriodExpired).unwrap(),
+            "grace_period_expired"
+        );
+        assert_eq!(
+            serde_json::to_value(RoomDestroyReason::AllOffline).unwrap(),
+            "all_offline"
+        );
+    }
 }
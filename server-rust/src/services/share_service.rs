@@ -3,7 +3,21 @@ use std::sync::RwLock;
 
 use crate::models::share::{ShareInfoParams, ShareInfoResponse};
 use crate::models::{ShareAccessLog, ShareInfo};
-use crate::utils::generate_share_id;
+use crate::utils::{
+    PasswordLockout, generate_share_id, generate_short_code, mask_ip, redact_ip,
+    validate_download_name,
+};
+
+/// Failure to rotate a share password; `Display` is the client-facing message
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RotatePasswordError {
+    #[error("Share not found")]
+    NotFound,
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{0}")]
+    Internal(String),
+}
 
 /// Request parameters for creating a share
 #[derive(Debug, Clone)]
@@ -17,6 +31,9 @@ pub struct CreateShareRequest {
     pub enable_password: bool,
     pub password: Option<String>,
     pub metadata: Option<HashMap<String, serde_json::Value>>,
+    pub download_as: Option<String>,
+    /// Storage filenames for a multi-file (zip) share; empty for a single file
+    pub bundle_files: Vec<String>,
 }
 
 impl CreateShareRequest {
@@ -38,6 +55,8 @@ impl CreateShareRequest {
             enable_password: false,
             password: None,
             metadata: None,
+            download_as: None,
+            bundle_files: Vec::new(),
         }
     }
 
@@ -60,12 +79,105 @@ impl CreateShareRequest {
         self.metadata = Some(metadata);
         self
     }
+
+    pub fn with_download_as(mut self, name: impl Into<String>) -> Self {
+        self.download_as = Some(name.into());
+        self
+    }
+
+    pub fn with_bundle_files(mut self, files: Vec<String>) -> Self {
+        self.bundle_files = files;
+        self
+    }
+}
+
+/// Default share expiration bounds in days (SHARE_MIN/MAX_EXPIRATION_DAYS)
+pub const DEFAULT_MIN_EXPIRATION_DAYS: i64 = 1;
+pub const DEFAULT_MAX_EXPIRATION_DAYS: i64 = 30;
+/// Expiration used when a request omits expiresInDays (SHARE_DEFAULT_EXPIRATION_DAYS)
+pub const DEFAULT_EXPIRATION_DAYS: i64 = 7;
+
+/// Read and validate the share expiration bounds from the environment
+pub fn expiration_bounds_from_env() -> Result<(i64, i64), String> {
+    parse_expiration_bounds(
+        std::env::var("SHARE_MIN_EXPIRATION_DAYS").ok().as_deref(),
+        std::env::var("SHARE_MAX_EXPIRATION_DAYS").ok().as_deref(),
+    )
+}
+
+/// Parse optional min/max expiration overrides (`None` or blank keeps the default)
+pub fn parse_expiration_bounds(min: Option<&str>, max: Option<&str>) -> Result<(i64, i64), String> {
+    let parse = |name: &str, value: Option<&str>, default: i64| match value.map(str::trim) {
+        None | Some("") => Ok(default),
+        Some(v) => v
+            .parse::<i64>()
+            .ok()
+            .filter(|&days| days >= 1)
+            .ok_or_else(|| format!("{} must be a positive number of days, got {:?}", name, v)),
+    };
+    let min = parse(
+        "SHARE_MIN_EXPIRATION_DAYS",
+        min,
+        DEFAULT_MIN_EXPIRATION_DAYS,
+    )?;
+    let max = parse(
+        "SHARE_MAX_EXPIRATION_DAYS",
+        max,
+        DEFAULT_MAX_EXPIRATION_DAYS,
+    )?;
+    if min > max {
+        return Err(format!(
+            "SHARE_MIN_EXPIRATION_DAYS ({}) must not exceed SHARE_MAX_EXPIRATION_DAYS ({})",
+            min, max
+        ));
+    }
+    Ok((min, max))
+}
+
+/// Read and validate SHARE_DEFAULT_EXPIRATION_DAYS against the configured bounds
+pub fn default_expiration_from_env(min_days: i64, max_days: i64) -> Result<i64, String> {
+    parse_default_expiration(
+        std::env::var("SHARE_DEFAULT_EXPIRATION_DAYS")
+            .ok()
+            .as_deref(),
+        min_days,
+        max_days,
+    )
+}
+
+/// Parse an optional default expiration (`None` or blank falls back to 7 days, clamped
+/// into range); an explicit value outside `min_days..=max_days` is rejected
+pub fn parse_default_expiration(
+    value: Option<&str>,
+    min_days: i64,
+    max_days: i64,
+) -> Result<i64, String> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(DEFAULT_EXPIRATION_DAYS.clamp(min_days, max_days)),
+        Some(v) => v
+            .parse::<i64>()
+            .ok()
+            .filter(|days| (min_days..=max_days).contains(days))
+            .ok_or_else(|| {
+                format!(
+                    "SHARE_DEFAULT_EXPIRATION_DAYS must be within {}-{} days, got {:?}",
+                    min_days, max_days, v
+                )
+            }),
+    }
 }
 
 /// Service for managing file shares
 pub struct ShareService {
     shares: RwLock<HashMap<String, ShareInfo>>,
     user_shares: RwLock<HashMap<String, Vec<String>>>, // user_id -> [share_id]
+    short_codes: RwLock<HashMap<String, String>>,      // short link code -> share_id
+    password_lockout: PasswordLockout,                 // "share_id:ip" -> failed attempts
+    dry_run: bool,                                     // cleanup only reports, never deletes
+    max_access_logs: usize,                            // per-share log cap (oldest evicted)
+    expiration_days: (i64, i64),                       // allowed expiresInDays range (inclusive)
+    default_expiration_days: i64,                      // used when expiresInDays is omitted
+    redact_access_logs: bool,                          // store masked IPs in access logs
 }
 
 impl ShareService {
@@ -73,14 +185,97 @@ impl ShareService {
         Self {
             shares: RwLock::new(HashMap::new()),
             user_shares: RwLock::new(HashMap::new()),
+            short_codes: RwLock::new(HashMap::new()),
+            // SHARE_PASSWORD_MAX_ATTEMPTS (default 5) / SHARE_PASSWORD_LOCKOUT_SECS (default 300)
+            password_lockout: PasswordLockout::from_env("SHARE_PASSWORD", 5, 300),
+            dry_run: false,
+            max_access_logs: std::env::var("MAX_ACCESS_LOGS_PER_SHARE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&max: &usize| max > 0)
+                .unwrap_or(1000),
+            expiration_days: (DEFAULT_MIN_EXPIRATION_DAYS, DEFAULT_MAX_EXPIRATION_DAYS),
+            default_expiration_days: DEFAULT_EXPIRATION_DAYS,
+            redact_access_logs: std::env::var("LOG_REDACT_ACCESS_LOGS")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
         }
     }
 
+    /// Override the allowed expiration range (see `expiration_bounds_from_env`)
+    pub fn with_expiration_bounds(mut self, min_days: i64, max_days: i64) -> Self {
+        self.expiration_days = (min_days, max_days);
+        self
+    }
+
+    /// Inclusive (min, max) expiration in days accepted for new shares
+    pub fn expiration_bounds(&self) -> (i64, i64) {
+        self.expiration_days
+    }
+
+    /// Override the expiration applied when a request doesn't specify one
+    /// (see `default_expiration_from_env`)
+    pub fn with_default_expiration(mut self, days: i64) -> Self {
+        self.default_expiration_days = days;
+        self
+    }
+
+    /// Expiration in days for requests without expiresInDays, kept within the bounds
+    pub fn default_expiration(&self) -> i64 {
+        let (min, max) = self.expiration_days;
+        self.default_expiration_days.clamp(min, max)
+    }
+
+    pub fn is_valid_expiration(&self, days: i64) -> bool {
+        let (min, max) = self.expiration_days;
+        (min..=max).contains(&days)
+    }
+
+    /// Cap the access log kept per share; older entries are evicted first
+    pub fn with_max_access_logs(mut self, max: usize) -> Self {
+        self.max_access_logs = max.max(1);
+        self
+    }
+
+    /// Store masked IPs (see `mask_ip`) in share access logs instead of full addresses
+    pub fn with_redacted_access_logs(mut self, redact: bool) -> Self {
+        self.redact_access_logs = redact;
+        self
+    }
+
+    fn stored_ip(&self, ip_address: String) -> String {
+        if self.redact_access_logs {
+            mask_ip(&ip_address)
+        } else {
+            ip_address
+        }
+    }
+
+    /// In dry-run mode cleanup logs and returns candidates without deleting anything
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Override the password lockout policy (0 attempts disables lockout)
+    pub fn with_password_lockout(
+        mut self,
+        max_attempts: u32,
+        lockout: std::time::Duration,
+    ) -> Self {
+        self.password_lockout = PasswordLockout::new(max_attempts, lockout);
+        self
+    }
+
     /// Create a new share
     pub fn create_share(
         &self,
         req: CreateShareRequest,
     ) -> Result<(ShareInfo, Option<String>), String> {
+        if let Some(ref name) = req.download_as {
+            validate_download_name(name)?;
+        }
+
         let share_id = generate_share_id();
 
         let (password_hash, generated_password) = if let Some(ref pwd) = req.password {
@@ -121,6 +316,8 @@ impl ShareService {
             expires_in_days: req.expires_in_days,
             password_hash,
             metadata,
+            download_as: req.download_as,
+            bundle_files: req.bundle_files,
         });
 
         {
@@ -154,6 +351,24 @@ impl ShareService {
             .map(|s| s.to_response())
     }
 
+    /// Get share infos for several IDs, keeping only shares created by `user_id`
+    /// (unknown and foreign IDs are silently omitted)
+    pub fn get_owned_share_infos(
+        &self,
+        share_ids: &[String],
+        user_id: &str,
+    ) -> HashMap<String, ShareInfoResponse> {
+        let Ok(shares) = self.shares.read() else {
+            return HashMap::new();
+        };
+        share_ids
+            .iter()
+            .filter_map(|id| shares.get(id))
+            .filter(|s| s.created_by == user_id)
+            .map(|s| (s.share_id.clone(), s.to_response()))
+            .collect()
+    }
+
     /// Get all shares for a user (with full ShareInfo for filtering)
     pub fn get_user_shares(&self, user_id: &str) -> Vec<ShareInfo> {
         // Unified lock order: shares → user_shares
@@ -194,6 +409,38 @@ impl ShareService {
         }
     }
 
+    /// Remaining password lockout for an IP on a share, if it is locked out
+    pub fn password_lockout_remaining(
+        &self,
+        share_id: &str,
+        ip_address: &str,
+    ) -> Option<std::time::Duration> {
+        self.password_lockout
+            .remaining_lockout(&format!("{}:{}", share_id, ip_address))
+    }
+
+    /// Record a failed password attempt. Returns true if the IP is now locked out.
+    pub fn record_password_failure(&self, share_id: &str, ip_address: &str) -> bool {
+        let locked = self
+            .password_lockout
+            .record_failure(&format!("{}:{}", share_id, ip_address));
+        if locked {
+            tracing::warn!(
+                "Share {} locked for {} after {} failed password attempts",
+                share_id,
+                redact_ip(ip_address),
+                self.password_lockout.max_attempts()
+            );
+        }
+        locked
+    }
+
+    /// Clear failed password attempts after a successful download
+    pub fn reset_password_failures(&self, share_id: &str, ip_address: &str) {
+        self.password_lockout
+            .reset(&format!("{}:{}", share_id, ip_address));
+    }
+
     /// Record access to a share
     pub fn record_access(
         &self,
@@ -207,19 +454,83 @@ impl ShareService {
         let mut shares = self.shares.write().map_err(|_| "Lock error")?;
         match shares.get_mut(share_id) {
             Some(share) => {
+                let ip_address = self.stored_ip(ip_address);
                 share.record_access(ip_address, success, bytes, error, user_agent);
+                // access_count is tracked separately and keeps counting past the cap
+                while share.access_logs.len() > self.max_access_logs {
+                    share.access_logs.pop_front();
+                }
                 Ok(())
             }
             None => Err("Share not found".to_string()),
         }
     }
 
+    /// Replace a protected share's password (`None` generates one). The old password stops
+    /// working immediately and the stored plaintext copy is dropped, so the returned
+    /// password is the only time it is revealed. The rotation is recorded in the access log.
+    pub fn rotate_password(
+        &self,
+        share_id: &str,
+        new_password: Option<&str>,
+        ip_address: String,
+        user_agent: Option<String>,
+    ) -> Result<String, RotatePasswordError> {
+        match self.get_share(share_id) {
+            None => return Err(RotatePasswordError::NotFound),
+            Some(share) if !share.has_password => {
+                return Err(RotatePasswordError::Invalid(
+                    "Share is not password protected".to_string(),
+                ));
+            }
+            Some(_) => {}
+        }
+        let password = match new_password {
+            Some(pwd) => {
+                let len = pwd.chars().count();
+                if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&len) {
+                    return Err(RotatePasswordError::Invalid(format!(
+                        "Password must be {}-{} characters",
+                        MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+                    )));
+                }
+                pwd.to_string()
+            }
+            None => generate_random_password(),
+        };
+        // Hash before taking the lock: bcrypt is deliberately slow
+        let hash = bcrypt::hash(&password, bcrypt::DEFAULT_COST)
+            .map_err(|e| RotatePasswordError::Internal(e.to_string()))?;
+
+        let mut shares = self
+            .shares
+            .write()
+            .map_err(|_| RotatePasswordError::Internal("Lock error".to_string()))?;
+        let share = shares
+            .get_mut(share_id)
+            .ok_or(RotatePasswordError::NotFound)?;
+        share.password_hash = Some(hash);
+        if let Some(metadata) = share.metadata.as_mut() {
+            metadata.remove("plainPassword");
+        }
+        share.record_event(self.stored_ip(ip_address), user_agent, "passwordRotated");
+        while share.access_logs.len() > self.max_access_logs {
+            share.access_logs.pop_front();
+        }
+        tracing::info!("Share password rotated: {}", share_id);
+        Ok(password)
+    }
+
     /// Get access logs for a share
     pub fn get_access_logs(&self, share_id: &str) -> Vec<ShareAccessLog> {
         self.shares
             .read()
             .ok()
-            .and_then(|shares| shares.get(share_id).map(|s| s.access_logs.clone()))
+            .and_then(|shares| {
+                shares
+                    .get(share_id)
+                    .map(|s| s.access_logs.iter().cloned().collect())
+            })
             .unwrap_or_default()
     }
 
@@ -248,12 +559,48 @@ impl ShareService {
             if let Some(shares) = user_shares.get_mut(&s.created_by) {
                 shares.retain(|id| id != share_id);
             }
+            // Short links live exactly as long as their share
+            if let Ok(mut codes) = self.short_codes.write() {
+                codes.retain(|_, id| id != share_id);
+            }
             tracing::info!("Share deleted: {}", share_id);
         }
 
         Ok(share)
     }
 
+    /// Short link code for an active share, reusing the existing one if already shortened
+    pub fn shorten_share(&self, share_id: &str) -> Result<String, String> {
+        if !self
+            .get_share(share_id)
+            .is_some_and(|s| s.is_active && !s.is_expired())
+        {
+            return Err("Share not found".to_string());
+        }
+
+        let mut codes = self.short_codes.write().map_err(|_| "Lock error")?;
+        if let Some((code, _)) = codes.iter().find(|(_, id)| *id == share_id) {
+            return Ok(code.clone());
+        }
+        let code = loop {
+            let code = generate_short_code();
+            if !codes.contains_key(&code) {
+                break code;
+            }
+        };
+        codes.insert(code.clone(), share_id.to_string());
+        tracing::info!("Short link {} created for share {}", code, share_id);
+        Ok(code)
+    }
+
+    /// Share ID a short link code points to, while that share is still downloadable
+    pub fn resolve_short_code(&self, code: &str) -> Option<String> {
+        let share_id = self.short_codes.read().ok()?.get(code).cloned()?;
+        self.get_share(&share_id)
+            .filter(|s| s.is_active && !s.is_expired())
+            .map(|_| share_id)
+    }
+
     /// Cleanup expired shares
     pub fn cleanup_expired_shares(&self) -> Vec<ShareInfo> {
         // Collect expired share IDs first (avoid nested locking)
@@ -269,6 +616,20 @@ impl ShareService {
                 .collect()
         };
 
+        if self.dry_run {
+            let shares = match self.shares.read() {
+                Ok(s) => s,
+                Err(_) => return Vec::new(),
+            };
+            return expired_ids
+                .iter()
+                .filter_map(|id| shares.get(id).cloned())
+                .inspect(|share| {
+                    tracing::info!("[dry-run] Share {} would be deleted", share.share_id)
+                })
+                .collect();
+        }
+
         // Delete shares one by one (delete_share handles its own locking)
         let mut expired = Vec::new();
         for id in expired_ids {
@@ -281,6 +642,9 @@ impl ShareService {
             tracing::info!("Cleaned up {} expired shares", expired.len());
         }
 
+        // Drop expired password lockout entries
+        self.password_lockout.cleanup();
+
         // Clean up access logs older than 30 days
         let thirty_days_ago = chrono::Utc::now() - chrono::Duration::days(30);
         if let Ok(mut shares) = self.shares.write() {
@@ -310,20 +674,54 @@ impl Default for ShareService {
     }
 }
 
-/// Generate random 6-character password
+/// Alphabet for auto-generated share passwords (digits, lowercase, uppercase)
+const PASSWORD_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Minimum entropy (bits) of an auto-generated password at the default length.
+/// 10 chars from a 62-symbol alphabet gives ~59.5 bits.
+pub const MIN_PASSWORD_ENTROPY_BITS: f64 = 56.0;
+
+const DEFAULT_PASSWORD_LENGTH: usize = 10;
+// Shorter passwords stay verifiable (bcrypt hashes are length-agnostic), but new ones never drop below this
+const MIN_PASSWORD_LENGTH: usize = 6;
+const MAX_PASSWORD_LENGTH: usize = 64;
+
+/// Auto-generated password length (SHARE_PASSWORD_LENGTH env var, default 10)
+static SHARE_PASSWORD_LENGTH: std::sync::LazyLock<usize> = std::sync::LazyLock::new(|| {
+    std::env::var("SHARE_PASSWORD_LENGTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PASSWORD_LENGTH)
+        .clamp(MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH)
+});
+
+/// Generate a random password using the OS CSPRNG.
+/// Always contains at least one digit, one lowercase and one uppercase letter.
 fn generate_random_password() -> String {
-    use rand::Rng;
-    let mut rng = rand::rng();
-    (0..6)
-        .map(|_| {
-            let idx = rng.random_range(0..36);
-            if idx < 10 {
-                (b'0' + idx) as char
-            } else {
-                (b'a' + idx - 10) as char
-            }
-        })
-        .collect()
+    generate_password_with_length(*SHARE_PASSWORD_LENGTH)
+}
+
+fn generate_password_with_length(length: usize) -> String {
+    use rand::rngs::OsRng;
+    use rand::{Rng, TryRngCore};
+    let mut rng = OsRng.unwrap_err();
+    loop {
+        let pwd: String = (0..length)
+            .map(|_| PASSWORD_ALPHABET[rng.random_range(0..PASSWORD_ALPHABET.len())] as char)
+            .collect();
+        // Rejection sampling keeps the distribution uniform over valid passwords
+        if pwd.chars().any(|c| c.is_ascii_digit())
+            && pwd.chars().any(|c| c.is_ascii_lowercase())
+            && pwd.chars().any(|c| c.is_ascii_uppercase())
+        {
+            return pwd;
+        }
+    }
+}
+
+/// Entropy in bits of a password of `length` chars drawn from the generator alphabet
+pub fn password_entropy_bits(length: usize) -> f64 {
+    length as f64 * (PASSWORD_ALPHABET.len() as f64).log2()
 }
 
 #[cfg(test)]
@@ -331,6 +729,22 @@ mod tests {
     use super::*;
     use chrono::{Duration, Utc};
 
+    #[test]
+    fn test_expiration_bounds_default_and_override() {
+        assert_eq!(parse_expiration_bounds(None, Some("  ")), Ok((1, 30)));
+        assert_eq!(parse_expiration_bounds(Some("2"), Some("90")), Ok((2, 90)));
+        assert!(parse_expiration_bounds(Some("0"), None).is_err());
+        assert!(parse_expiration_bounds(None, Some("ninety")).is_err());
+        assert!(parse_expiration_bounds(Some("40"), None).is_err());
+
+        let service = ShareService::new();
+        assert!(service.is_valid_expiration(30));
+        assert!(!service.is_valid_expiration(31));
+        let service = ShareService::new().with_expiration_bounds(1, 90);
+        assert!(service.is_valid_expiration(90));
+        assert!(!service.is_valid_expiration(91));
+    }
+
     // createShare tests
     #[test]
     fn test_create_share_no_password() {
@@ -357,7 +771,7 @@ mod tests {
         assert!(share.has_password());
         assert!(generated_pwd.is_some());
         let pwd = generated_pwd.unwrap();
-        assert_eq!(pwd.len(), 6);
+        assert_eq!(pwd.len(), *SHARE_PASSWORD_LENGTH);
         assert_eq!(share.access_count, 0);
         assert!(share.is_active);
         assert_eq!(share.created_by, "user1");
@@ -392,6 +806,23 @@ mod tests {
         assert!(share.verify_password("custom"));
     }
 
+    #[test]
+    fn test_default_expiration_parse_and_clamp() {
+        assert_eq!(parse_default_expiration(None, 1, 30), Ok(7));
+        assert_eq!(parse_default_expiration(Some(" "), 10, 30), Ok(10));
+        assert_eq!(parse_default_expiration(Some("1"), 1, 30), Ok(1));
+        assert!(parse_default_expiration(Some("0"), 1, 30).is_err());
+        assert!(parse_default_expiration(Some("31"), 1, 30).is_err());
+        assert!(parse_default_expiration(Some("week"), 1, 30).is_err());
+
+        let service = ShareService::new().with_default_expiration(1);
+        assert_eq!(service.default_expiration(), 1);
+        let service = ShareService::new()
+            .with_expiration_bounds(3, 30)
+            .with_default_expiration(1);
+        assert_eq!(service.default_expiration(), 3);
+    }
+
     #[test]
     fn test_create_share_default_expiration() {
         let service = ShareService::new();
@@ -498,6 +929,30 @@ mod tests {
         assert_eq!(logs[0].error_message, Some("wrong_password".to_string()));
     }
 
+    #[test]
+    fn test_redacted_access_logs_store_masked_ip() {
+        let service = ShareService::new().with_redacted_access_logs(true);
+        let (share, _) = service
+            .create_share(CreateShareRequest::new(
+                "test.txt", "test.txt", 100, "room1", "user1",
+            ))
+            .unwrap();
+
+        service
+            .record_access(
+                &share.share_id,
+                "192.168.1.77".to_string(),
+                true,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let logs = service.get_access_logs(&share.share_id);
+        assert_eq!(logs[0].ip_address, "192.168.1.x");
+    }
+
     #[test]
     fn test_record_access_nonexistent() {
         let service = ShareService::new();
@@ -512,6 +967,35 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_access_logs_capped_fifo() {
+        let service = ShareService::new().with_max_access_logs(3);
+        let (share, _) = service
+            .create_share(CreateShareRequest::new(
+                "test.txt", "test.txt", 100, "room1", "user1",
+            ))
+            .unwrap();
+
+        for i in 0..5 {
+            service
+                .record_access(
+                    &share.share_id,
+                    format!("10.0.0.{}", i),
+                    true,
+                    Some(100),
+                    None,
+                    None,
+                )
+                .unwrap();
+        }
+
+        let logs = service.get_access_logs(&share.share_id);
+        let ips: Vec<&str> = logs.iter().map(|l| l.ip_address.as_str()).collect();
+        assert_eq!(ips, ["10.0.0.2", "10.0.0.3", "10.0.0.4"]);
+        // The counter is independent of the capped log
+        assert_eq!(service.get_share(&share.share_id).unwrap().access_count, 5);
+    }
+
     // getUserShares tests
     #[test]
     fn test_get_user_shares() {
@@ -613,6 +1097,37 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
 
+    #[test]
+    fn test_short_code_lives_with_its_share() {
+        let service = ShareService::new();
+        let (share, _) = service
+            .create_share(CreateShareRequest::new(
+                "a.txt", "a.txt", 100, "room1", "user1",
+            ))
+            .unwrap();
+        let (expired, _) = service
+            .create_share(
+                CreateShareRequest::new("b.txt", "b.txt", 100, "room1", "user1")
+                    .with_expiration(-1),
+            )
+            .unwrap();
+
+        let code = service.shorten_share(&share.share_id).unwrap();
+        assert!(code.len() < share.share_id.len());
+        // Shortening again reuses the code
+        assert_eq!(service.shorten_share(&share.share_id).unwrap(), code);
+        assert_eq!(
+            service.resolve_short_code(&code),
+            Some(share.share_id.clone())
+        );
+        assert!(service.shorten_share(&expired.share_id).is_err());
+
+        service.revoke_share(&share.share_id).unwrap();
+        assert!(service.resolve_short_code(&code).is_none());
+        service.delete_share(&share.share_id).unwrap();
+        assert!(service.short_codes.read().unwrap().is_empty());
+    }
+
     // cleanup tests
     #[test]
     fn test_cleanup_expired_shares() {
@@ -645,6 +1160,22 @@ mod tests {
         assert!(service.get_share(&expired_share.share_id).is_none());
     }
 
+    #[test]
+    fn test_cleanup_dry_run_keeps_shares() {
+        let service = ShareService::new().with_dry_run(true);
+        let (expired_share, _) = service
+            .create_share(
+                CreateShareRequest::new("expired.txt", "expired.txt", 100, "room1", "user1")
+                    .with_expiration(-1),
+            )
+            .unwrap();
+
+        let candidates = service.cleanup_expired_shares();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].share_id, expired_share.share_id);
+        assert!(service.get_share(&expired_share.share_id).is_some());
+    }
+
     #[test]
     fn test_cleanup_old_access_logs() {
         let service = ShareService::new();
@@ -677,8 +1208,9 @@ mod tests {
                     bytes_transferred: Some(512),
                     error_message: None,
                     user_agent: None,
+                    event: None,
                 };
-                s.access_logs.push(old_log);
+                s.access_logs.push_back(old_log);
             }
         }
 
@@ -692,6 +1224,119 @@ mod tests {
         assert_eq!(service.get_access_logs(&share.share_id).len(), 1);
     }
 
+    // generated password tests
+    #[test]
+    fn test_generated_password_length_and_classes() {
+        for length in [6, 10, 32] {
+            let pwd = generate_password_with_length(length);
+            assert_eq!(pwd.len(), length);
+            assert!(pwd.bytes().all(|b| PASSWORD_ALPHABET.contains(&b)));
+            assert!(pwd.chars().any(|c| c.is_ascii_digit()));
+            assert!(pwd.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(pwd.chars().any(|c| c.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn test_default_password_entropy() {
+        assert_eq!(DEFAULT_PASSWORD_LENGTH, 10);
+        assert!(password_entropy_bits(DEFAULT_PASSWORD_LENGTH) >= MIN_PASSWORD_ENTROPY_BITS);
+        assert!(password_entropy_bits(*SHARE_PASSWORD_LENGTH) >= password_entropy_bits(6));
+    }
+
+    #[test]
+    fn test_legacy_short_password_still_verifies() {
+        // Shares created before the length change keep working
+        let service = ShareService::new();
+        let (share, _) = service
+            .create_share(
+                CreateShareRequest::new("f.txt", "f.txt", 1, "room1", "user1")
+                    .with_password("abc123"),
+            )
+            .unwrap();
+        assert!(service.verify_password(&share.share_id, "abc123").unwrap());
+    }
+
+    // password lockout tests
+    #[test]
+    fn test_password_lockout_after_failures() {
+        let service =
+            ShareService::new().with_password_lockout(3, std::time::Duration::from_secs(60));
+        assert!(!service.record_password_failure("share1", "10.0.0.1"));
+        assert!(!service.record_password_failure("share1", "10.0.0.1"));
+        assert!(
+            service
+                .password_lockout_remaining("share1", "10.0.0.1")
+                .is_none()
+        );
+        assert!(service.record_password_failure("share1", "10.0.0.1"));
+        assert!(
+            service
+                .password_lockout_remaining("share1", "10.0.0.1")
+                .is_some()
+        );
+
+        // Different IP and different share are unaffected
+        assert!(
+            service
+                .password_lockout_remaining("share1", "10.0.0.2")
+                .is_none()
+        );
+        assert!(
+            service
+                .password_lockout_remaining("share2", "10.0.0.1")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_password_lockout_expires() {
+        let service =
+            ShareService::new().with_password_lockout(1, std::time::Duration::from_millis(50));
+        assert!(service.record_password_failure("share1", "10.0.0.1"));
+        assert!(
+            service
+                .password_lockout_remaining("share1", "10.0.0.1")
+                .is_some()
+        );
+        std::thread::sleep(std::time::Duration::from_millis(80));
+        assert!(
+            service
+                .password_lockout_remaining("share1", "10.0.0.1")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_password_lockout_reset_on_success() {
+        let service =
+            ShareService::new().with_password_lockout(2, std::time::Duration::from_secs(60));
+        service.record_password_failure("share1", "10.0.0.1");
+        service.reset_password_failures("share1", "10.0.0.1");
+        assert!(!service.record_password_failure("share1", "10.0.0.1"));
+    }
+
+    #[test]
+    fn test_rotate_password_errors_are_typed() {
+        let service = ShareService::new();
+        let (open, _) = service
+            .create_share(CreateShareRequest::new(
+                "test.txt", "test.txt", 100, "room1", "user1",
+            ))
+            .unwrap();
+
+        assert_eq!(
+            service
+                .rotate_password("missing1", None, "10.0.0.1".to_string(), None)
+                .unwrap_err(),
+            RotatePasswordError::NotFound
+        );
+        assert!(matches!(
+            service.rotate_password(&open.share_id, None, "10.0.0.1".to_string(), None),
+            Err(RotatePasswordError::Invalid(_))
+        ));
+    }
+
     // verify_password tests
     #[test]
     fn test_verify_password_correct() {
@@ -1,14 +1,31 @@
 use serde::{Deserialize, Serialize};
 use socketioxide::SocketIo;
-use socketioxide::extract::{Data, SocketRef};
+use socketioxide::extract::{AckSender, Data, SocketRef};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
 
-use crate::models::Message;
-use crate::services::{JoinRoomRequest, RoomService};
-use crate::utils::{detect_device_type, generate_message_id, sanitize_message_content};
+use crate::middleware::rate_limit::{RateLimitMetrics, extract_client_ip};
+use crate::models::message::ContentFormat;
+use crate::models::{ClientInfo, Message};
+use crate::services::{FileManager, JoinRoomRequest, RoomService};
+use crate::utils::{
+    MessageFilter, detect_device_type, device_category, generate_message_id, is_safe_url,
+    prepare_message_content,
+};
+
+/// Prefix for generated usernames when the client sends no locale (DEFAULT_USERNAME_PREFIX)
+static DEFAULT_USERNAME_PREFIX: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
+    std::env::var("DEFAULT_USERNAME_PREFIX")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "用户".to_string())
+});
+
+/// Keyword filter applied to plain text messages (MESSAGE_FILTER_* env vars, off by default)
+static MESSAGE_FILTER: std::sync::LazyLock<MessageFilter> =
+    std::sync::LazyLock::new(MessageFilter::from_env);
 
 /// User info for client
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,10 +34,27 @@ pub struct UserInfo {
     pub id: String,
     pub name: String,
     pub device_type: String,
+    /// Normalized `device_type` for icon rendering (mobile/tablet/desktop/unknown)
+    pub device_category: String,
+    /// Client-reported platform, app version and client type, when provided
+    #[serde(default, flatten)]
+    pub client_info: ClientInfo,
     pub is_online: bool,
     pub last_seen: chrono::DateTime<chrono::Utc>,
+    /// Set in `userJoined` when the requested name was taken and had to be changed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub username_adjusted: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub fingerprint: Option<String>,
+    pub requested_username: Option<String>,
+}
+
+impl UserInfo {
+    /// Flag the name as adjusted when the join altered the requested username
+    pub fn with_requested_username(mut self, requested_username: Option<String>) -> Self {
+        self.username_adjusted = requested_username.is_some();
+        self.requested_username = requested_username;
+        self
+    }
 }
 
 impl From<&crate::models::User> for UserInfo {
@@ -29,9 +63,12 @@ impl From<&crate::models::User> for UserInfo {
             id: user.id.clone(),
             name: user.username.clone(),
             device_type: user.device_type.clone(),
+            device_category: device_category(&user.device_type).to_string(),
+            client_info: user.client_info.clone(),
             is_online: user.is_online,
             last_seen: user.last_seen,
-            fingerprint: user.fingerprint.clone(),
+            username_adjusted: false,
+            requested_username: None,
         }
     }
 }
@@ -42,6 +79,10 @@ pub struct JoinRoomPayload {
     pub room_key: String,
     pub user: Option<UserData>,
     pub fingerprint: Option<FingerprintData>,
+    /// Token from a previous join's ack/`sessionToken` event, resumes that identity
+    pub session_token: Option<String>,
+    /// Client locale (e.g. "en-US"), picks the generated username prefix
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +90,21 @@ pub struct JoinRoomPayload {
 pub struct UserData {
     pub name: Option<String>,
     pub device_type: Option<String>,
+    pub platform: Option<String>,
+    pub app_version: Option<String>,
+    /// web, desktop or mobile; other values are ignored
+    pub client_type: Option<String>,
+}
+
+impl UserData {
+    /// Validated platform details; invalid or oversized values are dropped
+    pub fn client_info(&self) -> ClientInfo {
+        ClientInfo::new(
+            self.platform.as_deref(),
+            self.app_version.as_deref(),
+            self.client_type.as_deref(),
+        )
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,6 +120,8 @@ pub struct JoinRoomWithPasswordPayload {
     pub password: String,
     pub user: Option<UserData>,
     pub fingerprint: Option<FingerprintData>,
+    pub session_token: Option<String>,
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -93,6 +151,37 @@ pub struct PinRoomPayload {
     pub pinned: bool,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRoomLockedPayload {
+    pub room_key: String,
+    pub locked: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomLockedEvent {
+    pub room_key: String,
+    pub locked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFileToRoomPayload {
+    pub source_file_id: String,
+    pub target_room_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastAnnouncementPayload {
+    pub room_key: String,
+    pub text: String,
+    /// Also keep the announcement in the room history
+    #[serde(default)]
+    pub pinned: bool,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RoomPinnedEvent {
@@ -100,6 +189,61 @@ pub struct RoomPinnedEvent {
     pub is_pinned: bool,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMessagesPayload {
+    pub room_key: String,
+    pub query: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultsEvent {
+    pub room_key: String,
+    pub query: String,
+    pub messages: Vec<Message>,
+}
+
+/// Longest client error context / message kept in the server log (in characters)
+pub const MAX_CLIENT_ERROR_CONTEXT_LEN: usize = 64;
+pub const MAX_CLIENT_ERROR_MESSAGE_LEN: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientErrorPayload {
+    /// Where the failure happened on the client (e.g. "p2p", "decrypt")
+    pub context: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestFileDownloadersPayload {
+    pub file_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDownloadersEvent {
+    pub file_id: String,
+    pub user_ids: Vec<String>,
+}
+
+/// Room the caller's device is still a member of (`myRooms` event), so it can offer to rejoin
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyRoomItem {
+    pub room_key: String,
+    pub has_password: bool,
+    pub is_pinned: bool,
+}
+
+/// Default / maximum number of search results returned
+const SEARCH_DEFAULT_LIMIT: usize = 20;
+const SEARCH_MAX_LIMIT: usize = 100;
+const SEARCH_MAX_QUERY_LENGTH: usize = 200;
+
 #[derive(Debug, Deserialize)]
 pub struct P2POfferRequest {
     #[serde(rename = "to")]
@@ -131,8 +275,18 @@ pub struct SendMessageRequest {
     pub file_info: Option<SendMessageFileInfo>,
     pub download_url: Option<String>,
     pub file_id: Option<String>,
+    /// Content is end-to-end encrypted; stored and relayed as-is
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Rendering hint for text content: plain (default), url, code or rich
+    pub content_format: Option<String>,
+    /// Self-destruct after this many seconds (1..=MAX_MESSAGE_TTL_SECS)
+    pub ttl_secs: Option<u64>,
 }
 
+/// Longest self-destruct delay a message may ask for (one day)
+pub const MAX_MESSAGE_TTL_SECS: u64 = 86_400;
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMessageFileInfo {
@@ -153,6 +307,9 @@ pub struct PasswordRequiredEvent {
 pub struct RoomPasswordSetEvent {
     pub room_key: String,
     pub has_password: bool,
+    /// Generated password, sent only to the creator of an auto-protected room
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -162,10 +319,82 @@ pub struct RoomLinkGeneratedEvent {
     pub share_link: String,
 }
 
+/// Reconnection token sent to a socket after it joins a room
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTokenEvent {
+    pub room_key: String,
+    pub session_token: String,
+}
+
+/// Owner token sent to the creator of a new room; authorizes the owner-only REST
+/// endpoints (`x-owner-token` header)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnerTokenEvent {
+    pub room_key: String,
+    pub owner_token: String,
+}
+
+/// Acknowledgement payload for emits sent with a callback:
+/// `{ ok: true, messageId?, sessionToken? }` or `{ ok: false, error }`
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketAck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SocketAck {
+    pub fn success() -> Self {
+        Self {
+            ok: true,
+            message_id: None,
+            session_token: None,
+            error: None,
+        }
+    }
+
+    pub fn joined(session_token: impl Into<String>) -> Self {
+        Self {
+            session_token: Some(session_token.into()),
+            ..Self::success()
+        }
+    }
+
+    pub fn message(message_id: impl Into<String>) -> Self {
+        Self {
+            message_id: Some(message_id.into()),
+            ..Self::success()
+        }
+    }
+
+    pub fn error(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message_id: None,
+            session_token: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+const JOIN_RATE_LIMITED: &str = "Too many join attempts. Please wait.";
+const SEND_MESSAGE_RATE_LIMITED: &str = "Too many messages. Please wait.";
+const SEARCH_RATE_LIMITED: &str = "Too many requests. Please wait.";
+const REQUEST_RATE_LIMITED: &str = "Too many requests. Please wait.";
+
 /// Socket-level rate limiter
 struct SocketRateLimiter {
     /// socket_id -> (event_key -> RateLimitEntry)
     limits: HashMap<String, HashMap<String, RateLimitEntry>>,
+    /// Rejections are reported as `socket:<event>`
+    metrics: Arc<RateLimitMetrics>,
 }
 
 struct RateLimitEntry {
@@ -174,9 +403,10 @@ struct RateLimitEntry {
 }
 
 impl SocketRateLimiter {
-    fn new() -> Self {
+    fn new(metrics: Arc<RateLimitMetrics>) -> Self {
         Self {
             limits: HashMap::new(),
+            metrics,
         }
     }
 
@@ -201,6 +431,7 @@ impl SocketRateLimiter {
         }
 
         if entry.count >= max_requests {
+            self.metrics.record_rejection(&format!("socket:{}", event));
             return false;
         }
 
@@ -241,18 +472,28 @@ fn get_rate_limit_config(event: &str) -> SocketRateLimitConfig {
             max_requests: 30,
             window_ms: 60_000,
         },
-        "requestUserList" => SocketRateLimitConfig {
-            max_requests: 20,
-            window_ms: 60_000,
-        },
-        "setRoomPassword" | "pinRoom" => SocketRateLimitConfig {
+        "requestUserList" | "requestRoomState" | "requestFileDownloaders" => {
+            SocketRateLimitConfig {
+                max_requests: 20,
+                window_ms: 60_000,
+            }
+        }
+        "setRoomPassword"
+        | "pinRoom"
+        | "setRoomLocked"
+        | "broadcastAnnouncement"
+        | "copyFileToRoom" => SocketRateLimitConfig {
             max_requests: 10,
             window_ms: 60_000,
         },
-        "shareRoomLink" => SocketRateLimitConfig {
+        "shareRoomLink" | "searchMessages" => SocketRateLimitConfig {
             max_requests: 20,
             window_ms: 60_000,
         },
+        "clientError" => SocketRateLimitConfig {
+            max_requests: 10,
+            window_ms: 60_000,
+        },
         _ => SocketRateLimitConfig {
             max_requests: 30,
             window_ms: 60_000,
@@ -260,9 +501,42 @@ fn get_rate_limit_config(event: &str) -> SocketRateLimitConfig {
     }
 }
 
+/// Apply the per-socket rate limit for `event`, returning `error` once it is exceeded.
+/// `room_limit` (a room owner's override) replaces the global max requests per window.
+fn check_event_rate_limit(
+    limiter: &mut SocketRateLimiter,
+    socket_id: &str,
+    event: &str,
+    room_limit: Option<u32>,
+    error: &str,
+) -> Result<(), String> {
+    let config = get_rate_limit_config(event);
+    let max_requests = room_limit.unwrap_or(config.max_requests);
+    if limiter.check_rate_limit(socket_id, event, max_requests, config.window_ms) {
+        Ok(())
+    } else {
+        Err(error.to_string())
+    }
+}
+
+/// Room owner's rate limit override for `event` in the room the socket has joined
+fn room_rate_limit_for_socket(
+    room_service: &RoomService,
+    socket_id: &str,
+    event: &str,
+) -> Option<u32> {
+    let user = room_service.get_user_by_socket(socket_id)?;
+    room_service.room_rate_limit(&user.room_key, event)
+}
+
 /// Setup Socket.IO event handlers
-pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
-    let rate_limiter = Arc::new(RwLock::new(SocketRateLimiter::new()));
+pub fn setup_socket_handlers(
+    io: &SocketIo,
+    room_service: Arc<RoomService>,
+    file_manager: Arc<FileManager>,
+    rate_limit_metrics: Arc<RateLimitMetrics>,
+) {
+    let rate_limiter = Arc::new(RwLock::new(SocketRateLimiter::new(rate_limit_metrics)));
 
     // Spawn background task to cleanup rate limit data every 5 minutes
     {
@@ -279,34 +553,41 @@ pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
 
     io.ns("/", move |socket: SocketRef| {
         let room_service = room_service.clone();
+        let file_manager = file_manager.clone();
         let rate_limiter = rate_limiter.clone();
 
         tracing::info!("Client connected: {}", socket.id);
 
+        if let Some(banner) = room_service.banner() {
+            let _ = socket.emit("serverBanner", &banner);
+        }
+
         // Handle join room
         socket.on("joinRoom", {
             let room_service = room_service.clone();
             let rate_limiter = rate_limiter.clone();
-            move |socket: SocketRef, Data::<JoinRoomPayload>(data)| {
+            move |socket: SocketRef, Data::<JoinRoomPayload>(data), ack: AckSender| {
                 let room_service = room_service.clone();
                 let rate_limiter = rate_limiter.clone();
                 async move {
-                    let config = get_rate_limit_config("joinRoom");
                     let allowed = {
                         let mut limiter = rate_limiter.write().await;
-                        limiter.check_rate_limit(
+                        check_event_rate_limit(
+                            &mut limiter,
                             &socket.id.to_string(),
                             "joinRoom",
-                            config.max_requests,
-                            config.window_ms,
+                            None,
+                            JOIN_RATE_LIMITED,
                         )
                     };
-                    if allowed {
-                        handle_join_room(socket, data, room_service).await;
+                    let result = if allowed.is_ok() {
+                        handle_join_room(socket, data, room_service).await
                     } else {
                         tracing::warn!("Rate limit exceeded for joinRoom: {}", socket.id);
-                        let _ = socket.emit("error", &"Too many join attempts. Please wait.");
-                    }
+                        let _ = socket.emit("error", &JOIN_RATE_LIMITED);
+                        Err(JOIN_RATE_LIMITED.to_string())
+                    };
+                    let _ = ack.send(&result.map_or_else(SocketAck::error, SocketAck::joined));
                 }
             }
         });
@@ -315,25 +596,27 @@ pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
         socket.on("joinRoomWithPassword", {
             let room_service = room_service.clone();
             let rate_limiter = rate_limiter.clone();
-            move |socket: SocketRef, Data::<JoinRoomWithPasswordPayload>(data)| {
+            move |socket: SocketRef, Data::<JoinRoomWithPasswordPayload>(data), ack: AckSender| {
                 let room_service = room_service.clone();
                 let rate_limiter = rate_limiter.clone();
                 async move {
-                    let config = get_rate_limit_config("joinRoomWithPassword");
                     let allowed = {
                         let mut limiter = rate_limiter.write().await;
-                        limiter.check_rate_limit(
+                        check_event_rate_limit(
+                            &mut limiter,
                             &socket.id.to_string(),
                             "joinRoomWithPassword",
-                            config.max_requests,
-                            config.window_ms,
+                            None,
+                            JOIN_RATE_LIMITED,
                         )
                     };
-                    if allowed {
-                        handle_join_room_with_password(socket, data, room_service).await;
+                    let result = if allowed.is_ok() {
+                        handle_join_room_with_password(socket, data, room_service).await
                     } else {
-                        let _ = socket.emit("error", &"Too many join attempts. Please wait.");
-                    }
+                        let _ = socket.emit("error", &JOIN_RATE_LIMITED);
+                        Err(JOIN_RATE_LIMITED.to_string())
+                    };
+                    let _ = ack.send(&result.map_or_else(SocketAck::error, SocketAck::joined));
                 }
             }
         });
@@ -342,25 +625,30 @@ pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
         socket.on("sendMessage", {
             let room_service = room_service.clone();
             let rate_limiter = rate_limiter.clone();
-            move |socket: SocketRef, Data::<SendMessageRequest>(data)| {
+            move |socket: SocketRef, Data::<SendMessageRequest>(data), ack: AckSender| {
                 let room_service = room_service.clone();
                 let rate_limiter = rate_limiter.clone();
                 async move {
-                    let config = get_rate_limit_config("sendMessage");
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "sendMessage");
                     let allowed = {
                         let mut limiter = rate_limiter.write().await;
-                        limiter.check_rate_limit(
-                            &socket.id.to_string(),
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
                             "sendMessage",
-                            config.max_requests,
-                            config.window_ms,
+                            room_limit,
+                            SEND_MESSAGE_RATE_LIMITED,
                         )
                     };
-                    if allowed {
-                        handle_send_message(socket, data, room_service).await;
+                    let result = if allowed.is_ok() {
+                        handle_send_message(socket, data, room_service).await
                     } else {
-                        let _ = socket.emit("error", &"Too many messages. Please wait.");
-                    }
+                        let _ = socket.emit("error", &SEND_MESSAGE_RATE_LIMITED);
+                        Err(SEND_MESSAGE_RATE_LIMITED.to_string())
+                    };
+                    let _ = ack.send(&result.map_or_else(SocketAck::error, SocketAck::message));
                 }
             }
         });
@@ -419,6 +707,37 @@ pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
             }
         });
 
+        // Handle request room state
+        socket.on("requestRoomState", {
+            let room_service = room_service.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<String>(room_key)| {
+                let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "requestRoomState");
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "requestRoomState",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        handle_request_room_state(socket, room_key, room_service).await;
+                    } else {
+                        let _ = socket.emit("error", &REQUEST_RATE_LIMITED);
+                    }
+                }
+            }
+        });
+
         // Handle set room password
         socket.on("setRoomPassword", {
             let room_service = room_service.clone();
@@ -500,101 +819,342 @@ pub fn setup_socket_handlers(io: &SocketIo, room_service: Arc<RoomService>) {
             }
         });
 
-        // Handle P2P offer (no rate limit, same as Node)
-        socket.on("p2pOffer", {
+        // Handle owner announcements
+        socket.on("broadcastAnnouncement", {
             let room_service = room_service.clone();
-            move |socket: SocketRef, Data::<P2POfferRequest>(data)| {
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<BroadcastAnnouncementPayload>(data)| {
                 let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
                 async move {
-                    handle_p2p_offer(socket, data, room_service).await;
+                    let socket_id = socket.id.to_string();
+                    let room_limit = room_rate_limit_for_socket(
+                        &room_service,
+                        &socket_id,
+                        "broadcastAnnouncement",
+                    );
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "broadcastAnnouncement",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        handle_broadcast_announcement(socket, data, room_service).await;
+                    } else {
+                        let _ = socket.emit("error", &REQUEST_RATE_LIMITED);
+                    }
                 }
             }
         });
 
-        // Handle P2P answer (no rate limit, same as Node)
-        socket.on("p2pAnswer", {
+        // Handle owner locking/unlocking the room to new joins
+        socket.on("setRoomLocked", {
             let room_service = room_service.clone();
-            move |socket: SocketRef, Data::<P2PAnswerRequest>(data)| {
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<SetRoomLockedPayload>(data)| {
                 let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
                 async move {
-                    handle_p2p_answer(socket, data, room_service).await;
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "setRoomLocked");
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "setRoomLocked",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        handle_set_room_locked(socket, data, room_service).await;
+                    } else {
+                        let _ = socket.emit("error", &REQUEST_RATE_LIMITED);
+                    }
                 }
             }
         });
 
-        // Handle P2P ICE candidate (no rate limit, same as Node)
-        socket.on("p2pIceCandidate", {
+        // Handle re-sharing a received file into another of the user's rooms
+        socket.on("copyFileToRoom", {
             let room_service = room_service.clone();
-            move |socket: SocketRef, Data::<P2PIceCandidateRequest>(data)| {
+            let file_manager = file_manager.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<CopyFileToRoomPayload>(data), ack: AckSender| {
                 let room_service = room_service.clone();
+                let file_manager = file_manager.clone();
+                let rate_limiter = rate_limiter.clone();
                 async move {
-                    handle_p2p_ice_candidate(socket, data, room_service).await;
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "copyFileToRoom");
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "copyFileToRoom",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                    };
+                    let result = match allowed {
+                        Ok(()) => {
+                            handle_copy_file_to_room(socket, data, room_service, file_manager).await
+                        }
+                        Err(error) => {
+                            let _ = socket.emit("error", &error);
+                            Err(error)
+                        }
+                    };
+                    let _ = ack.send(&result.map_or_else(SocketAck::error, SocketAck::message));
                 }
             }
         });
 
-        // Handle disconnect
-        socket.on_disconnect({
+        // Handle file download transparency
+        socket.on("requestFileDownloaders", {
             let room_service = room_service.clone();
+            let file_manager = file_manager.clone();
             let rate_limiter = rate_limiter.clone();
-            move |socket: SocketRef| {
+            move |socket: SocketRef, Data::<RequestFileDownloadersPayload>(data)| {
                 let room_service = room_service.clone();
+                let file_manager = file_manager.clone();
                 let rate_limiter = rate_limiter.clone();
                 async move {
-                    // Clean up rate limiter entries for disconnected socket
-                    {
+                    let socket_id = socket.id.to_string();
+                    let room_limit = room_rate_limit_for_socket(
+                        &room_service,
+                        &socket_id,
+                        "requestFileDownloaders",
+                    );
+                    let allowed = {
                         let mut limiter = rate_limiter.write().await;
-                        limiter.remove_socket(&socket.id.to_string());
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "requestFileDownloaders",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        match file_downloaders(
+                            &room_service,
+                            &file_manager,
+                            &socket_id,
+                            &data.file_id,
+                        ) {
+                            Ok(user_ids) => {
+                                let _ = socket.emit(
+                                    "fileDownloaders",
+                                    &FileDownloadersEvent {
+                                        file_id: data.file_id,
+                                        user_ids,
+                                    },
+                                );
+                            }
+                            Err(error) => {
+                                let _ = socket.emit("error", &error);
+                            }
+                        }
+                    } else {
+                        let _ = socket.emit("error", &REQUEST_RATE_LIMITED);
                     }
-                    handle_disconnect(socket, room_service).await;
                 }
             }
         });
-    });
-}
-
-async fn handle_join_room(
-    socket: SocketRef,
-    data: JoinRoomPayload,
-    room_service: Arc<RoomService>,
-) {
-    tracing::info!("joinRoom event received: room_key={}", data.room_key);
-
-    // Check if room requires password
-    if room_service.room_has_password(&data.room_key) {
-        tracing::info!("Room {} requires password", data.room_key);
-        let _ = socket.emit(
-            "passwordRequired",
-            &PasswordRequiredEvent {
-                room_key: data.room_key,
-            },
-        );
-        return;
-    }
 
-    // Generate user ID from fingerprint or random (UUID format to match shared schema)
-    let user_id = data
-        .fingerprint
-        .as_ref()
-        .map(|f| crate::utils::generate_user_id_from_fingerprint(&f.hash))
-        .unwrap_or_else(crate::utils::generate_user_id);
+        // Handle listing the rooms this device belongs to
+        socket.on("requestMyRooms", {
+            let room_service = room_service.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef| {
+                let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    let socket_id = socket.id.to_string();
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "requestMyRooms",
+                            None,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if !allowed {
+                        let _ = socket.emit("error", &REQUEST_RATE_LIMITED);
+                        return;
+                    }
+                    match my_rooms(&room_service, &socket_id) {
+                        Ok(rooms) => {
+                            let _ = socket.emit("myRooms", &rooms);
+                        }
+                        Err(error) => {
+                            let _ = socket.emit("error", &error);
+                        }
+                    }
+                }
+            }
+        });
 
-    let username = data
-        .user
-        .as_ref()
-        .and_then(|u| u.name.clone())
-        .unwrap_or_else(|| {
-            use rand::Rng;
-            let suffix: String = rand::rng()
-                .sample_iter(&rand::distr::Alphanumeric)
-                .take(6)
-                .map(|b| (b as char).to_ascii_lowercase())
-                .collect();
-            format!("用户{}", suffix)
+        // Handle message search
+        socket.on("searchMessages", {
+            let room_service = room_service.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<SearchMessagesPayload>(data)| {
+                let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "searchMessages");
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "searchMessages",
+                            room_limit,
+                            SEARCH_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        handle_search_messages(socket, data, room_service).await;
+                    } else {
+                        let _ = socket.emit("error", &SEARCH_RATE_LIMITED);
+                    }
+                }
+            }
         });
 
-    // Detect device type: prefer client-provided value, fallback to User-Agent detection
-    let device_type = data
+        // Handle client-side failure reports (logged only; excess reports are dropped)
+        socket.on("clientError", {
+            let room_service = room_service.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef, Data::<ClientErrorPayload>(data)| {
+                let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    let socket_id = socket.id.to_string();
+                    let room_limit =
+                        room_rate_limit_for_socket(&room_service, &socket_id, "clientError");
+                    let allowed = {
+                        let mut limiter = rate_limiter.write().await;
+                        check_event_rate_limit(
+                            &mut limiter,
+                            &socket_id,
+                            "clientError",
+                            room_limit,
+                            REQUEST_RATE_LIMITED,
+                        )
+                        .is_ok()
+                    };
+                    if allowed {
+                        log_client_error(&room_service, &socket_id, &data);
+                    }
+                }
+            }
+        });
+
+        // Handle P2P offer (no rate limit, same as Node)
+        socket.on("p2pOffer", {
+            let room_service = room_service.clone();
+            move |socket: SocketRef, Data::<P2POfferRequest>(data)| {
+                let room_service = room_service.clone();
+                async move {
+                    handle_p2p_offer(socket, data, room_service).await;
+                }
+            }
+        });
+
+        // Handle P2P answer (no rate limit, same as Node)
+        socket.on("p2pAnswer", {
+            let room_service = room_service.clone();
+            move |socket: SocketRef, Data::<P2PAnswerRequest>(data)| {
+                let room_service = room_service.clone();
+                async move {
+                    handle_p2p_answer(socket, data, room_service).await;
+                }
+            }
+        });
+
+        // Handle P2P ICE candidate (no rate limit, same as Node)
+        socket.on("p2pIceCandidate", {
+            let room_service = room_service.clone();
+            move |socket: SocketRef, Data::<P2PIceCandidateRequest>(data)| {
+                let room_service = room_service.clone();
+                async move {
+                    handle_p2p_ice_candidate(socket, data, room_service).await;
+                }
+            }
+        });
+
+        // Handle disconnect
+        socket.on_disconnect({
+            let room_service = room_service.clone();
+            let rate_limiter = rate_limiter.clone();
+            move |socket: SocketRef| {
+                let room_service = room_service.clone();
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    // Clean up rate limiter entries for disconnected socket
+                    {
+                        let mut limiter = rate_limiter.write().await;
+                        limiter.remove_socket(&socket.id.to_string());
+                    }
+                    handle_disconnect(socket, room_service).await;
+                }
+            }
+        });
+    });
+}
+
+async fn handle_join_room(
+    socket: SocketRef,
+    mut data: JoinRoomPayload,
+    room_service: Arc<RoomService>,
+) -> Result<String, String> {
+    // Rooms live under the canonical key; the key as typed is kept for display
+    let typed_room_key = data.room_key.clone();
+    data.room_key = validated_join_key(&socket, &room_service, &typed_room_key)?;
+    tracing::info!("joinRoom event received: room_key={}", data.room_key);
+
+    // Check if room requires password
+    if room_service.room_has_password(&data.room_key) {
+        tracing::info!("Room {} requires password", data.room_key);
+        let _ = socket.emit(
+            "passwordRequired",
+            &PasswordRequiredEvent {
+                room_key: data.room_key,
+            },
+        );
+        return Err("Password required".to_string());
+    }
+
+    let user_id = join_user_id(data.fingerprint.as_ref());
+
+    let username = resolve_username(
+        data.user.as_ref().and_then(|u| u.name.clone()),
+        data.locale.as_deref(),
+    );
+
+    // Detect device type: prefer client-provided value, fallback to User-Agent detection
+    let device_type = data
         .user
         .as_ref()
         .and_then(|u| u.device_type.clone())
@@ -608,27 +1168,38 @@ async fn handle_join_room(
             detect_device_type(ua)
         });
 
+    let client_info = data
+        .user
+        .as_ref()
+        .map(UserData::client_info)
+        .unwrap_or_default();
     let fingerprint_hash = data.fingerprint.as_ref().map(|f| f.hash.as_str());
 
     let socket_id = socket.id.to_string();
+    let client_ip = extract_client_ip(&socket.req_parts().headers);
 
     let join_req = JoinRoomRequest {
-        room_key: &data.room_key,
+        room_key: &typed_room_key,
         user_id: &user_id,
         username: &username,
         socket_id: &socket_id,
         password: None,
         device_type: &device_type,
+        client_info: Some(&client_info),
         fingerprint: fingerprint_hash,
+        client_ip: Some(&client_ip),
+        session_token: data.session_token.as_deref(),
     };
 
+    // The creator of a new room is the only one told an auto-generated password
+    let is_new_room = !room_service.room_exists(&data.room_key);
     match room_service.join_room(join_req) {
-        Ok((user, users)) => {
+        Ok((user, users, requested_username)) => {
             // Join socket.io room
             let _ = socket.join(data.room_key.clone());
 
             // Convert to client format
-            let user_info = UserInfo::from(&user);
+            let user_info = UserInfo::from(&user).with_requested_username(requested_username);
             let user_list: Vec<UserInfo> = users.iter().map(UserInfo::from).collect();
 
             // Send userJoined event to the joining user
@@ -649,14 +1220,13 @@ async fn handle_join_room(
             }
 
             // Send room password status to joining user
-            let has_password = room_service.room_has_password(&data.room_key);
             let _ = socket.emit(
                 "roomPasswordSet",
-                &RoomPasswordSetEvent {
-                    room_key: data.room_key.clone(),
-                    has_password,
-                },
+                &password_status(&room_service, &data.room_key, is_new_room),
             );
+            if is_new_room {
+                emit_owner_token(&socket, &room_service, &data.room_key, fingerprint_hash);
+            }
 
             // Send room pinned status to joining user
             let is_pinned = room_service.is_room_pinned(&data.room_key);
@@ -675,44 +1245,35 @@ async fn handle_join_room(
             let _ = socket.to(data.room_key).emit("userList", &user_list);
 
             tracing::info!("User {} joined room successfully", user.username);
+            issue_session_token(&socket, &room_service, &user)
         }
         Err(error) => {
             tracing::error!("Failed to join room: {}", error);
             let _ = socket.emit("error", &error);
+            Err(error)
         }
     }
 }
 
 async fn handle_join_room_with_password(
     socket: SocketRef,
-    data: JoinRoomWithPasswordPayload,
+    mut data: JoinRoomWithPasswordPayload,
     room_service: Arc<RoomService>,
-) {
+) -> Result<String, String> {
+    // Rooms live under the canonical key; the key as typed is kept for display
+    let typed_room_key = data.room_key.clone();
+    data.room_key = validated_join_key(&socket, &room_service, &typed_room_key)?;
     tracing::info!(
         "joinRoomWithPassword event received: room_key={}",
         data.room_key
     );
 
-    // Generate user ID from fingerprint or random (UUID format to match shared schema)
-    let user_id = data
-        .fingerprint
-        .as_ref()
-        .map(|f| crate::utils::generate_user_id_from_fingerprint(&f.hash))
-        .unwrap_or_else(crate::utils::generate_user_id);
+    let user_id = join_user_id(data.fingerprint.as_ref());
 
-    let username = data
-        .user
-        .as_ref()
-        .and_then(|u| u.name.clone())
-        .unwrap_or_else(|| {
-            use rand::Rng;
-            let suffix: String = rand::rng()
-                .sample_iter(&rand::distr::Alphanumeric)
-                .take(6)
-                .map(|b| (b as char).to_ascii_lowercase())
-                .collect();
-            format!("用户{}", suffix)
-        });
+    let username = resolve_username(
+        data.user.as_ref().and_then(|u| u.name.clone()),
+        data.locale.as_deref(),
+    );
 
     // Detect device type: prefer client-provided value, fallback to User-Agent detection
     let device_type = data
@@ -729,27 +1290,38 @@ async fn handle_join_room_with_password(
             detect_device_type(ua)
         });
 
+    let client_info = data
+        .user
+        .as_ref()
+        .map(UserData::client_info)
+        .unwrap_or_default();
     let fingerprint_hash = data.fingerprint.as_ref().map(|f| f.hash.as_str());
 
     let socket_id = socket.id.to_string();
+    let client_ip = extract_client_ip(&socket.req_parts().headers);
 
     let join_req = JoinRoomRequest {
-        room_key: &data.room_key,
+        room_key: &typed_room_key,
         user_id: &user_id,
         username: &username,
         socket_id: &socket_id,
         password: Some(&data.password),
         device_type: &device_type,
+        client_info: Some(&client_info),
         fingerprint: fingerprint_hash,
+        client_ip: Some(&client_ip),
+        session_token: data.session_token.as_deref(),
     };
 
+    // The creator of a new room is the only one told an auto-generated password
+    let is_new_room = !room_service.room_exists(&data.room_key);
     match room_service.join_room(join_req) {
-        Ok((user, users)) => {
+        Ok((user, users, requested_username)) => {
             // Join socket.io room
             let _ = socket.join(data.room_key.clone());
 
             // Convert to client format
-            let user_info = UserInfo::from(&user);
+            let user_info = UserInfo::from(&user).with_requested_username(requested_username);
             let user_list: Vec<UserInfo> = users.iter().map(UserInfo::from).collect();
 
             // Send userJoined event to the joining user
@@ -763,14 +1335,13 @@ async fn handle_join_room_with_password(
             }
 
             // Send room password status to joining user
-            let has_password = room_service.room_has_password(&data.room_key);
             let _ = socket.emit(
                 "roomPasswordSet",
-                &RoomPasswordSetEvent {
-                    room_key: data.room_key.clone(),
-                    has_password,
-                },
+                &password_status(&room_service, &data.room_key, is_new_room),
             );
+            if is_new_room {
+                emit_owner_token(&socket, &room_service, &data.room_key, fingerprint_hash);
+            }
 
             // Send room pinned status to joining user
             let is_pinned = room_service.is_room_pinned(&data.room_key);
@@ -792,72 +1363,227 @@ async fn handle_join_room_with_password(
                 "User {} joined password-protected room successfully",
                 user.username
             );
+            issue_session_token(&socket, &room_service, &user)
         }
         Err(error) => {
             tracing::error!("Failed to join room with password: {}", error);
             let _ = socket.emit("error", &error);
+            Err(error)
         }
     }
 }
 
+/// Password status for a user who just joined `room_key`; includes the password
+/// when their join created the room with an auto-generated one
+fn password_status(
+    room_service: &RoomService,
+    room_key: &str,
+    is_new_room: bool,
+) -> RoomPasswordSetEvent {
+    RoomPasswordSetEvent {
+        room_key: room_key.to_string(),
+        has_password: room_service.room_has_password(room_key),
+        password: is_new_room
+            .then(|| room_service.get_room_password(room_key))
+            .flatten(),
+    }
+}
+
+/// Send the owner token to the socket whose join created `room_key`
+fn emit_owner_token(
+    socket: &SocketRef,
+    room_service: &RoomService,
+    room_key: &str,
+    fingerprint: Option<&str>,
+) {
+    if let Some(owner_token) = fingerprint.and_then(|fp| room_service.owner_token(room_key, fp)) {
+        let _ = socket.emit(
+            "ownerToken",
+            &OwnerTokenEvent {
+                room_key: room_key.to_string(),
+                owner_token,
+            },
+        );
+    }
+}
+
+/// Issue a reconnection token for a joined user and send it to their socket
+fn issue_session_token(
+    socket: &SocketRef,
+    room_service: &RoomService,
+    user: &crate::models::User,
+) -> Result<String, String> {
+    let session_token = room_service.issue_session_token(&user.room_key, &user.id)?;
+    let _ = socket.emit(
+        "sessionToken",
+        &SessionTokenEvent {
+            room_key: user.room_key.clone(),
+            session_token: session_token.clone(),
+        },
+    );
+    Ok(session_token)
+}
+
+/// Canonical key for a join, telling the client when the key (as typed or once
+/// normalized) is invalid
+fn validated_join_key(
+    socket: &SocketRef,
+    room_service: &RoomService,
+    room_key: &str,
+) -> Result<String, String> {
+    room_service
+        .validated_room_key(room_key)
+        .map(|key| key.into_owned())
+        .map_err(|msg| {
+            let error = format!("Invalid room key format: {}", msg);
+            let _ = socket.emit("error", &error);
+            error
+        })
+}
+
+/// User ID a join asks for: derived from the fingerprint, or random (UUID format to
+/// match shared schema). The room service replaces it when the old identity is retired.
+fn join_user_id(fingerprint: Option<&FingerprintData>) -> String {
+    fingerprint
+        .map(|f| crate::utils::generate_user_id_from_fingerprint(&f.hash))
+        .unwrap_or_else(crate::utils::generate_user_id)
+}
+
+/// Explicit username if the client sent one, otherwise a generated fallback
+fn resolve_username(name: Option<String>, locale: Option<&str>) -> String {
+    name.unwrap_or_else(|| fallback_username(locale, &DEFAULT_USERNAME_PREFIX))
+}
+
+/// Username for a join without one: a locale-specific prefix ("用户" for zh, "User"
+/// otherwise; `default_prefix` when no locale is sent) plus a random 6-char suffix
+fn fallback_username(locale: Option<&str>, default_prefix: &str) -> String {
+    use rand::Rng;
+    let prefix = match locale.map(|l| l.trim().to_ascii_lowercase()) {
+        Some(l) if l.starts_with("zh") => "用户",
+        Some(l) if !l.is_empty() => "User",
+        _ => default_prefix,
+    };
+    let suffix: String = rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(6)
+        .map(|b| (b as char).to_ascii_lowercase())
+        .collect();
+    format!("{}{}", prefix, suffix)
+}
+
+/// Store and broadcast a message; returns the stored message id for the ack
 async fn handle_send_message(
     socket: SocketRef,
     data: SendMessageRequest,
     room_service: Arc<RoomService>,
-) {
+) -> Result<String, String> {
     let socket_id = socket.id.to_string();
 
-    if let Some(user) = room_service.get_user_by_socket(&socket_id) {
-        let sender = crate::models::message::MessageSender::from_user(&user);
-        let message = if data.msg_type == "text" {
-            // Sanitize text content to prevent XSS
-            let sanitized_content = sanitize_message_content(&data.content.unwrap_or_default());
-            Message::new_text(
-                generate_message_id(),
-                data.room_key.clone(),
-                sender,
-                sanitized_content,
-            )
-        } else {
-            let file_info = data.file_info.unwrap_or(SendMessageFileInfo {
-                name: "unknown".to_string(),
-                size: 0,
-                file_type: "application/octet-stream".to_string(),
-            });
-            let mut msg = Message::new_file(
-                generate_message_id(),
-                data.room_key.clone(),
-                sender,
-                file_info.name,
-                file_info.size,
-                file_info.file_type,
-                data.download_url.unwrap_or_default(),
-            );
-            msg.file_id = data.file_id;
-            msg
-        };
-
-        if room_service
-            .add_message(&data.room_key, message.clone())
-            .is_ok()
-        {
+    match store_message(&room_service, &socket_id, data) {
+        Ok(message) => {
             // Broadcast message to room (including sender)
-            let _ = socket.to(data.room_key.clone()).emit("message", &message);
+            let _ = socket
+                .to(message.room_key.clone())
+                .emit("message", &message);
             let _ = socket.emit("message", &message);
+            if let Some(ttl) = message.ttl_secs {
+                room_service.schedule_message_expiry(&message.room_key, &message.id, ttl);
+            }
             tracing::debug!(
                 "Message sent in room {} by {}",
-                data.room_key,
-                user.username
+                message.room_key,
+                message.sender.name
             );
+            Ok(message.id)
+        }
+        Err(error) => {
+            let _ = socket.emit("error", &error);
+            Err(error)
         }
     }
 }
 
+/// Validate and filter a message from the user bound to `socket_id`, then add it to the room
+fn store_message(
+    room_service: &RoomService,
+    socket_id: &str,
+    mut data: SendMessageRequest,
+) -> Result<Message, String> {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not in room".to_string())?;
+    if data
+        .ttl_secs
+        .is_some_and(|ttl| ttl == 0 || ttl > MAX_MESSAGE_TTL_SECS)
+    {
+        return Err(format!(
+            "Message TTL must be between 1 and {} seconds",
+            MAX_MESSAGE_TTL_SECS
+        ));
+    }
+    let ttl_secs = data.ttl_secs;
+    let sender = crate::models::message::MessageSender::from_user(&user);
+    let mut message = if data.msg_type == "text" {
+        let raw = data.content.unwrap_or_default();
+        let mut content_format = match data.content_format.as_deref() {
+            None => ContentFormat::Plain,
+            Some(f) => {
+                ContentFormat::parse(f).ok_or_else(|| "Invalid content format".to_string())?
+            }
+        };
+        // Only safe schemes may be rendered as links; anything else falls back to plain text
+        if content_format == ContentFormat::Url && !data.encrypted && !is_safe_url(&raw) {
+            content_format = ContentFormat::Plain;
+        }
+        // Sanitize text content to prevent XSS (encrypted payloads are opaque and kept as-is)
+        let content = prepare_message_content(&raw, data.encrypted)?;
+        // Keyword filter runs after sanitization; ciphertext can't be inspected
+        let content = if data.encrypted {
+            content
+        } else {
+            MESSAGE_FILTER.apply(&content)?
+        };
+        let mut msg = Message::new_text(
+            generate_message_id(),
+            data.room_key.clone(),
+            sender,
+            content,
+        );
+        msg.encrypted = data.encrypted;
+        msg.content_format = content_format;
+        msg
+    } else {
+        let file_info = data.file_info.unwrap_or(SendMessageFileInfo {
+            name: "unknown".to_string(),
+            size: 0,
+            file_type: "application/octet-stream".to_string(),
+        });
+        let mut msg = Message::new_file(
+            generate_message_id(),
+            data.room_key.clone(),
+            sender,
+            file_info.name,
+            file_info.size,
+            file_info.file_type,
+            data.download_url.unwrap_or_default(),
+        );
+        msg.file_id = data.file_id;
+        msg
+    };
+
+    message.ttl_secs = ttl_secs;
+
+    message.seq = room_service.add_message(&data.room_key, message.clone())?;
+    Ok(message)
+}
+
 async fn handle_leave_room(
     socket: SocketRef,
-    data: LeaveRoomRequest,
+    mut data: LeaveRoomRequest,
     room_service: Arc<RoomService>,
 ) {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
     let socket_id = socket.id.to_string();
 
     if let Some((room_key, _user)) = room_service.leave_room(&socket_id) {
@@ -880,6 +1606,126 @@ async fn handle_request_user_list(
     let _ = socket.emit("userList", &user_list);
 }
 
+/// Users who downloaded `file_id`, for a member of the file's room
+fn file_downloaders(
+    room_service: &RoomService,
+    file_manager: &FileManager,
+    socket_id: &str,
+    file_id: &str,
+) -> Result<Vec<String>, String> {
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not authenticated".to_string())?;
+    let file = file_manager
+        .get_room_file(file_id)
+        .ok_or_else(|| "File not found".to_string())?;
+    if file.room_key != user.room_key {
+        return Err("User not in room".to_string());
+    }
+    Ok(file.downloaders)
+}
+
+/// Rooms the socket's device (by the fingerprint it joined with) is a member of. Tied to
+/// the socket's own session: fingerprints are never sent to other members.
+fn my_rooms(room_service: &RoomService, socket_id: &str) -> Result<Vec<MyRoomItem>, String> {
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not authenticated".to_string())?;
+    let Some(fingerprint) = user.fingerprint else {
+        return Ok(Vec::new());
+    };
+    Ok(room_service
+        .rooms_for_fingerprint(&fingerprint)
+        .into_iter()
+        .filter_map(|key| room_service.get_room_info(&key))
+        .map(|info| MyRoomItem {
+            room_key: info.room_key,
+            has_password: info.has_password,
+            is_pinned: info.is_pinned,
+        })
+        .collect())
+}
+
+/// Keep at most `max_chars` of client-supplied text, dropping control characters
+/// (newlines included) so a report can't forge extra log lines
+fn clip_for_log(text: &str, max_chars: usize) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .take(max_chars)
+        .collect()
+}
+
+/// Log a client-reported failure with the reporting socket's user and room
+fn log_client_error(room_service: &RoomService, socket_id: &str, data: &ClientErrorPayload) {
+    let user = room_service.get_user_by_socket(socket_id);
+    tracing::warn!(
+        socket_id,
+        user_id = user.as_ref().map(|u| u.id.as_str()),
+        room_key = user.as_ref().map(|u| u.room_key.as_str()),
+        context = %clip_for_log(&data.context, MAX_CLIENT_ERROR_CONTEXT_LEN),
+        "Client error: {}",
+        clip_for_log(&data.message, MAX_CLIENT_ERROR_MESSAGE_LEN)
+    );
+}
+
+async fn handle_search_messages(
+    socket: SocketRef,
+    mut data: SearchMessagesPayload,
+    room_service: Arc<RoomService>,
+) {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
+    let socket_id = socket.id.to_string();
+
+    // Verify user is authenticated
+    let user = match room_service.get_user_by_socket(&socket_id) {
+        Some(u) => u,
+        None => {
+            let _ = socket.emit("error", &"User not authenticated");
+            return;
+        }
+    };
+
+    // Verify user is in the target room
+    if user.room_key != data.room_key {
+        let _ = socket.emit("error", &"User not in room");
+        return;
+    }
+
+    if data.query.chars().count() > SEARCH_MAX_QUERY_LENGTH {
+        let _ = socket.emit("error", &"Search query too long");
+        return;
+    }
+
+    let limit = data
+        .limit
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .min(SEARCH_MAX_LIMIT);
+    let messages = room_service.search_messages(&data.room_key, &data.query, limit);
+    let _ = socket.emit(
+        "searchResults",
+        &SearchResultsEvent {
+            room_key: data.room_key,
+            query: data.query,
+            messages,
+        },
+    );
+}
+
+async fn handle_request_room_state(
+    socket: SocketRef,
+    room_key: String,
+    room_service: Arc<RoomService>,
+) {
+    match room_service.get_room_state(&socket.id.to_string(), &room_key) {
+        Ok(state) => {
+            let _ = socket.emit("roomState", &state);
+        }
+        Err(e) => {
+            let _ = socket.emit("error", &e);
+        }
+    }
+}
+
 async fn handle_disconnect(socket: SocketRef, room_service: Arc<RoomService>) {
     let socket_id = socket.id.to_string();
     tracing::info!("Client disconnected: {}", socket_id);
@@ -895,9 +1741,10 @@ async fn handle_disconnect(socket: SocketRef, room_service: Arc<RoomService>) {
 
 async fn handle_set_room_password(
     socket: SocketRef,
-    data: SetRoomPasswordRequest,
+    mut data: SetRoomPasswordRequest,
     room_service: Arc<RoomService>,
 ) {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
     let socket_id = socket.id.to_string();
 
     // Verify user is authenticated
@@ -928,6 +1775,7 @@ async fn handle_set_room_password(
             let event = RoomPasswordSetEvent {
                 room_key: data.room_key.clone(),
                 has_password,
+                password: None,
             };
             let _ = socket
                 .to(data.room_key.clone())
@@ -948,9 +1796,10 @@ async fn handle_set_room_password(
 
 async fn handle_share_room_link(
     socket: SocketRef,
-    data: ShareRoomLinkRequest,
+    mut data: ShareRoomLinkRequest,
     room_service: Arc<RoomService>,
 ) {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
     let socket_id = socket.id.to_string();
 
     // Verify user is authenticated
@@ -1074,7 +1923,12 @@ async fn handle_p2p_ice_candidate(
     }
 }
 
-async fn handle_pin_room(socket: SocketRef, data: PinRoomPayload, room_service: Arc<RoomService>) {
+async fn handle_pin_room(
+    socket: SocketRef,
+    mut data: PinRoomPayload,
+    room_service: Arc<RoomService>,
+) {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
     let socket_id = socket.id.to_string();
 
     // Verify user is authenticated
@@ -1129,3 +1983,1156 @@ async fn handle_pin_room(socket: SocketRef, data: PinRoomPayload, room_service:
         }
     }
 }
+
+async fn handle_copy_file_to_room(
+    socket: SocketRef,
+    data: CopyFileToRoomPayload,
+    room_service: Arc<RoomService>,
+    file_manager: Arc<FileManager>,
+) -> Result<String, String> {
+    let socket_id = socket.id.to_string();
+    let result = match crate::routes::base_url_from_headers(&socket.req_parts().headers) {
+        Ok(base_url) => {
+            copy_file_to_room(&room_service, &file_manager, &socket_id, data, &base_url).await
+        }
+        Err(error) => Err(error.to_string()),
+    };
+
+    match result {
+        Ok(message) => {
+            // The sender's socket only joins its current room, so this reaches the
+            // target room's members (including the sender's other sessions there)
+            let _ = socket
+                .to(message.room_key.clone())
+                .emit("message", &message);
+            tracing::info!(
+                "File {:?} copied into room {} by {}",
+                message.file_id,
+                message.room_key,
+                message.sender.name
+            );
+            Ok(message.id)
+        }
+        Err(error) => {
+            let _ = socket.emit("error", &error);
+            Err(error)
+        }
+    }
+}
+
+/// Reference a file from the caller's current room in another room they belong to
+/// (matched by fingerprint) and post it there as a file message
+async fn copy_file_to_room(
+    room_service: &RoomService,
+    file_manager: &FileManager,
+    socket_id: &str,
+    mut data: CopyFileToRoomPayload,
+    base_url: &str,
+) -> Result<Message, String> {
+    data.target_room_key = room_service
+        .canonical_room_key(&data.target_room_key)
+        .into_owned();
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not authenticated".to_string())?;
+    let source = file_manager
+        .get_file(&data.source_file_id)
+        .ok_or_else(|| "File not found".to_string())?;
+    if source.room_key != user.room_key {
+        return Err("User not in room".to_string());
+    }
+    if data.target_room_key == user.room_key {
+        return Err("File is already in this room".to_string());
+    }
+    let fingerprint = user
+        .fingerprint
+        .as_deref()
+        .ok_or_else(|| "User fingerprint required".to_string())?;
+    if !room_service
+        .rooms_for_fingerprint(fingerprint)
+        .contains(&data.target_room_key)
+    {
+        return Err("User not in target room".to_string());
+    }
+
+    let copy = file_manager
+        .copy_to_room(&source.filename, &data.target_room_key)
+        .map_err(|e| e.to_string())?;
+    let mut message = Message::new_file(
+        generate_message_id(),
+        data.target_room_key.clone(),
+        crate::models::message::MessageSender::from_user(&user),
+        copy.original_name.clone(),
+        copy.size,
+        copy.mime_type.clone(),
+        format!("{}/api/files/download/{}", base_url, copy.filename),
+    );
+    message.file_id = Some(copy.filename.clone());
+    match room_service.add_message(&data.target_room_key, message.clone()) {
+        Ok(seq) => message.seq = seq,
+        Err(error) => {
+            // Nothing references the copy, so don't leave it listed in the target room
+            // (the source still holds the physical file)
+            if let Err(e) = file_manager.delete_file(&copy.filename).await {
+                tracing::warn!("Failed to discard unposted file copy: {}", e);
+            }
+            return Err(error);
+        }
+    }
+    Ok(message)
+}
+
+async fn handle_broadcast_announcement(
+    socket: SocketRef,
+    data: BroadcastAnnouncementPayload,
+    room_service: Arc<RoomService>,
+) {
+    let socket_id = socket.id.to_string();
+
+    match create_announcement(&room_service, &socket_id, data) {
+        Ok(message) => {
+            let event = serde_json::json!({
+                "type": "announcement",
+                "data": message,
+            });
+            // Broadcast to all users in the room (including sender)
+            let _ = socket
+                .to(message.room_key.clone())
+                .emit("systemMessage", &event);
+            let _ = socket.emit("systemMessage", &event);
+            tracing::info!(
+                "Announcement posted in room {} (pinned={})",
+                message.room_key,
+                message.pinned
+            );
+        }
+        Err(error) => {
+            let _ = socket.emit("error", &error);
+        }
+    }
+}
+
+async fn handle_set_room_locked(
+    socket: SocketRef,
+    data: SetRoomLockedPayload,
+    room_service: Arc<RoomService>,
+) {
+    let socket_id = socket.id.to_string();
+
+    match set_room_locked(&room_service, &socket_id, data) {
+        Ok(event) => {
+            // Broadcast to all users in the room (including sender)
+            let _ = socket.to(event.room_key.clone()).emit("roomLocked", &event);
+            let _ = socket.emit("roomLocked", &event);
+            tracing::info!(
+                "Room {} {}",
+                event.room_key,
+                if event.locked { "locked" } else { "unlocked" }
+            );
+        }
+        Err(error) => {
+            let _ = socket.emit("error", &error);
+        }
+    }
+}
+
+/// Apply a lock change from the owner bound to `socket_id`, returning the event to broadcast
+fn set_room_locked(
+    room_service: &RoomService,
+    socket_id: &str,
+    mut data: SetRoomLockedPayload,
+) -> Result<RoomLockedEvent, String> {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not authenticated".to_string())?;
+    if user.room_key != data.room_key {
+        return Err("User not in room".to_string());
+    }
+    let fingerprint = user
+        .fingerprint
+        .ok_or_else(|| "User fingerprint required".to_string())?;
+
+    let config = room_service
+        .set_room_locked(&data.room_key, &fingerprint, data.locked)
+        .map_err(|e| e.to_string())?;
+    Ok(RoomLockedEvent {
+        room_key: data.room_key,
+        locked: config.locked,
+    })
+}
+
+/// Sanitize an announcement from the owner bound to `socket_id` and create it
+fn create_announcement(
+    room_service: &RoomService,
+    socket_id: &str,
+    mut data: BroadcastAnnouncementPayload,
+) -> Result<Message, String> {
+    data.room_key = room_service.canonical_room_key(&data.room_key).into_owned();
+    let user = room_service
+        .get_user_by_socket(socket_id)
+        .ok_or_else(|| "User not authenticated".to_string())?;
+    if user.room_key != data.room_key {
+        return Err("User not in room".to_string());
+    }
+    let fingerprint = user
+        .fingerprint
+        .ok_or_else(|| "User fingerprint required".to_string())?;
+
+    let content = prepare_message_content(&data.text, false)?;
+    let content = MESSAGE_FILTER.apply(&content)?;
+    room_service
+        .create_announcement(&data.room_key, &fingerprint, content, data.pinned)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_message(room_key: &str, content: &str) -> SendMessageRequest {
+        SendMessageRequest {
+            room_key: room_key.to_string(),
+            msg_type: "text".to_string(),
+            content: Some(content.to_string()),
+            file_info: None,
+            download_url: None,
+            file_id: None,
+            encrypted: false,
+            content_format: None,
+            ttl_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_send_message_acks_with_message_id() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        let result =
+            store_message(&service, "s1", text_message("room123abc", "hello")).map(|m| m.id);
+        let message_id = result.clone().unwrap();
+        assert_eq!(service.get_messages("room123abc")[0].id, message_id);
+
+        let ack =
+            serde_json::to_value(result.map_or_else(SocketAck::error, SocketAck::message)).unwrap();
+        assert_eq!(
+            ack,
+            serde_json::json!({ "ok": true, "messageId": message_id })
+        );
+    }
+
+    #[test]
+    fn test_non_persisting_room_relays_without_history() {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u1", "Alice", "s1").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        service
+            .set_persist_messages("room123abc", "fp1", false)
+            .unwrap();
+
+        let message = store_message(&service, "s1", text_message("room123abc", "secret")).unwrap();
+        assert_eq!(message.seq, 1);
+
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u2", "Bob", "s2"))
+            .unwrap();
+        assert!(service.get_messages("room123abc").is_empty());
+    }
+
+    #[test]
+    fn test_persisting_room_keeps_history() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+        store_message(&service, "s1", text_message("room123abc", "hello")).unwrap();
+
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u2", "Bob", "s2"))
+            .unwrap();
+        let history = service.get_messages("room123abc");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].content.as_deref(), Some("hello"));
+    }
+
+    /// Alice (fp1) sits in room123abc via socket s1 and has also joined otherroom1;
+    /// returns the file manager holding one text file uploaded to room123abc
+    async fn copy_fixture(dir: &tempfile::TempDir) -> (RoomService, FileManager, String) {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("otherroom1", "u1b", "Alice", "s0").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u1", "Alice", "s1").with_fingerprint("fp1"),
+            )
+            .unwrap();
+        let files =
+            FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12).unwrap();
+        let info = files
+            .save_file("room123abc", "notes.txt", "text/plain", b"shared notes")
+            .await
+            .unwrap();
+        (service, files, info.filename)
+    }
+
+    fn copy_request(file_id: &str, target: &str) -> CopyFileToRoomPayload {
+        CopyFileToRoomPayload {
+            source_file_id: file_id.to_string(),
+            target_room_key: target.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_file_to_room_posts_file_message() {
+        let dir = tempfile::tempdir().unwrap();
+        let (service, files, file_id) = copy_fixture(&dir).await;
+
+        let message = copy_file_to_room(
+            &service,
+            &files,
+            "s1",
+            copy_request(&file_id, "otherroom1"),
+            "http://localhost:3001",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(message.room_key, "otherroom1");
+        let copy_id = message.file_id.clone().unwrap();
+        assert_ne!(copy_id, file_id);
+        assert_eq!(
+            message.download_url.as_deref(),
+            Some(format!("http://localhost:3001/api/files/download/{}", copy_id).as_str())
+        );
+        assert_eq!(service.get_messages("otherroom1")[0].id, message.id);
+
+        let copy = files.get_file(&copy_id).unwrap();
+        assert_eq!(copy.room_key, "otherroom1");
+        assert_eq!(copy.original_name, "notes.txt");
+        assert_eq!(copy.original_file_id.as_deref(), Some(file_id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_copy_file_to_room_requires_target_membership() {
+        let dir = tempfile::tempdir().unwrap();
+        let (service, files, file_id) = copy_fixture(&dir).await;
+        service
+            .join_room(
+                JoinRoomRequest::new("strangers1", "u9", "Mallory", "s9").with_fingerprint("fp9"),
+            )
+            .unwrap();
+
+        let result = copy_file_to_room(
+            &service,
+            &files,
+            "s1",
+            copy_request(&file_id, "strangers1"),
+            "http://localhost:3001",
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), "User not in target room");
+        assert!(files.list_room_files("strangers1").is_empty());
+        assert!(service.get_messages("strangers1").is_empty());
+
+        // Nor can a file be pulled out of a room the caller isn't in
+        let result = copy_file_to_room(
+            &service,
+            &files,
+            "s9",
+            copy_request(&file_id, "strangers1"),
+            "http://localhost:3001",
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), "User not in room");
+    }
+
+    #[tokio::test]
+    async fn test_copy_rejected_by_room_budget_discards_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let (service, files, file_id) = copy_fixture(&dir).await;
+        service
+            .set_room_message_rate_limit("otherroom1", "fp1", Some(1))
+            .unwrap();
+        copy_file_to_room(
+            &service,
+            &files,
+            "s1",
+            copy_request(&file_id, "otherroom1"),
+            "http://localhost:3001",
+        )
+        .await
+        .unwrap();
+
+        let result = copy_file_to_room(
+            &service,
+            &files,
+            "s1",
+            copy_request(&file_id, "otherroom1"),
+            "http://localhost:3001",
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), "Room message rate limit exceeded");
+        assert_eq!(files.list_room_files("otherroom1").len(), 1);
+        assert_eq!(service.get_messages("otherroom1").len(), 1);
+        // The source keeps its bytes
+        assert!(files.get_file(&file_id).unwrap().path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_copy_file_to_room_shares_physical_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (service, files, file_id) = copy_fixture(&dir).await;
+
+        let message = copy_file_to_room(
+            &service,
+            &files,
+            "s1",
+            copy_request(&file_id, "otherroom1"),
+            "http://localhost:3001",
+        )
+        .await
+        .unwrap();
+
+        let original = files.get_file(&file_id).unwrap();
+        let copy = files.get_file(message.file_id.as_deref().unwrap()).unwrap();
+        assert_eq!(copy.path, original.path);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // Deleting the original keeps the bytes for the copy
+        files.delete_file(&file_id).await.unwrap();
+        assert!(copy.path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_file_downloaders_gated_on_membership() {
+        let dir = tempfile::tempdir().unwrap();
+        let (service, files, file_id) = copy_fixture(&dir).await;
+        files.record_downloader(&file_id, "u1");
+        files.record_downloader(&file_id, "u2");
+
+        assert_eq!(
+            file_downloaders(&service, &files, "s1", &file_id).unwrap(),
+            vec!["u1".to_string(), "u2".to_string()]
+        );
+
+        // Alice's socket in otherroom1 can't inspect room123abc's files
+        assert_eq!(
+            file_downloaders(&service, &files, "s0", &file_id).unwrap_err(),
+            "User not in room"
+        );
+        assert!(file_downloaders(&service, &files, "nobody", &file_id).is_err());
+    }
+
+    #[test]
+    fn test_password_status_reveals_generated_password_to_creator_only() {
+        let service = RoomService::new().with_auto_room_password(true);
+        service
+            .join_room(JoinRoomRequest::new("newroom1", "u1", "Alice", "s1"))
+            .unwrap();
+        let generated = service.get_room_password("newroom1");
+
+        let creator = password_status(&service, "newroom1", true);
+        assert!(creator.has_password);
+        assert_eq!(creator.password, generated);
+
+        let joiner = serde_json::to_value(password_status(&service, "newroom1", false)).unwrap();
+        assert_eq!(
+            joiner,
+            serde_json::json!({ "roomKey": "newroom1", "hasPassword": true })
+        );
+    }
+
+    fn formatted_message(content: &str, format: &str) -> SendMessageRequest {
+        SendMessageRequest {
+            content_format: Some(format.to_string()),
+            ttl_secs: None,
+            ..text_message("room123abc", content)
+        }
+    }
+
+    #[test]
+    fn test_url_message_with_unsafe_scheme_downgraded() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        let safe = store_message(
+            &service,
+            "s1",
+            formatted_message("https://example.com", "url"),
+        )
+        .unwrap();
+        assert_eq!(safe.content_format, ContentFormat::Url);
+
+        let unsafe_url = store_message(
+            &service,
+            "s1",
+            formatted_message("javascript:alert('x')", "url"),
+        )
+        .unwrap();
+        assert_eq!(unsafe_url.content_format, ContentFormat::Plain);
+        assert_eq!(
+            unsafe_url.content.as_deref(),
+            Some("javascript:alert(&#x27;x&#x27;)")
+        );
+
+        assert!(store_message(&service, "s1", formatted_message("hi", "markdown")).is_err());
+    }
+
+    #[test]
+    fn test_code_message_preserves_whitespace() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        let code = "fn main() {\n\tlet x = 1;\n    if x < 2 {}\n}\n";
+        let message = store_message(&service, "s1", formatted_message(code, "code")).unwrap();
+        assert_eq!(message.content_format, ContentFormat::Code);
+        assert_eq!(
+            message.content.as_deref(),
+            Some("fn main() {\n\tlet x = 1;\n    if x &lt; 2 {}\n}\n")
+        );
+        assert_eq!(
+            serde_json::to_value(&message).unwrap()["contentFormat"],
+            "code"
+        );
+    }
+
+    #[test]
+    fn test_message_ttl_is_stored_and_bounded() {
+        let service = RoomService::new();
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        let mut request = text_message("room123abc", "123456");
+        request.ttl_secs = Some(30);
+        let message = store_message(&service, "s1", request).unwrap();
+        assert_eq!(message.ttl_secs, Some(30));
+        assert_eq!(serde_json::to_value(&message).unwrap()["ttlSecs"], 30);
+
+        let message = store_message(&service, "s1", text_message("room123abc", "keep")).unwrap();
+        assert!(message.expires_at().is_none());
+        assert!(
+            serde_json::to_value(&message)
+                .unwrap()
+                .get("ttlSecs")
+                .is_none()
+        );
+
+        for ttl in [0, MAX_MESSAGE_TTL_SECS + 1] {
+            let mut request = text_message("room123abc", "bad");
+            request.ttl_secs = Some(ttl);
+            assert!(store_message(&service, "s1", request).is_err());
+        }
+    }
+
+    #[test]
+    fn test_send_message_from_unknown_socket_fails() {
+        let service = RoomService::new();
+        assert!(store_message(&service, "nobody", text_message("room123abc", "hi")).is_err());
+    }
+
+    #[test]
+    fn test_rate_limited_send_acks_with_error() {
+        let metrics = Arc::new(RateLimitMetrics::new());
+        let mut limiter = SocketRateLimiter::new(metrics.clone());
+        let max = get_rate_limit_config("sendMessage").max_requests;
+        for _ in 0..max {
+            assert!(
+                check_event_rate_limit(
+                    &mut limiter,
+                    "s1",
+                    "sendMessage",
+                    None,
+                    SEND_MESSAGE_RATE_LIMITED
+                )
+                .is_ok()
+            );
+        }
+
+        let error = check_event_rate_limit(
+            &mut limiter,
+            "s1",
+            "sendMessage",
+            None,
+            SEND_MESSAGE_RATE_LIMITED,
+        )
+        .unwrap_err();
+        let ack = serde_json::to_value(SocketAck::error(error)).unwrap();
+        assert_eq!(
+            ack,
+            serde_json::json!({ "ok": false, "error": SEND_MESSAGE_RATE_LIMITED })
+        );
+
+        // The rejection is counted under the event name
+        assert_eq!(metrics.rejections("socket:sendMessage"), 1);
+        assert_eq!(metrics.rejections("socket:joinRoom"), 0);
+    }
+
+    /// Run `f` under a subscriber that formats events into a buffer, returning the output
+    fn capture_logs(f: impl FnOnce()) -> String {
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<std::sync::Mutex<Vec<u8>>>);
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_client_error_is_logged_with_user_context() {
+        let service = RoomService::new();
+        let (user, _, _) = service
+            .join_room(JoinRoomRequest::new("room123abc", "u1", "Alice", "s1"))
+            .unwrap();
+
+        let logs = capture_logs(|| {
+            log_client_error(
+                &service,
+                "s1",
+                &ClientErrorPayload {
+                    context: "p2p".to_string(),
+                    message: "ICE failed\nWARN forged line".to_string(),
+                },
+            )
+        });
+
+        assert!(logs.contains("WARN"));
+        assert!(logs.contains("Client error: ICE failedWARN forged line"));
+        assert!(logs.contains("socket_id=\"s1\""));
+        assert!(logs.contains(&format!("user_id=\"{}\"", user.id)));
+        assert!(logs.contains("room_key=\"room123abc\""));
+        assert!(logs.contains("context=p2p"));
+        assert_eq!(logs.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_client_error_text_is_bounded() {
+        let long = "x".repeat(MAX_CLIENT_ERROR_MESSAGE_LEN + 500);
+        assert_eq!(
+            clip_for_log(&long, MAX_CLIENT_ERROR_MESSAGE_LEN).len(),
+            MAX_CLIENT_ERROR_MESSAGE_LEN
+        );
+        assert_eq!(clip_for_log("de\r\u{1b}[31mcode", 64), "de[31mcode");
+    }
+
+    #[test]
+    fn test_client_error_spam_is_rate_limited() {
+        let metrics = Arc::new(RateLimitMetrics::new());
+        let mut limiter = SocketRateLimiter::new(metrics.clone());
+        let config = get_rate_limit_config("clientError");
+
+        let allowed = (0..config.max_requests * 3)
+            .filter(|_| {
+                limiter.check_rate_limit("s1", "clientError", config.max_requests, config.window_ms)
+            })
+            .count();
+
+        assert_eq!(allowed, config.max_requests as usize);
+        assert_eq!(
+            metrics.rejections("socket:clientError"),
+            u64::from(config.max_requests * 2)
+        );
+        // Other sockets keep their own budget
+        assert!(limiter.check_rate_limit(
+            "s2",
+            "clientError",
+            config.max_requests,
+            config.window_ms
+        ));
+    }
+
+    #[test]
+    fn test_join_ack_carries_session_token() {
+        let ack = serde_json::to_value(SocketAck::joined("tok123")).unwrap();
+        assert_eq!(
+            ack,
+            serde_json::json!({ "ok": true, "sessionToken": "tok123" })
+        );
+    }
+
+    /// Number of sendMessage events `socket_id` may send before being limited
+    fn allowed_sends(service: &RoomService, socket_id: &str) -> u32 {
+        let mut limiter = SocketRateLimiter::new(Arc::default());
+        let room_limit = room_rate_limit_for_socket(service, socket_id, "sendMessage");
+        let mut sent = 0;
+        while check_event_rate_limit(
+            &mut limiter,
+            socket_id,
+            "sendMessage",
+            room_limit,
+            SEND_MESSAGE_RATE_LIMITED,
+        )
+        .is_ok()
+        {
+            sent += 1;
+            assert!(sent <= 10_000, "rate limit never applied");
+        }
+        sent
+    }
+
+    #[test]
+    fn test_room_rate_limit_override_allows_more_messages() {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("busyroom1", "u1", "Alice", "s1").with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        let global = get_rate_limit_config("sendMessage").max_requests;
+        assert_eq!(allowed_sends(&service, "s1"), global);
+
+        service
+            .set_room_rate_limits(
+                "busyroom1",
+                "fp_owner",
+                HashMap::from([("sendMessage".to_string(), global * 3)]),
+            )
+            .unwrap();
+        assert_eq!(allowed_sends(&service, "s1"), global * 3);
+    }
+
+    #[test]
+    fn test_room_rate_limit_override_capped_by_ceiling() {
+        let service = RoomService::new().with_room_rate_limit_ceiling(100);
+        service
+            .join_room(
+                JoinRoomRequest::new("busyroom2", "u1", "Alice", "s1").with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+
+        let config = service
+            .set_room_rate_limits(
+                "busyroom2",
+                "fp_owner",
+                HashMap::from([("sendMessage".to_string(), u32::MAX)]),
+            )
+            .unwrap();
+        assert_eq!(config.rate_limits["sendMessage"], 100);
+        assert_eq!(allowed_sends(&service, "s1"), 100);
+    }
+
+    #[test]
+    fn test_reconnect_from_mobile_updates_broadcast_device() {
+        let service = RoomService::new();
+        let desktop = detect_device_type("Mozilla/5.0 (Windows NT 10.0; Win64; x64)");
+        let (user, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("deviceroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_alice")
+                    .with_device_type(&desktop),
+            )
+            .unwrap();
+        assert_eq!(UserInfo::from(&user).device_category, "desktop");
+        service.set_user_offline("s1");
+
+        let mobile = detect_device_type(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15",
+        );
+        let (resumed, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("deviceroom1", "u1-new", "Alice", "s2")
+                    .with_fingerprint("fp_alice")
+                    .with_device_type(&mobile),
+            )
+            .unwrap();
+
+        let broadcast: Vec<UserInfo> = users.iter().map(UserInfo::from).collect();
+        assert_eq!(broadcast.len(), 1);
+        assert_eq!(broadcast[0].id, user.id);
+        assert_eq!(broadcast[0].name, user.username);
+        assert_eq!(broadcast[0].device_type, "mobile");
+        assert_eq!(broadcast[0].device_category, "mobile");
+        assert_eq!(UserInfo::from(&resumed).device_category, "mobile");
+        assert_eq!(
+            service.get_user_by_socket("s2").unwrap().device_type,
+            "mobile"
+        );
+    }
+
+    #[test]
+    fn test_platform_info_round_trips_into_user_list() {
+        let service = RoomService::new();
+        let data: UserData = serde_json::from_value(serde_json::json!({
+            "name": "Alice",
+            "platform": " macOS\u{0007} ",
+            "appVersion": "1.2.0",
+            "clientType": "Desktop",
+        }))
+        .unwrap();
+        let client_info = data.client_info();
+        service
+            .join_room(
+                JoinRoomRequest::new("platformroom1", "u1", "Alice", "s1")
+                    .with_client_info(&client_info),
+            )
+            .unwrap();
+        let (_, users, _) = service
+            .join_room(JoinRoomRequest::new("platformroom1", "u2", "Bob", "s2"))
+            .unwrap();
+
+        let list: Vec<serde_json::Value> = users
+            .iter()
+            .map(|u| serde_json::to_value(UserInfo::from(u)).unwrap())
+            .collect();
+        let alice = list.iter().find(|u| u["name"] == "Alice").unwrap();
+        assert_eq!(alice["platform"], "macOS");
+        assert_eq!(alice["appVersion"], "1.2.0");
+        assert_eq!(alice["clientType"], "desktop");
+
+        // Absent fields are simply omitted
+        let bob = list.iter().find(|u| u["name"] == "Bob").unwrap();
+        assert!(bob.get("platform").is_none());
+        assert!(bob.get("appVersion").is_none());
+        assert!(bob.get("clientType").is_none());
+        assert_eq!(bob["deviceType"], "desktop");
+    }
+
+    #[test]
+    fn test_invalid_platform_info_is_dropped_or_bounded() {
+        let data: UserData = serde_json::from_value(serde_json::json!({
+            "platform": "x".repeat(100),
+            "appVersion": "   ",
+            "clientType": "toaster",
+        }))
+        .unwrap();
+        let info = data.client_info();
+
+        assert_eq!(
+            info.platform.unwrap().chars().count(),
+            crate::models::user::MAX_CLIENT_INFO_LEN
+        );
+        assert_eq!(info.app_version, None);
+        assert_eq!(info.client_type, None);
+
+        let legacy: UserData = serde_json::from_value(serde_json::json!({
+            "name": "Carol",
+            "deviceType": "mobile",
+        }))
+        .unwrap();
+        assert_eq!(legacy.client_info(), ClientInfo::default());
+    }
+
+    fn lock(locked: bool) -> SetRoomLockedPayload {
+        SetRoomLockedPayload {
+            room_key: "room123abc".to_string(),
+            locked,
+        }
+    }
+
+    #[test]
+    fn test_locked_room_rejects_new_fingerprint() {
+        let service = service_with_owner_and_member();
+
+        let event = set_room_locked(&service, "s1", lock(true)).unwrap();
+        assert_eq!(event.room_key, "room123abc");
+        assert!(event.locked);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "roomKey": "room123abc", "locked": true })
+        );
+
+        let err = service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u3", "Stranger", "s3")
+                    .with_fingerprint("fp_stranger"),
+            )
+            .unwrap_err();
+        assert_eq!(err, "Room is locked");
+        assert!(service.get_user_by_socket("s3").is_none());
+
+        // Unlocking lets new users in again
+        assert!(!set_room_locked(&service, "s1", lock(false)).unwrap().locked);
+        service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u3", "Stranger", "s3")
+                    .with_fingerprint("fp_stranger"),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_locked_room_allows_member_reconnect() {
+        let service = service_with_owner_and_member();
+        set_room_locked(&service, "s1", lock(true)).unwrap();
+        service.set_user_offline("s2");
+
+        let (user, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u2-new", "Member", "s4")
+                    .with_fingerprint("fp_member"),
+            )
+            .unwrap();
+        assert_eq!(user.id, "u2");
+        assert_eq!(service.get_user_by_socket("s4").unwrap().id, "u2");
+    }
+
+    #[test]
+    fn test_only_owner_can_lock_room() {
+        let service = service_with_owner_and_member();
+
+        let err = set_room_locked(&service, "s2", lock(true)).unwrap_err();
+        assert_eq!(err, "Only the room owner can perform this action");
+        service
+            .join_room(JoinRoomRequest::new("room123abc", "u3", "Stranger", "s3"))
+            .unwrap();
+    }
+
+    fn announcement(text: &str, pinned: bool) -> BroadcastAnnouncementPayload {
+        BroadcastAnnouncementPayload {
+            room_key: "room123abc".to_string(),
+            text: text.to_string(),
+            pinned,
+        }
+    }
+
+    fn service_with_owner_and_member() -> RoomService {
+        let service = RoomService::new();
+        service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u1", "Owner", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("room123abc", "u2", "Member", "s2")
+                    .with_fingerprint("fp_member"),
+            )
+            .unwrap();
+        service
+    }
+
+    #[test]
+    fn test_owner_broadcasts_sanitized_announcement() {
+        let service = service_with_owner_and_member();
+
+        let message = create_announcement(
+            &service,
+            "s1",
+            announcement("<b>Room closing</b> in 5 min", false),
+        )
+        .unwrap();
+        assert_eq!(
+            message.message_type,
+            crate::models::message::MessageType::System
+        );
+        assert!(!message.content.as_deref().unwrap().contains("<b>"));
+        // Not pinned: broadcast only, not kept in history
+        assert!(service.get_messages("room123abc").is_empty());
+    }
+
+    #[test]
+    fn test_non_owner_announcement_rejected() {
+        let service = service_with_owner_and_member();
+
+        let err = create_announcement(&service, "s2", announcement("hi all", true)).unwrap_err();
+        assert_eq!(err, "Only the room owner can perform this action");
+        assert!(service.get_messages("room123abc").is_empty());
+    }
+
+    #[test]
+    fn test_pinned_announcement_stored_in_history() {
+        let service = service_with_owner_and_member();
+
+        let message =
+            create_announcement(&service, "s1", announcement("Room closing in 5 min", true))
+                .unwrap();
+        let history = service.get_messages("room123abc");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, message.id);
+        assert!(history[0].pinned);
+        assert_eq!(history[0].seq, message.seq);
+    }
+
+    #[test]
+    fn test_pinned_announcements_keep_history_bounded() {
+        let service = service_with_owner_and_member();
+        let max_messages = service.get_room_config("room123abc").unwrap().max_messages;
+
+        for i in 0..max_messages + 50 {
+            create_announcement(&service, "s1", announcement(&format!("notice {}", i), true))
+                .unwrap();
+        }
+        let history = service.get_messages("room123abc");
+        assert!(history.len() <= max_messages);
+        assert_eq!(
+            history.iter().filter(|m| m.pinned).count(),
+            crate::models::room::MAX_PINNED_MESSAGES
+        );
+        assert_eq!(
+            history.last().unwrap().content.as_deref(),
+            Some(format!("notice {}", max_messages + 49).as_str())
+        );
+    }
+
+    fn assert_generated(name: &str, prefix: &str) {
+        let suffix = name.strip_prefix(prefix).expect("missing prefix");
+        assert_eq!(suffix.len(), 6);
+        assert!(
+            suffix
+                .chars()
+                .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+        );
+    }
+
+    #[test]
+    fn test_fallback_username_english_locale() {
+        assert_generated(&fallback_username(Some("en-US"), "用户"), "User");
+    }
+
+    #[test]
+    fn test_fallback_username_chinese_locale() {
+        assert_generated(&fallback_username(Some("zh-CN"), "Guest"), "用户");
+        assert_generated(&fallback_username(None, "用户"), "用户");
+        assert_generated(&fallback_username(None, "Guest"), "Guest");
+    }
+
+    #[test]
+    fn test_explicit_username_not_overridden() {
+        assert_eq!(
+            resolve_username(Some("Alice".to_string()), Some("zh-CN")),
+            "Alice"
+        );
+        assert_eq!(
+            resolve_username(Some("小明".to_string()), Some("en")),
+            "小明"
+        );
+    }
+
+    #[test]
+    fn test_user_info_username_adjusted_payload() {
+        let user = crate::models::User::new(
+            "user1".to_string(),
+            "Alice_x1y2z".to_string(),
+            "room1".to_string(),
+        );
+        let adjusted = serde_json::to_value(
+            UserInfo::from(&user).with_requested_username(Some("Alice".to_string())),
+        )
+        .unwrap();
+        assert_eq!(adjusted["usernameAdjusted"], true);
+        assert_eq!(adjusted["requestedUsername"], "Alice");
+
+        let unchanged =
+            serde_json::to_value(UserInfo::from(&user).with_requested_username(None)).unwrap();
+        assert!(unchanged.get("usernameAdjusted").is_none());
+        assert!(unchanged.get("requestedUsername").is_none());
+    }
+
+    #[test]
+    fn test_user_payloads_omit_fingerprint() {
+        let mut user = crate::models::User::new(
+            "user1".to_string(),
+            "Alice".to_string(),
+            "room1".to_string(),
+        );
+        user.fingerprint = Some("fp_secret".to_string());
+
+        let info = serde_json::to_value(UserInfo::from(&user)).unwrap();
+        assert!(info.get("fingerprint").is_none());
+        let raw = serde_json::to_value(&user).unwrap();
+        assert!(raw.get("fingerprint").is_none());
+        let sender =
+            serde_json::to_value(crate::models::message::MessageSender::from_user(&user)).unwrap();
+        assert!(sender.get("fingerprint").is_none());
+    }
+
+    #[test]
+    fn test_my_rooms_uses_socket_fingerprint() {
+        let service = RoomService::new();
+        service
+            .create_room("lockedroom1", Some("pw"), None)
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("lockedroom1", "u1", "Alice", "s1")
+                    .with_password("pw")
+                    .with_fingerprint("fp_mine"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("openroom1", "u1", "Alice", "s2").with_fingerprint("fp_mine"),
+            )
+            .unwrap();
+        service
+            .join_room(
+                JoinRoomRequest::new("openroom1", "u2", "Bob", "s3").with_fingerprint("fp_bob"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(my_rooms(&service, "s2").unwrap()).unwrap(),
+            serde_json::json!([
+                { "roomKey": "lockedroom1", "hasPassword": true, "isPinned": false },
+                { "roomKey": "openroom1", "hasPassword": false, "isPinned": false }
+            ])
+        );
+        // A co-member only sees their own rooms
+        assert_eq!(
+            serde_json::to_value(my_rooms(&service, "s3").unwrap()).unwrap(),
+            serde_json::json!([{ "roomKey": "openroom1", "hasPassword": false, "isPinned": false }])
+        );
+        assert!(my_rooms(&service, "unknown").is_err());
+    }
+
+    #[test]
+    fn test_retired_fingerprint_identity_gets_new_user_id() {
+        let service =
+            RoomService::new().with_fingerprint_reconnect_max_age(Some(std::time::Duration::ZERO));
+        let fingerprint = FingerprintData {
+            hash: "fp_retired".to_string(),
+        };
+        let derived_id = join_user_id(Some(&fingerprint));
+        let (first, _, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", &derived_id, "Alice", "s1")
+                    .with_fingerprint(&fingerprint.hash),
+            )
+            .unwrap();
+        assert_eq!(first.id, derived_id);
+        service.update_user_status("testroom", &first.id, false);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // The socket handler derives the same id again on rejoin
+        let rejoin_id = join_user_id(Some(&fingerprint));
+        assert_eq!(rejoin_id, derived_id);
+        let (fresh, users, _) = service
+            .join_room(
+                JoinRoomRequest::new("testroom", &rejoin_id, "Alice", "s2")
+                    .with_fingerprint(&fingerprint.hash),
+            )
+            .unwrap();
+        assert_ne!(fresh.id, derived_id);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].id, fresh.id);
+    }
+}
@@ -0,0 +1,163 @@
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Bytes sent per INSTREAM chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Upper bound for a whole scan (connect, stream, verdict)
+const SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest clamd reply we are willing to buffer
+const MAX_REPLY_LEN: usize = 1024;
+
+/// Outcome of scanning one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Malware found; carries the signature name reported by the scanner
+    Infected(String),
+}
+
+/// Malware scanner consulted before an upload is committed
+pub trait VirusScanner: Send + Sync {
+    fn scan<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<ScanVerdict>> + Send + 'a>>;
+}
+
+/// Scans files with a ClamAV daemon using the INSTREAM command
+#[derive(Debug, Clone)]
+pub struct ClamAvScanner {
+    /// `host:port` for TCP, or an absolute path to clamd's unix socket
+    addr: String,
+}
+
+impl ClamAvScanner {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self { addr: addr.into() }
+    }
+
+    /// Scanner for CLAMAV_ADDR, or None when unset (scanning disabled)
+    pub fn from_env() -> Option<Self> {
+        std::env::var("CLAMAV_ADDR")
+            .ok()
+            .map(|addr| addr.trim().to_string())
+            .filter(|addr| !addr.is_empty())
+            .map(Self::new)
+    }
+
+    async fn scan_file(&self, path: &Path) -> anyhow::Result<ScanVerdict> {
+        let mut file = fs::File::open(path).await?;
+        #[cfg(unix)]
+        if self.addr.starts_with('/') {
+            let mut stream = tokio::net::UnixStream::connect(&self.addr).await?;
+            return instream(&mut stream, &mut file).await;
+        }
+        let mut stream = tokio::net::TcpStream::connect(&self.addr).await?;
+        instream(&mut stream, &mut file).await
+    }
+}
+
+impl VirusScanner for ClamAvScanner {
+    fn scan<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<ScanVerdict>> + Send + 'a>> {
+        Box::pin(async move {
+            tokio::time::timeout(SCAN_TIMEOUT, self.scan_file(path))
+                .await
+                .map_err(|_| anyhow::anyhow!("Virus scan timed out"))?
+        })
+    }
+}
+
+/// Stream `file` to clamd as length-prefixed chunks and read back the verdict
+async fn instream<S, R>(stream: &mut S, file: &mut R) -> anyhow::Result<ScanVerdict>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    stream.write_all(b"zINSTREAM\0").await?;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        stream.write_all(&(n as u32).to_be_bytes()).await?;
+        stream.write_all(&buf[..n]).await?;
+    }
+    // A zero-length chunk ends the stream
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+
+    let mut reply = Vec::new();
+    let mut byte = [0u8; 1];
+    while stream.read(&mut byte).await? == 1 && byte[0] != 0 && reply.len() < MAX_REPLY_LEN {
+        reply.push(byte[0]);
+    }
+    parse_reply(&String::from_utf8_lossy(&reply))
+}
+
+/// Parse a clamd reply such as `stream: OK` or `stream: Eicar-Signature FOUND`
+fn parse_reply(reply: &str) -> anyhow::Result<ScanVerdict> {
+    let result = reply.trim().strip_prefix("stream:").unwrap_or(reply).trim();
+    if result == "OK" {
+        Ok(ScanVerdict::Clean)
+    } else if let Some(signature) = result.strip_suffix("FOUND") {
+        Ok(ScanVerdict::Infected(signature.trim().to_string()))
+    } else {
+        anyhow::bail!("Unexpected clamd reply: {}", reply.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("stream: OK").unwrap(), ScanVerdict::Clean);
+        assert_eq!(
+            parse_reply("stream: Eicar-Test-Signature FOUND").unwrap(),
+            ScanVerdict::Infected("Eicar-Test-Signature".to_string())
+        );
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR").is_err());
+        assert!(parse_reply("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instream_frames_file_and_reads_verdict() {
+        let (mut client, mut daemon) = tokio::io::duplex(1024);
+        let fake_clamd = tokio::spawn(async move {
+            let mut command = [0u8; 10];
+            daemon.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+
+            let mut received = Vec::new();
+            loop {
+                let len = daemon.read_u32().await.unwrap() as usize;
+                if len == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; len];
+                daemon.read_exact(&mut chunk).await.unwrap();
+                received.extend_from_slice(&chunk);
+            }
+            daemon.write_all(b"stream: OK\0").await.unwrap();
+            received
+        });
+
+        let mut file: &[u8] = b"harmless bytes";
+        let verdict = instream(&mut client, &mut file).await.unwrap();
+
+        assert_eq!(verdict, ScanVerdict::Clean);
+        assert_eq!(fake_clamd.await.unwrap(), b"harmless bytes");
+    }
+}
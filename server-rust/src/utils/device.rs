@@ -37,6 +37,17 @@ pub fn detect_device_type(user_agent: &str) -> String {
     "unknown".to_string()
 }
 
+/// Normalize a device type (UA-detected or client-provided) into the category
+/// clients render an icon for: `mobile`, `tablet`, `desktop` or `unknown`.
+pub fn device_category(device_type: &str) -> &'static str {
+    match device_type.trim().to_lowercase().as_str() {
+        "mobile" | "phone" | "iphone" | "android" | "ios" => "mobile",
+        "tablet" | "ipad" => "tablet",
+        "desktop" | "laptop" | "pc" | "mac" | "windows" | "linux" => "desktop",
+        _ => "unknown",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +117,19 @@ mod tests {
         let ua = "Mozilla/5.0 (iPad; CPU OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1";
         assert_eq!(detect_device_type(ua), "tablet");
     }
+
+    #[test]
+    fn test_device_category_normalizes() {
+        assert_eq!(device_category("mobile"), "mobile");
+        assert_eq!(device_category("iPhone"), "mobile");
+        assert_eq!(device_category(" Tablet "), "tablet");
+        assert_eq!(device_category("laptop"), "desktop");
+        assert_eq!(device_category("toaster"), "unknown");
+        assert_eq!(
+            device_category(&detect_device_type(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36"
+            )),
+            "desktop"
+        );
+    }
 }
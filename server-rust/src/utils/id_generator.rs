@@ -34,6 +34,29 @@ pub fn generate_share_id() -> String {
         .collect()
 }
 
+/// Generate an opaque reconnection session token (32 alphanumeric characters)
+pub fn generate_session_token() -> String {
+    use rand::Rng;
+    rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Length of share short link codes (`/s/{code}`)
+pub const SHORT_CODE_LEN: usize = 6;
+
+/// Generate an opaque short link code (mixed-case alphanumeric, shorter than a share ID)
+pub fn generate_short_code() -> String {
+    use rand::Rng;
+    const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut rng = rand::rng();
+    (0..SHORT_CODE_LEN)
+        .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +104,19 @@ mod tests {
         assert!(id.len() >= 8 && id.len() <= 10);
         assert!(id.chars().all(|c| c.is_ascii_alphanumeric()));
     }
+
+    #[test]
+    fn test_generate_short_code() {
+        let code = generate_short_code();
+        assert_eq!(code.len(), SHORT_CODE_LEN);
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn test_generate_session_token() {
+        let token = generate_session_token();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(token, generate_session_token());
+    }
 }
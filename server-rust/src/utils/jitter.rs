@@ -0,0 +1,62 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Upper bound for jitter so an interval can never collapse to zero
+pub const MAX_JITTER_PERCENT: u32 = 50;
+
+/// Spread of `percent`% (capped at `MAX_JITTER_PERCENT`) of `base`, in seconds
+fn spread_secs(base: Duration, percent: u32) -> f64 {
+    base.as_secs_f64() * f64::from(percent.min(MAX_JITTER_PERCENT)) / 100.0
+}
+
+/// `base` shifted by a random offset within ±`percent`%. Returns `base` unchanged when
+/// `percent` is 0, so periodic tasks keep a fixed schedule unless jitter is enabled.
+pub fn jittered(base: Duration, percent: u32) -> Duration {
+    let spread = spread_secs(base, percent);
+    if spread <= 0.0 {
+        return base;
+    }
+    let offset = rand::rng().random_range(-spread..=spread);
+    Duration::from_secs_f64(base.as_secs_f64() + offset)
+}
+
+/// Random delay in `[0, percent% of base]` before the first run of a periodic task
+pub fn initial_delay(base: Duration, percent: u32) -> Duration {
+    let spread = spread_secs(base, percent);
+    if spread <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(rand::rng().random_range(0.0..=spread))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jittered_stays_within_bounds() {
+        let base = Duration::from_secs(600);
+        for _ in 0..1000 {
+            let next = jittered(base, 10);
+            assert!(next >= Duration::from_secs(540), "{:?} below bound", next);
+            assert!(next <= Duration::from_secs(660), "{:?} above bound", next);
+        }
+    }
+
+    #[test]
+    fn test_jitter_percent_is_capped() {
+        let base = Duration::from_secs(60);
+        for _ in 0..1000 {
+            let next = jittered(base, 500);
+            assert!(next >= Duration::from_secs(30) && next <= Duration::from_secs(90));
+            assert!(initial_delay(base, 500) <= Duration::from_secs(30));
+        }
+    }
+
+    #[test]
+    fn test_zero_jitter_keeps_fixed_schedule() {
+        let base = Duration::from_secs(60);
+        assert_eq!(jittered(base, 0), base);
+        assert_eq!(initial_delay(base, 0), Duration::ZERO);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tracks failed password attempts per key and locks the key out after too many failures.
+///
+/// A key is typically `"<resource>:<client identity>"` (room + fingerprint, share + IP).
+/// Failures are counted within a rolling window equal to the lockout duration; once
+/// `max_attempts` failures accumulate, the key is locked until the lockout expires.
+pub struct PasswordLockout {
+    entries: Mutex<HashMap<String, AttemptEntry>>,
+    max_attempts: u32,
+    lockout: Duration,
+}
+
+struct AttemptEntry {
+    failures: u32,
+    first_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl PasswordLockout {
+    /// Create a tracker. `max_attempts == 0` disables lockout entirely.
+    pub fn new(max_attempts: u32, lockout: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_attempts,
+            lockout,
+        }
+    }
+
+    /// Load limits from `<PREFIX>_MAX_ATTEMPTS` and `<PREFIX>_LOCKOUT_SECS`
+    pub fn from_env(prefix: &str, default_max_attempts: u32, default_lockout_secs: u64) -> Self {
+        let max_attempts = std::env::var(format!("{}_MAX_ATTEMPTS", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_max_attempts);
+        let lockout_secs = std::env::var(format!("{}_LOCKOUT_SECS", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_lockout_secs);
+        Self::new(max_attempts, Duration::from_secs(lockout_secs))
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn lockout_duration(&self) -> Duration {
+        self.lockout
+    }
+
+    /// Check whether the key is currently locked out
+    pub fn is_locked(&self, key: &str) -> bool {
+        self.remaining_lockout(key).is_some()
+    }
+
+    /// Remaining lockout time for the key, if locked
+    pub fn remaining_lockout(&self, key: &str) -> Option<Duration> {
+        if self.max_attempts == 0 {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let entry = entries.get(key)?;
+        match entry.locked_until {
+            Some(until) if now < until => Some(until - now),
+            Some(_) => {
+                // Lock expired, start over with a clean slate
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Record a failed attempt. Returns true if the key is now locked out.
+    pub fn record_failure(&self, key: &str) -> bool {
+        if self.max_attempts == 0 {
+            return false;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let entry = entries.entry(key.to_string()).or_insert(AttemptEntry {
+            failures: 0,
+            first_failure: now,
+            locked_until: None,
+        });
+
+        // Failures outside the window no longer count
+        if entry.locked_until.is_none() && now.duration_since(entry.first_failure) >= self.lockout {
+            entry.failures = 0;
+            entry.first_failure = now;
+        }
+
+        entry.failures += 1;
+        if entry.failures >= self.max_attempts {
+            entry.locked_until = Some(now + self.lockout);
+            return true;
+        }
+        false
+    }
+
+    /// Clear failures for the key (called after a successful authentication)
+    pub fn reset(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(key);
+    }
+
+    /// Drop entries whose lock or failure window has expired
+    pub fn cleanup(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, entry| match entry.locked_until {
+            Some(until) => now < until,
+            None => now.duration_since(entry.first_failure) < self.lockout,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locks_after_max_failures() {
+        let lockout = PasswordLockout::new(3, Duration::from_secs(60));
+        assert!(!lockout.record_failure("room:fp"));
+        assert!(!lockout.record_failure("room:fp"));
+        assert!(!lockout.is_locked("room:fp"));
+        assert!(lockout.record_failure("room:fp"));
+        assert!(lockout.is_locked("room:fp"));
+        // Other keys are unaffected
+        assert!(!lockout.is_locked("room:other"));
+    }
+
+    #[test]
+    fn test_unlocks_after_window() {
+        let lockout = PasswordLockout::new(1, Duration::from_millis(50));
+        assert!(lockout.record_failure("key"));
+        assert!(lockout.is_locked("key"));
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(!lockout.is_locked("key"));
+    }
+
+    #[test]
+    fn test_reset_clears_failures() {
+        let lockout = PasswordLockout::new(2, Duration::from_secs(60));
+        lockout.record_failure("key");
+        lockout.reset("key");
+        assert!(!lockout.record_failure("key"));
+        assert!(!lockout.is_locked("key"));
+    }
+
+    #[test]
+    fn test_zero_max_attempts_disables_lockout() {
+        let lockout = PasswordLockout::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            assert!(!lockout.record_failure("key"));
+        }
+        assert!(!lockout.is_locked("key"));
+    }
+}
@@ -0,0 +1,161 @@
+/// What to do with a message that contains a filtered keyword
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterAction {
+    /// Reject the whole message
+    Reject,
+    /// Replace each matched keyword with `*`
+    Mask,
+}
+
+/// Optional keyword filter for text messages (off when no words are configured).
+///
+/// Words come from `MESSAGE_FILTER_WORDS` (comma-separated) and/or `MESSAGE_FILTER_FILE`
+/// (one word per line, `#` comments allowed). `MESSAGE_FILTER_ACTION=reject|mask` picks
+/// the behavior (default `mask`). Matching is case-insensitive.
+#[derive(Debug, Clone)]
+pub struct MessageFilter {
+    words: Vec<Vec<char>>, // lowercased
+    action: FilterAction,
+}
+
+impl MessageFilter {
+    pub fn new<S: AsRef<str>>(words: &[S], action: FilterAction) -> Self {
+        let words = words
+            .iter()
+            .map(|w| w.as_ref().trim())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.chars().flat_map(char::to_lowercase).collect())
+            .collect();
+        Self { words, action }
+    }
+
+    pub fn from_env() -> Self {
+        let mut words: Vec<String> = std::env::var("MESSAGE_FILTER_WORDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|w| w.to_string())
+            .collect();
+
+        if let Ok(path) = std::env::var("MESSAGE_FILTER_FILE") {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => words.extend(
+                    content
+                        .lines()
+                        .filter(|l| !l.trim_start().starts_with('#'))
+                        .map(|l| l.to_string()),
+                ),
+                Err(e) => tracing::warn!("Failed to read MESSAGE_FILTER_FILE {}: {}", path, e),
+            }
+        }
+
+        let action = match std::env::var("MESSAGE_FILTER_ACTION")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "reject" => FilterAction::Reject,
+            _ => FilterAction::Mask,
+        };
+
+        let filter = Self::new(&words, action);
+        if filter.is_enabled() {
+            tracing::info!(
+                "Message filter enabled: {} words, action={:?}",
+                filter.words.len(),
+                filter.action
+            );
+        }
+        filter
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.words.is_empty()
+    }
+
+    /// Apply the filter. Returns the (possibly masked) content, or an error in reject mode.
+    pub fn apply(&self, content: &str) -> Result<String, &'static str> {
+        if !self.is_enabled() {
+            return Ok(content.to_string());
+        }
+
+        let chars: Vec<char> = content.chars().collect();
+        let mut masked = vec![false; chars.len()];
+        let mut found = false;
+
+        for start in 0..chars.len() {
+            for word in &self.words {
+                if let Some(len) = match_at(&chars[start..], word) {
+                    found = true;
+                    masked[start..start + len]
+                        .iter_mut()
+                        .for_each(|m| *m = true);
+                }
+            }
+        }
+
+        if !found {
+            return Ok(content.to_string());
+        }
+        match self.action {
+            FilterAction::Reject => Err("Message contains blocked content"),
+            FilterAction::Mask => Ok(chars
+                .iter()
+                .zip(masked)
+                .map(|(c, m)| if m { '*' } else { *c })
+                .collect()),
+        }
+    }
+}
+
+/// Case-insensitive prefix match; returns the number of original chars matched
+fn match_at(text: &[char], word: &[char]) -> Option<usize> {
+    let mut wi = 0;
+    for (consumed, c) in text.iter().enumerate() {
+        if wi == word.len() {
+            return Some(consumed);
+        }
+        for lc in c.to_lowercase() {
+            if word.get(wi) != Some(&lc) {
+                return None;
+            }
+            wi += 1;
+        }
+        if wi == word.len() {
+            return Some(consumed + 1);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_mode_blocks_flagged_message() {
+        let filter = MessageFilter::new(&["badword"], FilterAction::Reject);
+        assert!(filter.apply("this has a BadWord in it").is_err());
+    }
+
+    #[test]
+    fn test_mask_mode_replaces_matches() {
+        let filter = MessageFilter::new(&["darn", "heck"], FilterAction::Mask);
+        assert_eq!(
+            filter.apply("Darn it, what the heck").unwrap(),
+            "**** it, what the ****"
+        );
+    }
+
+    #[test]
+    fn test_clean_message_passes_untouched() {
+        let filter = MessageFilter::new(&["badword"], FilterAction::Reject);
+        assert_eq!(filter.apply("hello world").unwrap(), "hello world");
+    }
+
+    #[test]
+    fn test_disabled_without_words() {
+        let filter = MessageFilter::new::<&str>(&[], FilterAction::Reject);
+        assert!(!filter.is_enabled());
+        assert_eq!(filter.apply("anything goes").unwrap(), "anything goes");
+    }
+}
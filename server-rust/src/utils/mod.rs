@@ -1,11 +1,30 @@
 pub mod device;
 pub mod id_generator;
+pub mod jitter;
+pub mod lockout;
+pub mod message_filter;
+pub mod redact;
 pub mod sanitize;
+pub mod signing;
+pub mod stream_pool;
+pub mod throttle;
 pub mod validation;
 
-pub use device::detect_device_type;
+pub use device::{detect_device_type, device_category};
 pub use id_generator::{
-    generate_message_id, generate_share_id, generate_user_id, generate_user_id_from_fingerprint,
+    generate_message_id, generate_session_token, generate_share_id, generate_short_code,
+    generate_user_id, generate_user_id_from_fingerprint,
+};
+pub use lockout::PasswordLockout;
+pub use message_filter::{FilterAction, MessageFilter};
+pub use redact::{log_redaction_enabled, mask_fingerprint, mask_ip, redact_fingerprint, redact_ip};
+pub use sanitize::{
+    DEFAULT_MAX_FILENAME_LEN, is_safe_url, normalize_filename, prepare_message_content,
+    sanitize_message_content,
+};
+pub use signing::ShareUrlSigner;
+pub use stream_pool::{GuardedReader, StreamGuard, StreamPool};
+pub use throttle::{ThrottledReader, throttle_download};
+pub use validation::{
+    normalize_room_key, validate_download_name, validate_message_content, validate_room_key,
 };
-pub use sanitize::sanitize_message_content;
-pub use validation::validate_room_key;
@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+
+use sha2::{Digest, Sha256};
+
+/// LOG_REDACT=true masks IPs and fingerprints in log output
+static LOG_REDACT: LazyLock<bool> = LazyLock::new(|| {
+    std::env::var("LOG_REDACT")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+});
+
+/// Whether log sites should pass IPs and fingerprints through `redact_*`
+pub fn log_redaction_enabled() -> bool {
+    *LOG_REDACT
+}
+
+/// Mask the host part of an IP: `203.0.113.7` -> `203.0.113.x`,
+/// IPv6 keeps its first three groups. Anything unparseable is hashed.
+pub fn mask_ip(ip: &str) -> String {
+    match ip.trim().parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.x", a, b, c)
+        }
+        Ok(IpAddr::V6(v6)) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::x", s[0], s[1], s[2])
+        }
+        Err(_) => short_hash("ip", ip),
+    }
+}
+
+/// Replace a fingerprint with a short stable hash, so log lines can still be
+/// correlated without exposing the identifier
+pub fn mask_fingerprint(fingerprint: &str) -> String {
+    short_hash("fp", fingerprint)
+}
+
+fn short_hash(prefix: &str, value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let hex: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}#{}", prefix, hex)
+}
+
+/// `ip` as it should appear in logs
+pub fn redact_ip(ip: &str) -> Cow<'_, str> {
+    redact_with(log_redaction_enabled(), ip, mask_ip)
+}
+
+/// `fingerprint` as it should appear in logs
+pub fn redact_fingerprint(fingerprint: &str) -> Cow<'_, str> {
+    redact_with(log_redaction_enabled(), fingerprint, mask_fingerprint)
+}
+
+fn redact_with(enabled: bool, value: &str, mask: fn(&str) -> String) -> Cow<'_, str> {
+    if enabled {
+        Cow::Owned(mask(value))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_ip() {
+        assert_eq!(mask_ip("203.0.113.77"), "203.0.113.x");
+        assert_eq!(mask_ip("2001:db8:85a3::8a2e:370:7334"), "2001:db8:85a3::x");
+        let unknown = mask_ip("unknown");
+        assert!(unknown.starts_with("ip#"));
+        assert_ne!(unknown, "unknown");
+    }
+
+    #[test]
+    fn test_mask_fingerprint_is_stable_and_hides_value() {
+        let masked = mask_fingerprint("abcdef0123456789");
+        assert_eq!(masked, mask_fingerprint("abcdef0123456789"));
+        assert_ne!(masked, mask_fingerprint("abcdef0123456788"));
+        assert!(!masked.contains("abcdef"));
+    }
+
+    #[test]
+    fn test_redaction_is_opt_in() {
+        assert_eq!(redact_with(false, "203.0.113.77", mask_ip), "203.0.113.77");
+        assert_eq!(redact_with(true, "203.0.113.77", mask_ip), "203.0.113.x");
+        // LOG_REDACT is unset in the unit test binary
+        assert_eq!(redact_ip("203.0.113.77"), "203.0.113.77");
+        assert_eq!(redact_fingerprint("abcdef0123456789"), "abcdef0123456789");
+    }
+}
@@ -9,10 +9,138 @@ pub fn sanitize_message_content(content: &str) -> String {
         .replace('\'', "&#x27;")
 }
 
+/// Validate and prepare text message content for storage.
+/// Encrypted payloads are opaque ciphertext and are stored as-is (only the size
+/// limit applies); plain text is HTML-escaped.
+pub fn prepare_message_content(content: &str, encrypted: bool) -> Result<String, &'static str> {
+    super::validation::validate_message_content(content)?;
+    if encrypted {
+        Ok(content.to_string())
+    } else {
+        Ok(sanitize_message_content(content))
+    }
+}
+
+/// Whether text is a single link with a scheme safe to make clickable (http/https/mailto).
+/// `javascript:`, `data:` and other schemes are rejected.
+pub fn is_safe_url(content: &str) -> bool {
+    let url = content.trim();
+    if url.is_empty() || url.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme) && lower.len() > scheme.len())
+}
+
+/// Default cap on stored display filenames, in characters (MAX_FILENAME_LEN)
+pub const DEFAULT_MAX_FILENAME_LEN: usize = 255;
+
+/// Bidirectional control characters (e.g. RTL override) that can disguise an extension
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Normalize an uploaded filename for display and storage: control and bidi characters
+/// are removed, surrounding whitespace is trimmed, and names longer than `max_len`
+/// characters are shortened from the stem so the extension survives.
+pub fn normalize_filename(name: &str, max_len: usize) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|&c| !c.is_control() && !is_bidi_control(c))
+        .collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() {
+        return "file".to_string();
+    }
+    if cleaned.chars().count() <= max_len {
+        return cleaned.to_string();
+    }
+
+    let (stem, ext) = match cleaned.rfind('.') {
+        Some(dot) if dot > 0 => cleaned.split_at(dot),
+        _ => (cleaned, ""),
+    };
+    let ext_len = ext.chars().count();
+    if ext_len >= max_len {
+        return cleaned.chars().take(max_len).collect();
+    }
+    let stem: String = stem.chars().take(max_len - ext_len).collect();
+    format!("{}{}", stem.trim_end(), ext)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_safe_url() {
+        assert!(is_safe_url("https://example.com/a?b=c"));
+        assert!(is_safe_url(" mailto:me@example.com "));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url("JaVaScRiPt:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<b>x</b>"));
+        assert!(!is_safe_url("https://example.com and more"));
+    }
+
+    #[test]
+    fn test_prepare_encrypted_content_bypasses_sanitization() {
+        let ciphertext = "v1:<AbC+/=>&'\"";
+        assert_eq!(
+            prepare_message_content(ciphertext, true).unwrap(),
+            ciphertext
+        );
+    }
+
+    #[test]
+    fn test_normalize_filename_strips_control_chars() {
+        assert_eq!(normalize_filename("re\u{0}port\n.txt", 255), "report.txt");
+        // RTL override that would render "invoice_txt.exe" as "invoice_exe.txt"
+        assert_eq!(
+            normalize_filename("invoice_\u{202E}txt.exe", 255),
+            "invoice_txt.exe"
+        );
+        assert_eq!(normalize_filename(" \u{7}\t ", 255), "file");
+    }
+
+    #[test]
+    fn test_normalize_filename_truncates_keeping_extension() {
+        let name = format!("{}.pdf", "a".repeat(300));
+        let normalized = normalize_filename(&name, 20);
+        assert_eq!(normalized, format!("{}.pdf", "a".repeat(16)));
+
+        // Counted in characters, not bytes
+        let name = format!("{}.txt", "文".repeat(30));
+        assert_eq!(normalize_filename(&name, 10).chars().count(), 10);
+        assert!(normalize_filename(&name, 10).ends_with(".txt"));
+    }
+
+    #[test]
+    fn test_normalize_filename_passes_normal_names() {
+        assert_eq!(normalize_filename("photo 2024.jpg", 255), "photo 2024.jpg");
+        assert_eq!(normalize_filename(".env", 255), ".env");
+        assert_eq!(normalize_filename("报告.docx", 255), "报告.docx");
+    }
+
+    #[test]
+    fn test_prepare_plain_content_is_sanitized() {
+        assert_eq!(
+            prepare_message_content("<b>hi</b>", false).unwrap(),
+            "&lt;b&gt;hi&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn test_prepare_content_enforces_size_limit() {
+        let oversized = "x".repeat(crate::utils::validation::MAX_MESSAGE_LENGTH + 1);
+        assert!(prepare_message_content(&oversized, true).is_err());
+        assert!(prepare_message_content(&oversized, false).is_err());
+    }
+
     #[test]
     fn test_sanitize_script_tag() {
         let input = "<script>alert('xss')</script>";
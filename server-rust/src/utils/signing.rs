@@ -0,0 +1,150 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs public share URLs so share IDs can't be enumerated (SIGNED_SHARE_URLS).
+/// A signature covers the share ID and its expiry, carried as `?exp=&sig=`.
+#[derive(Clone)]
+pub struct ShareUrlSigner {
+    key: Vec<u8>,
+}
+
+impl std::fmt::Debug for ShareUrlSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShareUrlSigner").finish_non_exhaustive()
+    }
+}
+
+impl ShareUrlSigner {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// Signer when SIGNED_SHARE_URLS=true, keyed by SHARE_URL_SECRET
+    pub fn from_env() -> Option<Self> {
+        Self::from_config(
+            std::env::var("SIGNED_SHARE_URLS").ok().as_deref(),
+            std::env::var("SHARE_URL_SECRET").ok().as_deref(),
+        )
+    }
+
+    /// Without a secret a random key is used, so signed links stop working on restart
+    pub fn from_config(enabled: Option<&str>, secret: Option<&str>) -> Option<Self> {
+        if enabled.is_none_or(|v| v.to_lowercase() != "true") {
+            return None;
+        }
+        match secret.filter(|s| !s.is_empty()) {
+            Some(secret) => Some(Self::new(secret.as_bytes())),
+            None => {
+                use rand::rngs::OsRng;
+                use rand::{RngCore, TryRngCore};
+                tracing::warn!(
+                    "SIGNED_SHARE_URLS is on without SHARE_URL_SECRET, using a random key"
+                );
+                let mut key = vec![0u8; 32];
+                OsRng.unwrap_err().fill_bytes(&mut key);
+                Some(Self::new(key))
+            }
+        }
+    }
+
+    /// HMAC over `share_id` and its expiry (unix seconds)
+    fn mac(&self, share_id: &str, expires_at: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(format!("{}:{}", share_id, expires_at).as_bytes());
+        mac
+    }
+
+    /// Hex signature over `share_id` and its expiry (unix seconds)
+    pub fn sign(&self, share_id: &str, expires_at: i64) -> String {
+        self.mac(share_id, expires_at)
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Query string (`exp=..&sig=..`) to append to a share URL
+    pub fn query(&self, share_id: &str, expires_at: i64) -> String {
+        format!("exp={}&sig={}", expires_at, self.sign(share_id, expires_at))
+    }
+
+    /// Whether `sig` is valid for `share_id` and `exp`, and `exp` hasn't passed
+    pub fn verify(&self, share_id: &str, exp: Option<i64>, sig: Option<&str>) -> bool {
+        let (Some(exp), Some(sig)) = (exp, sig) else {
+            return false;
+        };
+        if exp < chrono::Utc::now().timestamp() {
+            return false;
+        }
+        let Some(sig) = decode_hex(sig) else {
+            return false;
+        };
+        // Constant-time comparison
+        self.mac(share_id, exp).verify_slice(&sig).is_ok()
+    }
+}
+
+/// Bytes of a lowercase or uppercase hex string
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_is_hex_hmac_sha256_of_id_and_expiry() {
+        // HMAC-SHA256(key "secret", "abcd1234:1700000000")
+        assert_eq!(
+            ShareUrlSigner::new("secret").sign("abcd1234", 1_700_000_000),
+            "7a5ddccd0f44ea0d15adf47a958549bdd2c47f625a13bfb1ee9ea666a2400f0e"
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let signer = ShareUrlSigner::new("secret");
+        let exp = chrono::Utc::now().timestamp() + 3600;
+        let sig = signer.sign("abcd1234", exp);
+
+        assert!(signer.verify("abcd1234", Some(exp), Some(&sig)));
+        assert!(!signer.verify("abcd1235", Some(exp), Some(&sig)));
+        assert!(!signer.verify("abcd1234", Some(exp + 1), Some(&sig)));
+        assert!(!signer.verify("abcd1234", Some(exp), Some(&sig[1..])));
+        assert!(!signer.verify("abcd1234", Some(exp), Some(&sig[2..])));
+        assert!(!signer.verify("abcd1234", Some(exp), Some(&format!("{}zz", &sig[2..]))));
+        assert!(!signer.verify("abcd1234", Some(exp), None));
+        assert!(!signer.verify("abcd1234", None, Some(&sig)));
+        assert!(!ShareUrlSigner::new("other").verify("abcd1234", Some(exp), Some(&sig)));
+    }
+
+    #[test]
+    fn test_verify_rejects_expired_signature() {
+        let signer = ShareUrlSigner::new("secret");
+        let exp = chrono::Utc::now().timestamp() - 1;
+        let sig = signer.sign("abcd1234", exp);
+        assert!(!signer.verify("abcd1234", Some(exp), Some(&sig)));
+    }
+
+    #[test]
+    fn test_signing_is_opt_in() {
+        assert!(ShareUrlSigner::from_config(None, Some("secret")).is_none());
+        assert!(ShareUrlSigner::from_config(Some("false"), Some("secret")).is_none());
+        assert!(ShareUrlSigner::from_config(Some("TRUE"), Some("secret")).is_some());
+        // Enabled without a secret still signs, with a random key
+        let a = ShareUrlSigner::from_config(Some("true"), None).unwrap();
+        let b = ShareUrlSigner::from_config(Some("true"), None).unwrap();
+        assert_ne!(a.sign("abcd1234", 0), b.sign("abcd1234", 0));
+    }
+}
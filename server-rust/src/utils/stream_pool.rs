@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Caps concurrent download streams, globally and per client IP.
+///
+/// Each traffic class (public shares, in-room files) gets its own pool so that
+/// saturating one can't starve the other.
+pub struct StreamPool {
+    active: AtomicUsize,
+    per_ip: Mutex<HashMap<String, usize>>,
+    max_global: usize,
+    max_per_ip: usize,
+}
+
+impl StreamPool {
+    pub fn new(max_global: usize, max_per_ip: usize) -> Self {
+        Self {
+            active: AtomicUsize::new(0),
+            per_ip: Mutex::new(HashMap::new()),
+            max_global,
+            max_per_ip,
+        }
+    }
+
+    /// Load limits from `<PREFIX>_MAX_CONCURRENT_GLOBAL` and `<PREFIX>_MAX_CONCURRENT_PER_IP`
+    pub fn from_env(prefix: &str, default_max_global: usize, default_max_per_ip: usize) -> Self {
+        let max_global = std::env::var(format!("{}_MAX_CONCURRENT_GLOBAL", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_max_global);
+        let max_per_ip = std::env::var(format!("{}_MAX_CONCURRENT_PER_IP", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_max_per_ip);
+        Self::new(max_global, max_per_ip)
+    }
+
+    /// Number of streams currently in flight
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Reserve a stream slot for `ip`; `None` when either limit is reached
+    pub fn acquire(&self, ip: &str) -> Option<StreamGuard<'_>> {
+        // Atomic check-and-increment for global counter
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (current < self.max_global).then_some(current + 1)
+            })
+            .ok()?;
+
+        // Check per-IP limit
+        let Ok(mut map) = self.per_ip.lock() else {
+            self.active.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        };
+        let count = map.entry(ip.to_string()).or_insert(0);
+        if *count >= self.max_per_ip {
+            // Rollback global counter
+            if *count == 0 {
+                map.remove(ip);
+            }
+            self.active.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+        *count += 1;
+
+        Some(StreamGuard {
+            pool: self,
+            ip: ip.to_string(),
+        })
+    }
+}
+
+/// RAII guard for a stream slot - releases it on drop
+pub struct StreamGuard<'a> {
+    pool: &'a StreamPool,
+    ip: String,
+}
+
+impl<'a> StreamGuard<'a> {
+    /// Move the slot into a download body reader, so it stays taken until the body
+    /// has been streamed (or the client went away) rather than until the handler returns
+    pub fn hold_while<R>(self, reader: R) -> GuardedReader<'a, R> {
+        GuardedReader {
+            inner: reader,
+            _guard: self,
+        }
+    }
+}
+
+/// Reader that owns a stream slot for as long as it is alive
+pub struct GuardedReader<'a, R> {
+    inner: R,
+    _guard: StreamGuard<'a>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for GuardedReader<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl Drop for StreamGuard<'_> {
+    fn drop(&mut self) {
+        self.pool.active.fetch_sub(1, Ordering::AcqRel);
+        if let Ok(mut map) = self.pool.per_ip.lock()
+            && let Some(count) = map.get_mut(&self.ip)
+        {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                map.remove(&self.ip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_ip_limit() {
+        let pool = StreamPool::new(10, 2);
+        let _a = pool.acquire("1.1.1.1").unwrap();
+        let _b = pool.acquire("1.1.1.1").unwrap();
+        assert!(pool.acquire("1.1.1.1").is_none());
+        assert!(pool.acquire("2.2.2.2").is_some());
+    }
+
+    #[test]
+    fn test_global_limit_and_release() {
+        let pool = StreamPool::new(1, 5);
+        let guard = pool.acquire("1.1.1.1").unwrap();
+        assert!(pool.acquire("2.2.2.2").is_none());
+        assert_eq!(pool.active(), 1);
+
+        drop(guard);
+        assert_eq!(pool.active(), 0);
+        assert!(pool.acquire("2.2.2.2").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_reader_releases_slot_when_dropped() {
+        use tokio::io::AsyncReadExt;
+
+        let pool = StreamPool::new(1, 1);
+        let mut reader = pool.acquire("1.1.1.1").unwrap().hold_while(&b"data"[..]);
+        assert!(pool.acquire("1.1.1.1").is_none());
+
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).await.unwrap();
+        assert_eq!(body, b"data");
+        assert_eq!(pool.active(), 1);
+
+        drop(reader);
+        assert_eq!(pool.active(), 0);
+    }
+
+    #[test]
+    fn test_saturated_pool_does_not_block_other_pool() {
+        let shares = StreamPool::new(2, 2);
+        let files = StreamPool::new(2, 2);
+        let _s1 = shares.acquire("1.1.1.1").unwrap();
+        let _s2 = shares.acquire("1.1.1.1").unwrap();
+        assert!(shares.acquire("1.1.1.1").is_none());
+
+        assert!(files.acquire("1.1.1.1").is_some());
+    }
+}
@@ -0,0 +1,107 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::{Instant, Sleep};
+
+/// Per-stream download cap in bytes/sec (MAX_DOWNLOAD_BYTES_PER_SEC_PER_STREAM, 0 = unlimited)
+static DOWNLOAD_STREAM_RATE: std::sync::LazyLock<u64> = std::sync::LazyLock::new(|| {
+    std::env::var("MAX_DOWNLOAD_BYTES_PER_SEC_PER_STREAM")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+});
+
+/// Apply the configured per-stream download cap to `reader` (unchanged when unlimited)
+pub fn throttle_download(
+    reader: Box<dyn AsyncRead + Send + Unpin>,
+) -> Box<dyn AsyncRead + Send + Unpin> {
+    match *DOWNLOAD_STREAM_RATE {
+        0 => reader,
+        rate => Box::new(ThrottledReader::new(reader, rate)),
+    }
+}
+
+/// Paces reads so the average throughput stays at or below `bytes_per_sec`.
+///
+/// After each read the reader computes when that many bytes are due and sleeps until
+/// then before the next read, so bursts are bounded by a single read buffer.
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_sec: u64,
+    started: Instant,
+    sent: u64,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<R> ThrottledReader<R> {
+    pub fn new(inner: R, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: Instant::now(),
+            sent: 0,
+            delay: None,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ThrottledReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if let Some(delay) = self.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+        }
+
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let read = (buf.filled().len() - before) as u64;
+        if read > 0 {
+            self.sent += read;
+            let due = self.started
+                + Duration::from_secs_f64(self.sent as f64 / self.bytes_per_sec as f64);
+            if due > Instant::now() {
+                self.delay = Some(Box::pin(tokio::time::sleep_until(due)));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_throttled_read_takes_minimum_time() {
+        let payload = vec![7u8; 2000];
+        let mut reader = ThrottledReader::new(payload.as_slice(), 4000);
+
+        let start = std::time::Instant::now();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+
+        assert_eq!(out, payload);
+        // 2000 bytes at 4000 B/s is due no earlier than 0.5s after the start
+        assert!(start.elapsed() >= Duration::from_millis(490));
+    }
+
+    #[tokio::test]
+    async fn test_fast_rate_does_not_delay() {
+        let payload = vec![7u8; 2000];
+        let mut reader = ThrottledReader::new(payload.as_slice(), 100 * 1024 * 1024);
+
+        let start = std::time::Instant::now();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+
+        assert_eq!(out.len(), 2000);
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+}
@@ -25,10 +25,79 @@ pub fn validate_room_key(key: &str) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Canonical form of a room key: trimmed, lowercased, with `-`/`_` separators removed,
+/// so `Team-Chat1`, `team_chat1` and `TEAMCHAT1` name the same room
+pub fn normalize_room_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Maximum text message length in characters (matching Node.js MessageContentSchema)
+pub const MAX_MESSAGE_LENGTH: usize = 50_000;
+
+/// Validate text message content length.
+pub fn validate_message_content(content: &str) -> Result<(), &'static str> {
+    if content.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err("Message content too long (max 50,000 characters)");
+    }
+    Ok(())
+}
+
+/// Maximum length of a custom download filename in characters
+pub const MAX_DOWNLOAD_NAME_LENGTH: usize = 255;
+
+/// Validate a custom download filename (e.g. a share's `download_as`).
+///
+/// Must be non-empty, at most 255 characters, and a bare filename: no path
+/// separators, no control characters, and not `.` or `..`.
+pub fn validate_download_name(name: &str) -> Result<(), &'static str> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() || trimmed == "." || trimmed == ".." {
+        return Err("Download filename cannot be empty");
+    }
+    if name.chars().count() > MAX_DOWNLOAD_NAME_LENGTH {
+        return Err("Download filename too long (max 255 characters)");
+    }
+    if name.contains(['/', '\\']) || name.chars().any(char::is_control) {
+        return Err("Download filename contains invalid characters");
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_download_name() {
+        assert!(validate_download_name("Invoice-2024.pdf").is_ok());
+        assert!(validate_download_name("报告 final.docx").is_ok());
+        assert!(validate_download_name("").is_err());
+        assert!(validate_download_name("..").is_err());
+        assert!(validate_download_name("../etc/passwd").is_err());
+        assert!(validate_download_name("a\\b.txt").is_err());
+        assert!(validate_download_name("line\nbreak.txt").is_err());
+        assert!(validate_download_name(&"a".repeat(MAX_DOWNLOAD_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_message_content_length() {
+        assert!(validate_message_content("hello").is_ok());
+        assert!(validate_message_content(&"a".repeat(MAX_MESSAGE_LENGTH)).is_ok());
+        assert!(validate_message_content(&"a".repeat(MAX_MESSAGE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_room_key() {
+        assert_eq!(normalize_room_key("Team-Chat1"), "teamchat1");
+        assert_eq!(normalize_room_key("TEAM_CHAT1"), "teamchat1");
+        assert_eq!(normalize_room_key(" team--chat1 "), "teamchat1");
+        assert_eq!(normalize_room_key("room123abc"), "room123abc");
+    }
+
     #[test]
     fn test_valid_room_key() {
         assert!(validate_room_key("abc123").is_ok());
@@ -0,0 +1,184 @@
+/// Admin Banner Tests
+///
+/// ADMIN_TOKEN and SERVER_BANNER are read from the environment, so the banner endpoint
+/// is exercised in its own test binary with both configured up front.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::admin;
+    use cloud_clipboard_server::services::socket::setup_socket_handlers;
+    use cloud_clipboard_server::services::{
+        BannerSeverity, FileManager, RoomEvent, RoomService, ServerBanner, ShareService,
+    };
+    use socketioxide::SocketIo;
+    use std::sync::{Arc, Once};
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-admin-token";
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe {
+            std::env::set_var("ADMIN_TOKEN", TOKEN);
+            std::env::set_var("SERVER_BANNER", "Maintenance tonight at 22:00");
+            std::env::set_var("SERVER_BANNER_SEVERITY", "warning");
+        });
+
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/admin", admin::router())
+            .with_state(state)
+    }
+
+    async fn post_banner(
+        app: Router,
+        token: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/admin/banner")
+            .header("x-admin-token", token)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_configured_banner_is_sent_on_connect() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        assert_eq!(
+            state.room_service.banner(),
+            Some(ServerBanner {
+                text: "Maintenance tonight at 22:00".to_string(),
+                severity: BannerSeverity::Warning,
+            })
+        );
+
+        let (socket_layer, io) = SocketIo::builder().build_layer();
+        setup_socket_handlers(
+            &io,
+            state.room_service.clone(),
+            state.file_manager.clone(),
+            state.rate_limit_metrics.clone(),
+        );
+        let app = Router::new().layer(socket_layer);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/socket.io/?EIO=4&transport=polling",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // Engine.IO polling handshake: "0{...sid...}", then connect to the "/" namespace
+        let client = reqwest::Client::new();
+        let handshake = client.get(&url).send().await.unwrap().text().await.unwrap();
+        let open: serde_json::Value = serde_json::from_str(&handshake[1..]).unwrap();
+        let url = format!("{}&sid={}", url, open["sid"].as_str().unwrap());
+        client.post(&url).body("40").send().await.unwrap();
+
+        // Packets arrive "\x1e"-separated: the connect ack, then the banner event
+        let polled = client.get(&url).send().await.unwrap().text().await.unwrap();
+        let event = polled
+            .split('\u{1e}')
+            .find_map(|packet| packet.strip_prefix("42"))
+            .expect("no event sent on connect");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(event).unwrap(),
+            serde_json::json!([
+                "serverBanner",
+                { "text": "Maintenance tonight at 22:00", "severity": "warning" }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_rebroadcasts_banner() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let mut events = state.room_service.subscribe();
+
+        let (status, json) = post_banner(
+            create_router(state.clone()),
+            TOKEN,
+            serde_json::json!({ "text": "Upgrade complete", "severity": "info" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["text"], "Upgrade complete");
+        assert_eq!(json["data"]["severity"], "info");
+
+        let Ok(RoomEvent::BannerChanged(Some(banner))) = events.try_recv() else {
+            panic!("expected a banner broadcast");
+        };
+        assert_eq!(banner.text, "Upgrade complete");
+        assert_eq!(state.room_service.banner(), Some(banner));
+
+        // Empty text clears it, broadcast as null
+        let (status, json) = post_banner(
+            create_router(state.clone()),
+            TOKEN,
+            serde_json::json!({ "text": "" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["data"].is_null());
+        assert!(matches!(
+            events.try_recv(),
+            Ok(RoomEvent::BannerChanged(None))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_banner_rejects_invalid_input_and_bad_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let mut events = state.room_service.subscribe();
+
+        let (status, json) = post_banner(
+            create_router(state.clone()),
+            TOKEN,
+            serde_json::json!({ "text": "Hi", "severity": "loud" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "INVALID_REQUEST");
+
+        let (status, _) = post_banner(
+            create_router(state.clone()),
+            "wrong-token",
+            serde_json::json!({ "text": "Hi" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        assert!(events.try_recv().is_err());
+        assert_eq!(
+            state.room_service.banner().unwrap().text,
+            "Maintenance tonight at 22:00"
+        );
+    }
+}
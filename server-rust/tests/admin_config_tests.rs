@@ -0,0 +1,125 @@
+/// Admin Config Tests
+///
+/// The config endpoint resolves settings from the environment, so it is exercised in
+/// its own test binary with known values and secrets configured up front.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::admin;
+    use cloud_clipboard_server::services::{FileManager, RoomService, ShareService};
+    use std::sync::{Arc, Once};
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "admin-token-do-not-leak";
+    const SHARE_SECRET: &str = "share-secret-do-not-leak";
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe {
+            std::env::set_var("ADMIN_TOKEN", TOKEN);
+            std::env::set_var("SIGNED_SHARE_URLS", "true");
+            std::env::set_var("SHARE_URL_SECRET", SHARE_SECRET);
+            std::env::set_var("UPLOAD_RATE_LIMIT", "7");
+            std::env::set_var("FILE_CLEANUP_INTERVAL_SECONDS", "123");
+            std::env::set_var("CLIENT_URL", "https://clip.example.com");
+            std::env::set_var("CORS_MAX_AGE", "900");
+        });
+
+        AppState::new(
+            Arc::new(RoomService::new().with_room_key_normalization(true)),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    async fn get_config(state: AppState, token: &str) -> (StatusCode, String) {
+        let app = Router::new()
+            .nest("/api/admin", admin::router())
+            .with_state(state);
+        let request = Request::builder()
+            .uri("/api/admin/config")
+            .header("x-admin-token", token)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn collect_keys(value: &serde_json::Value, keys: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    keys.push(key.clone());
+                    collect_keys(value, keys);
+                }
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect_keys(v, keys)),
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn test_config_reports_resolved_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let (status, body) = get_config(create_state(&dir), TOKEN).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let data = &json["data"];
+        assert_eq!(data["rateLimits"]["uploadMax"], 7);
+        assert_eq!(data["cleanup"]["fileCleanupIntervalSecs"], 123);
+        assert_eq!(data["files"]["maxFileSize"], 10 * 1024 * 1024);
+        assert_eq!(data["files"]["retentionHours"], 12);
+        assert_eq!(
+            data["corsOrigins"],
+            serde_json::json!(["https://clip.example.com"])
+        );
+        assert_eq!(data["corsMaxAgeSecs"], 900);
+        assert_eq!(data["rooms"]["normalizeRoomKeys"], true);
+        assert_eq!(data["features"]["signedShareUrls"], true);
+        assert_eq!(data["features"]["virusScan"], false);
+    }
+
+    #[tokio::test]
+    async fn test_config_never_includes_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let (status, body) = get_config(create_state(&dir), TOKEN).await;
+        assert_eq!(status, StatusCode::OK);
+
+        assert!(!body.contains(TOKEN));
+        assert!(!body.contains(SHARE_SECRET));
+
+        let mut keys = Vec::new();
+        collect_keys(&serde_json::from_str(&body).unwrap(), &mut keys);
+        for key in keys {
+            let lower = key.to_lowercase();
+            assert!(!lower.contains("secret"), "{}", key);
+            assert!(
+                !["admintoken", "token", "password", "encryptionkey"].contains(&lower.as_str()),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_config_requires_admin_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let (status, body) = get_config(create_state(&dir), "wrong-token").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(!body.contains("rateLimits"));
+    }
+}
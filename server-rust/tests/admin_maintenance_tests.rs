@@ -0,0 +1,169 @@
+/// Admin Maintenance Tests
+///
+/// ADMIN_TOKEN is read once per process, so POST /api/admin/maintenance is exercised
+/// in its own test binary with the token configured up front.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::admin;
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, JoinRoomRequest, RoomService, ShareService,
+    };
+    use std::sync::{Arc, Once};
+    use std::time::{Duration, SystemTime};
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-admin-token";
+
+    /// Files expire immediately (zero retention) so the sweep has something to remove
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe { std::env::set_var("ADMIN_TOKEN", TOKEN) });
+
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 0)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/admin", admin::router())
+            .with_state(state)
+    }
+
+    async fn post_maintenance(app: Router, token: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/api/admin/maintenance");
+        if let Some(token) = token {
+            builder = builder.header("x-admin-token", token);
+        }
+        let response = app
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// Two expired shares, one live; one expired file; one idle room; one stale orphan
+    async fn seed(state: &AppState, dir: &tempfile::TempDir) {
+        for (name, days) in [("a.txt", -1), ("b.txt", -1), ("c.txt", 7)] {
+            state
+                .share_service
+                .create_share(
+                    CreateShareRequest::new(name, name, 10, "room1", "user1").with_expiration(days),
+                )
+                .unwrap();
+        }
+
+        state
+            .file_manager
+            .save_file("room1", "old.txt", "text/plain", b"old")
+            .await
+            .unwrap();
+
+        state
+            .room_service
+            .join_room(JoinRoomRequest::new("idleroom1", "u1", "Alice", "s1"))
+            .unwrap();
+        state.room_service.set_user_offline("s1");
+        state
+            .room_service
+            .join_room(JoinRoomRequest::new("liveroom1", "u2", "Bob", "s2"))
+            .unwrap();
+
+        // Past the orphan grace period so the sweep may remove it
+        let orphan = dir.path().join("orphan.bin");
+        std::fs::write(&orphan, b"x").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&orphan)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(600))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_reports_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        seed(&state, &dir).await;
+
+        let (status, json) = post_maintenance(create_router(state.clone()), Some(TOKEN)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["expiredShares"], 2);
+        assert_eq!(json["data"]["expiredFiles"], 1);
+        assert_eq!(json["data"]["inactiveRooms"], 1);
+        assert_eq!(json["data"]["orphanedFiles"], 1);
+        assert!(!dir.path().join("orphan.bin").exists());
+        assert!(state.room_service.room_exists("liveroom1"));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        seed(&state, &dir).await;
+
+        let (_, first) = post_maintenance(create_router(state.clone()), Some(TOKEN)).await;
+        let (status, second) = post_maintenance(create_router(state), Some(TOKEN)).await;
+
+        assert_eq!(first["data"]["expiredShares"], 2);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            second["data"],
+            serde_json::json!({
+                "expiredShares": 0,
+                "expiredFiles": 0,
+                "inactiveRooms": 0,
+                "orphanedFiles": 0,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_runs_never_double_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        seed(&state, &dir).await;
+
+        // A manual run racing the background sweeps
+        let (report, shares, files, rooms) = tokio::join!(
+            admin::run_maintenance(&state),
+            async { state.share_service.cleanup_expired_shares().len() },
+            state.file_manager.cleanup_expired_files(),
+            async { state.room_service.cleanup_inactive_rooms().len() },
+        );
+
+        assert_eq!(report.expired_shares + shares, 2);
+        assert_eq!(report.expired_files + files.len(), 1);
+        assert_eq!(report.inactive_rooms + rooms, 1);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_requires_admin_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+
+        let (status, json) = post_maintenance(create_router(state), Some("wrong")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(json["success"], false);
+    }
+}
@@ -0,0 +1,180 @@
+/// Admin Room Provisioning Tests
+///
+/// ADMIN_TOKEN is read once per process, so admin-authorized routes are exercised
+/// in their own test binary with the token configured up front.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::rooms;
+    use cloud_clipboard_server::services::{
+        FileManager, JoinRoomRequest, RoomService, ShareService,
+    };
+    use std::sync::{Arc, Once};
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-admin-token";
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe { std::env::set_var("ADMIN_TOKEN", TOKEN) });
+
+        AppState::new(
+            Arc::new(RoomService::new().with_room_creation(false)),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/rooms", rooms::router())
+            .with_state(state)
+    }
+
+    async fn post_room(
+        app: Router,
+        token: Option<&str>,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/api/rooms")
+            .header("content-type", "application/json");
+        if let Some(token) = token {
+            builder = builder.header("x-admin-token", token);
+        }
+        let response = app
+            .oneshot(builder.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_admin_creates_open_room_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let room_service = state.room_service.clone();
+
+        let (status, json) = post_room(
+            create_router(state),
+            Some(TOKEN),
+            serde_json::json!({
+                "roomKey": "openroom1",
+                "config": { "maxMessages": 50, "persistMessages": false }
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["key"], "openroom1");
+        assert_eq!(json["data"]["hasPassword"], false);
+        assert_eq!(json["data"]["config"]["maxMessages"], 50);
+        assert_eq!(json["data"]["config"]["persistMessages"], false);
+
+        // Joinable even though self-service creation is disabled
+        assert!(
+            room_service
+                .join_room(JoinRoomRequest::new("openroom1", "u1", "Alice", "s1"))
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_create_rejects_unsupported_rate_limit_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let room_service = state.room_service.clone();
+
+        let (status, json) = post_room(
+            create_router(state),
+            Some(TOKEN),
+            serde_json::json!({
+                "roomKey": "badconfig1",
+                "config": { "maxMessages": 50, "rateLimits": { "joinRoom": 5 } }
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "INVALID_REQUEST");
+        assert!(!room_service.room_exists("badconfig1"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_create_at_capacity_reports_capacity_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = create_state(&dir);
+        state.room_service = Arc::new(RoomService::new().with_max_total_rooms(1));
+        state
+            .room_service
+            .admin_create_room("fullroom1", None, None)
+            .unwrap();
+
+        let (status, json) = post_room(
+            create_router(state),
+            Some(TOKEN),
+            serde_json::json!({ "roomKey": "fullroom2" }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["code"], "SERVER_AT_CAPACITY");
+    }
+
+    #[tokio::test]
+    async fn test_admin_creates_password_room() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let room_service = state.room_service.clone();
+
+        let (status, json) = post_room(
+            create_router(state),
+            Some(TOKEN),
+            serde_json::json!({ "roomKey": "lockedroom1", "password": "s3cret" }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["hasPassword"], true);
+        assert!(
+            room_service
+                .verify_room_password("lockedroom1", "s3cret")
+                .unwrap()
+        );
+        assert!(
+            room_service
+                .join_room(JoinRoomRequest::new("lockedroom1", "u1", "Alice", "s1"))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_create_rejects_unauthorized() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let room_service = state.room_service.clone();
+        let app = create_router(state);
+
+        let body = serde_json::json!({ "roomKey": "sneakyroom1" });
+        let (status, _) = post_room(app.clone(), None, body.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = post_room(app, Some("wrong-token"), body).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        assert!(!room_service.room_exists("sneakyroom1"));
+    }
+}
@@ -0,0 +1,43 @@
+/// API JSON Rendering Tests
+///
+/// Verifies the PRETTY_JSON toggle switches between indented and single-line bodies.
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+    use cloud_clipboard_server::routes::{ApiJson, ApiResponse};
+    use serde_json::json;
+
+    fn payload() -> ApiJson<ApiResponse<serde_json::Value>> {
+        ApiJson(ApiResponse {
+            success: true,
+            message: Some("ok".to_string()),
+            data: Some(json!({ "roomKey": "room123abc", "userCount": 2 })),
+            code: None,
+        })
+    }
+
+    #[test]
+    fn test_pretty_and_compact_render_same_payload() {
+        let pretty = String::from_utf8(payload().render(true).unwrap()).unwrap();
+        let compact = String::from_utf8(payload().render(false).unwrap()).unwrap();
+
+        assert!(pretty.contains('\n'));
+        assert!(pretty.contains("\n  \"success\": true"));
+        assert!(!compact.contains('\n'));
+
+        let pretty_value: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        let compact_value: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        assert_eq!(pretty_value, compact_value);
+    }
+
+    #[tokio::test]
+    async fn test_default_response_is_compact_json() {
+        let response = payload().into_response();
+        assert_eq!(response.headers()["content-type"], "application/json");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!body.contains(&b'\n'));
+    }
+}
@@ -0,0 +1,111 @@
+// Download Bandwidth Limit Tests
+//
+// The public download route uses a process-wide tracker configured from
+// MAX_DOWNLOAD_BYTES_PER_MINUTE, so these live in their own test binary.
+
+mod bandwidth_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share::{self, BandwidthTracker};
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_tracker_rejects_with_seconds_until_reset() {
+        let tracker = BandwidthTracker::with_limit(100);
+        assert!(tracker.check_and_record("1.2.3.4", 80).is_ok());
+
+        let retry_after = tracker.check_and_record("1.2.3.4", 30).unwrap_err();
+        assert!((1..=60).contains(&retry_after));
+
+        // Other clients have their own window
+        assert!(tracker.check_and_record("5.6.7.8", 100).is_ok());
+    }
+
+    #[test]
+    fn test_tracker_allows_again_after_window_reset() {
+        let tracker = BandwidthTracker::with_limit(100).with_window(Duration::from_millis(50));
+        assert!(tracker.check_and_record("1.2.3.4", 100).is_ok());
+        assert!(tracker.check_and_record("1.2.3.4", 1).is_err());
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(tracker.check_and_record("1.2.3.4", 100).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_download_over_limit_returns_retry_after() {
+        // SAFETY: the only test in this binary that reads the environment
+        unsafe { std::env::set_var("MAX_DOWNLOAD_BYTES_PER_MINUTE", "10") };
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        let info = state
+            .file_manager
+            .save_file("room123abc", "notes.txt", "text/plain", b"12345678")
+            .await
+            .unwrap();
+        let (share, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new(
+                info.path.to_string_lossy(),
+                info.filename.clone(),
+                info.size,
+                "room123abc",
+                "user1",
+            ))
+            .unwrap();
+
+        let app = Router::new()
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state);
+        let download = || {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri(format!("/public/file/{}", share.share_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(download().await.unwrap().status(), StatusCode::OK);
+
+        let response = download().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+        assert!(response.headers().contains_key("x-ratelimit-reset"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "RATE_LIMITED");
+        assert_eq!(json["data"]["retryAfterSecs"], retry_after);
+        assert!(
+            json["message"]
+                .as_str()
+                .unwrap()
+                .contains(&format!("{} seconds", retry_after))
+        );
+    }
+}
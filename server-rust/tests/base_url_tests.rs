@@ -0,0 +1,112 @@
+/// Base URL Tests
+///
+/// Verifies share/download URL construction against the ALLOWED_HOSTS allowlist.
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use cloud_clipboard_server::routes::{SchemePolicy, resolve_base_url};
+
+    fn headers_with_host(host: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("host", host.parse().unwrap());
+        headers
+    }
+
+    fn allowlist() -> Vec<String> {
+        vec!["clip.example.com".to_string(), "localhost:3001".to_string()]
+    }
+
+    #[test]
+    fn test_spoofed_host_rejected() {
+        let headers = headers_with_host("evil.attacker.com");
+        assert!(resolve_base_url(&headers, None, &allowlist(), SchemePolicy::TrustProxy).is_err());
+        // A port on a non-listed host doesn't help either
+        let headers = headers_with_host("localhost:9999");
+        assert!(resolve_base_url(&headers, None, &allowlist(), SchemePolicy::TrustProxy).is_err());
+    }
+
+    #[test]
+    fn test_allowed_host_accepted() {
+        let mut headers = headers_with_host("Clip.Example.com:8443");
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+        assert_eq!(
+            resolve_base_url(&headers, None, &allowlist(), SchemePolicy::TrustProxy).unwrap(),
+            "https://Clip.Example.com:8443"
+        );
+
+        let headers = headers_with_host("localhost:3001");
+        assert_eq!(
+            resolve_base_url(&headers, None, &allowlist(), SchemePolicy::TrustProxy).unwrap(),
+            "http://localhost:3001"
+        );
+    }
+
+    #[test]
+    fn test_public_url_overrides_host_and_allowlist() {
+        let headers = headers_with_host("evil.attacker.com");
+        assert_eq!(
+            resolve_base_url(
+                &headers,
+                Some("https://public.example.com/"),
+                &allowlist(),
+                SchemePolicy::TrustProxy
+            )
+            .unwrap(),
+            "https://public.example.com"
+        );
+        assert_eq!(
+            resolve_base_url(
+                &headers,
+                Some("https://public.example.com"),
+                &[],
+                SchemePolicy::TrustProxy
+            )
+            .unwrap(),
+            "https://public.example.com"
+        );
+    }
+
+    #[test]
+    fn test_no_allowlist_accepts_any_host() {
+        let headers = headers_with_host("anything.example.org");
+        assert_eq!(
+            resolve_base_url(&headers, None, &[], SchemePolicy::TrustProxy).unwrap(),
+            "http://anything.example.org"
+        );
+    }
+
+    #[test]
+    fn test_untrusted_proxy_proto_ignored() {
+        let mut headers = headers_with_host("clip.example.com");
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+
+        // ALLOW_HTTP=true: plain http regardless of what the proxy claims
+        assert_eq!(
+            resolve_base_url(&headers, None, &[], SchemePolicy::new(false, true)).unwrap(),
+            "http://clip.example.com"
+        );
+
+        // HTTPS enforced: https even without (or against) the proxy header
+        headers.insert("x-forwarded-proto", "http".parse().unwrap());
+        assert_eq!(
+            resolve_base_url(&headers, None, &[], SchemePolicy::new(false, false)).unwrap(),
+            "https://clip.example.com"
+        );
+    }
+
+    #[test]
+    fn test_trusted_proxy_proto_honored_and_validated() {
+        let mut headers = headers_with_host("clip.example.com");
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+        assert_eq!(
+            resolve_base_url(&headers, None, &[], SchemePolicy::new(true, false)).unwrap(),
+            "https://clip.example.com"
+        );
+
+        headers.insert("x-forwarded-proto", "javascript".parse().unwrap());
+        assert_eq!(
+            resolve_base_url(&headers, None, &[], SchemePolicy::new(true, false)).unwrap(),
+            "http://clip.example.com"
+        );
+    }
+}
@@ -53,6 +53,11 @@ impl TestDataFactory {
             timestamp: Utc::now(),
             room_key: room_key.to_string(),
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: None,
             download_url: None,
         }
@@ -73,6 +78,11 @@ impl TestDataFactory {
             timestamp: Utc::now(),
             room_key: room_key.to_string(),
             file_id: Some(format!("{}-{}", Utc::now().timestamp_millis(), file_name)),
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: Some(serde_json::json!({
                 "name": file_name,
                 "size": file_size,
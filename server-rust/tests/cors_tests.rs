@@ -0,0 +1,127 @@
+/// CORS Tests
+///
+/// Builds the CORS layer from an explicit config and checks the headers it adds to
+/// preflight and simple responses in both production and development modes.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, Response, header},
+        routing::get,
+    };
+    use cloud_clipboard_server::config::{CorsConfig, DEFAULT_CORS_MAX_AGE_SECS};
+    use tower::ServiceExt;
+
+    const ORIGIN: &str = "https://clip.example.com";
+
+    fn app(config: &CorsConfig, production: bool) -> Router {
+        Router::new()
+            .route("/api/ping", get(|| async { "pong" }))
+            .layer(config.layer(production))
+    }
+
+    async fn preflight(app: Router) -> Response<Body> {
+        let request = Request::builder()
+            .method("OPTIONS")
+            .uri("/api/ping")
+            .header(header::ORIGIN, ORIGIN)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    async fn simple_get(app: Router) -> Response<Body> {
+        let request = Request::builder()
+            .uri("/api/ping")
+            .header(header::ORIGIN, ORIGIN)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    fn header_str(response: &Response<Body>, name: header::HeaderName) -> &str {
+        response
+            .headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap())
+            .unwrap_or("")
+    }
+
+    fn exposed(response: &Response<Body>) -> Vec<String> {
+        header_str(response, header::ACCESS_CONTROL_EXPOSE_HEADERS)
+            .split(',')
+            .map(|h| h.trim().to_lowercase())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_default_config_sets_max_age_and_exposes_headers() {
+        let config = CorsConfig::default();
+        for production in [true, false] {
+            let response = preflight(app(&config, production)).await;
+            assert_eq!(
+                header_str(&response, header::ACCESS_CONTROL_MAX_AGE),
+                DEFAULT_CORS_MAX_AGE_SECS.to_string()
+            );
+
+            let response = simple_get(app(&config, production)).await;
+            let exposed = exposed(&response);
+            for name in [
+                "x-request-id",
+                "x-ratelimit-limit",
+                "x-ratelimit-remaining",
+                "x-ratelimit-reset",
+                "retry-after",
+            ] {
+                assert!(exposed.contains(&name.to_string()), "{}", name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dev_layer_uses_configured_values() {
+        let config = CorsConfig {
+            allowed_origins: vec![ORIGIN.to_string()],
+            max_age_secs: 1800,
+            expose_headers: vec!["x-request-id".to_string(), "x-custom".to_string()],
+        };
+
+        let response = preflight(app(&config, false)).await;
+        assert_eq!(
+            header_str(&response, header::ACCESS_CONTROL_MAX_AGE),
+            "1800"
+        );
+        assert_eq!(
+            header_str(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            ORIGIN
+        );
+
+        let response = simple_get(app(&config, false)).await;
+        assert_eq!(exposed(&response), vec!["x-request-id", "x-custom"]);
+        assert_eq!(
+            header_str(&response, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            "true"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zero_max_age_omits_header() {
+        let config = CorsConfig {
+            max_age_secs: 0,
+            ..CorsConfig::default()
+        };
+        let response = preflight(app(&config, true)).await;
+        assert!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_MAX_AGE)
+                .is_none()
+        );
+        assert_eq!(
+            header_str(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            "*"
+        );
+    }
+}
@@ -108,6 +108,20 @@ mod tests {
     }
 
     // File size limit tests
+    #[test]
+    fn test_parse_retention_hours() {
+        use cloud_clipboard_server::routes::files::{MAX_RETENTION_HOURS, parse_retention_hours};
+        assert_eq!(parse_retention_hours("1"), Some(1));
+        assert_eq!(parse_retention_hours(" 48 "), Some(48));
+        assert_eq!(parse_retention_hours("0"), None);
+        assert_eq!(parse_retention_hours("-3"), None);
+        assert_eq!(parse_retention_hours("abc"), None);
+        assert_eq!(
+            parse_retention_hours(&(MAX_RETENTION_HOURS + 1).to_string()),
+            None
+        );
+    }
+
     #[test]
     fn test_file_size_limit() {
         const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB
@@ -128,8 +142,10 @@ mod tests {
 
     #[test]
     fn test_max_files_per_request() {
-        const MAX_FILES: usize = 1;
-        assert_eq!(MAX_FILES, 1);
+        assert_eq!(
+            cloud_clipboard_server::routes::files::MAX_FILES_PER_REQUEST,
+            1
+        );
     }
 
     // File upload validation tests
@@ -813,3 +829,1296 @@ mod tests {
         assert_eq!(files_to_delete.len(), 3);
     }
 }
+
+/// Router-level tests against the real files router and services
+#[cfg(test)]
+mod router_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::files;
+    use cloud_clipboard_server::services::{
+        FileManager, RoomService, ScanVerdict, ShareService, UploadFs, UploadWriter, VirusScanner,
+    };
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest(
+                "/api/files",
+                files::router(state.rate_limit_metrics.clone()),
+            )
+            .with_state(state)
+    }
+
+    async fn body_json(response: axum::response::Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_downloads_record_distinct_room_members() {
+        use cloud_clipboard_server::services::JoinRoomRequest;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        for (user_id, name, socket_id) in [
+            ("u1", "Alice", "s1"),
+            ("u2", "Bob", "s2"),
+            ("u3", "Carol", "s3"),
+        ] {
+            state
+                .room_service
+                .join_room(JoinRoomRequest::new("room123abc", user_id, name, socket_id))
+                .unwrap();
+        }
+        let token_u1 = state
+            .room_service
+            .issue_session_token("room123abc", "u1")
+            .unwrap();
+        let token_u2 = state
+            .room_service
+            .issue_session_token("room123abc", "u2")
+            .unwrap();
+        let foreign_token = state
+            .room_service
+            .issue_session_token("otherroom1", "u3")
+            .unwrap();
+        let info = state
+            .file_manager
+            .save_file("room123abc", "report.txt", "text/plain", b"report")
+            .await
+            .unwrap();
+        let app = create_router(state.clone());
+
+        // Query param, header, a repeat download, another room's token, an unknown
+        // token and a bare member ID (spoofable, so ignored)
+        let requests = [
+            (Some(token_u1.as_str()), None),
+            (None, Some(token_u2.as_str())),
+            (Some(token_u1.as_str()), None),
+            (Some(foreign_token.as_str()), None),
+            (Some("forged"), None),
+        ];
+        for (query_token, header_token) in requests {
+            let mut uri = format!("/api/files/download/{}", info.filename);
+            if let Some(token) = query_token {
+                uri.push_str(&format!("?sessionToken={}", token));
+            }
+            let mut builder = Request::builder().uri(uri);
+            if let Some(token) = header_token {
+                builder = builder.header("x-session-token", token);
+            }
+            let response = app
+                .clone()
+                .oneshot(builder.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/download/{}?userId=u3", info.filename))
+                    .header("x-user-id", "u3")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(
+            state.file_manager.downloaders(&info.filename),
+            Some(vec!["u1".to_string(), "u2".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_count_appears_in_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "report.txt", "text/plain", b"report")
+            .await
+            .unwrap();
+        let app = create_router(state);
+
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/api/files/download/{}", info.filename))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files")
+                    .header("x-room-key", "room123abc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        let files = json["data"]["files"].as_array().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(json["data"]["total"], 1);
+        assert_eq!(files[0]["fileId"], info.filename.as_str());
+        assert_eq!(files[0]["name"], "report.txt");
+        assert_eq!(files[0]["downloadCount"], 2);
+        // Server paths are never exposed
+        assert!(files[0].get("path").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_listing_requires_room_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    /// Three room files uploaded in order with sizes 20, 5, 10; returns the router
+    async fn listing_fixture(dir: &tempfile::TempDir) -> Router {
+        let state = create_state(dir);
+        for (name, size) in [("a.txt", 20), ("b.txt", 5), ("c.txt", 10)] {
+            state
+                .file_manager
+                .save_file("room123abc", name, "text/plain", &vec![size as u8; size])
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        create_router(state)
+    }
+
+    async fn list_names(app: Router, query: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files{}", query))
+                    .header("x-room-key", "room123abc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        (status, body_json(response).await)
+    }
+
+    fn names(json: &serde_json::Value) -> Vec<&str> {
+        json["data"]["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_listing_sort_orders() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = listing_fixture(&dir).await;
+
+        for (query, expected) in [
+            ("", ["a.txt", "b.txt", "c.txt"]),
+            ("?sort=uploadedAt", ["a.txt", "b.txt", "c.txt"]),
+            ("?sort=uploadedAt:desc", ["c.txt", "b.txt", "a.txt"]),
+            ("?sort=size:asc", ["b.txt", "c.txt", "a.txt"]),
+            ("?sort=size:desc", ["a.txt", "c.txt", "b.txt"]),
+        ] {
+            let (status, json) = list_names(app.clone(), query).await;
+            assert_eq!(status, StatusCode::OK, "{}", query);
+            assert_eq!(names(&json), expected, "{}", query);
+        }
+
+        let (status, json) = list_names(app, "?sort=name:asc").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "INVALID_REQUEST");
+    }
+
+    #[tokio::test]
+    async fn test_listing_pagination_boundaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = listing_fixture(&dir).await;
+
+        let (_, json) = list_names(app.clone(), "?limit=2").await;
+        assert_eq!(names(&json), ["a.txt", "b.txt"]);
+        assert_eq!(json["data"]["total"], 3);
+        assert_eq!(json["data"]["limit"], 2);
+
+        // Last partial page
+        let (_, json) = list_names(app.clone(), "?limit=2&offset=2").await;
+        assert_eq!(names(&json), ["c.txt"]);
+        assert_eq!(json["data"]["total"], 3);
+        assert_eq!(json["data"]["offset"], 2);
+
+        // Pagination applies after sorting
+        let (_, json) = list_names(app.clone(), "?limit=1&offset=1&sort=size:desc").await;
+        assert_eq!(names(&json), ["c.txt"]);
+
+        // Past the end and empty pages still report the full total
+        let (_, json) = list_names(app.clone(), "?offset=3").await;
+        assert!(names(&json).is_empty());
+        assert_eq!(json["data"]["total"], 3);
+        let (_, json) = list_names(app, "?limit=0").await;
+        assert!(names(&json).is_empty());
+        assert_eq!(json["data"]["total"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_saturated_share_pool_does_not_block_file_download() {
+        use cloud_clipboard_server::routes::share::SHARE_STREAMS;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "report.txt", "text/plain", b"report")
+            .await
+            .unwrap();
+
+        let ip = "203.0.113.56";
+        let share_guards: Vec<_> = std::iter::from_fn(|| SHARE_STREAMS.acquire(ip)).collect();
+        assert!(!share_guards.is_empty());
+        assert!(SHARE_STREAMS.acquire(ip).is_none());
+
+        let response = create_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/download/{}", info.filename))
+                    .header("x-forwarded-for", ip)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_open_download_body_holds_stream_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "report.txt", "text/plain", b"report")
+            .await
+            .unwrap();
+        let app = create_router(state);
+        let download = || {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/download/{}", info.filename))
+                    .header("x-forwarded-for", "203.0.113.57")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // Responses whose bodies have not been read yet keep their slots (5 per IP)
+        let mut open = Vec::new();
+        for _ in 0..5 {
+            let response = download().await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            open.push(response);
+        }
+        let response = download().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Finishing one transfer frees its slot
+        let body = axum::body::to_bytes(open.pop().unwrap().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"report");
+        assert_eq!(download().await.unwrap().status(), StatusCode::OK);
+    }
+
+    async fn preview(app: Router, uri: String) -> axum::response::Response {
+        app.oneshot(
+            Request::builder()
+                .uri(uri)
+                .header("x-room-key", "room123abc")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_preview_text_file_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file(
+                "room123abc",
+                "notes.txt",
+                "text/plain",
+                b"hello preview world",
+            )
+            .await
+            .unwrap();
+
+        let response = preview(
+            create_router(state),
+            format!("/api/files/{}/preview?bytes=5", info.filename),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["content"], "hello");
+        assert_eq!(json["data"]["bytes"], 5);
+        assert_eq!(json["data"]["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_preview_binary_file_unsupported() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file(
+                "room123abc",
+                "image.png",
+                "image/png",
+                b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+            )
+            .await
+            .unwrap();
+
+        let response = preview(
+            create_router(state),
+            format!("/api/files/{}/preview", info.filename),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_preview_byte_bound_clamped() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let content = "a".repeat(files::MAX_PREVIEW_BYTES + 100);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "big.txt", "text/plain", content.as_bytes())
+            .await
+            .unwrap();
+
+        let response = preview(
+            create_router(state),
+            format!("/api/files/{}/preview?bytes=100000000", info.filename),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["bytes"], files::MAX_PREVIEW_BYTES);
+        assert_eq!(json["data"]["truncated"], true);
+    }
+
+    async fn send(app: Router, method: &str, uri: &str) -> axum::response::Response {
+        app.oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_head_download_returns_headers_without_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "报告.txt", "text/plain", b"report body")
+            .await
+            .unwrap();
+        let app = create_router(state.clone());
+        let uri = format!("/api/files/download/{}", info.filename);
+
+        let head = send(app.clone(), "HEAD", &uri).await;
+        let get = send(app, "GET", &uri).await;
+        assert_eq!(head.status(), StatusCode::OK);
+        for name in [
+            header::CONTENT_TYPE,
+            header::CONTENT_LENGTH,
+            header::CONTENT_DISPOSITION,
+        ] {
+            assert_eq!(head.headers().get(&name), get.headers().get(&name));
+        }
+        assert_eq!(head.headers()[header::CONTENT_LENGTH], "11");
+
+        let body = axum::body::to_bytes(head.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+        // Probing is not a download
+        assert_eq!(
+            state
+                .file_manager
+                .get_file(&info.filename)
+                .unwrap()
+                .download_count,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_download_enforces_get_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        for (uri, status) in [
+            (
+                "/api/files/download/..%2F..%2Fetc%2Fpasswd",
+                StatusCode::BAD_REQUEST,
+            ),
+            ("/api/files/download/missing.txt", StatusCode::NOT_FOUND),
+        ] {
+            let head = send(app.clone(), "HEAD", uri).await;
+            let get = send(app.clone(), "GET", uri).await;
+            assert_eq!(head.status(), status);
+            assert_eq!(get.status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_decompresses_stored_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap()
+                    .with_compression(true),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        let data = "line of a very repetitive log\n".repeat(500);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "app.log", "text/plain", data.as_bytes())
+            .await
+            .unwrap();
+        assert!(info.compressed);
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/download/{}", info.filename))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_LENGTH],
+            data.len().to_string().as_str()
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, data.as_bytes());
+    }
+
+    fn verify_request(file_id: &str, room_key: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().uri(format!("/api/files/{}/verify", file_id));
+        if let Some(room_key) = room_key {
+            builder = builder.header("x-room-key", room_key);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_intact_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "notes.txt", "text/plain", b"intact contents")
+            .await
+            .unwrap();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(verify_request(&info.filename, Some("room123abc")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["ok"], true);
+        assert_eq!(json["data"]["expectedHash"], info.hash.unwrap().as_str());
+        assert_eq!(json["data"]["actualHash"], json["data"]["expectedHash"]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_corrupted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file(
+                "room123abc",
+                "notes.txt",
+                "text/plain",
+                b"original contents",
+            )
+            .await
+            .unwrap();
+        std::fs::write(&info.path, b"tampered contents").unwrap();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(verify_request(&info.filename, Some("room123abc")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["ok"], false);
+        assert_eq!(json["data"]["expectedHash"], info.hash.unwrap().as_str());
+        assert_ne!(json["data"]["actualHash"], json["data"]["expectedHash"]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_requires_room_membership() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "notes.txt", "text/plain", b"private")
+            .await
+            .unwrap();
+        let app = create_router(state);
+
+        let response = app
+            .clone()
+            .oneshot(verify_request(&info.filename, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .oneshot(verify_request(&info.filename, Some("otherroom1")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    const BOUNDARY: &str = "test-boundary-7MA4YWxk";
+
+    /// Multipart part: (field name, optional filename, content)
+    fn multipart_upload(parts: &[(&str, Option<&str>, &[u8])]) -> Request<Body> {
+        let mut body = Vec::new();
+        for (name, filename, content) in parts {
+            body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+            match filename {
+                Some(filename) => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n",
+                        name, filename
+                    )
+                    .as_bytes(),
+                ),
+                None => body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name)
+                        .as_bytes(),
+                ),
+            }
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+
+        Request::builder()
+            .method("POST")
+            .uri("/api/files/upload")
+            .header("host", "localhost:3001")
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    /// Files left in the upload directory (including temp files)
+    fn stored_files(dir: &tempfile::TempDir) -> usize {
+        std::fs::read_dir(dir.path()).unwrap().count()
+    }
+
+    /// Rejected uploads remove their temp file in the background; wait for it
+    async fn assert_no_files_left(dir: &tempfile::TempDir) {
+        for _ in 0..100 {
+            if stored_files(dir) == 0 {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(stored_files(dir), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_streams_single_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let file_manager = state.file_manager.clone();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("notes.txt"), b"streamed upload contents"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["name"], "notes.txt");
+        assert_eq!(json["data"]["size"], 24);
+
+        let info = file_manager
+            .get_file(json["data"]["fileId"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(info.room_key, "room123abc");
+        assert_eq!(
+            std::fs::read(&info.path).unwrap(),
+            b"streamed upload contents"
+        );
+        // Only the stored file remains; the temp file was moved into place
+        assert_eq!(stored_files(&dir), 1);
+    }
+
+    fn timestamp(value: &serde_json::Value) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap())
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn test_upload_and_listing_report_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .clone()
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("default.txt"), b"global retention"),
+            ]))
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        let uploaded_at =
+            chrono::DateTime::from_timestamp_millis(json["data"]["lastModified"].as_i64().unwrap())
+                .unwrap();
+        let expires_at = timestamp(&json["data"]["expiresAt"]);
+        // create_state configures a 12 hour global retention (lastModified is in whole ms)
+        assert_eq!(
+            (expires_at - uploaded_at).num_milliseconds(),
+            chrono::Duration::hours(12).num_milliseconds()
+        );
+
+        let response = app
+            .clone()
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("retentionHours", None, b"2"),
+                ("file", Some("short.txt"), b"per-file retention"),
+            ]))
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        let expected = chrono::Utc::now() + chrono::Duration::hours(2);
+        let short_expiry = timestamp(&json["data"]["expiresAt"]);
+        assert!((expected - short_expiry).num_seconds().abs() < 5);
+
+        // The listing reports the same expiry per file
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/files")
+                    .header("x-room-key", "room123abc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        let listed: std::collections::HashMap<&str, _> = json["data"]["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| (f["name"].as_str().unwrap(), timestamp(&f["expiresAt"])))
+            .collect();
+        assert_eq!(listed["default.txt"], expires_at);
+        assert_eq!(listed["short.txt"], short_expiry);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_retention_hours_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        // The body ends inside the retentionHours field, so reading it fails; that must
+        // not fall back to the default retention
+        let mut request = multipart_upload(&[("roomKey", None, b"room123abc")]);
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"roomKey\"\r\n\r\nroom123abc\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"retentionHours\"\r\n\r\n2",
+            b = BOUNDARY
+        );
+        *request.body_mut() = Body::from(body);
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "Failed to read retentionHours");
+        assert_no_files_left(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_upload_broadcasts_file_uploaded_event() {
+        use cloud_clipboard_server::services::RoomEvent;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let mut events = state.room_service.subscribe();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("notes.txt"), b"hello room"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+
+        let Ok(RoomEvent::FileUploaded(file)) = events.try_recv() else {
+            panic!("expected a FileUploaded event");
+        };
+        assert_eq!(file.room_key, "room123abc");
+        assert_eq!(file.file_id, json["data"]["fileId"].as_str().unwrap());
+        assert_eq!(file.name, "notes.txt");
+
+        let payload = serde_json::to_value(&file).unwrap();
+        assert_eq!(payload["fileId"], json["data"]["fileId"]);
+        assert_eq!(payload["name"], "notes.txt");
+    }
+
+    #[tokio::test]
+    async fn test_upload_response_reports_hash_and_duplicate() {
+        use sha2::{Digest, Sha256};
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+        let upload = |name: &'static str| {
+            multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some(name), b"dedup me please"),
+            ])
+        };
+
+        let response = app.clone().oneshot(upload("first.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let first = body_json(response).await;
+        let expected_hash = format!("{:x}", Sha256::digest(b"dedup me please"));
+        assert_eq!(first["data"]["hash"], expected_hash);
+        assert_eq!(first["data"]["isDuplicate"], false);
+        assert!(first["data"].get("originalFileId").is_none());
+
+        let response = app.oneshot(upload("second.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let second = body_json(response).await;
+        assert_eq!(second["data"]["isDuplicate"], true);
+        assert_eq!(second["data"]["originalFileId"], first["data"]["fileId"]);
+        assert_eq!(second["data"]["hash"], expected_hash);
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejects_too_many_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("a.txt"), b"first"),
+                ("file", Some("b.txt"), b"second"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_no_files_left(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_upload_without_file_part_returns_400() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(multipart_upload(&[("roomKey", None, b"room123abc")]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "file is required");
+    }
+
+    #[tokio::test]
+    async fn test_empty_file_rejected_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("empty.txt"), b""),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "Empty file");
+        assert_no_files_left(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_empty_file_allowed_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap()
+                    .with_allow_empty_uploads(true),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("empty.txt"), b""),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["data"]["size"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejects_blank_filename() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        for name in ["", "   "] {
+            let response = app
+                .clone()
+                .oneshot(multipart_upload(&[
+                    ("roomKey", None, b"room123abc"),
+                    ("file", Some(name), b"contents"),
+                ]))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let json = body_json(response).await;
+            assert_eq!(json["message"], "Filename required");
+        }
+        assert_no_files_left(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_upload_requires_room_key_before_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(multipart_upload(&[
+                ("file", Some("notes.txt"), b"contents"),
+                ("roomKey", None, b"room123abc"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "roomKey must be sent before the file");
+        assert_no_files_left(&dir).await;
+    }
+
+    fn upload_from(ip: &str, name: &str) -> Request<Body> {
+        let mut request = multipart_upload(&[
+            ("roomKey", None, b"room123abc"),
+            ("file", Some(name), name.as_bytes()),
+        ]);
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", ip.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_upload_rate_limit_is_per_ip() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        // Default UPLOAD_RATE_LIMIT is 5 per minute
+        for i in 0..5 {
+            let response = app
+                .clone()
+                .oneshot(upload_from("203.0.113.1", &format!("burst{}.txt", i)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(upload_from("203.0.113.1", "burst5.txt"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "0");
+        let json = body_json(response).await;
+        assert_eq!(json["code"], "RATE_LIMITED");
+
+        let response = app
+            .oneshot(upload_from("203.0.113.2", "other.txt"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Join room123abc as a member with `fingerprint`, so uploads are keyed on it
+    fn join_as(state: &AppState, fingerprint: &str) {
+        use cloud_clipboard_server::services::JoinRoomRequest;
+
+        let user_id = format!("user-{}", fingerprint);
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("room123abc", &user_id, fingerprint, fingerprint)
+                    .with_fingerprint(fingerprint),
+            )
+            .unwrap();
+    }
+
+    /// Upload from a fresh IP each time, so only the per-uploader quota can trigger
+    fn upload_as(fingerprint: &str, ip: &str, name: &str, body: &[u8]) -> Request<Body> {
+        let mut request =
+            multipart_upload(&[("roomKey", None, b"room123abc"), ("file", Some(name), body)]);
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", ip.parse().unwrap());
+        request
+            .headers_mut()
+            .insert("x-user-fingerprint", fingerprint.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_per_uploader_request_limit() {
+        use cloud_clipboard_server::middleware::rate_limit::UploadQuota;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir).with_upload_quota(UploadQuota::new(
+            2,
+            0,
+            std::time::Duration::from_secs(60),
+        ));
+        join_as(&state, "fp-a");
+        join_as(&state, "fp-b");
+        let app = create_router(state.clone());
+
+        for i in 0..2 {
+            let request = upload_as("fp-a", &format!("198.51.100.{}", i), "a.txt", b"a");
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // A new IP doesn't reset the fingerprint's budget
+        let request = upload_as("fp-a", "198.51.100.9", "a.txt", b"a");
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let json = body_json(response).await;
+        assert_eq!(json["code"], "RATE_LIMITED");
+        assert_eq!(json["message"], "Upload rate limit exceeded");
+        assert_eq!(state.rate_limit_metrics.rejections("/api/files/upload"), 1);
+
+        let request = upload_as("fp-b", "198.51.100.9", "b.txt", b"b");
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_per_uploader_byte_limit() {
+        use cloud_clipboard_server::middleware::rate_limit::UploadQuota;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir).with_upload_quota(UploadQuota::new(
+            0,
+            100,
+            std::time::Duration::from_secs(60),
+        ));
+        join_as(&state, "fp-a");
+        let app = create_router(state);
+
+        let request = upload_as("fp-a", "198.51.100.1", "a.txt", &[b'a'; 60]);
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 60 + 60 bytes exceeds the 100-byte window budget
+        let request = upload_as("fp-a", "198.51.100.2", "b.txt", &[b'b'; 60]);
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "Upload volume limit exceeded");
+
+        // The rejected upload wasn't counted, so a smaller one still fits
+        let request = upload_as("fp-a", "198.51.100.3", "c.txt", &[b'c'; 40]);
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_uploader_is_limited_per_ip() {
+        use cloud_clipboard_server::middleware::rate_limit::UploadQuota;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir).with_upload_quota(UploadQuota::new(
+            2,
+            0,
+            std::time::Duration::from_secs(60),
+        ));
+        join_as(&state, "fp-member");
+        let app = create_router(state);
+
+        // Fingerprints that aren't room members don't get their own budget
+        for fingerprint in ["fp-x", "fp-y"] {
+            let request = upload_as(fingerprint, "198.51.100.7", "a.txt", b"a");
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let request = upload_as("fp-z", "198.51.100.7", "a.txt", b"a");
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // A claimed member ID that isn't in the room is treated the same way
+        let mut request = multipart_upload(&[
+            ("roomKey", None, b"room123abc"),
+            ("file", Some("b.txt"), b"b"),
+        ]);
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", "198.51.100.7".parse().unwrap());
+        request
+            .headers_mut()
+            .insert("x-user-id", "user-nobody".parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // A verified member from the same IP has their own budget
+        let request = upload_as("fp-member", "198.51.100.7", "c.txt", b"c");
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Upload temp files whose writes never complete, like a hung network mount
+    struct HungMountFs;
+
+    struct HungWriter(tokio::fs::File);
+
+    impl tokio::io::AsyncWrite for HungWriter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            _buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            std::task::Poll::Pending
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            Pin::new(&mut self.0).poll_shutdown(cx)
+        }
+    }
+
+    impl UploadFs for HungMountFs {
+        fn create<'a>(
+            &'a self,
+            path: &'a std::path::Path,
+        ) -> Pin<Box<dyn Future<Output = std::io::Result<UploadWriter>> + Send + 'a>> {
+            Box::pin(async move {
+                Ok(Box::new(HungWriter(tokio::fs::File::create(path).await?)) as UploadWriter)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_on_hung_storage_returns_503() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_manager = FileManager::new_with_config(dir.path().to_path_buf(), 1024, 12)
+            .unwrap()
+            .with_upload_fs(Arc::new(HungMountFs))
+            .with_io_timeout(std::time::Duration::from_millis(50));
+        let state = AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(file_manager),
+            Arc::new(ShareService::new()),
+        );
+
+        let request = multipart_upload(&[
+            ("roomKey", None, b"room123abc"),
+            ("file", Some("a.txt"), b"hello"),
+        ]);
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "Storage unavailable");
+
+        // The partial temp file is gone with the failed request
+        assert_no_files_left(&dir).await;
+    }
+
+    /// Flags any file containing the EICAR marker, like a signature match
+    struct FakeScanner;
+
+    impl VirusScanner for FakeScanner {
+        fn scan<'a>(
+            &'a self,
+            path: &'a std::path::Path,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<ScanVerdict>> + Send + 'a>> {
+            Box::pin(async move {
+                let bytes = tokio::fs::read(path).await?;
+                Ok(if bytes.windows(5).any(|w| w == b"EICAR") {
+                    ScanVerdict::Infected("Eicar-Test-Signature".to_string())
+                } else {
+                    ScanVerdict::Clean
+                })
+            })
+        }
+    }
+
+    fn scanning_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap()
+                    .with_scanner(Arc::new(FakeScanner)),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_clean_upload_passes_virus_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = scanning_state(&dir);
+        let file_manager = state.file_manager.clone();
+
+        let response = create_router(state)
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                ("file", Some("clean.txt"), b"nothing to see here"),
+            ]))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert!(
+            file_manager
+                .get_file(json["data"]["fileId"].as_str().unwrap())
+                .is_some()
+        );
+        assert_eq!(stored_files(&dir), 1);
+    }
+
+    #[tokio::test]
+    async fn test_infected_upload_rejected_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = scanning_state(&dir);
+        let file_manager = state.file_manager.clone();
+
+        let response = create_router(state)
+            .oneshot(multipart_upload(&[
+                ("roomKey", None, b"room123abc"),
+                (
+                    "file",
+                    Some("payload.txt"),
+                    b"X5O!P%@AP EICAR-STANDARD-ANTIVIRUS-TEST",
+                ),
+            ]))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "File rejected by virus scan");
+        assert!(file_manager.list_room_files("room123abc").is_empty());
+        // The temp file is gone too
+        assert_no_files_left(&dir).await;
+    }
+}
@@ -0,0 +1,74 @@
+/// Health Routes Tests
+///
+/// Exercises the health and readiness endpoints against the real router.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::health;
+    use cloud_clipboard_server::services::{FileManager, RoomService, ShareService};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .route("/health", get(health::health_check))
+            .route("/health/ready", get(health::readiness_check))
+            .with_state(state)
+    }
+
+    async fn get_status(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_readiness_transitions_after_mark_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let app = create_router(state.clone());
+
+        let (status, json) = get_status(app.clone(), "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["ready"], false);
+
+        state.mark_ready();
+
+        let (status, json) = get_status(app, "/health/ready").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["ready"], true);
+    }
+
+    #[tokio::test]
+    async fn test_liveness_ok_before_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let (status, json) = get_status(app, "/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["success"], true);
+    }
+}
@@ -35,7 +35,7 @@ mod tests {
         let service = create_room_service();
 
         // Create room and join user
-        let (user, users) = service
+        let (user, users, _) = service
             .join_room(
                 JoinRoomRequest::new("room123", "user1", "Alice", "socket1")
                     .with_fingerprint("fp1"),
@@ -47,7 +47,7 @@ mod tests {
         assert!(service.room_exists("room123"));
 
         // Second user joins
-        let (user2, users2) = service
+        let (user2, users2, _) = service
             .join_room(
                 JoinRoomRequest::new("room123", "user2", "Bob", "socket2")
                     .with_device_type("mobile")
@@ -67,6 +67,11 @@ mod tests {
             timestamp: Utc::now(),
             room_key: "room123".to_string(),
             file_id: None,
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: None,
             download_url: None,
         };
@@ -136,7 +141,7 @@ mod tests {
         let service = create_room_service();
 
         // First user joins
-        let (user1, _) = service
+        let (user1, _, _) = service
             .join_room(
                 JoinRoomRequest::new("room123", "user1", "Alice", "socket1")
                     .with_fingerprint("fp1"),
@@ -145,7 +150,7 @@ mod tests {
         assert_eq!(user1.username, "Alice");
 
         // Second user with same name gets a modified name
-        let (user2, _) = service
+        let (user2, _, _) = service
             .join_room(
                 JoinRoomRequest::new("room123", "user2", "Alice", "socket2")
                     .with_fingerprint("fp2"),
@@ -369,7 +374,7 @@ mod tests {
         let file_manager = create_file_manager();
 
         // User joins room
-        let (user, _) = room_service
+        let (user, _, _) = room_service
             .join_room(
                 JoinRoomRequest::new("room123", "user1", "Alice", "socket1")
                     .with_fingerprint("fp1"),
@@ -396,6 +401,11 @@ mod tests {
             timestamp: Utc::now(),
             room_key: "room123".to_string(),
             file_id: Some(file_info.filename.clone()),
+            seq: 0,
+            encrypted: false,
+            pinned: false,
+            content_format: Default::default(),
+            ttl_secs: None,
             file_info: Some(FileInfo {
                 name: file_info.original_name.clone(),
                 size: file_info.size,
@@ -0,0 +1,71 @@
+/// Log Redaction Tests
+///
+/// LOG_REDACT is read once per process, so redacted log output is exercised
+/// in its own test binary with redaction enabled up front.
+#[cfg(test)]
+mod tests {
+    use cloud_clipboard_server::services::{CreateShareRequest, ShareService};
+    use cloud_clipboard_server::utils::{redact_fingerprint, redact_ip};
+    use std::sync::{Arc, Mutex, Once};
+
+    fn enable_redaction() {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe {
+            std::env::set_var("LOG_REDACT", "true");
+        });
+    }
+
+    /// Run `f` under a subscriber that formats events into a buffer, returning the output
+    fn capture_logs(f: impl FnOnce()) -> String {
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_redact_helpers_mask_when_enabled() {
+        enable_redaction();
+        assert_eq!(redact_ip("203.0.113.77"), "203.0.113.x");
+        let fingerprint = redact_fingerprint("abcdef0123456789");
+        assert!(fingerprint.starts_with("fp#"));
+        assert!(!fingerprint.contains("abcdef"));
+    }
+
+    #[test]
+    fn test_lockout_log_masks_ip() {
+        enable_redaction();
+        let service =
+            ShareService::new().with_password_lockout(1, std::time::Duration::from_secs(60));
+        let (share, _) = service
+            .create_share(CreateShareRequest::new(
+                "test.txt", "test.txt", 100, "room1", "user1",
+            ))
+            .unwrap();
+
+        let logs = capture_logs(|| {
+            assert!(service.record_password_failure(&share.share_id, "203.0.113.77"));
+        });
+
+        assert!(logs.contains("203.0.113.x"), "{}", logs);
+        assert!(!logs.contains("203.0.113.77"), "{}", logs);
+    }
+}
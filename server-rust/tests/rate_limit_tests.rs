@@ -218,6 +218,54 @@ mod tests {
         assert!(response.message.contains("Too many requests"));
     }
 
+    #[tokio::test]
+    async fn test_rate_limited_response_has_stable_code() {
+        use cloud_clipboard_server::middleware::rate_limit::{
+            RateLimitConfig, rate_limit_exceeded_response,
+        };
+
+        let response = rate_limit_exceeded_response(&RateLimitConfig::default(), 5);
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["code"], "RATE_LIMITED");
+        assert_eq!(json["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_rejection_increments_endpoint_counter() {
+        use axum::{Router, body::Body, http::Request, routing::get};
+        use cloud_clipboard_server::middleware::rate_limit::{
+            RateLimitMetrics, RateLimitMiddleware, create_rate_limiter_with_window,
+        };
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let metrics = Arc::new(RateLimitMetrics::new());
+        let app = Router::new().route("/", get(|| async { "ok" })).layer(
+            RateLimitMiddleware::new(create_rate_limiter_with_window(1, 60))
+                .with_metrics(metrics.clone(), "/api/rooms"),
+        );
+        let request = || {
+            Request::builder()
+                .uri("/")
+                .header("x-forwarded-for", "10.0.0.1")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(metrics.rejections("/api/rooms"), 0);
+
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(metrics.rejections("/api/rooms"), 1);
+        assert_eq!(metrics.snapshot().get("/api/rooms"), Some(&1));
+    }
+
     #[test]
     fn test_rate_limit_headers() {
         struct RateLimitHeaders {
@@ -31,6 +31,11 @@ fn create_test_message(user: &User, room_key: &str, content: &str) -> Message {
         timestamp: Utc::now(),
         room_key: room_key.to_string(),
         file_id: None,
+        seq: 0,
+        encrypted: false,
+        pinned: false,
+        content_format: Default::default(),
+        ttl_secs: None,
         file_info: None,
         download_url: None,
     }
@@ -97,7 +102,7 @@ fn test_join_room_multiple_users() {
 #[test]
 fn test_join_room_reconnect_with_fingerprint() {
     let service = RoomService::new();
-    let (user1, _) = service
+    let (user1, _, _) = service
         .join_room(
             JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
                 .with_fingerprint("fp_hash_1"),
@@ -106,7 +111,7 @@ fn test_join_room_reconnect_with_fingerprint() {
 
     service.set_user_offline("socket1");
 
-    let (user2, users) = service
+    let (user2, users, _) = service
         .join_room(
             JoinRoomRequest::new("testroom", "user1_new", "TestUser", "socket2")
                 .with_fingerprint("fp_hash_1"),
@@ -134,13 +139,13 @@ fn test_join_room_no_password_required() {
 #[test]
 fn test_unique_username_generation() {
     let service = RoomService::new();
-    let (user1, _) = service
+    let (user1, _, _) = service
         .join_room(
             JoinRoomRequest::new("testroom", "user1", "TestUser", "socket1")
                 .with_fingerprint("fp1"),
         )
         .unwrap();
-    let (user2, _) = service
+    let (user2, _, _) = service
         .join_room(
             JoinRoomRequest::new("testroom", "user2", "TestUser", "socket2")
                 .with_fingerprint("fp2"),
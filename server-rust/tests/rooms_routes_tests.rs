@@ -373,3 +373,498 @@ mod tests {
         assert!(room.is_none());
     }
 }
+
+/// Router-level tests against the real rooms router and services
+#[cfg(test)]
+mod router_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::rooms;
+    use cloud_clipboard_server::services::{
+        FileManager, JoinRoomRequest, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/rooms", rooms::router())
+            .with_state(state)
+    }
+
+    async fn get_audit(app: Router, owner_token: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder().uri("/api/rooms/auditroom1/audit");
+        if let Some(token) = owner_token {
+            builder = builder.header("x-owner-token", token);
+        }
+        let response = app
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_readable_by_owner_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("auditroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("auditroom1", "u2", "Bob", "s2").with_fingerprint("fp_bob"),
+            )
+            .unwrap();
+        let owner_token = state
+            .room_service
+            .owner_token("auditroom1", "fp_owner")
+            .unwrap();
+        assert!(
+            state
+                .room_service
+                .owner_token("auditroom1", "fp_bob")
+                .is_none()
+        );
+        let app = create_router(state);
+
+        let (status, json) = get_audit(app.clone(), Some(&owner_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = json["data"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["action"], "join");
+        assert_eq!(entries[1]["username"], "Bob");
+
+        // Knowing the owner's fingerprint is not enough
+        let (status, json) = get_audit(app.clone(), Some("fp_owner")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "ACCESS_DENIED");
+
+        let (status, _) = get_audit(app, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_owner_sets_room_rate_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new().with_room_rate_limit_ceiling(200)),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("limitroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        let room_service = state.room_service.clone();
+        let owner_token = room_service.owner_token("limitroom1", "fp_owner").unwrap();
+        let app = create_router(state);
+
+        let put = |token: &str, body: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri("/api/rooms/limitroom1/rate-limits")
+                .header("content-type", "application/json")
+                .header("x-owner-token", token)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(put(&owner_token, r#"{"rateLimits":{"sendMessage":1000}}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["rateLimits"]["sendMessage"], 200);
+        assert_eq!(
+            room_service.room_rate_limit("limitroom1", "sendMessage"),
+            Some(200)
+        );
+
+        let response = app
+            .clone()
+            .oneshot(put("fp_owner", r#"{"rateLimits":{"sendMessage":50}}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(put(&owner_token, r#"{"rateLimits":{"joinRoom":50}}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "INVALID_REQUEST");
+    }
+
+    #[tokio::test]
+    async fn test_keys_invalid_once_normalized_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new().with_room_key_normalization(true)),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        let room_service = state.room_service.clone();
+        let app = create_router(state);
+
+        // `a-----1` is valid as typed but collapses to `a1`
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/rooms/create")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"roomKey":"a-----1"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(room_service.room_keys().is_empty());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/rooms/info")
+                    .header("x-room-key", "a-----1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_owner_token_required_to_set_message_rate_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("budgetroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        let room_service = state.room_service.clone();
+        let owner_token = room_service.owner_token("budgetroom1", "fp_owner").unwrap();
+        let app = create_router(state);
+
+        let put = |room: &str, token: Option<&str>| {
+            let mut builder = Request::builder()
+                .method("PUT")
+                .uri(format!("/api/rooms/{}/message-rate-limit", room))
+                .header("content-type", "application/json")
+                // A claimed fingerprint no longer grants owner access
+                .header("x-user-fingerprint", "fp_owner");
+            if let Some(token) = token {
+                builder = builder.header("x-owner-token", token);
+            }
+            builder
+                .body(Body::from(r#"{"messagesPerMinute":5}"#))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(put("budgetroom1", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(put("budgetroom1", Some("fp_owner")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "ACCESS_DENIED");
+        assert_eq!(
+            room_service
+                .get_room_config("budgetroom1")
+                .unwrap()
+                .message_rate_limit,
+            None
+        );
+
+        let response = app
+            .clone()
+            .oneshot(put("missing1room", Some(&owner_token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(put("budgetroom1", Some(&owner_token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            room_service
+                .get_room_config("budgetroom1")
+                .unwrap()
+                .message_rate_limit,
+            Some(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_owner_token_required_to_disable_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        state
+            .room_service
+            .join_room(
+                JoinRoomRequest::new("persistroom1", "u1", "Alice", "s1")
+                    .with_fingerprint("fp_owner"),
+            )
+            .unwrap();
+        let room_service = state.room_service.clone();
+        let owner_token = room_service
+            .owner_token("persistroom1", "fp_owner")
+            .unwrap();
+        let app = create_router(state);
+
+        let put = |room: &str, token: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/api/rooms/{}/persistence", room))
+                .header("content-type", "application/json")
+                .header("x-owner-token", token)
+                .body(Body::from(r#"{"persistMessages":false}"#))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(put("persistroom1", "fp_owner"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(
+            room_service
+                .get_room_config("persistroom1")
+                .unwrap()
+                .persist_messages
+        );
+
+        let response = app
+            .clone()
+            .oneshot(put("missing1room", &owner_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "ROOM_NOT_FOUND");
+
+        let response = app
+            .oneshot(put("persistroom1", &owner_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            !room_service
+                .get_room_config("persistroom1")
+                .unwrap()
+                .persist_messages
+        );
+    }
+
+    #[tokio::test]
+    async fn test_destroy_room_requires_admin() {
+        // ADMIN_TOKEN is not set in the test environment, so the endpoint is disabled
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        state
+            .room_service
+            .join_room(JoinRoomRequest::new("doomedroom1", "u1", "Alice", "s1"))
+            .unwrap();
+        let room_service = state.room_service.clone();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/rooms/doomedroom1")
+                    .header("x-admin-token", "guess")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(room_service.room_exists("doomedroom1"));
+    }
+
+    #[tokio::test]
+    async fn test_create_room_maps_policy_rejections() {
+        async fn create(
+            room_service: RoomService,
+            keys: &[&str],
+        ) -> (StatusCode, serde_json::Value) {
+            let dir = tempfile::tempdir().unwrap();
+            let state = AppState::new(
+                Arc::new(room_service),
+                Arc::new(
+                    FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                        .unwrap(),
+                ),
+                Arc::new(ShareService::new()),
+            );
+            let app = create_router(state);
+            let mut last = None;
+            for key in keys {
+                let response = app
+                    .clone()
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/rooms/create")
+                            .header("content-type", "application/json")
+                            .header("x-forwarded-for", "10.0.0.1")
+                            .body(Body::from(format!(r#"{{"roomKey":"{}"}}"#, key)))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                last = Some((status, serde_json::from_slice(&body).unwrap()));
+            }
+            last.unwrap()
+        }
+
+        let (status, json) =
+            create(RoomService::new().with_room_creation(false), &["newroom1"]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "ACCESS_DENIED");
+
+        let (status, json) = create(
+            RoomService::new().with_max_total_rooms(1),
+            &["fullroom1", "fullroom2"],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["code"], "SERVER_AT_CAPACITY");
+
+        let (status, json) = create(
+            RoomService::new().with_max_rooms_per_fingerprint(1),
+            &["iproom1", "iproom2"],
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(json["code"], "RATE_LIMITED");
+    }
+
+    #[tokio::test]
+    async fn test_verify_password_locks_out_after_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(
+                RoomService::new().with_password_lockout(3, std::time::Duration::from_secs(60)),
+            ),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        );
+        state
+            .room_service
+            .create_room("secretroom1", Some("s3cret"), None)
+            .unwrap();
+        let app = create_router(state);
+
+        let verify = |ip: &str, password: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/rooms/secretroom1/verify-password")
+                .header("content-type", "application/json")
+                .header("x-forwarded-for", ip)
+                .body(Body::from(format!(r#"{{"password":"{}"}}"#, password)))
+                .unwrap()
+        };
+
+        for _ in 0..3 {
+            let response = app
+                .clone()
+                .oneshot(verify("10.0.0.1", "wrong"))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["data"]["valid"], false);
+        }
+
+        // Even the correct password is rejected while locked out
+        let response = app
+            .clone()
+            .oneshot(verify("10.0.0.1", "s3cret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "TOO_MANY_ATTEMPTS");
+
+        // Another client is unaffected, and success clears its failures
+        let response = app.oneshot(verify("10.0.0.2", "s3cret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
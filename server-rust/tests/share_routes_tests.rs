@@ -218,6 +218,8 @@ mod tests {
                 expires_in_days,
                 password_hash,
                 metadata,
+                download_as: None,
+                bundle_files: Vec::new(),
             });
 
             self.shares.insert(share_id.clone(), share.clone());
@@ -1685,3 +1687,1016 @@ mod tests {
         assert_ne!(share1_created_by, "user2");
     }
 }
+
+/// Router-level tests for GET /public/file/{share_id} against the real services
+#[cfg(test)]
+mod public_download_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, Request, StatusCode, header},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share;
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, RoomService, ShareService,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    /// Upload a file and share it, returning the public download's Content-Disposition
+    async fn shared_disposition(download_as: Option<&str>) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "stored_abc.pdf", "application/pdf", b"%PDF")
+            .await
+            .unwrap();
+
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "originalFilename".to_string(),
+            serde_json::Value::String("stored_abc.pdf".to_string()),
+        );
+        let mut req = CreateShareRequest::new(
+            info.path.to_string_lossy(),
+            info.filename.clone(),
+            info.size,
+            "room123abc",
+            "user1",
+        )
+        .with_metadata(metadata);
+        if let Some(name) = download_as {
+            req = req.with_download_as(name);
+        }
+        let (share, _) = state.share_service.create_share(req).unwrap();
+
+        let app = Router::new()
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/public/file/{}", share.share_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_download_as_overrides_filename() {
+        let disposition = shared_disposition(Some("Invoice 2024.pdf")).await;
+        assert_eq!(
+            disposition,
+            "attachment; filename=\"Invoice 2024.pdf\"; filename*=UTF-8''Invoice%202024%2Epdf"
+        );
+    }
+
+    /// Share an uploaded file and return the Content-Disposition for `?{query}`
+    async fn disposition_with_query(name: &str, mime: &str, content: &[u8], query: &str) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", name, mime, content)
+            .await
+            .unwrap();
+        let (share, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new(
+                info.path.to_string_lossy(),
+                info.filename.clone(),
+                info.size,
+                "room123abc",
+                "user1",
+            ))
+            .unwrap();
+
+        let app = Router::new()
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/public/file/{}?{}", share.share_id, query))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+        response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[tokio::test]
+    async fn test_inline_disposition_for_image() {
+        let disposition =
+            disposition_with_query("photo.png", "image/png", PNG, "disposition=inline").await;
+        assert!(disposition.starts_with("inline;"));
+    }
+
+    #[tokio::test]
+    async fn test_inline_disposition_refused_for_html() {
+        let disposition = disposition_with_query(
+            "page.html",
+            "text/html",
+            b"<html><script>alert(1)</script></html>",
+            "disposition=inline",
+        )
+        .await;
+        assert!(disposition.starts_with("attachment;"));
+    }
+
+    #[tokio::test]
+    async fn test_default_disposition_is_attachment() {
+        let disposition = disposition_with_query("photo.png", "image/png", PNG, "").await;
+        assert!(disposition.starts_with("attachment;"));
+    }
+
+    #[tokio::test]
+    async fn test_download_falls_back_to_file_name() {
+        let disposition = shared_disposition(None).await;
+        assert!(disposition.contains("filename=\"stored_abc.pdf\""));
+    }
+
+    async fn download_bytes(state: AppState, share_id: &str) -> (HeaderMap, Vec<u8>) {
+        let app = Router::new()
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/public/file/{}", share_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers().clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (headers, bytes.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_multi_file_share_streams_zip_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let a = state
+            .file_manager
+            .save_file("room123abc", "a.txt", "text/plain", b"first file")
+            .await
+            .unwrap();
+        let b = state
+            .file_manager
+            .save_file("room123abc", "b.txt", "text/plain", b"second file")
+            .await
+            .unwrap();
+        let (share, _) = state
+            .share_service
+            .create_share(
+                CreateShareRequest::new("", "bundle.zip", 21, "room123abc", "user1")
+                    .with_bundle_files(vec![a.filename.clone(), b.filename.clone()]),
+            )
+            .unwrap();
+
+        let (headers, bytes) = download_bytes(state, &share.share_id).await;
+        assert_eq!(headers[header::CONTENT_TYPE], "application/zip");
+        assert!(
+            headers[header::CONTENT_DISPOSITION]
+                .to_str()
+                .unwrap()
+                .contains("filename=\"bundle.zip\"")
+        );
+
+        let zip = async_zip::base::read::mem::ZipFileReader::new(bytes)
+            .await
+            .unwrap();
+        let mut contents = Vec::new();
+        for index in 0..zip.file().entries().len() {
+            let name = zip.file().entries()[index]
+                .filename()
+                .as_str()
+                .unwrap()
+                .to_string();
+            let mut data = Vec::new();
+            zip.reader_with_entry(index)
+                .await
+                .unwrap()
+                .read_to_end_checked(&mut data)
+                .await
+                .unwrap();
+            contents.push((name, data));
+        }
+        assert_eq!(
+            contents,
+            vec![
+                ("a.txt".to_string(), b"first file".to_vec()),
+                ("b.txt".to_string(), b"second file".to_vec()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_single_file_share_streams_raw_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "notes.txt", "text/plain", b"just the file")
+            .await
+            .unwrap();
+        let (share, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new(
+                info.path.to_string_lossy(),
+                info.filename.clone(),
+                info.size,
+                "room123abc",
+                "user1",
+            ))
+            .unwrap();
+
+        let (headers, bytes) = download_bytes(state, &share.share_id).await;
+        assert_eq!(headers[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(headers[header::CONTENT_LENGTH], "13");
+        assert_eq!(bytes, b"just the file");
+    }
+
+    #[tokio::test]
+    async fn test_detach_from_room_hides_file_but_share_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file("room123abc", "a.txt", "text/plain", b"for outsiders")
+            .await
+            .unwrap();
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state.clone());
+
+        let body = serde_json::json!({ "fileId": info.filename, "detachFromRoom": true });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/share")
+                    .header("host", "localhost:3001")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let share_id = json["data"]["shareId"].as_str().unwrap();
+
+        assert!(state.file_manager.list_room_files("room123abc").is_empty());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/public/file/{}", share_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"for outsiders");
+    }
+
+    #[tokio::test]
+    async fn test_bundle_rejects_files_from_different_rooms() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let a = state
+            .file_manager
+            .save_file("room123abc", "a.txt", "text/plain", b"room one")
+            .await
+            .unwrap();
+        let b = state
+            .file_manager
+            .save_file("otherroom1", "b.txt", "text/plain", b"room two")
+            .await
+            .unwrap();
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .with_state(state);
+
+        let body = serde_json::json!({ "fileIds": [a.filename, b.filename] }).to_string();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/share")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// POST /api/share for `file_id`, returning the status and JSON body
+    async fn post_share(state: AppState, file_id: &str) -> (StatusCode, serde_json::Value) {
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .with_state(state);
+        let body = serde_json::json!({ "fileId": file_id }).to_string();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/share")
+                    .header("host", "localhost:3001")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_share_rejects_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+
+        let (status, json) = post_share(state.clone(), "no-such-file.txt").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["message"], "File not found");
+        assert_eq!(json["code"], "FILE_NOT_FOUND");
+
+        // Still tracked, but its bytes are gone from disk
+        let info = state
+            .file_manager
+            .save_file("room123abc", "gone.txt", "text/plain", b"vanished")
+            .await
+            .unwrap();
+        std::fs::remove_file(&info.path).unwrap();
+        let (status, json) = post_share(state.clone(), &info.filename).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "FILE_NOT_FOUND");
+        assert!(
+            state
+                .share_service
+                .get_user_shares("temp-user-id")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_share_derives_metadata_from_stored_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let info = state
+            .file_manager
+            .save_file(
+                "room123abc",
+                "report.pdf",
+                "application/pdf",
+                b"%PDF-1.7 body",
+            )
+            .await
+            .unwrap();
+
+        let (status, json) = post_share(state.clone(), &info.filename).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let share = state
+            .share_service
+            .get_share(json["data"]["shareId"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(share.file_name, info.filename);
+        assert_eq!(share.file_size, 13);
+        assert_eq!(share.file_path, info.path.to_string_lossy());
+        assert_eq!(share.room_key, "room123abc");
+    }
+
+    /// POST /api/share for a fresh file with the given expiresInDays
+    async fn create_share_status(share_service: ShareService, days: i64) -> StatusCode {
+        create_share_json(share_service, Some(days)).await.0
+    }
+
+    /// POST /api/share for a fresh file, omitting expiresInDays when `days` is None
+    async fn create_share_json(
+        share_service: ShareService,
+        days: Option<i64>,
+    ) -> (StatusCode, serde_json::Value) {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(share_service),
+        );
+        let info = state
+            .file_manager
+            .save_file("room123abc", "a.txt", "text/plain", b"expiring")
+            .await
+            .unwrap();
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .with_state(state);
+
+        let mut body = serde_json::json!({ "fileId": info.filename });
+        if let Some(days) = days {
+            body["expiresInDays"] = days.into();
+        }
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/share")
+                    .header("host", "localhost:3001")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    /// Whole days between a created share's createdAt and expiresAt
+    fn share_lifetime_days(json: &serde_json::Value) -> i64 {
+        let parse = |field: &str| {
+            chrono::DateTime::parse_from_rfc3339(json["data"][field].as_str().unwrap()).unwrap()
+        };
+        (parse("expiresAt") - parse("createdAt")).num_days()
+    }
+
+    #[tokio::test]
+    async fn test_share_omitted_expiration_uses_configured_default() {
+        let (status, json) = create_share_json(ShareService::new(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(share_lifetime_days(&json), 7);
+
+        let one_day = || ShareService::new().with_default_expiration(1);
+        let (status, json) = create_share_json(one_day(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(share_lifetime_days(&json), 1);
+
+        // An explicit value still wins over the configured default
+        let (status, json) = create_share_json(one_day(), Some(14)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(share_lifetime_days(&json), 14);
+    }
+
+    #[tokio::test]
+    async fn test_share_expiration_respects_configured_bounds() {
+        // Defaults unchanged: 1-30 days
+        assert_eq!(
+            create_share_status(ShareService::new(), 30).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            create_share_status(ShareService::new(), 31).await,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Raised maximum allows longer shares but still rejects beyond it
+        let raised = || ShareService::new().with_expiration_bounds(1, 90);
+        assert_eq!(create_share_status(raised(), 90).await, StatusCode::OK);
+        assert_eq!(
+            create_share_status(raised(), 91).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    async fn public_download_json(
+        state: AppState,
+        share_id: &str,
+        auth: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let app = Router::new()
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state);
+        let mut req = Request::builder().uri(format!("/public/file/{}", share_id));
+        if let Some(auth) = auth {
+            req = req.header(header::AUTHORIZATION, auth);
+        }
+        let response = app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_download_errors_carry_stable_codes() {
+        use base64::Engine;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let (share, _) = state
+            .share_service
+            .create_share(
+                CreateShareRequest::new("/tmp/x", "x.txt", 1, "room123abc", "user1")
+                    .with_password("secret123"),
+            )
+            .unwrap();
+
+        let (status, json) = public_download_json(state.clone(), "missing1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "SHARE_NOT_FOUND");
+        assert_eq!(json["message"], "Share not found");
+
+        let (status, json) = public_download_json(state.clone(), &share.share_id, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(json["code"], "PASSWORD_REQUIRED");
+
+        let wrong = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(":nope")
+        );
+        let (status, json) = public_download_json(state, &share.share_id, Some(&wrong)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(json["code"], "INVALID_PASSWORD");
+    }
+
+    #[test]
+    fn test_invalid_download_as_rejected() {
+        let service = ShareService::new();
+        for name in ["../evil.pdf", "dir\\evil.pdf", ""] {
+            let req = CreateShareRequest::new("/tmp/x", "x", 1, "room123abc", "user1")
+                .with_download_as(name);
+            assert!(service.create_share(req).is_err());
+        }
+    }
+}
+
+/// Router-level tests for POST /api/share/batch
+#[cfg(test)]
+mod batch_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share;
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    async fn post_batch(app: Router, ids: &[String]) -> (StatusCode, serde_json::Value) {
+        let body = serde_json::json!({ "shareIds": ids }).to_string();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/share/batch")
+                    .header("content-type", "application/json")
+                    .header("x-user-id", "user1")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_batch_returns_only_owned_shares() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let (mine, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new("a", "a.txt", 1, "room1", "user1"))
+            .unwrap();
+        let (theirs, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new("b", "b.txt", 1, "room1", "user2"))
+            .unwrap();
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .with_state(state);
+
+        let ids = vec![
+            mine.share_id.clone(),
+            theirs.share_id.clone(),
+            "missing1".to_string(),
+        ];
+        let (status, json) = post_batch(app, &ids).await;
+        assert_eq!(status, StatusCode::OK);
+        let data = json["data"].as_object().unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[&mine.share_id]["fileName"], "a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_oversized_id_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = Router::new()
+            .nest("/api/share", share::router())
+            .with_state(create_state(&dir));
+
+        let ids: Vec<String> = (0..=share::MAX_BATCH_SHARE_IDS)
+            .map(|i| format!("share{:04}", i))
+            .collect();
+        let (status, json) = post_batch(app, &ids).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["success"], false);
+    }
+}
+
+/// Router-level tests for share short links (POST /api/share/{id}/shorten, GET /s/{code})
+#[cfg(test)]
+mod short_link_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share;
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/share", share::router())
+            .route("/s/{code}", get(share::resolve_short_link))
+            .with_state(state)
+    }
+
+    fn create_share(state: &AppState, name: &str) -> String {
+        let (share, _) = state
+            .share_service
+            .create_share(CreateShareRequest::new(
+                name,
+                name,
+                10,
+                "room123abc",
+                "user1",
+            ))
+            .unwrap();
+        share.share_id
+    }
+
+    async fn shorten(
+        app: Router,
+        share_id: &str,
+        user_id: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/share/{}/shorten", share_id))
+                    .header("host", "localhost:3001")
+                    .header("x-user-id", user_id)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn follow(app: Router, uri: &str) -> axum::response::Response {
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_shorten_produces_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let share_id = create_share(&state, "a.txt");
+        let app = create_router(state);
+
+        let (status, json) = shorten(app.clone(), &share_id, "user1").await;
+        assert_eq!(status, StatusCode::OK);
+        let code = json["data"]["code"].as_str().unwrap();
+        assert!(code.len() < share_id.len());
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(
+            json["data"]["shortUrl"],
+            format!("http://localhost:3001/s/{}", code)
+        );
+
+        // Only the share's creator may shorten it
+        let (status, _) = shorten(app, &share_id, "user2").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_short_link_redirects_to_its_share() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let first = create_share(&state, "a.txt");
+        let second = create_share(&state, "b.txt");
+        let app = create_router(state);
+
+        let (_, json) = shorten(app.clone(), &first, "user1").await;
+        let first_code = json["data"]["code"].as_str().unwrap().to_string();
+        let (_, json) = shorten(app.clone(), &second, "user1").await;
+        let second_code = json["data"]["code"].as_str().unwrap().to_string();
+
+        let response = follow(app.clone(), &format!("/s/{}", first_code)).await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            format!("/public/file/{}", first).as_str()
+        );
+
+        // The query (e.g. a share password) is carried over
+        let response = follow(app, &format!("/s/{}?password=p%40ss", second_code)).await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            format!("/public/file/{}?password=p%40ss", second).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_or_revoked_code_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let share_id = create_share(&state, "a.txt");
+        let share_service = state.share_service.clone();
+        let app = create_router(state);
+
+        let response = follow(app.clone(), "/s/zzzzzz").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(header::LOCATION).is_none());
+
+        let (_, json) = shorten(app.clone(), &share_id, "user1").await;
+        let code = json["data"]["code"].as_str().unwrap().to_string();
+        share_service.revoke_share(&share_id).unwrap();
+        let response = follow(app.clone(), &format!("/s/{}", code)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A revoked share can't be shortened either
+        let (status, json) = shorten(app, &share_id, "user1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "SHARE_NOT_FOUND");
+    }
+}
+
+/// Router-level tests for POST /api/share/{share_id}/rotate-password
+#[cfg(test)]
+mod rotate_password_tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share;
+    use cloud_clipboard_server::services::{
+        CreateShareRequest, FileManager, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/share", share::router())
+            .route("/public/file/{share_id}", get(share::public_download))
+            .with_state(state)
+    }
+
+    /// A real stored file shared by user1 behind `password`
+    async fn protected_share(state: &AppState, password: &str) -> String {
+        let info = state
+            .file_manager
+            .save_file("room123abc", "secret.txt", "text/plain", b"top secret")
+            .await
+            .unwrap();
+        let (share, _) = state
+            .share_service
+            .create_share(
+                CreateShareRequest::new(
+                    info.path.to_string_lossy(),
+                    info.filename,
+                    info.size,
+                    "room123abc",
+                    "user1",
+                )
+                .with_password(password),
+            )
+            .unwrap();
+        share.share_id
+    }
+
+    async fn rotate(
+        app: Router,
+        share_id: &str,
+        user_id: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(format!("/api/share/{}/rotate-password", share_id))
+            .header("x-user-id", user_id);
+        let body = match body {
+            Some(json) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = app.oneshot(builder.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn download_status(app: Router, share_id: &str, password: &str) -> StatusCode {
+        app.oneshot(
+            Request::builder()
+                .uri(format!("/public/file/{}?password={}", share_id, password))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+    }
+
+    #[tokio::test]
+    async fn test_rotation_invalidates_old_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let share_id = protected_share(&state, "leaked123").await;
+        let app = create_router(state.clone());
+        assert_eq!(
+            download_status(app.clone(), &share_id, "leaked123").await,
+            StatusCode::OK
+        );
+
+        let (status, json) = rotate(app.clone(), &share_id, "user1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let new_password = json["data"]["password"].as_str().unwrap().to_string();
+        assert_ne!(new_password, "leaked123");
+
+        assert_eq!(
+            download_status(app.clone(), &share_id, "leaked123").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            download_status(app, &share_id, &new_password).await,
+            StatusCode::OK
+        );
+
+        let logs = state.share_service.get_access_logs(&share_id);
+        assert_eq!(
+            logs.iter()
+                .filter(|l| l.event.as_deref() == Some("passwordRotated"))
+                .count(),
+            1
+        );
+        // The rotation itself isn't counted as a download
+        let share = state.share_service.get_share(&share_id).unwrap();
+        assert_eq!(share.access_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_accepts_chosen_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let share_id = protected_share(&state, "leaked123").await;
+        let app = create_router(state);
+
+        let body = serde_json::json!({ "password": "fresh-pass" });
+        let (status, json) = rotate(app.clone(), &share_id, "user1", Some(body)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["password"], "fresh-pass");
+        assert_eq!(
+            download_status(app.clone(), &share_id, "fresh-pass").await,
+            StatusCode::OK
+        );
+
+        let body = serde_json::json!({ "password": "abc" });
+        let (status, json) = rotate(app, &share_id, "user1", Some(body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "INVALID_REQUEST");
+    }
+
+    #[tokio::test]
+    async fn test_rotation_requires_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let share_id = protected_share(&state, "leaked123").await;
+        let app = create_router(state);
+
+        let (status, json) = rotate(app.clone(), &share_id, "intruder", None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "ACCESS_DENIED");
+        assert_eq!(
+            download_status(app.clone(), &share_id, "leaked123").await,
+            StatusCode::OK
+        );
+
+        let (status, json) = rotate(app, "missing1", "user1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "SHARE_NOT_FOUND");
+    }
+}
@@ -89,8 +89,10 @@ mod tests {
 
         let p1 = pwd1.unwrap();
         let p2 = pwd2.unwrap();
-        assert_eq!(p1.len(), 6);
-        assert_eq!(p2.len(), 6);
+        // Default SHARE_PASSWORD_LENGTH is 10 with mixed case and digits
+        assert!(p1.len() >= 6);
+        assert_eq!(p1.len(), p2.len());
+        assert!(p1.chars().any(|c| c.is_ascii_uppercase()));
         assert_ne!(p1, p2, "Auto-generated passwords must differ");
     }
 
@@ -206,6 +206,21 @@ mod tests {
         assert_eq!(service.get_user_shares("user1").len(), 3);
     }
 
+    #[test]
+    fn test_batch_share_infos_only_owned() {
+        let service = create_service();
+        let mine = create_share_simple(&service, "mine.txt", "user1");
+        let theirs = create_share_simple(&service, "theirs.txt", "user2");
+
+        let infos = service.get_owned_share_infos(
+            &[mine.clone(), theirs.clone(), "unknown123".to_string()],
+            "user1",
+        );
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[&mine].file_name, "mine.txt");
+        assert!(!infos.contains_key(&theirs));
+    }
+
     // ===== Access log tests =====
 
     #[test]
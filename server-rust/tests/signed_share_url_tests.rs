@@ -0,0 +1,197 @@
+/// Signed Share URL Tests
+///
+/// SIGNED_SHARE_URLS is read once per process, so signed downloads are exercised
+/// in their own test binary with signing enabled up front.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::share;
+    use cloud_clipboard_server::services::{FileManager, RoomService, ShareService};
+    use std::sync::{Arc, Once};
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        static INIT: Once = Once::new();
+        // SAFETY: runs once, before any test reads the environment
+        INIT.call_once(|| unsafe {
+            std::env::set_var("SIGNED_SHARE_URLS", "true");
+            std::env::set_var("SHARE_URL_SECRET", "test-signing-secret");
+        });
+
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .nest("/api/share", share::router())
+            .route("/public/file/{share_id}", get(share::public_download))
+            .route("/s/{code}", get(share::resolve_short_link))
+            .with_state(state)
+    }
+
+    async fn send(
+        app: Router,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> axum::response::Response {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("host", "localhost:3001")
+            .header("x-user-id", "user1");
+        let body = match body {
+            Some(json) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        app.oneshot(builder.body(body).unwrap()).await.unwrap()
+    }
+
+    async fn body_json(response: axum::response::Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    /// Upload a file, share it, and return (share_id, path and query of the signed URL)
+    async fn signed_share(app: &Router, state: &AppState, name: &str) -> (String, String) {
+        let info = state
+            .file_manager
+            .save_file("room123abc", name, "text/plain", name.as_bytes())
+            .await
+            .unwrap();
+        let body = serde_json::json!({ "fileId": info.filename });
+        let response = send(app.clone(), "POST", "/api/share", Some(body)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        let url = json["data"]["url"].as_str().unwrap();
+        let path = url
+            .strip_prefix("http://localhost:3001")
+            .unwrap()
+            .to_string();
+        (json["data"]["shareId"].as_str().unwrap().to_string(), path)
+    }
+
+    #[tokio::test]
+    async fn test_valid_signature_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let app = create_router(state.clone());
+
+        let (share_id, path) = signed_share(&app, &state, "a.txt").await;
+        assert!(path.starts_with(&format!("/public/file/{}?exp=", share_id)));
+        assert!(path.contains("&sig="));
+
+        let response = send(app, "GET", &path, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_or_tampered_requests_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let app = create_router(state.clone());
+        let (share_a, path_a) = signed_share(&app, &state, "a.txt").await;
+        let (share_b, _) = signed_share(&app, &state, "b.txt").await;
+
+        let unsigned = format!("/public/file/{}", share_a);
+        // a's signature presented for b's ID
+        let swapped_id = path_a.replace(&share_a, &share_b);
+        let flipped_sig = {
+            let last = path_a.chars().last().unwrap();
+            let replacement = if last == '0' { '1' } else { '0' };
+            format!("{}{}", &path_a[..path_a.len() - 1], replacement)
+        };
+        let (exp, _) = path_a.split_once("&sig=").unwrap();
+        let exp_value: i64 = exp.split("exp=").nth(1).unwrap().parse().unwrap();
+        let extended_exp = path_a.replace(
+            &format!("exp={}", exp_value),
+            &format!("exp={}", exp_value + 86400),
+        );
+
+        for uri in [unsigned, swapped_id, flipped_sig, extended_exp] {
+            let response = send(app.clone(), "GET", &uri, None).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+            assert_eq!(body_json(response).await["code"], "ACCESS_DENIED");
+        }
+
+        // Unknown IDs are rejected the same way, revealing nothing about existence
+        let response = send(app, "GET", "/public/file/zzzzzzzz", None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_short_link_redirect_is_signed() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        let app = create_router(state.clone());
+        let (share_id, _) = signed_share(&app, &state, "a.txt").await;
+
+        let response = send(
+            app.clone(),
+            "POST",
+            &format!("/api/share/{}/shorten", share_id),
+            None,
+        )
+        .await;
+        let json = body_json(response).await;
+        let code = json["data"]["code"].as_str().unwrap().to_string();
+        let short_path = json["data"]["shortUrl"]
+            .as_str()
+            .unwrap()
+            .strip_prefix("http://localhost:3001")
+            .unwrap()
+            .to_string();
+        assert!(short_path.starts_with(&format!("/s/{}?exp=", code)));
+
+        // Bare or forged short links can't be used to enumerate shares
+        let (_, short_query) = short_path.split_once('?').unwrap();
+        for uri in [
+            format!("/s/{}", code),
+            format!("/s/zzzzzz?{}", short_query),
+            // The short link's signature doesn't open the share directly
+            format!("/public/file/{}?{}", share_id, short_query),
+        ] {
+            let response = send(app.clone(), "GET", &uri, None).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+            assert_eq!(body_json(response).await["code"], "ACCESS_DENIED");
+        }
+
+        let response = send(
+            app.clone(),
+            "GET",
+            &format!("{}&password=x", short_path),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        let location = response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(location.contains("sig="));
+        assert!(!location.contains(short_query));
+        assert!(location.ends_with("&password=x"));
+        let location = location.trim_end_matches("&password=x").to_string();
+
+        let response = send(app, "GET", &location, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,125 @@
+/// Stats Routes Tests
+///
+/// Exercises GET /api/stats against the real router and services.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, Request, StatusCode},
+        routing::get,
+    };
+    use cloud_clipboard_server::AppState;
+    use cloud_clipboard_server::routes::{is_admin_authorized, stats};
+    use cloud_clipboard_server::services::{
+        FileManager, JoinRoomRequest, RoomService, ShareService,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn create_state(dir: &tempfile::TempDir) -> AppState {
+        AppState::new(
+            Arc::new(RoomService::new()),
+            Arc::new(
+                FileManager::new_with_config(dir.path().to_path_buf(), 10 * 1024 * 1024, 12)
+                    .unwrap(),
+            ),
+            Arc::new(ShareService::new()),
+        )
+    }
+
+    fn create_router(state: AppState) -> Router {
+        Router::new()
+            .route("/api/stats", get(stats::get_stats))
+            .with_state(state)
+    }
+
+    #[tokio::test]
+    async fn test_stats_reflect_rooms_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+
+        state
+            .room_service
+            .join_room(JoinRoomRequest::new("room1", "user1", "Alice", "socket1"))
+            .unwrap();
+        state
+            .room_service
+            .join_room(JoinRoomRequest::new("room2", "user2", "Bob", "socket2"))
+            .unwrap();
+        state
+            .file_manager
+            .save_file("room1", "a.txt", "text/plain", b"hello")
+            .await
+            .unwrap();
+
+        let stats = stats::collect_stats(&state);
+        assert_eq!(stats.rooms.total_rooms, 2);
+        assert_eq!(stats.rooms.total_users, 2);
+        assert_eq!(stats.files.total_files, 1);
+        assert_eq!(stats.files.total_size, 5);
+        assert!(stats.uptime >= 0.0);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["rooms"]["totalRooms"], 2);
+        assert_eq!(json["files"]["totalFiles"], 1);
+        assert!(json["activeDownloads"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_stats_rejected_without_admin_token() {
+        // ADMIN_TOKEN is not set in the test environment, so the endpoint is disabled
+        let dir = tempfile::tempdir().unwrap();
+        let app = create_router(create_state(&dir));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/stats")
+                    .header("x-admin-token", "guess")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["success"], false);
+        assert!(json.get("data").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unified_app_state_reports_uptime() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = create_state(&dir);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        assert!(state.uptime() >= std::time::Duration::from_millis(10));
+        // Clones share the same start time
+        let cloned = state.clone();
+        assert_eq!(cloned.start_time, state.start_time);
+        assert!(stats::collect_stats(&cloned).uptime > 0.0);
+    }
+
+    #[test]
+    fn test_admin_token_check() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_admin_authorized(&headers, Some("secret")));
+
+        headers.insert("x-admin-token", "wrong".parse().unwrap());
+        assert!(!is_admin_authorized(&headers, Some("secret")));
+
+        headers.insert("x-admin-token", "secret".parse().unwrap());
+        assert!(is_admin_authorized(&headers, Some("secret")));
+        // No configured token never authorizes
+        assert!(!is_admin_authorized(&headers, None));
+
+        let mut bearer = HeaderMap::new();
+        bearer.insert("authorization", "Bearer secret".parse().unwrap());
+        assert!(is_admin_authorized(&bearer, Some("secret")));
+    }
+}
@@ -0,0 +1,45 @@
+/// Version Route Tests
+///
+/// Checks that the build information embedded by build.rs is served in full.
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use cloud_clipboard_server::routes::api_info;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_version_fields_present_and_non_empty() {
+        let app = Router::new().route("/api/version", get(api_info::version));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/version")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        for field in ["version", "gitSha", "buildTime", "rustVersion"] {
+            let value = json[field].as_str().unwrap_or_default();
+            assert!(!value.is_empty(), "{} missing in {}", field, json);
+        }
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["rustVersion"].as_str().unwrap().starts_with("rustc"));
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(json["buildTime"].as_str().unwrap()).is_ok(),
+            "{}",
+            json["buildTime"]
+        );
+    }
+}